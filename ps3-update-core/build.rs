@@ -0,0 +1,14 @@
+fn main() {
+    // The gRPC code is generated from `proto/` with a pure-Rust protobuf
+    // compiler, so building doesn't need `protoc`
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto");
+        let files = protox::compile(["proto/ps3update.proto"], ["proto"])
+            .expect("failed to parse proto/ps3update.proto");
+        tonic_prost_build::configure()
+            .build_client(true)
+            .compile_fds(files)
+            .expect("failed to generate gRPC code");
+    }
+}
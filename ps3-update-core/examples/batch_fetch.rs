@@ -2,13 +2,14 @@
 //!
 //! Run with: cargo run --example batch_fetch
 
-use ps3_update_core::UpdateFetcher;
+use ps3_update_core::{TitleDatabase, UpdateFetcher};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== PS3 Update Core - Batch Fetch Example ===\n");
 
-    let fetcher = UpdateFetcher::new()?;
+    // The embedded title database names titles whose XML has no <TITLE>
+    let fetcher = UpdateFetcher::new()?.with_title_database(TitleDatabase::embedded());
 
     // List of popular PS3 games to check
     let games = vec![
@@ -0,0 +1,128 @@
+use crate::types::{PS3UpdateError, Result};
+use crate::utils::clean_title_id;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// User-defined names for title IDs (e.g. `mgs4` → `BLES00246`)
+///
+/// Alias keys are matched case-insensitively with punctuation and whitespace
+/// ignored, so `BLES-00246`, `bles 00246` and `Shelf A-12` style codes all work.
+#[derive(Debug, Clone, Default)]
+pub struct AliasMap {
+    aliases: HashMap<String, String>,
+}
+
+impl AliasMap {
+    /// Create an empty alias map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load aliases from a `.toml` file, or CSV (`alias,TITLEID`) for any other extension
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let is_toml = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.eq_ignore_ascii_case("toml"))
+            .unwrap_or(false);
+        if is_toml {
+            Self::parse_toml(&text)
+        } else {
+            Ok(Self::parse_csv(&text))
+        }
+    }
+
+    /// Parse `alias,TITLEID` lines; blank lines and `#` comments are ignored
+    pub fn parse_csv(text: &str) -> Self {
+        let mut map = Self::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some((alias, title_id)) = line.rsplit_once(',') {
+                map.insert(
+                    alias.trim().trim_matches('"'),
+                    title_id.trim().trim_matches('"'),
+                );
+            }
+        }
+        map
+    }
+
+    /// Parse `alias = "TITLEID"` pairs, either top-level or under an `[aliases]` table
+    pub fn parse_toml(text: &str) -> Result<Self> {
+        let table: toml::Table = text.parse()?;
+        let table = match table.get("aliases") {
+            Some(toml::Value::Table(inner)) => inner.clone(),
+            _ => table,
+        };
+
+        let mut map = Self::new();
+        for (alias, value) in &table {
+            let title_id = value.as_str().ok_or_else(|| {
+                PS3UpdateError::AliasParse(format!("alias '{}' must map to a string", alias))
+            })?;
+            map.insert(alias, title_id);
+        }
+        Ok(map)
+    }
+
+    /// Add or replace an alias
+    pub fn insert(&mut self, alias: &str, title_id: &str) {
+        let key = normalize_alias(alias);
+        let value = clean_title_id(title_id);
+        if !key.is_empty() && !value.is_empty() {
+            self.aliases.insert(key, value);
+        }
+    }
+
+    /// Resolve an alias to its title ID
+    pub fn resolve(&self, alias: &str) -> Option<&str> {
+        self.aliases
+            .get(&normalize_alias(alias))
+            .map(|s| s.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
+
+fn normalize_alias(alias: &str) -> String {
+    alias
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv() {
+        let map = AliasMap::parse_csv("# my shelf\nmgs4,BLES00246\n\"Shelf A-12\",bcus-98111\n");
+        assert_eq!(map.resolve("MGS4"), Some("BLES00246"));
+        assert_eq!(map.resolve("shelf a12"), Some("BCUS98111"));
+        assert_eq!(map.resolve("gow3"), None);
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let map = AliasMap::parse_toml("[aliases]\nmgs4 = \"BLES00246\"\n").unwrap();
+        assert_eq!(map.resolve("mgs4"), Some("BLES00246"));
+
+        let map = AliasMap::parse_toml("tlou = \"BCUS98174\"\n").unwrap();
+        assert_eq!(map.resolve("TLOU"), Some("BCUS98174"));
+
+        assert!(AliasMap::parse_toml("mgs4 = 4\n").is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use crate::types::{PS3UpdateError, Result};
+use crate::utils::clean_title_id;
+use serde::{Deserialize, Serialize};
+
+/// Kind of artwork to retrieve for a title
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArtKind {
+    /// The square ICON0.PNG shown on the XMB
+    Icon,
+    /// Box art / cover image
+    Cover,
+}
+
+/// Where artwork is retrieved from
+///
+/// URL templates substitute `{id}` with the cleaned title ID.
+#[derive(Debug, Clone)]
+pub enum ArtSource {
+    /// PSN title metadata (tmdb) XML; the icon URL is read from its `<icon>` element.
+    /// Sony's tmdb paths carry a per-title hash, so the full template must be supplied.
+    Tmdb { metadata_url: String },
+    /// Direct image URL templates
+    Template {
+        icon_url: Option<String>,
+        cover_url: Option<String>,
+    },
+}
+
+impl ArtSource {
+    /// Create a tmdb-backed source from a metadata URL template
+    pub fn tmdb(metadata_url: impl Into<String>) -> Self {
+        ArtSource::Tmdb {
+            metadata_url: metadata_url.into(),
+        }
+    }
+
+    /// Create a source from direct image URL templates
+    pub fn template(icon_url: Option<String>, cover_url: Option<String>) -> Self {
+        ArtSource::Template {
+            icon_url,
+            cover_url,
+        }
+    }
+}
+
+/// Downloaded artwork
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artwork {
+    pub title_id: String,
+    pub kind: ArtKind,
+    pub url: String,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// Fetches cover art and icons for PS3 titles
+pub struct ArtFetcher {
+    client: reqwest::Client,
+    source: ArtSource,
+}
+
+impl ArtFetcher {
+    /// Create a new ArtFetcher using the given source
+    pub fn new(source: ArtSource) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()?;
+        Ok(Self { client, source })
+    }
+
+    /// Resolve the image URL for a title without downloading the image
+    ///
+    /// Returns `None` when the source has no artwork of the requested kind.
+    pub async fn art_url(&self, title_id: &str, kind: ArtKind) -> Result<Option<String>> {
+        let cleaned = clean_title_id(title_id);
+        if cleaned.is_empty() {
+            return Err(PS3UpdateError::InvalidTitleId(
+                "Empty or invalid Title ID".into(),
+            ));
+        }
+
+        match &self.source {
+            ArtSource::Template {
+                icon_url,
+                cover_url,
+            } => {
+                let template = match kind {
+                    ArtKind::Icon => icon_url,
+                    ArtKind::Cover => cover_url,
+                };
+                Ok(template.as_deref().map(|t| expand_template(t, &cleaned)))
+            }
+            ArtSource::Tmdb { metadata_url } => {
+                // tmdb metadata only carries the XMB icon
+                if kind != ArtKind::Icon {
+                    return Ok(None);
+                }
+                let url = expand_template(metadata_url, &cleaned);
+                let resp = self.client.get(&url).send().await?;
+                if !resp.status().is_success() {
+                    return Ok(None);
+                }
+                let text = resp.text().await?;
+                Ok(extract_icon_from_tmdb(&text))
+            }
+        }
+    }
+
+    /// Download artwork for a title
+    pub async fn fetch(&self, title_id: &str, kind: ArtKind) -> Result<Artwork> {
+        let cleaned = clean_title_id(title_id);
+        let url = self
+            .art_url(&cleaned, kind)
+            .await?
+            .ok_or_else(|| PS3UpdateError::ArtNotFound(cleaned.clone()))?;
+
+        let resp = self.client.get(&url).send().await?;
+        if !resp.status().is_success() {
+            return Err(PS3UpdateError::ArtNotFound(cleaned));
+        }
+
+        let content_type = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+        let data = resp.bytes().await?.to_vec();
+
+        Ok(Artwork {
+            title_id: cleaned,
+            kind,
+            url,
+            content_type,
+            data,
+        })
+    }
+}
+
+fn expand_template(template: &str, title_id: &str) -> String {
+    template.replace("{id}", title_id)
+}
+
+fn extract_icon_from_tmdb(text: &str) -> Option<String> {
+    let start = text.find("<icon")?;
+    let open_end = start + text[start..].find('>')? + 1;
+    let close = text[open_end..].find("</icon>")?;
+    let url = text[open_end..open_end + close].trim();
+    if url.is_empty() {
+        None
+    } else {
+        Some(url.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_template() {
+        assert_eq!(
+            expand_template("https://art.example/{id}/ICON0.PNG", "BLES00779"),
+            "https://art.example/BLES00779/ICON0.PNG"
+        );
+    }
+
+    #[test]
+    fn test_extract_icon_from_tmdb() {
+        let xml =
+            r#"<tmdb><name>Test</name><icon type="png"> http://tmdb/ICON0.PNG </icon></tmdb>"#;
+        assert_eq!(
+            extract_icon_from_tmdb(xml),
+            Some("http://tmdb/ICON0.PNG".to_string())
+        );
+        assert_eq!(extract_icon_from_tmdb("<tmdb></tmdb>"), None);
+    }
+}
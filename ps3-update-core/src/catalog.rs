@@ -0,0 +1,975 @@
+use crate::manifest::ManifestEntry;
+use crate::types::{FetchResult, PS3UpdateError, PackageInfo, ProgressInfo, Result};
+use crate::utils::{clean_title_id, format_size, unix_timestamp};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS titles (
+    title_id     TEXT PRIMARY KEY,
+    game_title   TEXT NOT NULL,
+    first_seen   INTEGER NOT NULL,
+    last_checked INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS packages (
+    title_id    TEXT NOT NULL,
+    version     TEXT NOT NULL,
+    system_ver  TEXT NOT NULL,
+    size_bytes  INTEGER NOT NULL,
+    sha1        TEXT NOT NULL,
+    url         TEXT NOT NULL,
+    filename    TEXT NOT NULL,
+    first_seen  INTEGER NOT NULL,
+    last_seen   INTEGER NOT NULL,
+    changed_at  INTEGER NOT NULL,
+    PRIMARY KEY (title_id, version)
+);
+CREATE INDEX IF NOT EXISTS packages_first_seen ON packages (first_seen);
+CREATE INDEX IF NOT EXISTS packages_changed_at ON packages (changed_at);
+CREATE TABLE IF NOT EXISTS downloads (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    title_id    TEXT NOT NULL,
+    game_title  TEXT NOT NULL,
+    version     TEXT NOT NULL,
+    filename    TEXT NOT NULL,
+    url         TEXT NOT NULL,
+    size_bytes  INTEGER NOT NULL,
+    sha1        TEXT NOT NULL,
+    path        TEXT,
+    bytes       INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    success     INTEGER NOT NULL,
+    error       TEXT,
+    verified    INTEGER,
+    missing     INTEGER NOT NULL DEFAULT 0,
+    checked_at  INTEGER,
+    finished_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS downloads_title_id ON downloads (title_id);
+CREATE INDEX IF NOT EXISTS downloads_finished_at ON downloads (finished_at);
+";
+
+const DOWNLOAD_COLUMNS: &str = "title_id, game_title, version, filename, url, size_bytes, sha1, \
+                                path, bytes, duration_ms, success, error, verified, missing, \
+                                checked_at, finished_at, id";
+
+/// Aggregates read by `DownloadStats::from_row`
+const STATS_COLUMNS: &str = "COUNT(*), COALESCE(SUM(NOT success), 0), \
+                             COALESCE(SUM(CASE WHEN success THEN bytes END), 0), \
+                             COALESCE(SUM(CASE WHEN success THEN duration_ms END), 0), \
+                             MAX(finished_at)";
+
+const PACKAGE_COLUMNS: &str = "title_id, version, system_ver, size_bytes, sha1, url, filename, \
+                               first_seen, last_seen, changed_at";
+
+/// A title known to the catalog
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogTitle {
+    pub title_id: String,
+    pub game_title: String,
+    pub first_seen: u64,
+    pub last_checked: u64,
+}
+
+impl CatalogTitle {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            title_id: row.get(0)?,
+            game_title: row.get(1)?,
+            first_seen: row.get::<_, i64>(2)? as u64,
+            last_checked: row.get::<_, i64>(3)? as u64,
+        })
+    }
+}
+
+/// A package as recorded in the catalog, with discovery timestamps (Unix seconds)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogPackage {
+    pub title_id: String,
+    pub version: String,
+    pub system_ver: String,
+    pub size_bytes: u64,
+    pub sha1: String,
+    pub url: String,
+    pub filename: String,
+    /// When this version was first discovered
+    pub first_seen: u64,
+    /// When this version was last returned by the server
+    pub last_seen: u64,
+    /// When the size, hash or URL last changed (equals `first_seen` if never)
+    pub changed_at: u64,
+}
+
+impl CatalogPackage {
+    pub fn to_package_info(&self) -> PackageInfo {
+        PackageInfo {
+            version: self.version.clone(),
+            system_ver: self.system_ver.clone(),
+            size_bytes: self.size_bytes,
+            size_human: format_size(self.size_bytes),
+            url: self.url.clone(),
+            sha1: self.sha1.clone(),
+            filename: self.filename.clone(),
+        }
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            title_id: row.get(0)?,
+            version: row.get(1)?,
+            system_ver: row.get(2)?,
+            size_bytes: row.get::<_, i64>(3)? as u64,
+            sha1: row.get(4)?,
+            url: row.get(5)?,
+            filename: row.get(6)?,
+            first_seen: row.get::<_, i64>(7)? as u64,
+            last_seen: row.get::<_, i64>(8)? as u64,
+            changed_at: row.get::<_, i64>(9)? as u64,
+        })
+    }
+}
+
+/// One finished (or failed) download, as logged in the catalog
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownloadRecord {
+    /// Row ID, assigned by [`Catalog::record_download`]
+    pub id: i64,
+    pub title_id: String,
+    pub game_title: String,
+    pub version: String,
+    pub filename: String,
+    /// URL the file came from (may be an archive mirror)
+    pub url: String,
+    /// Size and SHA1 the update list gave for the package
+    pub size_bytes: u64,
+    pub sha1: String,
+    /// Where the file was saved, after any rename
+    pub path: Option<String>,
+    /// Bytes transferred, including for failed attempts
+    pub bytes: u64,
+    pub duration_secs: f64,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Outcome of the last verification, if one ran
+    pub verified: Option<bool>,
+    /// The file wasn't on disk when last checked
+    pub missing: bool,
+    /// Unix timestamp of the last re-check
+    pub checked_at: Option<u64>,
+    /// Unix timestamp when the download ended
+    pub finished_at: u64,
+}
+
+impl DownloadRecord {
+    /// Build a record from a job's manifest entry and final progress
+    pub fn from_progress(entry: &ManifestEntry, progress: &ProgressInfo) -> Self {
+        let package = &entry.package;
+        Self {
+            id: 0,
+            title_id: clean_title_id(&entry.title_id),
+            game_title: entry.game_name.clone(),
+            version: package.version.clone(),
+            filename: package.filename.clone(),
+            url: progress
+                .source
+                .clone()
+                .unwrap_or_else(|| package.url.clone()),
+            size_bytes: package.size_bytes,
+            sha1: package.sha1.clone(),
+            path: None,
+            bytes: progress.downloaded,
+            duration_secs: progress.elapsed_secs,
+            success: progress.done && progress.error.is_none(),
+            error: progress.error.clone(),
+            verified: progress.verified,
+            missing: false,
+            checked_at: None,
+            finished_at: unix_timestamp(),
+        }
+    }
+
+    /// The package as the update list gave it
+    pub fn to_package_info(&self) -> PackageInfo {
+        PackageInfo {
+            version: self.version.clone(),
+            system_ver: String::new(),
+            size_bytes: self.size_bytes,
+            size_human: format_size(self.size_bytes),
+            url: self.url.clone(),
+            sha1: self.sha1.clone(),
+            filename: self.filename.clone(),
+        }
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            title_id: row.get(0)?,
+            game_title: row.get(1)?,
+            version: row.get(2)?,
+            filename: row.get(3)?,
+            url: row.get(4)?,
+            size_bytes: row.get::<_, i64>(5)? as u64,
+            sha1: row.get(6)?,
+            path: row.get(7)?,
+            bytes: row.get::<_, i64>(8)? as u64,
+            duration_secs: row.get::<_, i64>(9)? as f64 / 1000.0,
+            success: row.get(10)?,
+            error: row.get(11)?,
+            verified: row.get(12)?,
+            missing: row.get(13)?,
+            checked_at: row.get::<_, Option<i64>>(14)?.map(|t| t as u64),
+            finished_at: row.get::<_, i64>(15)? as u64,
+            id: row.get(16)?,
+        })
+    }
+}
+
+/// Totals over a set of logged downloads
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DownloadStats {
+    /// Title ID these totals cover, or `None` for the whole catalog
+    pub title_id: Option<String>,
+    pub downloads: u64,
+    pub failures: u64,
+    /// Bytes of successful downloads
+    pub total_bytes: u64,
+    /// Total bytes over total transfer time of successful downloads
+    pub average_speed_bytes_per_sec: f64,
+    pub last_download: Option<u64>,
+}
+
+impl DownloadStats {
+    fn from_row(row: &Row, title_id: Option<String>) -> rusqlite::Result<Self> {
+        let total_bytes = row.get::<_, i64>(2)? as u64;
+        let duration_ms = row.get::<_, i64>(3)?;
+        Ok(Self {
+            title_id,
+            downloads: row.get::<_, i64>(0)? as u64,
+            failures: row.get::<_, i64>(1)? as u64,
+            total_bytes,
+            average_speed_bytes_per_sec: if duration_ms > 0 {
+                total_bytes as f64 / (duration_ms as f64 / 1000.0)
+            } else {
+                0.0
+            },
+            last_download: row.get::<_, Option<i64>>(4)?.map(|t| t as u64),
+        })
+    }
+}
+
+/// What changed when a fetch result was recorded
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordSummary {
+    /// Versions seen for the first time
+    pub new_versions: Vec<String>,
+    /// Known versions whose size, hash or URL changed
+    pub changed_versions: Vec<String>,
+}
+
+/// Portable dump of a catalog: "state of the update server as of `exported_at`"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogSnapshot {
+    pub format_version: u32,
+    pub exported_at: u64,
+    pub titles: Vec<CatalogTitle>,
+    pub packages: Vec<CatalogPackage>,
+}
+
+/// One CSV row of a snapshot: a package joined with its title's name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SnapshotRow {
+    title_id: String,
+    game_title: String,
+    version: String,
+    system_ver: String,
+    size_bytes: u64,
+    sha1: String,
+    url: String,
+    filename: String,
+    first_seen: u64,
+    last_seen: u64,
+    changed_at: u64,
+}
+
+/// How much an import added or merged
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub titles: usize,
+    pub packages: usize,
+}
+
+const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// SQLite-backed store of every fetched title's packages
+pub struct Catalog {
+    conn: Mutex<Connection>,
+}
+
+impl Catalog {
+    /// Open (or create) a catalog database file
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Open a catalog that lives only in memory
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Record a fetch result, returning which versions are new or changed
+    pub fn record_fetch(&self, result: &FetchResult) -> Result<RecordSummary> {
+        self.record_fetch_at(result, unix_timestamp())
+    }
+
+    /// Record a fetch result as if it happened at `timestamp`
+    pub fn record_fetch_at(&self, result: &FetchResult, timestamp: u64) -> Result<RecordSummary> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = timestamp as i64;
+        let mut summary = RecordSummary::default();
+
+        tx.execute(
+            "INSERT INTO titles (title_id, game_title, first_seen, last_checked)
+             VALUES (?1, ?2, ?3, ?3)
+             ON CONFLICT (title_id) DO UPDATE SET game_title = ?2, last_checked = ?3",
+            params![result.cleaned_title_id, result.game_title, now],
+        )?;
+
+        for pkg in &result.results {
+            let existing: Option<(i64, String, String)> = tx
+                .query_row(
+                    "SELECT size_bytes, sha1, url FROM packages WHERE title_id = ?1 AND version = ?2",
+                    params![result.cleaned_title_id, pkg.version],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()?;
+
+            match existing {
+                None => {
+                    tx.execute(
+                        "INSERT INTO packages (title_id, version, system_ver, size_bytes, sha1, url,
+                                               filename, first_seen, last_seen, changed_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8, ?8)",
+                        params![
+                            result.cleaned_title_id,
+                            pkg.version,
+                            pkg.system_ver,
+                            pkg.size_bytes as i64,
+                            pkg.sha1,
+                            pkg.url,
+                            pkg.filename,
+                            now
+                        ],
+                    )?;
+                    summary.new_versions.push(pkg.version.clone());
+                }
+                Some((size, sha1, url)) => {
+                    let changed =
+                        size as u64 != pkg.size_bytes || sha1 != pkg.sha1 || url != pkg.url;
+                    tx.execute(
+                        "UPDATE packages SET system_ver = ?3, size_bytes = ?4, sha1 = ?5, url = ?6,
+                                filename = ?7, last_seen = ?8,
+                                changed_at = CASE WHEN ?9 THEN ?8 ELSE changed_at END
+                         WHERE title_id = ?1 AND version = ?2",
+                        params![
+                            result.cleaned_title_id,
+                            pkg.version,
+                            pkg.system_ver,
+                            pkg.size_bytes as i64,
+                            pkg.sha1,
+                            pkg.url,
+                            pkg.filename,
+                            now,
+                            changed
+                        ],
+                    )?;
+                    if changed {
+                        summary.changed_versions.push(pkg.version.clone());
+                    }
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(summary)
+    }
+
+    /// All titles in the catalog, ordered by title ID
+    pub fn titles(&self) -> Result<Vec<CatalogTitle>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT title_id, game_title, first_seen, last_checked FROM titles ORDER BY title_id",
+        )?;
+        let rows = stmt.query_map([], CatalogTitle::from_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// A single title, if known
+    pub fn title(&self, title_id: &str) -> Result<Option<CatalogTitle>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT title_id, game_title, first_seen, last_checked FROM titles WHERE title_id = ?1",
+                params![title_id],
+                CatalogTitle::from_row,
+            )
+            .optional()?)
+    }
+
+    /// Packages recorded for a title, ordered by version
+    pub fn packages_for_title(&self, title_id: &str) -> Result<Vec<CatalogPackage>> {
+        self.query_packages(
+            &format!(
+                "SELECT {} FROM packages WHERE title_id = ?1 ORDER BY version",
+                PACKAGE_COLUMNS
+            ),
+            params![title_id],
+        )
+    }
+
+    /// Packages recorded under a file name, most recently seen first
+    pub fn packages_by_filename(&self, filename: &str) -> Result<Vec<CatalogPackage>> {
+        self.query_packages(
+            &format!(
+                "SELECT {} FROM packages WHERE filename = ?1 ORDER BY last_seen DESC",
+                PACKAGE_COLUMNS
+            ),
+            params![filename],
+        )
+    }
+
+    /// Whether a package with this download URL was ever recorded
+    pub fn contains_url(&self, url: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let found: Option<i64> = conn
+            .query_row(
+                "SELECT 1 FROM packages WHERE url = ?1 LIMIT 1",
+                params![url],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    /// Packages first discovered at or after `timestamp`
+    pub fn discovered_since(&self, timestamp: u64) -> Result<Vec<CatalogPackage>> {
+        self.query_packages(
+            &format!(
+                "SELECT {} FROM packages WHERE first_seen >= ?1 ORDER BY first_seen, title_id, version",
+                PACKAGE_COLUMNS
+            ),
+            params![timestamp as i64],
+        )
+    }
+
+    /// Packages that appeared or changed at or after `timestamp`
+    pub fn changed_since(&self, timestamp: u64) -> Result<Vec<CatalogPackage>> {
+        self.query_packages(
+            &format!(
+                "SELECT {} FROM packages WHERE changed_at >= ?1 ORDER BY changed_at, title_id, version",
+                PACKAGE_COLUMNS
+            ),
+            params![timestamp as i64],
+        )
+    }
+
+    /// Capture the whole catalog as a portable snapshot
+    pub fn snapshot(&self) -> Result<CatalogSnapshot> {
+        let packages = self.query_packages(
+            &format!(
+                "SELECT {} FROM packages ORDER BY title_id, version",
+                PACKAGE_COLUMNS
+            ),
+            [],
+        )?;
+        Ok(CatalogSnapshot {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            exported_at: unix_timestamp(),
+            titles: self.titles()?,
+            packages,
+        })
+    }
+
+    /// Merge a snapshot into this catalog
+    ///
+    /// Timestamps are merged so the earliest discovery and latest sighting win;
+    /// package details are taken from whichever side saw the package most recently.
+    pub fn import_snapshot(&self, snapshot: &CatalogSnapshot) -> Result<ImportSummary> {
+        if snapshot.format_version > SNAPSHOT_FORMAT_VERSION {
+            return Err(PS3UpdateError::Snapshot(format!(
+                "unsupported snapshot format version {}",
+                snapshot.format_version
+            )));
+        }
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        for t in &snapshot.titles {
+            tx.execute(
+                "INSERT INTO titles (title_id, game_title, first_seen, last_checked)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (title_id) DO UPDATE SET
+                    game_title = CASE WHEN excluded.last_checked > last_checked
+                                      THEN excluded.game_title ELSE game_title END,
+                    first_seen = MIN(first_seen, excluded.first_seen),
+                    last_checked = MAX(last_checked, excluded.last_checked)",
+                params![
+                    t.title_id,
+                    t.game_title,
+                    t.first_seen as i64,
+                    t.last_checked as i64
+                ],
+            )?;
+        }
+
+        for p in &snapshot.packages {
+            tx.execute(
+                "INSERT INTO packages (title_id, version, system_ver, size_bytes, sha1, url,
+                                       filename, first_seen, last_seen, changed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                 ON CONFLICT (title_id, version) DO UPDATE SET
+                    system_ver = CASE WHEN excluded.last_seen > last_seen
+                                      THEN excluded.system_ver ELSE system_ver END,
+                    size_bytes = CASE WHEN excluded.last_seen > last_seen
+                                      THEN excluded.size_bytes ELSE size_bytes END,
+                    sha1 = CASE WHEN excluded.last_seen > last_seen
+                                THEN excluded.sha1 ELSE sha1 END,
+                    url = CASE WHEN excluded.last_seen > last_seen
+                               THEN excluded.url ELSE url END,
+                    filename = CASE WHEN excluded.last_seen > last_seen
+                                    THEN excluded.filename ELSE filename END,
+                    first_seen = MIN(first_seen, excluded.first_seen),
+                    changed_at = MAX(changed_at, excluded.changed_at),
+                    last_seen = MAX(last_seen, excluded.last_seen)",
+                params![
+                    p.title_id,
+                    p.version,
+                    p.system_ver,
+                    p.size_bytes as i64,
+                    p.sha1,
+                    p.url,
+                    p.filename,
+                    p.first_seen as i64,
+                    p.last_seen as i64,
+                    p.changed_at as i64
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(ImportSummary {
+            titles: snapshot.titles.len(),
+            packages: snapshot.packages.len(),
+        })
+    }
+
+    /// Write the catalog to a pretty-printed JSON snapshot file
+    pub fn export_json(&self, path: impl AsRef<Path>) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.snapshot()?)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Merge a JSON snapshot file into this catalog
+    pub fn import_json(&self, path: impl AsRef<Path>) -> Result<ImportSummary> {
+        let text = std::fs::read_to_string(path)?;
+        let snapshot: CatalogSnapshot = serde_json::from_str(&text)?;
+        self.import_snapshot(&snapshot)
+    }
+
+    /// Write every package as one CSV row, including its title's name
+    pub fn export_csv(&self, path: impl AsRef<Path>) -> Result<()> {
+        let snapshot = self.snapshot()?;
+        let mut writer = csv::Writer::from_path(path)?;
+        for p in snapshot.packages {
+            let game_title = snapshot
+                .titles
+                .iter()
+                .find(|t| t.title_id == p.title_id)
+                .map(|t| t.game_title.clone())
+                .unwrap_or_default();
+            writer.serialize(SnapshotRow {
+                title_id: p.title_id,
+                game_title,
+                version: p.version,
+                system_ver: p.system_ver,
+                size_bytes: p.size_bytes,
+                sha1: p.sha1,
+                url: p.url,
+                filename: p.filename,
+                first_seen: p.first_seen,
+                last_seen: p.last_seen,
+                changed_at: p.changed_at,
+            })?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Merge a CSV file written by [`Catalog::export_csv`] into this catalog
+    pub fn import_csv(&self, path: impl AsRef<Path>) -> Result<ImportSummary> {
+        let mut reader = csv::Reader::from_path(path)?;
+        let mut snapshot = CatalogSnapshot {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            exported_at: unix_timestamp(),
+            titles: vec![],
+            packages: vec![],
+        };
+
+        for row in reader.deserialize::<SnapshotRow>() {
+            let row = row?;
+            match snapshot
+                .titles
+                .iter_mut()
+                .find(|t| t.title_id == row.title_id)
+            {
+                Some(t) => {
+                    t.first_seen = t.first_seen.min(row.first_seen);
+                    t.last_checked = t.last_checked.max(row.last_seen);
+                }
+                None => snapshot.titles.push(CatalogTitle {
+                    title_id: row.title_id.clone(),
+                    game_title: row.game_title.clone(),
+                    first_seen: row.first_seen,
+                    last_checked: row.last_seen,
+                }),
+            }
+            snapshot.packages.push(CatalogPackage {
+                title_id: row.title_id,
+                version: row.version,
+                system_ver: row.system_ver,
+                size_bytes: row.size_bytes,
+                sha1: row.sha1,
+                url: row.url,
+                filename: row.filename,
+                first_seen: row.first_seen,
+                last_seen: row.last_seen,
+                changed_at: row.changed_at,
+            });
+        }
+
+        self.import_snapshot(&snapshot)
+    }
+
+    /// Log a finished or failed download and return its row ID
+    ///
+    /// The record's own `id` is ignored.
+    pub fn record_download(&self, record: &DownloadRecord) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO downloads (title_id, game_title, version, filename, url, size_bytes, sha1,
+                                    path, bytes, duration_ms, success, error, verified, missing,
+                                    checked_at, finished_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+            params![
+                record.title_id,
+                record.game_title,
+                record.version,
+                record.filename,
+                record.url,
+                record.size_bytes as i64,
+                record.sha1,
+                record.path,
+                record.bytes as i64,
+                (record.duration_secs * 1000.0).round() as i64,
+                record.success,
+                record.error,
+                record.verified,
+                record.missing,
+                record.checked_at.map(|t| t as i64),
+                record.finished_at as i64
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Store a later check of a logged download's file
+    ///
+    /// `None` means the file is gone; it keeps its last verification.
+    pub fn record_check(&self, id: i64, verified: Option<bool>, checked_at: u64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE downloads SET missing = ?2, verified = COALESCE(?3, verified), checked_at = ?4
+             WHERE id = ?1",
+            params![id, verified.is_none(), verified, checked_at as i64],
+        )?;
+        Ok(())
+    }
+
+    /// A logged download by row ID
+    pub fn download(&self, id: i64) -> Result<Option<DownloadRecord>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                &format!("SELECT {} FROM downloads WHERE id = ?1", DOWNLOAD_COLUMNS),
+                params![id],
+                DownloadRecord::from_row,
+            )
+            .optional()?)
+    }
+
+    /// Every logged download, newest first
+    pub fn downloads(&self) -> Result<Vec<DownloadRecord>> {
+        self.query_downloads(
+            &format!(
+                "SELECT {} FROM downloads ORDER BY finished_at DESC, id DESC",
+                DOWNLOAD_COLUMNS
+            ),
+            [],
+        )
+    }
+
+    /// Totals for every download logged at or after `since` (0 for all time)
+    pub fn download_stats(&self, since: u64) -> Result<DownloadStats> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.query_row(
+            &format!(
+                "SELECT {} FROM downloads WHERE finished_at >= ?1",
+                STATS_COLUMNS
+            ),
+            params![since as i64],
+            |row| DownloadStats::from_row(row, None),
+        )?)
+    }
+
+    /// Per-title totals, ordered by title ID
+    pub fn download_stats_by_title(&self) -> Result<Vec<DownloadStats>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, title_id FROM downloads GROUP BY title_id ORDER BY title_id",
+            STATS_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], |row| DownloadStats::from_row(row, Some(row.get(5)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Every download logged for a title, newest first
+    pub fn download_history(&self, title_id: &str) -> Result<Vec<DownloadRecord>> {
+        self.query_downloads(
+            &format!(
+                "SELECT {} FROM downloads WHERE title_id = ?1 ORDER BY finished_at DESC, id DESC",
+                DOWNLOAD_COLUMNS
+            ),
+            params![clean_title_id(title_id)],
+        )
+    }
+
+    /// The most recent failed downloads, newest first
+    pub fn recent_failures(&self, limit: usize) -> Result<Vec<DownloadRecord>> {
+        self.query_downloads(
+            &format!(
+                "SELECT {} FROM downloads WHERE NOT success ORDER BY finished_at DESC, id DESC LIMIT ?1",
+                DOWNLOAD_COLUMNS
+            ),
+            params![limit as i64],
+        )
+    }
+
+    fn query_downloads(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<DownloadRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, DownloadRecord::from_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    fn query_packages(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<CatalogPackage>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, CatalogPackage::from_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fetch_result(packages: &[(&str, u64, &str)]) -> FetchResult {
+        FetchResult {
+            results: packages
+                .iter()
+                .map(|(version, size, sha1)| PackageInfo {
+                    version: version.to_string(),
+                    system_ver: "4.00".into(),
+                    size_bytes: *size,
+                    size_human: format_size(*size),
+                    url: format!("http://example/{}.pkg", version),
+                    sha1: sha1.to_string(),
+                    filename: format!("{}.pkg", version),
+                })
+                .collect(),
+            error: None,
+            game_title: "Test Game".into(),
+            cleaned_title_id: "BLES00001".into(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_query() {
+        let catalog = Catalog::open_in_memory().unwrap();
+
+        let summary = catalog
+            .record_fetch_at(&fetch_result(&[("01.00", 10, "aa")]), 100)
+            .unwrap();
+        assert_eq!(summary.new_versions, vec!["01.00"]);
+
+        let summary = catalog
+            .record_fetch_at(
+                &fetch_result(&[("01.00", 10, "aa"), ("01.01", 20, "bb")]),
+                200,
+            )
+            .unwrap();
+        assert_eq!(summary.new_versions, vec!["01.01"]);
+        assert!(summary.changed_versions.is_empty());
+
+        let summary = catalog
+            .record_fetch_at(
+                &fetch_result(&[("01.00", 11, "cc"), ("01.01", 20, "bb")]),
+                300,
+            )
+            .unwrap();
+        assert_eq!(summary.changed_versions, vec!["01.00"]);
+
+        let pkgs = catalog.packages_for_title("BLES00001").unwrap();
+        assert_eq!(pkgs.len(), 2);
+        assert_eq!(pkgs[0].first_seen, 100);
+        assert_eq!(pkgs[0].last_seen, 300);
+        assert_eq!(pkgs[0].sha1, "cc");
+
+        assert_eq!(catalog.discovered_since(150).unwrap().len(), 1);
+        assert_eq!(catalog.changed_since(250).unwrap()[0].version, "01.00");
+
+        let title = catalog.title("BLES00001").unwrap().unwrap();
+        assert_eq!(title.game_title, "Test Game");
+        assert_eq!(title.last_checked, 300);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+
+        let source = Catalog::open_in_memory().unwrap();
+        source
+            .record_fetch_at(
+                &fetch_result(&[("01.00", 10, "aa"), ("01.01", 20, "bb")]),
+                100,
+            )
+            .unwrap();
+        source.export_json(dir.join("snap.json")).unwrap();
+        source.export_csv(dir.join("snap.csv")).unwrap();
+
+        for file in ["snap.json", "snap.csv"] {
+            let target = Catalog::open_in_memory().unwrap();
+            target
+                .record_fetch_at(&fetch_result(&[("01.00", 10, "aa")]), 50)
+                .unwrap();
+            let summary = if file.ends_with(".json") {
+                target.import_json(dir.join(file)).unwrap()
+            } else {
+                target.import_csv(dir.join(file)).unwrap()
+            };
+            assert_eq!(summary.packages, 2);
+
+            let pkgs = target.packages_for_title("BLES00001").unwrap();
+            assert_eq!(pkgs.len(), 2);
+            // Earliest discovery and latest sighting are kept
+            assert_eq!(pkgs[0].first_seen, 50);
+            assert_eq!(pkgs[0].last_seen, 100);
+            assert_eq!(
+                target.title("BLES00001").unwrap().unwrap().game_title,
+                "Test Game"
+            );
+        }
+    }
+
+    #[test]
+    fn test_download_stats() {
+        let catalog = Catalog::open_in_memory().unwrap();
+        let record =
+            |title_id: &str, bytes: u64, secs: f64, error: Option<&str>, at: u64| DownloadRecord {
+                id: 0,
+                title_id: title_id.into(),
+                game_title: "Test Game".into(),
+                version: "01.01".into(),
+                filename: "01.01.pkg".into(),
+                url: "http://example/01.01.pkg".into(),
+                size_bytes: bytes,
+                sha1: String::new(),
+                path: None,
+                bytes,
+                duration_secs: secs,
+                success: error.is_none(),
+                error: error.map(str::to_string),
+                verified: None,
+                missing: false,
+                checked_at: None,
+                finished_at: at,
+            };
+        catalog
+            .record_download(&record("BLES00001", 1000, 2.0, None, 100))
+            .unwrap();
+        catalog
+            .record_download(&record("BLES00001", 50, 1.0, Some("timed out"), 200))
+            .unwrap();
+        catalog
+            .record_download(&record("BLUS30001", 3000, 2.0, None, 300))
+            .unwrap();
+
+        let stats = catalog.download_stats(0).unwrap();
+        assert_eq!(stats.downloads, 3);
+        assert_eq!(stats.failures, 1);
+        assert_eq!(stats.total_bytes, 4000);
+        assert_eq!(stats.average_speed_bytes_per_sec, 1000.0);
+        assert_eq!(stats.last_download, Some(300));
+        assert_eq!(catalog.download_stats(250).unwrap().downloads, 1);
+        assert_eq!(catalog.download_stats(1000).unwrap().last_download, None);
+
+        let by_title = catalog.download_stats_by_title().unwrap();
+        assert_eq!(by_title.len(), 2);
+        assert_eq!(by_title[0].title_id.as_deref(), Some("BLES00001"));
+        assert_eq!(by_title[0].total_bytes, 1000);
+
+        let history = catalog.download_history("bles-00001").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].error.as_deref(), Some("timed out"));
+        assert_eq!(history[1].duration_secs, 2.0);
+        assert_eq!(catalog.recent_failures(10).unwrap().len(), 1);
+
+        let id = catalog
+            .record_download(&DownloadRecord {
+                verified: Some(true),
+                ..record("BLES00001", 10, 1.0, None, 400)
+            })
+            .unwrap();
+        assert_eq!(catalog.downloads().unwrap()[0].id, id);
+        catalog.record_check(id, None, 500).unwrap();
+        let checked = catalog.download(id).unwrap().unwrap();
+        assert!(checked.missing);
+        assert_eq!(checked.verified, Some(true));
+        assert_eq!(checked.checked_at, Some(500));
+        catalog.record_check(id, Some(false), 600).unwrap();
+        let checked = catalog.download(id).unwrap().unwrap();
+        assert!(!checked.missing);
+        assert_eq!(checked.verified, Some(false));
+        assert_eq!(catalog.download(id + 1).unwrap(), None);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::types::{PS3UpdateError, Result};
+use crate::verify::file_sha1;
+use std::path::{Path, PathBuf};
+
+/// Name of the per-folder checksum list
+pub const SHA1SUMS: &str = "SHA1SUMS";
+
+/// Sidecar path for a file (`game.pkg` → `game.pkg.sha1`)
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sha1");
+    PathBuf::from(name)
+}
+
+/// Hash a file and write a `sha1sum`-compatible `.sha1` file next to it
+///
+/// Returns the hex digest.
+pub fn write_sha1_sidecar(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    let hash = file_sha1(path)?;
+    let name = file_name(path)?;
+    std::fs::write(sidecar_path(path), format_line(&hash, &name))?;
+    Ok(hash)
+}
+
+/// Hash every `.pkg` in `dir` and write a `SHA1SUMS` file listing them
+///
+/// Entries are sorted by file name. Returns the path of the written file.
+pub fn write_sha1sums(dir: impl AsRef<Path>) -> Result<PathBuf> {
+    let dir = dir.as_ref();
+    let mut names = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_pkg = path
+            .extension()
+            .map(|e| e.eq_ignore_ascii_case("pkg"))
+            .unwrap_or(false);
+        if is_pkg && path.is_file() {
+            names.push(file_name(&path)?);
+        }
+    }
+    names.sort();
+
+    let mut text = String::new();
+    for name in names {
+        text.push_str(&format_line(&file_sha1(dir.join(&name))?, &name));
+    }
+    let out = dir.join(SHA1SUMS);
+    std::fs::write(&out, text)?;
+    Ok(out)
+}
+
+/// Parse `sha1sum` output into `(hash, file name)` pairs
+///
+/// Both text (`hash  name`) and binary (`hash *name`) markers are accepted;
+/// hashes are lower-cased. Malformed lines are skipped.
+pub fn parse_sha1sums(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let (hash, rest) = line.trim_end().split_once(' ')?;
+            let name = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+            let valid = hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit());
+            (valid && !name.is_empty()).then(|| (hash.to_lowercase(), name.to_string()))
+        })
+        .collect()
+}
+
+fn format_line(hash: &str, name: &str) -> String {
+    format!("{}  {}\n", hash, name)
+}
+
+fn file_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.to_string())
+        .ok_or_else(|| PS3UpdateError::Download(format!("Invalid file name: {}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sha1sums() {
+        let text = "da39a3ee5e6b4b0d3255bfef95601890afd80709  a.pkg\n\
+                    DA39A3EE5E6B4B0D3255BFEF95601890AFD80709 *b c.pkg\n\
+                    garbage\n";
+        assert_eq!(
+            parse_sha1sums(text),
+            vec![
+                (
+                    "da39a3ee5e6b4b0d3255bfef95601890afd80709".to_string(),
+                    "a.pkg".to_string()
+                ),
+                (
+                    "da39a3ee5e6b4b0d3255bfef95601890afd80709".to_string(),
+                    "b c.pkg".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_sidecars() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join("b.pkg"), b"").unwrap();
+        std::fs::write(dir.join("a.pkg"), b"abc").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"ignored").unwrap();
+
+        let hash = write_sha1_sidecar(dir.join("b.pkg")).unwrap();
+        assert_eq!(hash, "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(
+            std::fs::read_to_string(dir.join("b.pkg.sha1")).unwrap(),
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709  b.pkg\n"
+        );
+
+        let sums = write_sha1sums(dir).unwrap();
+        let entries = parse_sha1sums(&std::fs::read_to_string(sums).unwrap());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0],
+            (
+                "a9993e364706816aba3e25717850c26c9cd0d89d".to_string(),
+                "a.pkg".to_string()
+            )
+        );
+    }
+}
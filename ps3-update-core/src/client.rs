@@ -0,0 +1,132 @@
+use crate::types::{PS3UpdateError, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
+
+/// User-Agent sent by a retail PS3 (firmware 4.90) when checking for game updates
+pub const PS3_USER_AGENT: &str = "PS3Application libhttp/4.90-000 (CellOS)";
+
+/// HTTP client tuning shared by the fetcher and downloader
+///
+/// The defaults match reqwest's own; [`ClientOptions::high_volume`] is tuned for
+/// scans of thousands of titles where connection reuse matters.
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// Maximum idle connections kept per host
+    pub pool_max_idle_per_host: usize,
+    /// How long idle connections are kept before closing (`None` keeps them forever)
+    pub pool_idle_timeout: Option<Duration>,
+    /// TCP keep-alive interval for open connections
+    pub tcp_keepalive: Option<Duration>,
+    /// Speak HTTP/2 without negotiation
+    pub http2_prior_knowledge: bool,
+    /// Let HTTP/2 connections size their flow-control window adaptively
+    pub http2_adaptive_window: bool,
+    /// User-Agent header (`None` sends no User-Agent, like reqwest's default)
+    pub user_agent: Option<String>,
+    /// Extra headers sent with every request
+    pub headers: Vec<(String, String)>,
+    /// Ask for gzip-compressed responses and decode them transparently
+    ///
+    /// The download manager never does, whatever this says: packages are
+    /// fetched as-is.
+    pub gzip: bool,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: usize::MAX,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            tcp_keepalive: None,
+            http2_prior_knowledge: false,
+            http2_adaptive_window: false,
+            user_agent: None,
+            headers: vec![],
+            gzip: true,
+        }
+    }
+}
+
+impl ClientOptions {
+    /// Preset for library-wide scans: a bounded warm pool with keep-alive
+    pub fn high_volume() -> Self {
+        Self {
+            pool_max_idle_per_host: 32,
+            pool_idle_timeout: Some(Duration::from_secs(120)),
+            tcp_keepalive: Some(Duration::from_secs(30)),
+            http2_prior_knowledge: false,
+            http2_adaptive_window: true,
+            ..Self::default()
+        }
+    }
+
+    /// Preset that sends the same User-Agent and headers as a real PS3
+    ///
+    /// Some CDN edges serve different (or no) content to generic clients.
+    /// The console never asks for compressed responses, so neither does this preset.
+    pub fn ps3_console() -> Self {
+        Self::default().with_ps3_headers()
+    }
+
+    /// Apply the PS3 console User-Agent and header set on top of these options
+    pub fn with_ps3_headers(mut self) -> Self {
+        self.user_agent = Some(PS3_USER_AGENT.to_string());
+        self.headers = vec![
+            ("Accept".into(), "*/*".into()),
+            ("Connection".into(), "Keep-Alive".into()),
+        ];
+        self.gzip = false;
+        self.http2_prior_knowledge = false;
+        self
+    }
+}
+
+/// Client builder with the settings common to every Sony request
+pub(crate) fn client_builder(options: &ClientOptions) -> Result<reqwest::ClientBuilder> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &options.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| PS3UpdateError::InvalidHeader(format!("{}: {}", name, e)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| PS3UpdateError::InvalidHeader(format!("{}: {}", name, e)))?;
+        headers.insert(name, value);
+    }
+
+    let mut builder = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .pool_max_idle_per_host(options.pool_max_idle_per_host)
+        .pool_idle_timeout(options.pool_idle_timeout)
+        .tcp_keepalive(options.tcp_keepalive)
+        .http2_adaptive_window(options.http2_adaptive_window)
+        .gzip(options.gzip)
+        .default_headers(headers);
+    if let Some(ua) = &options.user_agent {
+        builder = builder.user_agent(ua.clone());
+    }
+    if options.http2_prior_knowledge {
+        builder = builder.http2_prior_knowledge();
+    }
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ps3_console_preset() {
+        let options = ClientOptions::ps3_console();
+        assert_eq!(options.user_agent.as_deref(), Some(PS3_USER_AGENT));
+        assert!(!options.gzip);
+        assert!(client_builder(&options).unwrap().build().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let options = ClientOptions {
+            headers: vec![("Bad Header".into(), "x".into())],
+            ..ClientOptions::default()
+        };
+        assert!(client_builder(&options).is_err());
+    }
+}
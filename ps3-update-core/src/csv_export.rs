@@ -0,0 +1,242 @@
+use crate::library::{FileStatus, LibraryReport};
+use crate::outdated::OutdatedReport;
+use crate::types::{FetchResult, Result};
+use crate::utils::format_size;
+use serde::Serialize;
+use std::io::Write;
+
+/// One package of a fetch; titles without packages get a single empty row
+#[derive(Serialize)]
+struct FetchRow<'a> {
+    title_id: &'a str,
+    game_title: &'a str,
+    version: &'a str,
+    system_ver: &'a str,
+    size_bytes: Option<u64>,
+    size_human: &'a str,
+    sha1: &'a str,
+    filename: &'a str,
+    url: &'a str,
+    error: &'a str,
+}
+
+#[derive(Serialize)]
+struct OutdatedRow<'a> {
+    title_id: &'a str,
+    title: &'a str,
+    installed_version: &'a str,
+    latest_version: &'a str,
+    outdated: bool,
+    /// Missing versions in install order, separated by spaces
+    missing_versions: String,
+    packages: usize,
+    total_bytes: u64,
+    total_human: String,
+    error: &'a str,
+}
+
+#[derive(Serialize)]
+struct VerifyRow<'a> {
+    path: String,
+    status: &'static str,
+    expected_sha1: &'a str,
+    actual_sha1: &'a str,
+    detail: &'a str,
+}
+
+/// Write fetch results as CSV, one row per package
+///
+/// Columns: `title_id`, `game_title`, `version`, `system_ver`, `size_bytes`,
+/// `size_human`, `sha1`, `filename`, `url`, `error`. A title without
+/// updates, or whose fetch failed, still gets a row, with the package
+/// columns empty.
+pub fn write_fetch_csv(results: &[FetchResult], out: impl Write) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(out);
+    for result in results {
+        let error = result.error.as_deref().unwrap_or_default();
+        let empty = FetchRow {
+            title_id: &result.cleaned_title_id,
+            game_title: &result.game_title,
+            version: "",
+            system_ver: "",
+            size_bytes: None,
+            size_human: "",
+            sha1: "",
+            filename: "",
+            url: "",
+            error,
+        };
+        if result.results.is_empty() {
+            writer.serialize(empty)?;
+            continue;
+        }
+        for pkg in &result.results {
+            writer.serialize(FetchRow {
+                version: &pkg.version,
+                system_ver: &pkg.system_ver,
+                size_bytes: Some(pkg.size_bytes),
+                size_human: &pkg.size_human,
+                sha1: &pkg.sha1,
+                filename: &pkg.filename,
+                url: &pkg.url,
+                ..empty
+            })?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+impl OutdatedReport {
+    /// Write the report as CSV, one row per title
+    ///
+    /// Columns: `title_id`, `title`, `installed_version`, `latest_version`,
+    /// `outdated`, `missing_versions` (space-separated, in install order),
+    /// `packages`, `total_bytes`, `total_human`, `error`.
+    pub fn write_csv(&self, out: impl Write) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(out);
+        for title in &self.titles {
+            writer.serialize(OutdatedRow {
+                title_id: &title.title_id,
+                title: &title.title,
+                installed_version: title.installed_version.as_deref().unwrap_or_default(),
+                latest_version: title.latest_version.as_deref().unwrap_or_default(),
+                outdated: title.is_outdated(),
+                missing_versions: title
+                    .packages
+                    .iter()
+                    .map(|p| p.version.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                packages: title.packages.len(),
+                total_bytes: title.total_bytes,
+                total_human: format_size(title.total_bytes),
+                error: title.error.as_deref().unwrap_or_default(),
+            })?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl LibraryReport {
+    /// Write the report as CSV, one row per file
+    ///
+    /// Columns: `path`, `status` (`ok`, `corrupt`, `missing` or `unknown`),
+    /// `expected_sha1`, `actual_sha1`, `detail`.
+    pub fn write_csv(&self, out: impl Write) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(out);
+        for file in &self.files {
+            writer.serialize(VerifyRow {
+                path: file.path.display().to_string(),
+                status: match file.status {
+                    FileStatus::Ok => "ok",
+                    FileStatus::Corrupt => "corrupt",
+                    FileStatus::Missing => "missing",
+                    FileStatus::Unknown => "unknown",
+                },
+                expected_sha1: file.expected_sha1.as_deref().unwrap_or_default(),
+                actual_sha1: file.actual_sha1.as_deref().unwrap_or_default(),
+                detail: file.detail.as_deref().unwrap_or_default(),
+            })?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::FileCheck;
+    use crate::outdated::OutdatedTitle;
+    use crate::types::PackageInfo;
+    use std::path::PathBuf;
+
+    fn package(version: &str, size_bytes: u64) -> PackageInfo {
+        PackageInfo {
+            version: version.into(),
+            system_ver: "3.55".into(),
+            size_bytes,
+            size_human: format_size(size_bytes),
+            url: format!("http://example/{}.pkg", version),
+            sha1: "abc".into(),
+            filename: format!("{}.pkg", version),
+        }
+    }
+
+    fn csv_string(write: impl FnOnce(&mut Vec<u8>) -> Result<()>) -> String {
+        let mut out = vec![];
+        write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_fetch_csv() {
+        let results = [
+            FetchResult {
+                results: vec![package("01.02", 2048), package("01.01", 1024)],
+                error: None,
+                game_title: "Demon's Souls, EU".into(),
+                cleaned_title_id: "BLES00779".into(),
+            },
+            FetchResult {
+                results: vec![],
+                error: Some("no updates".into()),
+                game_title: String::new(),
+                cleaned_title_id: "BLUS12345".into(),
+            },
+        ];
+        let text = csv_string(|out| write_fetch_csv(&results, out));
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            "title_id,game_title,version,system_ver,size_bytes,size_human,sha1,filename,url,error"
+        );
+        assert_eq!(
+            lines[1],
+            "BLES00779,\"Demon's Souls, EU\",01.02,3.55,2048,2.00 KB,abc,01.02.pkg,http://example/01.02.pkg,"
+        );
+        assert_eq!(lines[3], "BLUS12345,,,,,,,,,no updates");
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn test_outdated_csv() {
+        let report = OutdatedReport {
+            titles: vec![OutdatedTitle {
+                title_id: "BLES00779".into(),
+                title: "Demon's Souls".into(),
+                installed_version: Some("01.00".into()),
+                latest_version: Some("01.04".into()),
+                packages: vec![package("01.03", 1024), package("01.04", 1024)],
+                total_bytes: 2048,
+                error: None,
+            }],
+        };
+        let text = csv_string(|out| report.write_csv(out));
+        assert_eq!(
+            text,
+            "title_id,title,installed_version,latest_version,outdated,missing_versions,packages,total_bytes,total_human,error\n\
+             BLES00779,Demon's Souls,01.00,01.04,true,01.03 01.04,2,2048,2.00 KB,\n"
+        );
+    }
+
+    #[test]
+    fn test_verify_csv() {
+        let report = LibraryReport {
+            files: vec![FileCheck {
+                path: PathBuf::from("BLES00779/a.pkg"),
+                status: FileStatus::Corrupt,
+                expected_sha1: Some("aa".into()),
+                actual_sha1: Some("bb".into()),
+                detail: None,
+            }],
+        };
+        let text = csv_string(|out| report.write_csv(out));
+        assert_eq!(
+            text,
+            "path,status,expected_sha1,actual_sha1,detail\nBLES00779/a.pkg,corrupt,aa,bb,\n"
+        );
+    }
+}
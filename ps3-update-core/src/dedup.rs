@@ -0,0 +1,138 @@
+use crate::library::collect_pkgs;
+use crate::types::Result;
+use crate::verify::file_sha1;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Byte-identical packages; the first file is the one kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub sha1: String,
+    pub size_bytes: u64,
+    pub files: Vec<PathBuf>,
+}
+
+impl DuplicateGroup {
+    /// Space freed by keeping only one copy
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.size_bytes * (self.files.len() as u64 - 1)
+    }
+}
+
+/// Result of a dedup pass
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DedupReport {
+    pub groups: Vec<DuplicateGroup>,
+    /// Total space held by redundant copies
+    pub reclaimable_bytes: u64,
+    /// Files replaced by hard links (always 0 for a dry run)
+    pub linked: usize,
+}
+
+/// Find byte-identical PKGs under `root` without changing anything
+///
+/// Only files of equal size are hashed. Files that are already hard links to
+/// each other are not reported.
+pub fn find_duplicates(root: impl AsRef<Path>) -> Result<DedupReport> {
+    let mut by_size: BTreeMap<u64, Vec<PathBuf>> = BTreeMap::new();
+    for path in collect_pkgs(root.as_ref())? {
+        let size = std::fs::metadata(&path)?.len();
+        by_size.entry(size).or_default().push(path);
+    }
+
+    let mut report = DedupReport::default();
+    for (size, paths) in by_size.into_iter().filter(|(_, p)| p.len() > 1) {
+        let mut by_hash: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        for path in paths {
+            let is_link = by_hash
+                .values()
+                .flatten()
+                .any(|seen| same_file(seen, &path));
+            if !is_link {
+                by_hash.entry(file_sha1(&path)?).or_default().push(path);
+            }
+        }
+        for (sha1, files) in by_hash.into_iter().filter(|(_, f)| f.len() > 1) {
+            let group = DuplicateGroup {
+                sha1,
+                size_bytes: size,
+                files,
+            };
+            report.reclaimable_bytes += group.reclaimable_bytes();
+            report.groups.push(group);
+        }
+    }
+    Ok(report)
+}
+
+/// Replace duplicate PKGs under `root` with hard links to a single copy
+///
+/// Each duplicate is swapped atomically via a temporary link, so an
+/// interrupted run never leaves a file missing.
+pub fn dedup_library(root: impl AsRef<Path>) -> Result<DedupReport> {
+    let mut report = find_duplicates(root)?;
+    for group in &report.groups {
+        let (keep, dups) = group.files.split_first().expect("groups have 2+ files");
+        for dup in dups {
+            let mut tmp = dup.as_os_str().to_owned();
+            tmp.push(".dedup");
+            let tmp = PathBuf::from(tmp);
+            std::fs::hard_link(keep, &tmp)?;
+            if let Err(e) = std::fs::rename(&tmp, dup) {
+                let _ = std::fs::remove_file(&tmp);
+                return Err(e.into());
+            }
+            report.linked += 1;
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &Path, _b: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup_library() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("EU")).unwrap();
+        std::fs::create_dir_all(root.join("US")).unwrap();
+        std::fs::write(root.join("EU/a.pkg"), b"same bytes").unwrap();
+        std::fs::write(root.join("US/a.pkg"), b"same bytes").unwrap();
+        std::fs::write(root.join("US/b.pkg"), b"diff bytes").unwrap();
+        std::fs::write(root.join("US/c.pkg"), b"unique").unwrap();
+
+        let report = find_duplicates(root).unwrap();
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(
+            report.groups[0].files,
+            vec![root.join("EU/a.pkg"), root.join("US/a.pkg")]
+        );
+        assert_eq!(report.reclaimable_bytes, 10);
+        assert_eq!(report.linked, 0);
+
+        let report = dedup_library(root).unwrap();
+        assert_eq!(report.linked, 1);
+        assert_eq!(std::fs::read(root.join("US/a.pkg")).unwrap(), b"same bytes");
+
+        // Already linked: nothing left to report on unix
+        #[cfg(unix)]
+        assert!(find_duplicates(root).unwrap().groups.is_empty());
+    }
+}
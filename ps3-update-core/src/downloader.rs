@@ -1,48 +1,530 @@
-use crate::types::{DownloadMode, PS3UpdateError, ProgressInfo, Result};
-use crate::utils::format_size;
+#[cfg(feature = "catalog")]
+use crate::catalog::{Catalog, DownloadRecord};
+use crate::checksums::write_sha1_sidecar;
+use crate::client::{client_builder, ClientOptions};
+use crate::manifest::{record_download, ManifestEntry};
+use crate::naming::{rename_in_place, NamingContext, NamingScheme};
+use crate::pkg::{check_package, PkgHeader};
+use crate::retention::version_from_filename;
+use crate::title_id::TitleId;
+use crate::types::{
+    DownloadMode, DownloadPhase, PS3UpdateError, PackageInfo, ProgressInfo, Result,
+};
+use crate::utils::{format_size, NameTemplate};
+use crate::verify::verify_download;
 use futures_util::StreamExt;
+use reqwest::StatusCode;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncSeekExt, AsyncWriteExt};
 
-/// Internal state for a download job
+/// Internal state for a download (or upload) job
 #[derive(Debug, Clone)]
-struct JobState {
-    filename: String,
-    total: u64,
-    downloaded: u64,
-    start: Instant,
-    done: bool,
-    error: Option<String>,
+pub(crate) struct JobState {
+    pub(crate) filename: String,
+    pub(crate) total: u64,
+    pub(crate) downloaded: u64,
+    pub(crate) start: Instant,
+    pub(crate) done: bool,
+    pub(crate) error: Option<String>,
+    pub(crate) error_code: Option<&'static str>,
+    pub(crate) source: Option<String>,
+    pub(crate) verifying: bool,
+    pub(crate) verified: Option<bool>,
+    pub(crate) already_downloaded: bool,
+    /// Rate limit the transfer obeys, adjustable while it runs
+    pub(crate) throttle: Option<Arc<Throttle>>,
+    /// When the job finished, so its elapsed time and speed stop changing
+    pub(crate) finished: Option<Instant>,
+    pub(crate) notes: Vec<String>,
+    pub(crate) attempts: u32,
+    /// The task running the job, aborted when the job is removed
+    pub(crate) task: Option<tokio::task::AbortHandle>,
+}
+
+impl JobState {
+    pub(crate) fn new(filename: impl Into<String>) -> Self {
+        Self {
+            filename: filename.into(),
+            total: 0,
+            downloaded: 0,
+            start: Instant::now(),
+            done: false,
+            error: None,
+            error_code: None,
+            source: None,
+            verifying: false,
+            verified: None,
+            already_downloaded: false,
+            throttle: None,
+            finished: None,
+            notes: vec![],
+            attempts: 1,
+            task: None,
+        }
+    }
+
+    /// End the job with `error`
+    pub(crate) fn fail(&mut self, error: &PS3UpdateError) {
+        self.error = Some(error.to_string());
+        self.error_code = Some(error.code());
+    }
+
+    /// Snapshot of the job for callers
+    pub(crate) fn progress(&self) -> ProgressInfo {
+        let total = self.total;
+        let downloaded = self.downloaded;
+        let percent = if total > 0 {
+            (downloaded as f64 / total as f64) * 100.0
+        } else {
+            0.0
+        };
+
+        let paused = self.throttle.as_ref().is_some_and(|t| t.is_paused());
+        let elapsed = self
+            .finished
+            .unwrap_or_else(Instant::now)
+            .duration_since(self.start)
+            .as_secs_f64()
+            .max(0.001);
+        let speed = if paused {
+            0.0
+        } else {
+            downloaded as f64 / elapsed
+        };
+        let speed_human = if speed > 0.0 {
+            format!("{}/s", format_size(speed as u64))
+        } else {
+            "0 B/s".to_string()
+        };
+
+        let phase = if self.done {
+            DownloadPhase::Done
+        } else if self.verifying {
+            DownloadPhase::Verifying
+        } else if self.source.is_none() {
+            DownloadPhase::Connecting
+        } else {
+            DownloadPhase::Downloading
+        };
+
+        ProgressInfo {
+            filename: Some(self.filename.clone()),
+            total,
+            downloaded,
+            remaining: total.saturating_sub(downloaded),
+            percent,
+            speed_bytes_per_sec: speed,
+            speed_human,
+            done: self.done,
+            error: self.error.clone(),
+            error_code: self.error_code.map(str::to_string),
+            source: self.source.clone(),
+            verified: self.verified,
+            already_downloaded: self.already_downloaded,
+            rate_limit: self.throttle.as_ref().and_then(|t| t.limit()),
+            paused,
+            elapsed_secs: elapsed,
+            notes: self.notes.clone(),
+            phase,
+            attempts: self.attempts,
+        }
+    }
+}
+
+/// An archive mirror tried when Sony no longer serves a package
+///
+/// The URL template may use `{path}` (the original URL path without the leading
+/// slash), `{filename}` and `{title_id}`, e.g.
+/// `https://archive.org/download/my-collection/{filename}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveMirror {
+    pub name: String,
+    pub url_template: String,
+}
+
+impl ArchiveMirror {
+    pub fn new(name: impl Into<String>, url_template: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            url_template: url_template.into(),
+        }
+    }
+
+    /// Mirror URL for an original package URL
+    pub fn url_for(&self, original_url: &str) -> String {
+        let path = reqwest::Url::parse(original_url)
+            .map(|u| u.path().trim_start_matches('/').to_string())
+            .unwrap_or_default();
+        let filename = path.rsplit('/').next().unwrap_or_default().to_string();
+        let title_id = path
+            .split('/')
+            .find_map(|segment| TitleId::parse(segment).ok())
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+
+        self.url_template
+            .replace("{path}", &path)
+            .replace("{filename}", &filename)
+            .replace("{title_id}", &title_id)
+    }
+}
+
+/// Per-job download settings
+#[derive(Debug, Clone, Default)]
+pub struct DownloadOptions {
+    pub mode: DownloadMode,
+    /// Fail the job if the downloaded PKG's content ID belongs to another title,
+    /// or its PARAM.SFO gives another version than the manifest entry's package
+    pub expected_title_id: Option<String>,
+    /// Write a `sha1sum`-compatible `.sha1` file next to the finished download
+    pub write_sha1_sidecar: bool,
+    /// Record the finished download in the destination folder's `manifest.json`
+    pub manifest: Option<ManifestEntry>,
+    /// Rename the file once downloaded; sidecars and the manifest use the new name
+    pub naming: NamingScheme,
+    /// Rename the file by this template instead of `naming`
+    pub name_template: Option<NameTemplate>,
+    /// Cap the transfer speed in bytes per second, shared by all parts of the job
+    pub rate_limit: Option<u64>,
+    /// Continue a partial file left by an earlier attempt instead of starting over
+    pub resume: bool,
+    /// Extra attempts after a network or server error
+    pub retries: u32,
+    /// Check the finished PKG against its digest block and the listed SHA1
+    pub verify: bool,
+    /// Don't download if the destination already holds a file of the right size
+    /// (and SHA1, when the manifest's package lists one)
+    pub skip_existing: bool,
+}
+
+impl DownloadOptions {
+    pub fn new(mode: DownloadMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// Check the downloaded package against this title ID
+    pub fn expect_title(mut self, title_id: impl Into<String>) -> Self {
+        self.expected_title_id = Some(title_id.into());
+        self
+    }
+
+    /// Write a `.sha1` sidecar once the download completes
+    pub fn with_sha1_sidecar(mut self) -> Self {
+        self.write_sha1_sidecar = true;
+        self
+    }
+
+    /// Record the package in the destination folder's `manifest.json` once downloaded
+    pub fn with_manifest(
+        mut self,
+        title_id: impl Into<String>,
+        game_name: impl Into<String>,
+        package: PackageInfo,
+    ) -> Self {
+        self.manifest = Some(ManifestEntry {
+            title_id: title_id.into(),
+            game_name: game_name.into(),
+            package,
+        });
+        self
+    }
+
+    /// Rename the finished download according to `scheme`
+    ///
+    /// The game name and version come from [`DownloadOptions::with_manifest`]
+    /// when set; otherwise the version is read from Sony's file name.
+    pub fn with_naming(mut self, scheme: NamingScheme) -> Self {
+        self.naming = scheme;
+        self
+    }
+
+    /// Rename the finished download by a template parsed with
+    /// [`NamingContext::PLACEHOLDERS`], e.g. `{title_id} {title} v{version}.pkg`
+    ///
+    /// Takes the place of [`DownloadOptions::with_naming`]'s scheme.
+    pub fn with_name_template(mut self, template: NameTemplate) -> Self {
+        self.name_template = Some(template);
+        self
+    }
+
+    /// Limit the download to `bytes_per_sec`
+    pub fn with_rate_limit(mut self, bytes_per_sec: u64) -> Self {
+        self.rate_limit = Some(bytes_per_sec).filter(|&n| n > 0);
+        self
+    }
+
+    /// Resume a partial download with a range request
+    ///
+    /// Resuming always uses a single connection; multi-part mode applies only
+    /// when there is nothing to resume. The partial file must have been written
+    /// front to back; a failed multi-part attempt deletes its file instead.
+    pub fn with_resume(mut self) -> Self {
+        self.resume = true;
+        self
+    }
+
+    /// Retry failed transfers up to `retries` times, waiting longer each time
+    ///
+    /// Missing packages (404/410) and failed checks are not retried.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Fail the job with [`PS3UpdateError::Verification`] if the PKG is corrupt
+    ///
+    /// The SHA1 from [`DownloadOptions::with_manifest`]'s package is checked
+    /// too when it is known.
+    pub fn with_verification(mut self) -> Self {
+        self.verify = true;
+        self
+    }
+
+    /// Keep an existing destination file whose size matches the server's
+    ///
+    /// When [`DownloadOptions::with_manifest`]'s package lists a SHA1, the file
+    /// must also verify against it; a corrupt copy is downloaded again. A kept
+    /// file is reported as [`ProgressInfo::already_downloaded`], and the job
+    /// still runs its checks, renaming and manifest update on it.
+    pub fn with_skip_existing(mut self) -> Self {
+        self.skip_existing = true;
+        self
+    }
+}
+
+/// Settings shared by every request a job makes
+struct Transfer {
+    throttle: Arc<Throttle>,
+    /// The manager's, shared with every other job
+    global: Arc<Throttle>,
+    resume: bool,
+    /// From the manifest, to tell a finished copy from a stale one
+    known_size: Option<u64>,
+    known_sha1: Option<String>,
+}
+
+impl Transfer {
+    /// Count `n` more bytes against the job's limit and then the manager's
+    async fn consume(&self, n: u64) {
+        self.throttle.consume(n).await;
+        self.global.consume(n).await;
+    }
+}
+
+/// Spreads a job's bytes over time to stay under a rate limit
+///
+/// The limit can change mid-transfer; 0 means unlimited. A paused throttle
+/// holds every chunk until it is resumed.
+#[derive(Debug)]
+pub(crate) struct Throttle {
+    bytes_per_sec: AtomicU64,
+    paused: AtomicBool,
+    state: Mutex<(Instant, u64)>,
+}
+
+// How often a paused transfer checks whether it was resumed
+const PAUSE_POLL: Duration = Duration::from_millis(200);
+
+impl Throttle {
+    fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: AtomicU64::new(bytes_per_sec),
+            paused: AtomicBool::new(false),
+            state: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Pause or resume, counting the limit from now so the pause doesn't skew it
+    fn set_paused(&self, paused: bool) {
+        let mut state = self.state.lock().unwrap();
+        self.paused.store(paused, Ordering::Relaxed);
+        *state = (Instant::now(), 0);
+    }
+
+    fn limit(&self) -> Option<u64> {
+        Some(self.bytes_per_sec.load(Ordering::Relaxed)).filter(|&n| n > 0)
+    }
+
+    /// Change the limit, counting from now so earlier bytes don't skew it
+    fn set_limit(&self, bytes_per_sec: u64) {
+        let mut state = self.state.lock().unwrap();
+        self.bytes_per_sec.store(bytes_per_sec, Ordering::Relaxed);
+        *state = (Instant::now(), 0);
+    }
+
+    /// Count `n` more bytes and wait until they fit under the limit
+    async fn consume(&self, n: u64) {
+        while self.is_paused() {
+            tokio::time::sleep(PAUSE_POLL).await;
+        }
+        let delay = {
+            let mut state = self.state.lock().unwrap();
+            let Some(bytes_per_sec) = self.limit() else {
+                return;
+            };
+            state.1 += n;
+            throttle_delay(state.1, state.0.elapsed(), bytes_per_sec)
+        };
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// How long to wait so `bytes` sent in `elapsed` averages `bytes_per_sec`
+fn throttle_delay(bytes: u64, elapsed: Duration, bytes_per_sec: u64) -> Duration {
+    Duration::from_secs_f64(bytes as f64 / bytes_per_sec as f64).saturating_sub(elapsed)
+}
+
+// Record something the user may want to know about how a job went
+fn add_note(jobs: &Mutex<HashMap<String, JobState>>, job_id: &str, note: String) {
+    if let Some(job) = jobs.lock().unwrap().get_mut(job_id) {
+        job.notes.push(note);
+    }
+}
+
+// Count another try at the file; it's connecting again until a server answers
+fn new_attempt(jobs: &Mutex<HashMap<String, JobState>>, job_id: &str) {
+    if let Some(job) = jobs.lock().unwrap().get_mut(job_id) {
+        job.attempts += 1;
+        job.source = None;
+    }
+}
+
+/// Wait before retry number `attempt` (1-based): 1s, 2s, 4s, … up to 30s
+fn retry_delay(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt.saturating_sub(1).min(5)).min(Duration::from_secs(30))
+}
+
+/// The `Content-Length` a HEAD response announces
+///
+/// reqwest's `content_length` reports the empty body actually sent instead.
+fn head_length(resp: &reqwest::Response) -> Option<u64> {
+    resp.headers()
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+}
+
+/// The full length from a 416's `Content-Range: bytes */<len>`
+fn range_total(resp: &reqwest::Response) -> Option<u64> {
+    resp.headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes */")?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn is_retryable(e: &PS3UpdateError) -> bool {
+    matches!(
+        e,
+        PS3UpdateError::Network(_)
+            | PS3UpdateError::Download(_)
+            | PS3UpdateError::HttpStatus { .. }
+            | PS3UpdateError::FileSystem(_)
+    )
 }
 
+/// Decides whether a package URL is known to have existed (e.g. from the catalog)
+pub type PackageFilter = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
 /// Download manager for PS3 update packages
+///
+/// Clones share their jobs and limits.
+#[derive(Clone)]
 pub struct DownloadManager {
     client: reqwest::Client,
     jobs: Arc<Mutex<HashMap<String, JobState>>>,
+    mirrors: Arc<Vec<ArchiveMirror>>,
+    mirror_filter: Option<PackageFilter>,
+    /// Limit on all jobs together, on top of each job's own
+    global: Arc<Throttle>,
+    #[cfg(feature = "catalog")]
+    catalog: Option<Arc<Catalog>>,
 }
 
 impl DownloadManager {
     /// Create a new DownloadManager
     pub fn new() -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .danger_accept_invalid_certs(true)
-            .build()?;
+        Self::with_client_options(&ClientOptions::default())
+    }
+
+    /// Create a new DownloadManager with tuned connection settings
+    pub fn with_client_options(options: &ClientOptions) -> Result<Self> {
+        // Packages are fetched as-is: decoding would drop the Content-Length
+        // that progress, size checks and multi-part ranges rely on
+        let client = client_builder(options)?.gzip(false).build()?;
 
         Ok(Self {
             client,
             jobs: Arc::new(Mutex::new(HashMap::new())),
+            mirrors: Arc::new(vec![]),
+            mirror_filter: None,
+            global: Arc::new(Throttle::new(0)),
+            #[cfg(feature = "catalog")]
+            catalog: None,
         })
     }
 
+    /// Retry against archive mirrors, in order, when the server returns 404/410
+    pub fn with_archive_mirrors(mut self, mirrors: Vec<ArchiveMirror>) -> Self {
+        self.mirrors = Arc::new(mirrors);
+        self
+    }
+
+    /// Only fall back to mirrors for URLs this filter accepts
+    ///
+    /// With the `catalog` feature, pass a closure over [`crate::Catalog::contains_url`]
+    /// so only packages the catalog says existed are looked up in archives.
+    pub fn with_mirror_filter(
+        mut self,
+        filter: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.mirror_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Log every job that carries a manifest entry in the catalog's
+    /// download history once it finishes or fails
+    ///
+    /// Jobs that found an intact copy already on disk aren't logged.
+    #[cfg(feature = "catalog")]
+    pub fn with_catalog(mut self, catalog: Arc<Catalog>) -> Self {
+        self.catalog = Some(catalog);
+        self
+    }
+
     /// Start a download job and return a job ID for tracking
     pub async fn start_download(
         &self,
         url: &str,
         dest_path: PathBuf,
         mode: DownloadMode,
+    ) -> Result<String> {
+        self.start_download_with(url, dest_path, DownloadOptions::new(mode))
+            .await
+    }
+
+    /// Start a download job with per-job options and return a job ID for tracking
+    pub async fn start_download_with(
+        &self,
+        url: &str,
+        dest_path: PathBuf,
+        options: DownloadOptions,
     ) -> Result<String> {
         let filename = dest_path
             .file_name()
@@ -56,61 +538,232 @@ impl DownloadManager {
         }
 
         let job_id = format!("{:x}", rand::random::<u64>());
+        let throttle = Arc::new(Throttle::new(options.rate_limit.unwrap_or(0)));
 
         {
+            let mut job = JobState::new(filename.clone());
+            job.throttle = Some(throttle.clone());
             let mut jobs = self.jobs.lock().unwrap();
-            jobs.insert(
-                job_id.clone(),
-                JobState {
-                    filename: filename.clone(),
-                    total: 0,
-                    downloaded: 0,
-                    start: Instant::now(),
-                    done: false,
-                    error: None,
-                },
-            );
+            jobs.insert(job_id.clone(), job);
         }
 
         let url = url.to_string();
         let client = self.client.clone();
         let jobs = self.jobs.clone();
         let job_id_clone = job_id.clone();
+        let mirrors = self.mirrors.clone();
+        let use_mirrors =
+            !mirrors.is_empty() && self.mirror_filter.as_ref().map(|f| f(&url)).unwrap_or(true);
+        #[cfg(feature = "catalog")]
+        let catalog = self.catalog.clone();
+
+        let transfer = Transfer {
+            throttle,
+            global: self.global.clone(),
+            resume: options.resume,
+            known_size: options
+                .manifest
+                .as_ref()
+                .map(|e| e.package.size_bytes)
+                .filter(|&n| n > 0),
+            known_sha1: options
+                .manifest
+                .as_ref()
+                .map(|e| e.package.sha1.clone())
+                .filter(|sha1| !sha1.trim().is_empty()),
+        };
+
+        let task = tokio::spawn(async move {
+            let existing = options.skip_existing
+                && Self::is_complete(
+                    &client,
+                    &url,
+                    &dest_path,
+                    transfer.known_size,
+                    transfer.known_sha1.clone(),
+                    &jobs,
+                    &job_id_clone,
+                )
+                .await;
 
-        tokio::spawn(async move {
-            let result = match mode {
-                DownloadMode::Direct => {
-                    Self::download_direct(&client, &url, &dest_path, &jobs, &job_id_clone).await
+            let mut attempt = 0;
+            let result = loop {
+                if existing {
+                    break Ok(());
                 }
-                DownloadMode::MultiPart { num_parts } => {
-                    // Try multipart, fallback to direct on any error
-                    let mp_result = Self::download_multipart(
-                        &client,
-                        &url,
-                        &dest_path,
-                        num_parts,
-                        &jobs,
-                        &job_id_clone,
-                    )
-                    .await;
+                let result = Self::transfer(
+                    &client,
+                    &url,
+                    &dest_path,
+                    options.mode,
+                    &transfer,
+                    &jobs,
+                    &job_id_clone,
+                )
+                .await;
+                match result {
+                    Err(e) if attempt < options.retries && is_retryable(&e) => {
+                        attempt += 1;
+                        add_note(&jobs, &job_id_clone, format!("Retried after: {}", e));
+                        tokio::time::sleep(retry_delay(attempt)).await;
+                        new_attempt(&jobs, &job_id_clone);
+                    }
+                    other => break other,
+                }
+            };
+
+            // Delisted content: try the archive mirrors before giving up
+            let result = match result {
+                Err(PS3UpdateError::NotFound(_)) if use_mirrors => Self::download_from_mirrors(
+                    &client,
+                    &url,
+                    &dest_path,
+                    &mirrors,
+                    &transfer,
+                    &jobs,
+                    &job_id_clone,
+                )
+                .await
+                .map_err(|_| PS3UpdateError::NotFound(url.clone())),
+                other => other,
+            };
+
+            // Make sure the server sent the package we asked for
+            let result = match (result, &options.expected_title_id) {
+                (Ok(()), Some(expected)) => check_package(
+                    &dest_path,
+                    expected,
+                    options
+                        .manifest
+                        .as_ref()
+                        .map(|e| e.package.version.as_str()),
+                ),
+                (other, _) => other,
+            };
 
-                    // If multipart fails, try direct download
-                    if mp_result.is_err() {
-                        Self::download_direct(&client, &url, &dest_path, &jobs, &job_id_clone).await
-                    } else {
-                        mp_result
+            let result = match result {
+                Ok(()) if options.verify => {
+                    let path = dest_path.clone();
+                    let sha1 = options
+                        .manifest
+                        .as_ref()
+                        .map(|e| e.package.sha1.clone())
+                        .unwrap_or_default();
+                    if let Some(job) = jobs.lock().unwrap().get_mut(&job_id_clone) {
+                        job.verifying = true;
                     }
+                    let result = tokio::task::spawn_blocking(move || verify_download(&path, &sha1))
+                        .await
+                        .map_err(PS3UpdateError::from)
+                        .and_then(|r| r.map(|_| ()));
+                    if let Some(job) = jobs.lock().unwrap().get_mut(&job_id_clone) {
+                        job.verifying = false;
+                        job.verified = match &result {
+                            Ok(()) => Some(true),
+                            Err(PS3UpdateError::Verification(_)) => Some(false),
+                            Err(_) => None,
+                        };
+                    }
+                    result
                 }
+                other => other,
             };
 
-            if let Err(e) = result {
-                let mut jobs = jobs.lock().unwrap();
-                if let Some(job) = jobs.get_mut(&job_id_clone) {
-                    job.done = true;
-                    job.error = Some(e.to_string());
+            let mut dest_path = dest_path;
+            let mut manifest = options.manifest;
+            let rename =
+                options.naming != NamingScheme::Original || options.name_template.is_some();
+            let result = match result {
+                Ok(()) if rename => Self::apply_naming(
+                    &dest_path,
+                    options.naming,
+                    options.name_template.as_ref(),
+                    options.expected_title_id.as_deref(),
+                    manifest.as_ref(),
+                )
+                .map(|renamed| {
+                    let name = renamed
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or_default()
+                        .to_string();
+                    if let Some(entry) = manifest.as_mut() {
+                        entry.package.filename = name.clone();
+                    }
+                    if let Some(job) = jobs.lock().unwrap().get_mut(&job_id_clone) {
+                        job.filename = name;
+                    }
+                    dest_path = renamed;
+                }),
+                other => other,
+            };
+
+            let result = match result {
+                Ok(()) if options.write_sha1_sidecar => {
+                    let path = dest_path.clone();
+                    tokio::task::spawn_blocking(move || write_sha1_sidecar(path))
+                        .await
+                        .map_err(PS3UpdateError::from)
+                        .and_then(|r| r.map(|_| ()))
+                }
+                other => other,
+            };
+
+            #[cfg(feature = "catalog")]
+            let logged = manifest.clone();
+            let result = match (result, manifest) {
+                (Ok(()), Some(entry)) => {
+                    let source = jobs
+                        .lock()
+                        .unwrap()
+                        .get(&job_id_clone)
+                        .and_then(|job| job.source.clone())
+                        .unwrap_or_else(|| entry.package.url.clone());
+                    let dir = dest_path
+                        .parent()
+                        .map(Path::to_path_buf)
+                        .unwrap_or_default();
+                    record_download(
+                        &dir,
+                        &entry.title_id,
+                        &entry.game_name,
+                        &entry.package,
+                        &source,
+                    )
+                    .map(|_| ())
+                }
+                (other, _) => other,
+            };
+
+            let mut jobs = jobs.lock().unwrap();
+            if let Some(job) = jobs.get_mut(&job_id_clone) {
+                job.done = true;
+                job.finished = Some(Instant::now());
+                if let Err(e) = result {
+                    job.fail(&e);
+                }
+            }
+
+            #[cfg(feature = "catalog")]
+            if let (Some(catalog), Some(entry)) = (catalog, logged) {
+                let Some(job) = jobs.get_mut(&job_id_clone) else {
+                    return;
+                };
+                let progress = job.progress();
+                if !progress.already_downloaded {
+                    let record = DownloadRecord {
+                        path: Some(dest_path.to_string_lossy().to_string()),
+                        ..DownloadRecord::from_progress(&entry, &progress)
+                    };
+                    if let Err(e) = catalog.record_download(&record) {
+                        job.notes.push(format!("Not logged in the catalog: {}", e));
+                    }
                 }
             }
         });
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&job_id) {
+            job.task = Some(task.abort_handle());
+        }
 
         Ok(job_id)
     }
@@ -119,86 +772,354 @@ impl DownloadManager {
     pub fn get_progress(&self, job_id: &str) -> Result<ProgressInfo> {
         let jobs = self.jobs.lock().unwrap();
 
-        if let Some(job) = jobs.get(job_id) {
-            let total = job.total;
-            let downloaded = job.downloaded;
-            let percent = if total > 0 {
-                (downloaded as f64 / total as f64) * 100.0
-            } else {
-                0.0
-            };
+        jobs.get(job_id)
+            .map(JobState::progress)
+            .ok_or_else(|| PS3UpdateError::JobNotFound(job_id.to_string()))
+    }
 
-            let elapsed = job.start.elapsed().as_secs_f64().max(0.001);
-            let speed = downloaded as f64 / elapsed;
-            let speed_human = if speed > 0.0 {
-                format!("{}/s", format_size(speed as u64))
-            } else {
-                "0 B/s".to_string()
-            };
+    /// Progress of every tracked job, keyed by job ID
+    pub fn jobs(&self) -> Vec<(String, ProgressInfo)> {
+        let jobs = self.jobs.lock().unwrap();
+        let mut list: Vec<_> = jobs
+            .iter()
+            .map(|(id, job)| (id.clone(), job.progress(), job.start))
+            .collect();
+        list.sort_by_key(|(_, _, start)| *start);
+        list.into_iter()
+            .map(|(id, progress, _)| (id, progress))
+            .collect()
+    }
 
-            Ok(ProgressInfo {
-                filename: Some(job.filename.clone()),
-                total,
-                downloaded,
-                percent,
-                speed_bytes_per_sec: speed,
-                speed_human,
-                done: job.done,
-                error: job.error.clone(),
-            })
-        } else {
-            Err(PS3UpdateError::JobNotFound(job_id.to_string()))
+    /// Wait until a job is done and return its final progress
+    pub async fn wait_for(&self, job_id: &str) -> Result<ProgressInfo> {
+        loop {
+            let progress = self.get_progress(job_id)?;
+            if progress.done {
+                return Ok(progress);
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+    }
+
+    /// Receive progress for every job, checked each `interval`
+    ///
+    /// Each [`ProgressSubscription::next`] returns only the jobs that moved
+    /// since the previous one, so a UI can redraw without polling job by job.
+    pub fn subscribe(&self, interval: Duration) -> ProgressSubscription {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        ProgressSubscription {
+            jobs: self.jobs.clone(),
+            ticker,
+            seen: HashMap::new(),
         }
     }
 
-    /// Remove a completed job from tracking
+    /// Change a running job's speed limit in bytes per second; 0 lifts it
+    ///
+    /// Takes effect on the next chunk, for every part of a multi-part job.
+    pub fn set_rate_limit(&self, job_id: &str, bytes_per_sec: u64) -> Result<()> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs
+            .get(job_id)
+            .ok_or_else(|| PS3UpdateError::JobNotFound(job_id.to_string()))?;
+        if let Some(throttle) = &job.throttle {
+            throttle.set_limit(bytes_per_sec);
+        }
+        Ok(())
+    }
+
+    /// Limit every job together to `bytes_per_sec`; 0 lifts the limit
+    ///
+    /// Running jobs slow down from their next chunk, and jobs started later
+    /// share the same limit. Each job's own limit still applies on top.
+    pub fn set_global_rate_limit(&self, bytes_per_sec: u64) {
+        self.global.set_limit(bytes_per_sec);
+    }
+
+    /// The limit set by [`set_global_rate_limit`](Self::set_global_rate_limit)
+    pub fn global_rate_limit(&self) -> Option<u64> {
+        self.global.limit()
+    }
+
+    /// Hold a running job after its current chunk until [`resume`](Self::resume)
+    ///
+    /// The connection stays open while paused; if the server drops it, the
+    /// job retries as usual once resumed.
+    pub fn pause(&self, job_id: &str) -> Result<()> {
+        self.set_paused(job_id, true)
+    }
+
+    /// Let a paused job carry on
+    pub fn resume(&self, job_id: &str) -> Result<()> {
+        self.set_paused(job_id, false)
+    }
+
+    fn set_paused(&self, job_id: &str, paused: bool) -> Result<()> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs
+            .get(job_id)
+            .ok_or_else(|| PS3UpdateError::JobNotFound(job_id.to_string()))?;
+        if let Some(throttle) = &job.throttle {
+            throttle.set_paused(paused);
+        }
+        Ok(())
+    }
+
+    /// Stop tracking a job, cancelling its transfer if it is still running
+    ///
+    /// A cancelled job writes nothing more, but leaves its partial file behind.
     pub fn remove_job(&self, job_id: &str) {
-        let mut jobs = self.jobs.lock().unwrap();
-        jobs.remove(job_id);
+        let removed = self.jobs.lock().unwrap().remove(job_id);
+        if let Some(task) = removed.and_then(|job| job.task) {
+            task.abort();
+        }
+    }
+
+    async fn download_from_mirrors(
+        client: &reqwest::Client,
+        url: &str,
+        dest_path: &Path,
+        mirrors: &[ArchiveMirror],
+        transfer: &Transfer,
+        jobs: &Arc<Mutex<HashMap<String, JobState>>>,
+        job_id: &str,
+    ) -> Result<()> {
+        let mut last_err = PS3UpdateError::NotFound(url.to_string());
+        for mirror in mirrors {
+            new_attempt(jobs, job_id);
+            let mirror_url = mirror.url_for(url);
+            match Self::download_direct(client, &mirror_url, dest_path, transfer, jobs, job_id)
+                .await
+            {
+                Ok(()) => {
+                    add_note(
+                        jobs,
+                        job_id,
+                        format!(
+                            "No longer on the update server; downloaded from {}",
+                            mirror.name
+                        ),
+                    );
+                    return Ok(());
+                }
+                Err(e) => last_err = e,
+            }
+        }
+        Err(last_err)
+    }
+
+    /// Fetch the file once in the requested mode
+    async fn transfer(
+        client: &reqwest::Client,
+        url: &str,
+        dest_path: &Path,
+        mode: DownloadMode,
+        transfer: &Transfer,
+        jobs: &Arc<Mutex<HashMap<String, JobState>>>,
+        job_id: &str,
+    ) -> Result<()> {
+        let partial = transfer.resume
+            && tokio::fs::metadata(dest_path)
+                .await
+                .map(|m| m.len() > 0)
+                .unwrap_or(false);
+        let mode = match mode {
+            DownloadMode::Auto if !partial => Self::auto_mode(client, url).await,
+            other => other,
+        };
+        match mode {
+            DownloadMode::MultiPart { num_parts } if !partial => {
+                // Try multipart, fallback to direct on any error
+                let mp_result = Self::download_multipart(
+                    client, url, dest_path, num_parts, transfer, jobs, job_id,
+                )
+                .await;
+
+                // If multipart fails, try direct download
+                if let Err(e) = mp_result {
+                    // Parts land out of order, so what's there can't be resumed
+                    let _ = tokio::fs::remove_file(dest_path).await;
+                    add_note(
+                        jobs,
+                        job_id,
+                        format!(
+                            "Multi-part download failed ({}); used a single connection",
+                            e
+                        ),
+                    );
+                    Self::download_direct(client, url, dest_path, transfer, jobs, job_id).await
+                } else {
+                    mp_result
+                }
+            }
+            _ => Self::download_direct(client, url, dest_path, transfer, jobs, job_id).await,
+        }
+    }
+
+    /// What [`DownloadMode::Auto`] means for `url`: multi-part when the
+    /// server reports a large enough file, a single connection otherwise
+    async fn auto_mode(client: &reqwest::Client, url: &str) -> DownloadMode {
+        let size = match client.head(url).send().await {
+            Ok(resp) if resp.status().is_success() => head_length(&resp).unwrap_or(0),
+            _ => 0,
+        };
+        if size >= DownloadMode::AUTO_MIN_SIZE {
+            DownloadMode::MultiPart {
+                num_parts: DownloadMode::AUTO_PARTS,
+            }
+        } else {
+            DownloadMode::Direct
+        }
+    }
+
+    /// Whether `dest_path` already holds the whole file, intact when its SHA1 is known
+    async fn is_complete(
+        client: &reqwest::Client,
+        url: &str,
+        dest_path: &Path,
+        known_size: Option<u64>,
+        known_sha1: Option<String>,
+        jobs: &Arc<Mutex<HashMap<String, JobState>>>,
+        job_id: &str,
+    ) -> bool {
+        let Ok(existing) = tokio::fs::metadata(dest_path).await.map(|m| m.len()) else {
+            return false;
+        };
+        let expected = match known_size {
+            Some(size) => Some(size),
+            None => match client.head(url).send().await {
+                Ok(resp) if resp.status().is_success() => head_length(&resp),
+                _ => None,
+            },
+        };
+        if expected != Some(existing) || !Self::is_intact(dest_path, known_sha1).await {
+            return false;
+        }
+
+        let mut jobs = jobs.lock().unwrap();
+        if let Some(job) = jobs.get_mut(job_id) {
+            job.total = existing;
+            job.downloaded = existing;
+            job.already_downloaded = true;
+        }
+        true
+    }
+
+    /// Whether `dest_path` matches `sha1`; true when there's nothing to check against
+    async fn is_intact(dest_path: &Path, sha1: Option<String>) -> bool {
+        let Some(sha1) = sha1 else {
+            return true;
+        };
+        let path = dest_path.to_path_buf();
+        tokio::task::spawn_blocking(move || verify_download(&path, &sha1))
+            .await
+            .map(|r| r.is_ok())
+            .unwrap_or(false)
     }
 
     async fn download_direct(
         client: &reqwest::Client,
         url: &str,
         dest_path: &Path,
+        transfer: &Transfer,
         jobs: &Arc<Mutex<HashMap<String, JobState>>>,
         job_id: &str,
     ) -> Result<()> {
-        let resp = client.get(url).send().await?;
+        let mut existing = if transfer.resume {
+            tokio::fs::metadata(dest_path)
+                .await
+                .map(|m| m.len())
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        let mut request = client.get(url);
+        if existing > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+        }
+        let mut resp = request.send().await?;
 
+        if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE && existing > 0 {
+            // Nothing past the end of our copy: either it's complete, or it
+            // isn't this file at all
+            let expected = transfer.known_size.or_else(|| range_total(&resp));
+            if expected == Some(existing)
+                && Self::is_intact(dest_path, transfer.known_sha1.clone()).await
+            {
+                let mut jobs = jobs.lock().unwrap();
+                if let Some(job) = jobs.get_mut(job_id) {
+                    job.total = existing;
+                    job.downloaded = existing;
+                    job.source = Some(url.to_string());
+                }
+                return Ok(());
+            }
+            add_note(
+                jobs,
+                job_id,
+                "The partial file didn't match the server's; started over".into(),
+            );
+            existing = 0;
+            resp = client.get(url).send().await?;
+        }
+
+        let status = resp.status();
+        if status == StatusCode::NOT_FOUND || status == StatusCode::GONE {
+            return Err(PS3UpdateError::NotFound(url.to_string()));
+        }
         if !resp.status().is_success() {
-            return Err(PS3UpdateError::Download(format!(
-                "HTTP error: {}",
-                resp.status()
-            )));
+            return Err(PS3UpdateError::HttpStatus {
+                status: resp.status(),
+                url: url.to_string(),
+            });
         }
 
-        let total_size = resp.content_length().unwrap_or(0);
+        // Servers that ignore the range send the whole file again
+        let resumed = existing > 0 && status == StatusCode::PARTIAL_CONTENT;
+        let offset = if resumed { existing } else { 0 };
+        if resumed {
+            add_note(
+                jobs,
+                job_id,
+                format!("Resumed from {}", format_size(existing)),
+            );
+        }
+        let total_size = resp.content_length().map(|n| n + offset).unwrap_or(0);
 
         {
             let mut jobs = jobs.lock().unwrap();
             if let Some(job) = jobs.get_mut(job_id) {
                 job.total = total_size;
+                job.downloaded = offset;
+                job.source = Some(url.to_string());
             }
         }
 
-        let mut file = tokio::fs::File::create(dest_path).await?;
+        let mut file = if resumed {
+            tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(dest_path)
+                .await?
+        } else {
+            tokio::fs::File::create(dest_path).await?
+        };
         let mut stream = resp.bytes_stream();
 
         while let Some(chunk) = stream.next().await {
             let chunk = chunk?;
             file.write_all(&chunk).await?;
 
-            let mut jobs = jobs.lock().unwrap();
-            if let Some(job) = jobs.get_mut(job_id) {
-                job.downloaded = job.downloaded.saturating_add(chunk.len() as u64);
+            {
+                let mut jobs = jobs.lock().unwrap();
+                if let Some(job) = jobs.get_mut(job_id) {
+                    job.downloaded = job.downloaded.saturating_add(chunk.len() as u64);
+                }
             }
+            transfer.consume(chunk.len() as u64).await;
         }
-
-        let mut jobs = jobs.lock().unwrap();
-        if let Some(job) = jobs.get_mut(job_id) {
-            job.done = true;
-        }
+        file.flush().await?;
 
         Ok(())
     }
@@ -208,13 +1129,13 @@ impl DownloadManager {
         url: &str,
         dest_path: &Path,
         num_parts: usize,
+        transfer: &Transfer,
         jobs: &Arc<Mutex<HashMap<String, JobState>>>,
         job_id: &str,
     ) -> Result<()> {
         // First, check if server supports range requests
         let head_resp = client.head(url).send().await?;
-        let total_size = head_resp
-            .content_length()
+        let total_size = head_length(&head_resp)
             .ok_or_else(|| PS3UpdateError::Download("Cannot determine file size".into()))?;
 
         // Ensure total_size is valid
@@ -230,13 +1151,15 @@ impl DownloadManager {
             .unwrap_or(false);
 
         if !accept_ranges {
-            return Self::download_direct(client, url, dest_path, jobs, job_id).await;
+            return Self::download_direct(client, url, dest_path, transfer, jobs, job_id).await;
         }
 
         {
             let mut jobs = jobs.lock().unwrap();
             if let Some(job) = jobs.get_mut(job_id) {
                 job.total = total_size;
+                job.downloaded = 0;
+                job.source = Some(url.to_string());
             }
         }
 
@@ -276,10 +1199,10 @@ impl DownloadManager {
                     .await?;
 
                 if !resp.status().is_success() && resp.status().as_u16() != 206 {
-                    return Err(PS3UpdateError::Download(format!(
-                        "Range request failed: {}",
-                        resp.status()
-                    )));
+                    return Err(PS3UpdateError::HttpStatus {
+                        status: resp.status(),
+                        url,
+                    });
                 }
 
                 let mut stream = resp.bytes_stream();
@@ -294,11 +1217,15 @@ impl DownloadManager {
                     let chunk = chunk?;
                     file.write_all(&chunk).await?;
 
-                    let mut jobs = jobs.lock().unwrap();
-                    if let Some(job) = jobs.get_mut(&job_id) {
-                        job.downloaded = job.downloaded.saturating_add(chunk.len() as u64);
+                    {
+                        let mut jobs = jobs.lock().unwrap();
+                        if let Some(job) = jobs.get_mut(&job_id) {
+                            job.downloaded = job.downloaded.saturating_add(chunk.len() as u64);
+                        }
                     }
+                    transfer.consume(chunk.len() as u64).await;
                 }
+                file.flush().await?;
 
                 Ok::<(), PS3UpdateError>(())
             }
@@ -306,16 +1233,48 @@ impl DownloadManager {
 
         let results: Vec<Result<()>> = futures_util::future::join_all(futures).await;
 
-        let mut jobs = jobs.lock().unwrap();
-        if let Some(job) = jobs.get_mut(job_id) {
-            job.done = true;
-            if results.iter().any(|r| r.is_err()) {
-                job.error = Some("One or more parts failed".into());
-            }
+        if results.iter().any(|r| r.is_err()) {
+            return Err(PS3UpdateError::Download("One or more parts failed".into()));
         }
 
         Ok(())
     }
+
+    fn apply_naming(
+        dest_path: &Path,
+        scheme: NamingScheme,
+        template: Option<&NameTemplate>,
+        expected_title_id: Option<&str>,
+        entry: Option<&ManifestEntry>,
+    ) -> Result<PathBuf> {
+        let original = dest_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let header = PkgHeader::read_from(dest_path).ok();
+        let title_id = entry
+            .map(|e| e.title_id.clone())
+            .or_else(|| expected_title_id.map(str::to_string))
+            .or_else(|| Some(header.as_ref()?.title_id()?.as_str().to_string()))
+            .unwrap_or_default();
+        let version = entry
+            .map(|e| e.package.version.clone())
+            .or_else(|| version_from_filename(original))
+            .unwrap_or_default();
+
+        let ctx = NamingContext {
+            original,
+            title_id: &title_id,
+            game_name: entry.map(|e| e.game_name.as_str()).unwrap_or_default(),
+            version: &version,
+            content_id: header.as_ref().map(|h| h.content_id.as_str()),
+        };
+        let name = match template {
+            Some(template) => ctx.render(template),
+            None => scheme.file_name(&ctx),
+        };
+        rename_in_place(dest_path, &name)
+    }
 }
 
 impl Default for DownloadManager {
@@ -323,3 +1282,269 @@ impl Default for DownloadManager {
         Self::new().expect("Failed to create DownloadManager")
     }
 }
+
+/// Progress updates from [`DownloadManager::subscribe`]
+pub struct ProgressSubscription {
+    jobs: Arc<Mutex<HashMap<String, JobState>>>,
+    ticker: tokio::time::Interval,
+    /// What was last reported for each job
+    seen: HashMap<String, Reported>,
+}
+
+/// The parts of a job's progress that make it worth reporting again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Reported {
+    downloaded: u64,
+    total: u64,
+    limit: Option<u64>,
+    paused: bool,
+    verifying: bool,
+    done: bool,
+}
+
+impl ProgressSubscription {
+    /// Wait until a job has moved and return the ones that did, oldest first
+    ///
+    /// A job is reported once more when it finishes, then not again.
+    pub async fn next(&mut self) -> Vec<(String, ProgressInfo)> {
+        loop {
+            self.ticker.tick().await;
+            let changed = self.changed();
+            if !changed.is_empty() {
+                return changed;
+            }
+        }
+    }
+
+    fn changed(&mut self) -> Vec<(String, ProgressInfo)> {
+        let jobs = self.jobs.lock().unwrap();
+        self.seen.retain(|id, _| jobs.contains_key(id));
+
+        let mut changed: Vec<_> = jobs
+            .iter()
+            .filter(|(id, job)| {
+                let throttle = job.throttle.as_ref();
+                let state = Reported {
+                    downloaded: job.downloaded,
+                    total: job.total,
+                    limit: throttle.and_then(|t| t.limit()),
+                    paused: throttle.is_some_and(|t| t.is_paused()),
+                    verifying: job.verifying,
+                    done: job.done,
+                };
+                self.seen.insert(id.to_string(), state) != Some(state)
+            })
+            .map(|(id, job)| (id.clone(), job.progress(), job.start))
+            .collect();
+        changed.sort_by_key(|(_, _, start)| *start);
+        changed
+            .into_iter()
+            .map(|(id, progress, _)| (id, progress))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mirror_url() {
+        let url = "http://b0.ww.np.dl.playstation.net/tppkg/np/BLES00779/BLES00779_T6/abc/EP9000-BLES00779_00-PATCH-A0102-V0100-PE.pkg";
+        let mirror = ArchiveMirror::new("archive", "https://archive.example/{title_id}/{filename}");
+        assert_eq!(
+            mirror.url_for(url),
+            "https://archive.example/BLES00779/EP9000-BLES00779_00-PATCH-A0102-V0100-PE.pkg"
+        );
+
+        let mirror = ArchiveMirror::new("tree", "https://mirror.example/{path}");
+        assert_eq!(
+            mirror.url_for(url),
+            "https://mirror.example/tppkg/np/BLES00779/BLES00779_T6/abc/EP9000-BLES00779_00-PATCH-A0102-V0100-PE.pkg"
+        );
+    }
+
+    #[test]
+    fn test_apply_naming() {
+        let tmp = tempfile::tempdir().unwrap();
+        let original = tmp
+            .path()
+            .join("EP0001-BLES00779_00-PATCH-A0102-V0100-PE.pkg");
+        std::fs::write(&original, b"pkg").unwrap();
+        let entry = ManifestEntry {
+            title_id: "BLES00779".into(),
+            game_name: "Game".into(),
+            package: PackageInfo {
+                version: "01.02".into(),
+                system_ver: "03.40".into(),
+                size_bytes: 3,
+                size_human: "3 B".into(),
+                url: "http://example/a.pkg".into(),
+                sha1: String::new(),
+                filename: "EP0001-BLES00779_00-PATCH-A0102-V0100-PE.pkg".into(),
+            },
+        };
+
+        let renamed = DownloadManager::apply_naming(
+            &original,
+            NamingScheme::Descriptive,
+            None,
+            None,
+            Some(&entry),
+        )
+        .unwrap();
+        assert_eq!(renamed, tmp.path().join("Game [BLES00779] v01.02.pkg"));
+        assert!(!original.exists());
+        assert_eq!(std::fs::read(&renamed).unwrap(), b"pkg");
+
+        // A file that already has its name stays where it is
+        let again = DownloadManager::apply_naming(
+            &renamed,
+            NamingScheme::Descriptive,
+            None,
+            None,
+            Some(&entry),
+        )
+        .unwrap();
+        assert_eq!(again, renamed);
+        assert_eq!(std::fs::read(&again).unwrap(), b"pkg");
+    }
+
+    #[test]
+    fn test_throttle_and_retry_delays() {
+        let second = Duration::from_secs(1);
+        assert_eq!(throttle_delay(2000, Duration::ZERO, 1000), 2 * second);
+        assert_eq!(throttle_delay(2000, second, 1000), second);
+        assert_eq!(throttle_delay(500, second, 1000), Duration::ZERO);
+
+        assert_eq!(retry_delay(1), second);
+        assert_eq!(retry_delay(3), 4 * second);
+        assert_eq!(retry_delay(50), 30 * second);
+    }
+
+    #[tokio::test]
+    async fn test_progress_subscription() {
+        let manager = DownloadManager::new().unwrap();
+        let mut updates = manager.subscribe(Duration::from_millis(10));
+        manager
+            .jobs
+            .lock()
+            .unwrap()
+            .insert("a".to_string(), JobState::new("a.pkg"));
+
+        let first = updates.next().await;
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].0, "a");
+
+        // Nothing moved, so nothing is reported
+        assert!(updates.changed().is_empty());
+
+        if let Some(job) = manager.jobs.lock().unwrap().get_mut("a") {
+            job.downloaded = 10;
+            job.done = true;
+        }
+        let last = updates.next().await;
+        assert_eq!(last[0].1.downloaded, 10);
+        assert!(last[0].1.done);
+        assert!(updates.changed().is_empty());
+    }
+
+    #[test]
+    fn test_progress_phase() {
+        let jobs = Mutex::new(HashMap::new());
+        jobs.lock()
+            .unwrap()
+            .insert("a".to_string(), JobState::new("a.pkg"));
+        let progress = || jobs.lock().unwrap()["a"].progress();
+        assert_eq!(progress().phase, DownloadPhase::Connecting);
+        assert_eq!(progress().attempts, 1);
+
+        if let Some(job) = jobs.lock().unwrap().get_mut("a") {
+            job.source = Some("http://example.com/a.pkg".to_string());
+            job.total = 100;
+            job.downloaded = 40;
+        }
+        assert_eq!(progress().phase, DownloadPhase::Downloading);
+        assert_eq!(progress().remaining, 60);
+
+        new_attempt(&jobs, "a");
+        assert_eq!(progress().phase, DownloadPhase::Connecting);
+        assert_eq!(progress().attempts, 2);
+
+        if let Some(job) = jobs.lock().unwrap().get_mut("a") {
+            job.verifying = true;
+        }
+        assert_eq!(progress().phase, DownloadPhase::Verifying);
+        if let Some(job) = jobs.lock().unwrap().get_mut("a") {
+            job.done = true;
+        }
+        assert_eq!(progress().phase, DownloadPhase::Done);
+    }
+
+    #[test]
+    fn test_set_rate_limit() {
+        let manager = DownloadManager::new().unwrap();
+        let mut job = JobState::new("a.pkg");
+        job.throttle = Some(Arc::new(Throttle::new(0)));
+        manager.jobs.lock().unwrap().insert("a".to_string(), job);
+        assert_eq!(manager.get_progress("a").unwrap().rate_limit, None);
+
+        manager.set_rate_limit("a", 1024).unwrap();
+        assert_eq!(manager.get_progress("a").unwrap().rate_limit, Some(1024));
+        manager.set_rate_limit("a", 0).unwrap();
+        assert_eq!(manager.get_progress("a").unwrap().rate_limit, None);
+        assert!(manager.set_rate_limit("b", 1024).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_global_rate_limit() {
+        let manager = DownloadManager::new().unwrap();
+        assert_eq!(manager.global_rate_limit(), None);
+        manager.set_global_rate_limit(1000);
+        assert_eq!(manager.global_rate_limit(), Some(1000));
+
+        // Two jobs' bytes count against the same budget
+        let first = Transfer {
+            throttle: Arc::new(Throttle::new(0)),
+            global: manager.global.clone(),
+            resume: false,
+            known_size: None,
+            known_sha1: None,
+        };
+        let second = Transfer {
+            throttle: Arc::new(Throttle::new(0)),
+            global: manager.global.clone(),
+            resume: false,
+            known_size: None,
+            known_sha1: None,
+        };
+        let started = Instant::now();
+        first.consume(100).await;
+        second.consume(100).await;
+        assert!(started.elapsed() >= Duration::from_millis(150));
+
+        manager.set_global_rate_limit(0);
+        assert_eq!(manager.global_rate_limit(), None);
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let manager = DownloadManager::new().unwrap();
+        let throttle = Arc::new(Throttle::new(0));
+        let mut job = JobState::new("a.pkg");
+        job.throttle = Some(throttle.clone());
+        manager.jobs.lock().unwrap().insert("a".to_string(), job);
+
+        manager.pause("a").unwrap();
+        assert!(manager.get_progress("a").unwrap().paused);
+        let held = tokio::time::timeout(Duration::from_millis(300), throttle.consume(1)).await;
+        assert!(held.is_err());
+
+        manager.resume("a").unwrap();
+        assert!(!manager.get_progress("a").unwrap().paused);
+        tokio::time::timeout(Duration::from_secs(1), throttle.consume(1))
+            .await
+            .unwrap();
+        assert!(manager.pause("b").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use crate::types::{FetchResult, PackageInfo};
+use quick_xml::escape::escape;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A package and where it should be saved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedDownload {
+    pub title_id: String,
+    pub game_title: String,
+    pub package: PackageInfo,
+    /// Folder the package goes into
+    pub dir: PathBuf,
+}
+
+impl PlannedDownload {
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(&self.package.filename)
+    }
+}
+
+/// Every package of a fetch, saved into `dir` under its server name
+pub fn plan_fetch(result: &FetchResult, dir: &Path) -> Vec<PlannedDownload> {
+    result
+        .results
+        .iter()
+        .map(|pkg| PlannedDownload {
+            title_id: result.cleaned_title_id.clone(),
+            game_title: result.game_title.clone(),
+            package: pkg.clone(),
+            dir: dir.to_path_buf(),
+        })
+        .collect()
+}
+
+/// Download list formats for external tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    /// An `aria2c --input-file` list
+    Aria2,
+    /// A POSIX shell script calling `wget`
+    Wget,
+    /// An RFC 5854 Metalink document
+    Metalink,
+}
+
+impl ExportFormat {
+    pub fn render(&self, downloads: &[PlannedDownload]) -> String {
+        match self {
+            ExportFormat::Aria2 => aria2_input(downloads),
+            ExportFormat::Wget => wget_script(downloads),
+            ExportFormat::Metalink => metalink(downloads),
+        }
+    }
+}
+
+/// An input file for `aria2c -i`, with `dir=` and `out=` for each package
+///
+/// A listed SHA1 goes in `checksum=sha-1=`, so aria2 checks each file as it
+/// finishes. Some update lists hash the PKG without its 32-byte digest block,
+/// which aria2's whole-file check reports as a mismatch; [`crate::verify_pkg`]
+/// accepts either.
+pub fn aria2_input(downloads: &[PlannedDownload]) -> String {
+    let mut out = String::new();
+    for download in downloads {
+        let pkg = &download.package;
+        out.push_str(&format!(
+            "# {} {} v{}\n",
+            download.title_id, download.game_title, pkg.version
+        ));
+        out.push_str(&format!("{}\n", pkg.url));
+        out.push_str(&format!("  dir={}\n", download.dir.display()));
+        out.push_str(&format!("  out={}\n", pkg.filename));
+        if !pkg.sha1.is_empty() {
+            out.push_str(&format!("  checksum=sha-1={}\n", pkg.sha1));
+        }
+    }
+    out
+}
+
+/// A shell script that downloads each package with `wget -c`
+///
+/// Folders are created as needed, and the listed SHA1 of each package is
+/// left as a comment for checking with [`crate::verify_pkg`].
+pub fn wget_script(downloads: &[PlannedDownload]) -> String {
+    let mut out = String::from("#!/bin/sh\nset -e\n");
+    let mut dirs: Vec<&Path> = vec![];
+    for download in downloads {
+        let pkg = &download.package;
+        out.push_str(&format!(
+            "\n# {} {} v{}\n",
+            download.title_id, download.game_title, pkg.version
+        ));
+        if !pkg.sha1.is_empty() {
+            out.push_str(&format!("# sha1 {}\n", pkg.sha1));
+        }
+        if !dirs.contains(&download.dir.as_path()) {
+            dirs.push(&download.dir);
+            out.push_str(&format!(
+                "mkdir -p {}\n",
+                shell_quote(&download.dir.display().to_string())
+            ));
+        }
+        out.push_str(&format!(
+            "wget -c -O {} {}\n",
+            shell_quote(&download.path().display().to_string()),
+            shell_quote(&pkg.url)
+        ));
+    }
+    out
+}
+
+/// An RFC 5854 Metalink document with one `<file>` per package
+///
+/// Each file carries its size, URL, title ID (`<identity>`), version and game
+/// name, which makes the document useful as a record of what was archived.
+/// A listed SHA1 goes in a `<hash type="sha-1">` element, with the same
+/// caveat as in [`aria2_input`]. File names are the server names; the
+/// planned folders are left to the download manager.
+pub fn metalink(downloads: &[PlannedDownload]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <metalink xmlns=\"urn:ietf:params:xml:ns:metalink\">\n",
+    );
+    out.push_str(&format!(
+        "  <generator>ps3-update-core/{}</generator>\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    for download in downloads {
+        let pkg = &download.package;
+        let mut description = format!("{} v{}", download.game_title, pkg.version);
+        if !pkg.system_ver.is_empty() {
+            description.push_str(&format!(", requires system {}", pkg.system_ver));
+        }
+        out.push_str(&format!("  <file name=\"{}\">\n", escape(&pkg.filename)));
+        out.push_str(&format!(
+            "    <identity>{}</identity>\n",
+            escape(&download.title_id)
+        ));
+        out.push_str(&format!(
+            "    <version>{}</version>\n",
+            escape(&pkg.version)
+        ));
+        out.push_str(&format!(
+            "    <description>{}</description>\n",
+            escape(&description)
+        ));
+        if pkg.size_bytes > 0 {
+            out.push_str(&format!("    <size>{}</size>\n", pkg.size_bytes));
+        }
+        if !pkg.sha1.is_empty() {
+            out.push_str(&format!(
+                "    <hash type=\"sha-1\">{}</hash>\n",
+                escape(&pkg.sha1)
+            ));
+        }
+        out.push_str(&format!("    <url>{}</url>\n", escape(&pkg.url)));
+        out.push_str("  </file>\n");
+    }
+    out.push_str("</metalink>\n");
+    out
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetcher::tests::SAMPLE_XML;
+    use crate::fetcher::UpdateFetcher;
+
+    #[test]
+    fn test_export_formats() {
+        let fetcher = UpdateFetcher::new().unwrap();
+        let result = fetcher.parse_updates("BLES00779", SAMPLE_XML).unwrap();
+        let mut downloads = plan_fetch(&result, Path::new("/srv/Demon's Souls"));
+        assert_eq!(downloads.len(), 2);
+
+        let aria2 = aria2_input(&downloads);
+        assert!(aria2.contains(
+            "http://example/a/EP0001-BLES00779_00-PATCH-A0101-V0100-PE.pkg\n  dir=/srv/Demon's Souls\n  out=EP0001-BLES00779_00-PATCH-A0101-V0100-PE.pkg\n  checksum=sha-1=aa\n"
+        ));
+
+        let wget = ExportFormat::Wget.render(&downloads);
+        assert_eq!(wget.matches("mkdir -p").count(), 1);
+        assert!(wget.contains("mkdir -p '/srv/Demon'\\''s Souls'\n"));
+        assert!(wget.contains(
+            "wget -c -O '/srv/Demon'\\''s Souls/EP0001-BLES00779_00-PATCH-A0101-V0100-PE.pkg' 'http://example/a/EP0001-BLES00779_00-PATCH-A0101-V0100-PE.pkg'\n"
+        ));
+
+        let xml = ExportFormat::Metalink.render(&downloads);
+        assert_eq!(xml.matches("<file ").count(), 2);
+        assert!(xml.contains("<identity>BLES00779</identity>"));
+        assert!(xml.contains("<size>2048</size>"));
+        assert!(
+            xml.contains("<description>Test Game v01.01, requires system 03.4000</description>")
+        );
+        assert!(xml.contains("<hash type=\"sha-1\">bb</hash>"));
+        // Well-formed enough for an XML parser
+        let mut reader = quick_xml::Reader::from_str(&xml);
+        while !matches!(reader.read_event().unwrap(), quick_xml::events::Event::Eof) {}
+
+        // Without a listed SHA1 there's nothing to check against
+        for download in &mut downloads {
+            download.package.sha1.clear();
+        }
+        assert!(!aria2_input(&downloads).contains("checksum="));
+        assert!(!metalink(&downloads).contains("<hash"));
+    }
+}
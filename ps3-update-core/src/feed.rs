@@ -0,0 +1,282 @@
+use crate::utils::{format_size, unix_timestamp};
+use crate::watch::DiscoveredUpdate;
+use quick_xml::escape::escape;
+use serde::{Deserialize, Serialize};
+
+const FEED_TITLE: &str = "PS3 game updates";
+
+/// Syndication formats for a feed of discovered updates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FeedFormat {
+    /// An RFC 4287 Atom feed
+    #[default]
+    Atom,
+    /// An RSS 2.0 channel
+    Rss,
+}
+
+impl FeedFormat {
+    /// Render `updates` (newest first) as a feed document
+    pub fn render(&self, updates: &[DiscoveredUpdate]) -> String {
+        match self {
+            FeedFormat::Atom => atom_feed(updates),
+            FeedFormat::Rss => rss_feed(updates),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "application/atom+xml",
+            FeedFormat::Rss => "application/rss+xml",
+        }
+    }
+}
+
+fn entry_title(update: &DiscoveredUpdate) -> String {
+    let name = if update.game_title.is_empty() {
+        &update.title_id
+    } else {
+        &update.game_title
+    };
+    format!("{} {}", name, update.package.version)
+}
+
+fn entry_summary(update: &DiscoveredUpdate) -> String {
+    let pkg = &update.package;
+    let mut summary = format!(
+        "Update {} for {} ({}), {}",
+        pkg.version,
+        update.game_title,
+        update.title_id,
+        format_size(pkg.size_bytes)
+    );
+    if !pkg.system_ver.is_empty() {
+        summary.push_str(&format!(", requires system {}", pkg.system_ver));
+    }
+    summary
+}
+
+/// When the feed last changed: its newest entry, or now when it's empty
+fn feed_updated(updates: &[DiscoveredUpdate]) -> u64 {
+    updates
+        .iter()
+        .map(|u| u.discovered_at)
+        .max()
+        .unwrap_or_else(unix_timestamp)
+}
+
+/// An Atom feed with one entry per update, identified by its package URL
+pub fn atom_feed(updates: &[DiscoveredUpdate]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <feed xmlns=\"http://www.w3.org/2005/Atom\">\n",
+    );
+    out.push_str(&format!("  <title>{}</title>\n", FEED_TITLE));
+    out.push_str("  <id>urn:ps3-update-core:watch</id>\n");
+    out.push_str(&format!(
+        "  <updated>{}</updated>\n",
+        rfc3339(feed_updated(updates))
+    ));
+    out.push_str("  <author><name>ps3-update-core</name></author>\n");
+    out.push_str(&format!(
+        "  <generator version=\"{}\">ps3-update-core</generator>\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    for update in updates {
+        let url = escape(&update.package.url);
+        out.push_str("  <entry>\n");
+        out.push_str(&format!(
+            "    <title>{}</title>\n",
+            escape(&entry_title(update))
+        ));
+        out.push_str(&format!("    <id>{}</id>\n", url));
+        out.push_str(&format!("    <link href=\"{}\"/>\n", url));
+        out.push_str(&format!(
+            "    <updated>{}</updated>\n",
+            rfc3339(update.discovered_at)
+        ));
+        out.push_str(&format!(
+            "    <category term=\"{}\"/>\n",
+            escape(&update.title_id)
+        ));
+        out.push_str(&format!(
+            "    <summary>{}</summary>\n",
+            escape(&entry_summary(update))
+        ));
+        out.push_str("  </entry>\n");
+    }
+    out.push_str("</feed>\n");
+    out
+}
+
+/// An RSS 2.0 channel with one item per update
+///
+/// The channel has no `<link>`, as the feed has no web page of its own;
+/// each item links to its package.
+pub fn rss_feed(updates: &[DiscoveredUpdate]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <rss version=\"2.0\">\n  <channel>\n",
+    );
+    out.push_str(&format!("    <title>{}</title>\n", FEED_TITLE));
+    out.push_str("    <description>New update versions for watched PS3 titles</description>\n");
+    out.push_str(&format!(
+        "    <lastBuildDate>{}</lastBuildDate>\n",
+        rfc2822(feed_updated(updates))
+    ));
+    out.push_str(&format!(
+        "    <generator>ps3-update-core/{}</generator>\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    for update in updates {
+        let url = escape(&update.package.url);
+        out.push_str("    <item>\n");
+        out.push_str(&format!(
+            "      <title>{}</title>\n",
+            escape(&entry_title(update))
+        ));
+        out.push_str(&format!("      <link>{}</link>\n", url));
+        out.push_str(&format!(
+            "      <guid isPermaLink=\"false\">{}</guid>\n",
+            url
+        ));
+        out.push_str(&format!(
+            "      <pubDate>{}</pubDate>\n",
+            rfc2822(update.discovered_at)
+        ));
+        out.push_str(&format!(
+            "      <category>{}</category>\n",
+            escape(&update.title_id)
+        ));
+        out.push_str(&format!(
+            "      <description>{}</description>\n",
+            escape(&entry_summary(update))
+        ));
+        out.push_str("    </item>\n");
+    }
+    out.push_str("  </channel>\n</rss>\n");
+    out
+}
+
+/// Calendar date and time of a Unix timestamp, in UTC
+struct DateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u64,
+    minute: u64,
+    second: u64,
+    /// 0 is Sunday
+    weekday: u64,
+}
+
+impl DateTime {
+    fn from_unix(timestamp: u64) -> Self {
+        let days = (timestamp / 86400) as i64;
+        let secs = timestamp % 86400;
+        // Days to civil date, after Howard Hinnant's `civil_from_days`
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        DateTime {
+            year,
+            month,
+            day,
+            hour: secs / 3600,
+            minute: secs / 60 % 60,
+            second: secs % 60,
+            // 1970-01-01 was a Thursday
+            weekday: (timestamp / 86400 + 4) % 7,
+        }
+    }
+}
+
+/// `2024-05-01T12:00:00Z`, as Atom wants
+fn rfc3339(timestamp: u64) -> String {
+    let t = DateTime::from_unix(timestamp);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        t.year, t.month, t.day, t.hour, t.minute, t.second
+    )
+}
+
+/// `Wed, 01 May 2024 12:00:00 +0000`, as RSS wants
+fn rfc2822(timestamp: u64) -> String {
+    const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let t = DateTime::from_unix(timestamp);
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} +0000",
+        DAYS[t.weekday as usize],
+        t.day,
+        MONTHS[t.month as usize - 1],
+        t.year,
+        t.hour,
+        t.minute,
+        t.second
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PackageInfo;
+
+    fn update() -> DiscoveredUpdate {
+        DiscoveredUpdate {
+            title_id: "BLES00779".into(),
+            game_title: "Demon's Souls & Co".into(),
+            package: PackageInfo {
+                version: "01.04".into(),
+                system_ver: "3.41".into(),
+                size_bytes: 1024,
+                size_human: "1.00 KB".into(),
+                url: "http://example/a.pkg?x=1&y=2".into(),
+                sha1: String::new(),
+                filename: "a.pkg".into(),
+            },
+            discovered_at: 1_714_564_800,
+        }
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(1_714_564_800), "2024-05-01T12:00:00Z");
+        assert_eq!(rfc2822(1_714_564_800), "Wed, 01 May 2024 12:00:00 +0000");
+        assert_eq!(rfc3339(951_825_599), "2000-02-29T11:59:59Z");
+    }
+
+    #[test]
+    fn test_feeds() {
+        let updates = [update()];
+        let atom = FeedFormat::Atom.render(&updates);
+        assert!(atom.contains("<updated>2024-05-01T12:00:00Z</updated>"));
+        assert!(atom.contains("<title>Demon&apos;s Souls &amp; Co 01.04</title>"));
+        assert!(atom.contains("<id>http://example/a.pkg?x=1&amp;y=2</id>"));
+        assert!(atom.contains("requires system 3.41"));
+
+        let rss = FeedFormat::Rss.render(&updates);
+        assert!(rss.contains("<pubDate>Wed, 01 May 2024 12:00:00 +0000</pubDate>"));
+        assert!(rss.contains("<category>BLES00779</category>"));
+
+        // Both parse as XML
+        for doc in [atom, rss] {
+            let mut reader = quick_xml::Reader::from_str(&doc);
+            loop {
+                match reader.read_event().unwrap() {
+                    quick_xml::events::Event::Eof => break,
+                    _ => continue,
+                }
+            }
+        }
+    }
+}
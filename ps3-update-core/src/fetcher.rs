@@ -1,7 +1,12 @@
+use crate::aliases::AliasMap;
+use crate::client::{client_builder, ClientOptions};
+use crate::titledb::{TitleDatabase, TitleMatch, TitleSuggestion};
 use crate::types::{FetchResult, PackageInfo, PS3UpdateError, Result};
-use crate::utils::{clean_title_id, format_size};
+use crate::utils::{clean_title_id, compare_versions, format_size};
+use futures_util::StreamExt;
 use quick_xml::de::from_str;
 use serde::Deserialize;
+use std::sync::Arc;
 
 const PS3_UPDATE_BASE_URL: &str = "https://a0.ww.np.dl.playstation.net";
 
@@ -35,11 +40,11 @@ struct TitlePatch {
     #[serde(rename = "package")]
     package: Option<Vec<PackageAttr>>,
     #[serde(rename = "PACKAGE")]
-    PACKAGE: Option<Vec<PackageAttr>>,
+    package_upper: Option<Vec<PackageAttr>>,
     #[serde(rename = "tag")]
     tag: Option<TagNode>,
     #[serde(rename = "TAG")]
-    TAG: Option<TagNode>,
+    tag_upper: Option<TagNode>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -47,63 +52,170 @@ struct TagNode {
     #[serde(rename = "package")]
     package: Option<Vec<PackageAttr>>,
     #[serde(rename = "PACKAGE")]
-    PACKAGE: Option<Vec<PackageAttr>>,
+    package_upper: Option<Vec<PackageAttr>>,
 }
 
 /// PS3 Update Fetcher
 pub struct UpdateFetcher {
     client: reqwest::Client,
+    title_db: Option<Arc<TitleDatabase>>,
+    aliases: Option<Arc<AliasMap>>,
+    base_url: String,
 }
 
 impl UpdateFetcher {
     /// Create a new UpdateFetcher with default settings
     pub fn new() -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .danger_accept_invalid_certs(true)
-            .build()?;
-        Ok(Self { client })
+        Self::with_client_options(&ClientOptions::default())
+    }
+
+    /// Create a new UpdateFetcher with tuned connection settings
+    pub fn with_client_options(options: &ClientOptions) -> Result<Self> {
+        // Large -ver.xml files are requested compressed and decoded transparently
+        let client = client_builder(options)?.build()?;
+        Ok(Self {
+            client,
+            title_db: None,
+            aliases: None,
+            base_url: PS3_UPDATE_BASE_URL.to_string(),
+        })
+    }
+
+    /// Fetch `-ver.xml` files from another server, such as a local mirror or
+    /// a `MockUpdateServer`, instead of Sony's
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Use an alias map that is consulted before title ID validation
+    pub fn with_aliases(mut self, aliases: AliasMap) -> Self {
+        self.aliases = Some(Arc::new(aliases));
+        self
+    }
+
+    /// Resolve user input to a cleaned title ID, applying aliases first
+    pub fn resolve_title_id(&self, input: &str) -> String {
+        self.aliases
+            .as_ref()
+            .and_then(|a| a.resolve(input))
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| clean_title_id(input))
+    }
+
+    /// Use a title database to name titles whose XML carries no title
+    pub fn with_title_database(mut self, db: TitleDatabase) -> Self {
+        self.title_db = Some(Arc::new(db));
+        self
+    }
+
+    /// Look up a game name in the configured title database without hitting Sony
+    pub fn title_name(&self, title_id: &str) -> Option<String> {
+        self.title_db
+            .as_ref()
+            .and_then(|db| db.lookup(&self.resolve_title_id(title_id)))
+            .map(|s| s.to_string())
+    }
+
+    /// Search the title database for games matching a name
+    ///
+    /// Returns candidate title IDs across all regions, best match first.
+    /// Always empty when no title database is configured.
+    pub fn search(&self, name: &str) -> Vec<TitleMatch> {
+        self.title_db
+            .as_ref()
+            .map(|db| db.search(name, 25))
+            .unwrap_or_default()
+    }
+
+    /// Suggest known title IDs close to one that returned no updates
+    ///
+    /// Offers near misses such as the same number under another region prefix
+    /// or transposed digits, drawn from the title database; a fetch that finds
+    /// nothing returns them in [`PS3UpdateError::NoUpdatesFound`]. Always empty
+    /// when no database is configured.
+    pub fn suggest(&self, title_id: &str) -> Vec<TitleSuggestion> {
+        self.title_db
+            .as_ref()
+            .map(|db| db.suggest(&self.resolve_title_id(title_id), 5))
+            .unwrap_or_default()
     }
 
     /// Check if the PS3 update server is accessible
     pub async fn check_server_status(&self) -> bool {
         self.client
-            .head(PS3_UPDATE_BASE_URL)
+            .head(&self.base_url)
             .send()
             .await
             .is_ok()
     }
 
+    /// Fetch the raw `-ver.xml` document for a title
+    ///
+    /// The body is returned decoded, even when the server sent it gzip-compressed.
+    pub async fn fetch_raw_xml(&self, title_id: &str) -> Result<String> {
+        let cleaned = self.resolve_title_id(title_id);
+        self.fetch_xml_for(&cleaned).await
+    }
+
     /// Fetch available updates for a given PS3 title ID
     pub async fn fetch_updates(&self, title_id: &str) -> Result<FetchResult> {
-        let cleaned = clean_title_id(title_id);
+        let cleaned = self.resolve_title_id(title_id);
+        let text = self.fetch_xml_for(&cleaned).await?;
+        self.parse_updates(&cleaned, &text)
+    }
 
-        if cleaned.is_empty() {
-            return Err(PS3UpdateError::InvalidTitleId(
-                "Empty or invalid Title ID".into(),
-            ));
+    /// Fetch updates for several titles, up to `concurrency` at a time
+    ///
+    /// Title IDs are cleaned and each is fetched once. `on_result` is called as
+    /// each title finishes with the number finished so far and the number of
+    /// titles; the returned list keeps the order the titles were given in.
+    pub async fn fetch_updates_batch(
+        &self,
+        title_ids: &[impl AsRef<str>],
+        concurrency: usize,
+        mut on_result: impl FnMut(usize, usize, &str, &Result<FetchResult>),
+    ) -> Vec<(String, Result<FetchResult>)> {
+        let mut unique: Vec<String> = vec![];
+        for title_id in title_ids {
+            let cleaned = self.resolve_title_id(title_id.as_ref());
+            if !cleaned.is_empty() && !unique.contains(&cleaned) {
+                unique.push(cleaned);
+            }
         }
 
-        let url = format!(
-            "{}/tpl/np/{id}/{id}-ver.xml",
-            PS3_UPDATE_BASE_URL,
-            id = cleaned
-        );
-
-        let resp = self.client.get(&url).send().await?;
-
-        if !resp.status().is_success() {
-            return Err(PS3UpdateError::NoUpdatesFound(cleaned));
+        let total = unique.len();
+        let mut finished = Vec::with_capacity(total);
+        let mut fetches = futures_util::stream::iter(unique.into_iter().enumerate())
+            .map(|(index, title_id)| async move {
+                let result = self.fetch_updates(&title_id).await;
+                (index, title_id, result)
+            })
+            .buffer_unordered(concurrency.max(1));
+        while let Some((index, title_id, result)) = fetches.next().await {
+            on_result(finished.len() + 1, total, &title_id, &result);
+            finished.push((index, title_id, result));
         }
 
-        let text = resp.text().await?;
+        finished.sort_by_key(|(index, _, _)| *index);
+        finished
+            .into_iter()
+            .map(|(_, title_id, result)| (title_id, result))
+            .collect()
+    }
+
+    /// Parse a `-ver.xml` document obtained elsewhere (e.g. a local mirror)
+    pub fn parse_updates(&self, title_id: &str, text: &str) -> Result<FetchResult> {
+        let cleaned = self.resolve_title_id(title_id);
 
         // Try to extract <TITLE> directly from raw XML as a fallback
-        let raw_title = Self::extract_title_from_xml(&text);
+        let raw_title = Self::extract_title_from_xml(text);
 
-        let parsed: TitlePatch = from_str(&text)
-            .map_err(|e| PS3UpdateError::XmlParse(e.to_string()))?;
+        let parsed: TitlePatch = from_str(text)?;
 
-        let game_title = raw_title.unwrap_or_else(|| "Unknown Title".to_string());
+        let game_title = raw_title
+            .or_else(|| self.title_name(&cleaned))
+            .unwrap_or_else(|| "Unknown Title".to_string());
         let pkgs = Self::extract_packages(parsed);
 
         if pkgs.is_empty() {
@@ -117,7 +229,7 @@ impl UpdateFetcher {
 
         // Override game title if available in package metadata
         let game_title = pkgs
-            .get(0)
+            .first()
             .and_then(|p| p.paramsfo.as_ref())
             .and_then(|pf| pf.title.as_ref())
             .map(|t| t.trim().to_string())
@@ -125,15 +237,11 @@ impl UpdateFetcher {
 
         let mut results: Vec<PackageInfo> = pkgs
             .into_iter()
-            .map(|p| Self::package_attr_to_info(p))
+            .map(Self::package_attr_to_info)
             .collect();
 
         // Sort by version (highest first)
-        results.sort_by(|a, b| {
-            let va = a.version.parse::<f32>().unwrap_or(0.0);
-            let vb = b.version.parse::<f32>().unwrap_or(0.0);
-            vb.partial_cmp(&va).unwrap_or(std::cmp::Ordering::Equal)
-        });
+        results.sort_by(|a, b| compare_versions(&b.version, &a.version));
 
         Ok(FetchResult {
             results,
@@ -143,6 +251,31 @@ impl UpdateFetcher {
         })
     }
 
+    async fn fetch_xml_for(&self, cleaned: &str) -> Result<String> {
+        if cleaned.is_empty() {
+            return Err(PS3UpdateError::InvalidTitleId(
+                "Empty or invalid Title ID".into(),
+            ));
+        }
+
+        let url = format!(
+            "{}/tpl/np/{id}/{id}-ver.xml",
+            self.base_url,
+            id = cleaned
+        );
+
+        let resp = self.client.get(&url).send().await?;
+
+        if !resp.status().is_success() {
+            return Err(PS3UpdateError::NoUpdatesFound {
+                title_id: cleaned.to_string(),
+                suggestions: self.suggest(cleaned),
+            });
+        }
+
+        Ok(resp.text().await?)
+    }
+
     fn extract_title_from_xml(text: &str) -> Option<String> {
         if let Some(start) = text.find("<TITLE>") {
             if let Some(end) = text[start + 7..].find("</TITLE>") {
@@ -159,11 +292,11 @@ impl UpdateFetcher {
     fn extract_packages(tp: TitlePatch) -> Vec<PackageAttr> {
         let mut pkgs: Vec<PackageAttr> = vec![];
 
-        if let Some(tag) = tp.tag.or(tp.TAG) {
+        if let Some(tag) = tp.tag.or(tp.tag_upper) {
             if let Some(mut list) = tag.package {
                 pkgs.append(&mut list);
             }
-            if let Some(mut list) = tag.PACKAGE {
+            if let Some(mut list) = tag.package_upper {
                 pkgs.append(&mut list);
             }
         }
@@ -172,7 +305,7 @@ impl UpdateFetcher {
             if let Some(mut list) = tp.package {
                 pkgs.append(&mut list);
             }
-            if let Some(mut list) = tp.PACKAGE {
+            if let Some(mut list) = tp.package_upper {
                 pkgs.append(&mut list);
             }
         }
@@ -205,7 +338,7 @@ impl UpdateFetcher {
 
         let filename = url
             .split('/')
-            .last()
+            .next_back()
             .unwrap_or("update.pkg")
             .to_string();
 
@@ -226,3 +359,33 @@ impl Default for UpdateFetcher {
         Self::new().expect("Failed to create UpdateFetcher")
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const SAMPLE_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<titlepatch titleid="BLES00779">
+  <tag name="BLES00779_T2">
+    <package version="01.01" size="1024" digest="aa" url="http://example/a/EP0001-BLES00779_00-PATCH-A0101-V0100-PE.pkg" ps3_system_ver="03.4000"/>
+    <package version="01.02" size="2048" digest="bb" url="http://example/a/EP0001-BLES00779_00-PATCH-A0102-V0100-PE.pkg" ps3_system_ver="03.4100">
+      <PARAMSFO><TITLE>Test Game</TITLE></PARAMSFO>
+    </package>
+  </tag>
+</titlepatch>"#;
+
+    #[test]
+    fn test_parse_updates() {
+        let fetcher = UpdateFetcher::new().unwrap();
+        let result = fetcher.parse_updates("bles-00779", SAMPLE_XML).unwrap();
+        assert_eq!(result.cleaned_title_id, "BLES00779");
+        assert_eq!(result.game_title, "Test Game");
+        assert_eq!(result.results.len(), 2);
+        assert_eq!(result.results[0].version, "01.02");
+        assert_eq!(result.results[0].sha1, "bb");
+        assert_eq!(
+            result.results[1].filename,
+            "EP0001-BLES00779_00-PATCH-A0101-V0100-PE.pkg"
+        );
+    }
+}
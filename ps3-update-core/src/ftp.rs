@@ -0,0 +1,312 @@
+use crate::downloader::JobState;
+use crate::installed::{installed_from_sfo, merge_installed, InstallKind, InstalledGame};
+use crate::sfo::ParamSfo;
+use crate::types::{PS3UpdateError, ProgressInfo, Result};
+use crate::webman::WebMan;
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::ToSocketAddrs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use suppaftp::types::FileType;
+use suppaftp::FtpStream;
+
+/// Folder the PS3's Package Manager installs from
+pub const CONSOLE_PACKAGES_DIR: &str = "/dev_hdd0/packages";
+
+pub const DEFAULT_FTP_PORT: u16 = 21;
+
+/// FTP connection settings for a console running webMAN or multiMAN
+///
+/// Both accept any login by default, so the anonymous defaults usually work.
+#[derive(Debug, Clone)]
+pub struct ConsoleFtp {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub timeout: Duration,
+}
+
+impl ConsoleFtp {
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port: DEFAULT_FTP_PORT,
+            username: "anonymous".to_string(),
+            password: String::new(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    pub fn with_login(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = username.into();
+        self.password = password.into();
+        self
+    }
+
+    /// `ftp://` URL of a path on the console
+    pub fn url_for(&self, remote_path: &str) -> String {
+        format!("ftp://{}:{}{}", self.host, self.port, remote_path)
+    }
+
+    /// Open a logged-in binary-mode session (blocking)
+    pub fn connect(&self) -> Result<FtpStream> {
+        let addr = (self.host.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| PS3UpdateError::Ftp(format!("cannot resolve {}", self.host)))?;
+        let mut ftp = FtpStream::connect_timeout(addr, self.timeout)?;
+        ftp.login(self.username.as_str(), self.password.as_str())?;
+        ftp.transfer_type(FileType::Binary)?;
+        Ok(ftp)
+    }
+
+    /// Upload `local` into `remote_dir` (blocking), reporting `(sent, total)` bytes
+    ///
+    /// Returns the remote path of the uploaded file.
+    pub fn upload(
+        &self,
+        local: impl AsRef<Path>,
+        remote_dir: &str,
+        mut progress: impl FnMut(u64, u64),
+    ) -> Result<String> {
+        let local = local.as_ref();
+        let name = local.file_name().and_then(|n| n.to_str()).ok_or_else(|| {
+            PS3UpdateError::Ftp(format!("invalid file name: {}", local.display()))
+        })?;
+        let remote = remote_path(remote_dir, name);
+
+        let file = std::fs::File::open(local)?;
+        let total = file.metadata()?.len();
+        progress(0, total);
+
+        let mut ftp = self.connect()?;
+        // Usually exists already; a real problem shows up in the upload itself
+        let _ = ftp.mkdir(remote_dir);
+        let mut reader = CountingReader {
+            inner: file,
+            sent: 0,
+            on_read: |sent| progress(sent, total),
+        };
+        ftp.put_file(&remote, &mut reader)?;
+        let _ = ftp.quit();
+        Ok(remote)
+    }
+
+    /// List the games installed on the console with their versions (blocking)
+    ///
+    /// Reads `PARAM.SFO` from each `/dev_hdd0/game` folder (HDD titles and
+    /// disc-game patches) and from folder-format dumps in `GAMES`/`GAMEZ`.
+    /// Disc images in `PS3ISO` are not read. The result can go straight into
+    /// [`crate::check_outdated`].
+    pub fn scan_games(&self) -> Result<Vec<InstalledGame>> {
+        let mut ftp = self.connect()?;
+        let mut found = vec![];
+        for (dir, kind, sfo_path) in [
+            ("/dev_hdd0/game", InstallKind::Hdd, "PARAM.SFO"),
+            ("/dev_hdd0/GAMES", InstallKind::Disc, "PS3_GAME/PARAM.SFO"),
+            ("/dev_hdd0/GAMEZ", InstallKind::Disc, "PS3_GAME/PARAM.SFO"),
+        ] {
+            // Missing folders are normal (no dumps on this console)
+            let Ok(entries) = ftp.nlst(Some(dir)) else {
+                continue;
+            };
+            for name in entries.iter().filter_map(|e| entry_name(e)) {
+                let path = format!("{}/{}/{}", dir, name, sfo_path);
+                let Ok(bytes) = ftp.retr_as_buffer(&path) else {
+                    continue;
+                };
+                let Ok(sfo) = ParamSfo::parse(bytes.get_ref()) else {
+                    continue;
+                };
+                let folder = path.trim_end_matches("/PARAM.SFO");
+                found.extend(installed_from_sfo(&sfo, kind, PathBuf::from(folder)));
+            }
+        }
+        let _ = ftp.quit();
+        Ok(merge_installed(found))
+    }
+}
+
+/// Uploads packages to a console in the background, tracked like downloads
+pub struct FtpUploader {
+    console: ConsoleFtp,
+    jobs: Arc<Mutex<HashMap<String, JobState>>>,
+    install: Option<WebMan>,
+}
+
+impl FtpUploader {
+    pub fn new(console: ConsoleFtp) -> Self {
+        Self {
+            console,
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            install: None,
+        }
+    }
+
+    /// Ask webMAN to install each package once its upload finishes
+    ///
+    /// A failed install request marks the job as failed.
+    pub fn with_install(mut self, webman: WebMan) -> Self {
+        self.install = Some(webman);
+        self
+    }
+
+    pub fn console(&self) -> &ConsoleFtp {
+        &self.console
+    }
+
+    /// Start uploading a PKG to the console's `/dev_hdd0/packages`
+    pub fn start_upload(&self, local: PathBuf) -> Result<String> {
+        self.start_upload_to(local, CONSOLE_PACKAGES_DIR)
+    }
+
+    /// Start uploading a file into `remote_dir` and return a job ID for tracking
+    ///
+    /// [`ProgressInfo::downloaded`] counts bytes sent, and
+    /// [`ProgressInfo::source`] holds the file's `ftp://` URL on the console.
+    pub fn start_upload_to(&self, local: PathBuf, remote_dir: &str) -> Result<String> {
+        let filename = local
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("update.pkg")
+            .to_string();
+        let remote_dir = remote_dir.to_string();
+        let job_id = format!("{:x}", rand::random::<u64>());
+
+        let mut job = JobState::new(filename.clone());
+        job.source = Some(self.console.url_for(&remote_path(&remote_dir, &filename)));
+        self.jobs.lock().unwrap().insert(job_id.clone(), job);
+
+        let console = self.console.clone();
+        let install = self.install.clone();
+        let jobs = self.jobs.clone();
+        let job_id_clone = job_id.clone();
+        tokio::spawn(async move {
+            let upload_jobs = jobs.clone();
+            let upload_id = job_id_clone.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                console.upload(&local, &remote_dir, |sent, total| {
+                    if let Some(job) = upload_jobs.lock().unwrap().get_mut(&upload_id) {
+                        job.downloaded = sent;
+                        job.total = total;
+                    }
+                })
+            })
+            .await
+            .map_err(PS3UpdateError::from)
+            .and_then(|r| r);
+
+            let result = match (result, &install) {
+                (Ok(remote), Some(webman)) => webman.install(&remote).await.map(|_| remote),
+                (other, _) => other,
+            };
+
+            let mut jobs = jobs.lock().unwrap();
+            if let Some(job) = jobs.get_mut(&job_id_clone) {
+                job.done = true;
+                if let Err(e) = result {
+                    job.fail(&e);
+                }
+            }
+        });
+
+        Ok(job_id)
+    }
+
+    /// Get progress information for an upload
+    pub fn get_progress(&self, job_id: &str) -> Result<ProgressInfo> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.get(job_id)
+            .map(JobState::progress)
+            .ok_or_else(|| PS3UpdateError::JobNotFound(job_id.to_string()))
+    }
+
+    /// Wait until an upload is done and return its final progress
+    pub async fn wait_for(&self, job_id: &str) -> Result<ProgressInfo> {
+        loop {
+            let progress = self.get_progress(job_id)?;
+            if progress.done {
+                return Ok(progress);
+            }
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+    }
+
+    /// Remove a completed upload from tracking
+    pub fn remove_job(&self, job_id: &str) {
+        self.jobs.lock().unwrap().remove(job_id);
+    }
+}
+
+struct CountingReader<R, F> {
+    inner: R,
+    sent: u64,
+    on_read: F,
+}
+
+impl<R: Read, F: FnMut(u64)> Read for CountingReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.sent += n as u64;
+        (self.on_read)(self.sent);
+        Ok(n)
+    }
+}
+
+/// Folder name from an `NLST` line, which some servers return as a full path
+fn entry_name(entry: &str) -> Option<&str> {
+    let name = entry.trim_end_matches(['/', '\r']).rsplit('/').next()?;
+    (!name.is_empty() && name != "." && name != "..").then_some(name)
+}
+
+fn remote_path(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_path() {
+        assert_eq!(
+            remote_path("/dev_hdd0/packages/", "a.pkg"),
+            "/dev_hdd0/packages/a.pkg"
+        );
+        let console = ConsoleFtp::new("192.168.1.20");
+        assert_eq!(
+            console.url_for(&remote_path(CONSOLE_PACKAGES_DIR, "a.pkg")),
+            "ftp://192.168.1.20:21/dev_hdd0/packages/a.pkg"
+        );
+    }
+
+    #[test]
+    fn test_entry_name() {
+        assert_eq!(entry_name("BLES00779"), Some("BLES00779"));
+        assert_eq!(entry_name("/dev_hdd0/game/NPEB00001/"), Some("NPEB00001"));
+        assert_eq!(entry_name(".."), None);
+    }
+
+    #[tokio::test]
+    async fn test_upload_failure_is_reported() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("update.pkg");
+        std::fs::write(&path, b"data").unwrap();
+
+        // Nothing listens on port 1
+        let uploader = FtpUploader::new(ConsoleFtp::new("127.0.0.1").with_port(1));
+        let job_id = uploader.start_upload(path.clone()).unwrap();
+        let progress = uploader.wait_for(&job_id).await.unwrap();
+        assert!(progress.done);
+        assert!(progress.error.is_some());
+        assert_eq!(progress.total, 4);
+    }
+}
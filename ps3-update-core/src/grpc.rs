@@ -0,0 +1,313 @@
+use crate::server::{ApiError, ApiServer, ApiState, DownloadRequest, JobInfo, EVENT_INTERVAL};
+use crate::types::{PS3UpdateError, PackageInfo, Result};
+use axum::http::StatusCode;
+use futures_util::stream::{self, Stream};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::metadata::MetadataValue;
+use tonic::{Request, Response, Status};
+
+/// Code generated from `proto/ps3update.proto`
+pub mod proto {
+    tonic::include_proto!("ps3update.v1");
+}
+
+use proto::ps3_update_server::{Ps3Update, Ps3UpdateServer};
+
+/// The `ps3update.v1.Ps3Update` gRPC service
+///
+/// Created by [`ApiServer::grpc_service`]; its jobs are the JSON API's jobs.
+pub struct GrpcService {
+    state: Arc<ApiState>,
+}
+
+impl ApiServer {
+    /// The gRPC service, for adding to a `tonic` server next to other services
+    pub fn grpc_service(&self) -> Ps3UpdateServer<GrpcService> {
+        Ps3UpdateServer::new(GrpcService {
+            state: self.state.clone(),
+        })
+    }
+
+    /// Serve the gRPC API on `addr` until the process exits
+    pub async fn serve_grpc(&self, addr: SocketAddr) -> Result<()> {
+        tonic::transport::Server::builder()
+            .add_service(self.grpc_service())
+            .serve(addr)
+            .await?;
+        Ok(())
+    }
+}
+
+/// The error's code rides along as `error-code` metadata
+impl From<ApiError> for Status {
+    fn from(ApiError(status, code, message, _): ApiError) -> Self {
+        let mut status = match status {
+            StatusCode::BAD_REQUEST => Status::invalid_argument(message),
+            StatusCode::NOT_FOUND => Status::not_found(message),
+            StatusCode::BAD_GATEWAY => Status::unavailable(message),
+            _ => Status::internal(message),
+        };
+        status
+            .metadata_mut()
+            .insert("error-code", MetadataValue::from_static(code));
+        status
+    }
+}
+
+fn to_status(e: PS3UpdateError) -> Status {
+    ApiError::from(e).into()
+}
+
+impl From<PackageInfo> for proto::Package {
+    fn from(pkg: PackageInfo) -> Self {
+        proto::Package {
+            version: pkg.version,
+            system_ver: pkg.system_ver,
+            size_bytes: pkg.size_bytes,
+            size_human: pkg.size_human,
+            url: pkg.url,
+            sha1: pkg.sha1,
+            filename: pkg.filename,
+        }
+    }
+}
+
+impl From<JobInfo> for proto::Job {
+    fn from(job: JobInfo) -> Self {
+        let progress = job.progress;
+        proto::Job {
+            id: job.id,
+            filename: progress.filename.unwrap_or_default(),
+            total: progress.total,
+            downloaded: progress.downloaded,
+            percent: progress.percent,
+            speed_bytes_per_sec: progress.speed_bytes_per_sec,
+            speed_human: progress.speed_human,
+            done: progress.done,
+            error: progress.error,
+            source: progress.source,
+        }
+    }
+}
+
+impl GrpcService {
+    fn job(&self, id: String) -> std::result::Result<proto::Job, Status> {
+        let progress = self.state.downloads.get_progress(&id).map_err(to_status)?;
+        Ok(JobInfo { id, progress }.into())
+    }
+}
+
+type JobStream = Pin<Box<dyn Stream<Item = std::result::Result<proto::Job, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Ps3Update for GrpcService {
+    async fn fetch_updates(
+        &self,
+        request: Request<proto::FetchUpdatesRequest>,
+    ) -> std::result::Result<Response<proto::FetchUpdatesResponse>, Status> {
+        let result = self
+            .state
+            .fetcher
+            .fetch_updates(&request.into_inner().title_id)
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(proto::FetchUpdatesResponse {
+            title_id: result.cleaned_title_id,
+            game_title: result.game_title,
+            packages: result.results.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn start_download(
+        &self,
+        request: Request<proto::StartDownloadRequest>,
+    ) -> std::result::Result<Response<proto::Job>, Status> {
+        let request = request.into_inner();
+        let job = self
+            .state
+            .start_download(&DownloadRequest {
+                url: request.url,
+                title_id: request.title_id,
+                filename: request.filename,
+                parts: Some(request.parts as usize),
+                mode: None,
+                resume: request.resume,
+                verify: request.verify,
+                retries: request.retries,
+            })
+            .await?;
+        Ok(Response::new(job.into()))
+    }
+
+    async fn get_job(
+        &self,
+        request: Request<proto::JobRequest>,
+    ) -> std::result::Result<Response<proto::Job>, Status> {
+        Ok(Response::new(self.job(request.into_inner().id)?))
+    }
+
+    async fn list_jobs(
+        &self,
+        _request: Request<proto::ListJobsRequest>,
+    ) -> std::result::Result<Response<proto::ListJobsResponse>, Status> {
+        let jobs = self
+            .state
+            .downloads
+            .jobs()
+            .into_iter()
+            .map(|(id, progress)| JobInfo { id, progress }.into())
+            .collect();
+        Ok(Response::new(proto::ListJobsResponse { jobs }))
+    }
+
+    async fn remove_job(
+        &self,
+        request: Request<proto::JobRequest>,
+    ) -> std::result::Result<Response<proto::RemoveJobResponse>, Status> {
+        let id = request.into_inner().id;
+        self.job(id.clone())?;
+        self.state.downloads.remove_job(&id);
+        Ok(Response::new(proto::RemoveJobResponse {}))
+    }
+
+    type WatchJobStream = JobStream;
+
+    async fn watch_job(
+        &self,
+        request: Request<proto::JobRequest>,
+    ) -> std::result::Result<Response<Self::WatchJobStream>, Status> {
+        let id = request.into_inner().id;
+        let first = self.job(id.clone())?;
+        let state = self.state.clone();
+        // (job to send, or the last one sent; whether it was sent)
+        let stream = stream::unfold((first, false), move |(last, sent)| {
+            let state = state.clone();
+            let id = id.clone();
+            async move {
+                if !sent {
+                    return Some((Ok(last.clone()), (last, true)));
+                }
+                if last.done {
+                    return None;
+                }
+                loop {
+                    tokio::time::sleep(EVENT_INTERVAL).await;
+                    let next: proto::Job = match state.downloads.get_progress(&id) {
+                        Ok(progress) => JobInfo {
+                            id: id.clone(),
+                            progress,
+                        }
+                        .into(),
+                        // Removed while being watched
+                        Err(e) => {
+                            let mut end = last.clone();
+                            end.done = true;
+                            return Some((Err(to_status(e)), (end, true)));
+                        }
+                    };
+                    // Speed changes with every poll; only send real progress
+                    if (next.downloaded, next.total, next.done)
+                        != (last.downloaded, last.total, last.done)
+                    {
+                        return Some((Ok(next.clone()), (next, true)));
+                    }
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "test-util")]
+    use futures_util::StreamExt;
+    #[cfg(feature = "test-util")]
+    use tonic::Code;
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_grpc_jobs() {
+        let mock = crate::MockUpdateServer::new().start().await.unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let server = ApiServer::new(dir).unwrap();
+        let service = GrpcService {
+            state: server.state.clone(),
+        };
+
+        let missing = service
+            .get_job(Request::new(proto::JobRequest { id: "nope".into() }))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), Code::NotFound);
+        assert_eq!(
+            missing.metadata().get("error-code").unwrap(),
+            "job_not_found"
+        );
+
+        let refused = service
+            .start_download(Request::new(proto::StartDownloadRequest {
+                url: "ftp://example/a.pkg".into(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(refused.code(), Code::InvalidArgument);
+
+        // The mock server has no such file, so the job fails quickly
+        let job = service
+            .start_download(Request::new(proto::StartDownloadRequest {
+                url: mock.url("/a.pkg"),
+                title_id: Some("BLES00779".into()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(job.filename, "a.pkg");
+
+        let updates: Vec<_> = service
+            .watch_job(Request::new(proto::JobRequest { id: job.id.clone() }))
+            .await
+            .unwrap()
+            .into_inner()
+            .collect()
+            .await;
+        let last = updates.last().unwrap().as_ref().unwrap();
+        assert!(last.done);
+        assert!(last.error.is_some());
+
+        let jobs = service
+            .list_jobs(Request::new(proto::ListJobsRequest {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .jobs;
+        assert_eq!(jobs.len(), 1);
+        // The JSON API sees the same job
+        assert!(server.state.downloads.get_progress(&job.id).is_ok());
+
+        service
+            .remove_job(Request::new(proto::JobRequest { id: job.id }))
+            .await
+            .unwrap();
+        assert!(server.state.downloads.jobs().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_serve_grpc_address_in_use() {
+        let tmp = tempfile::tempdir().unwrap();
+        let server = ApiServer::new(tmp.path()).unwrap();
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let e = server
+            .serve_grpc(taken.local_addr().unwrap())
+            .await
+            .unwrap_err();
+        assert_eq!(e.code(), "server");
+    }
+}
@@ -0,0 +1,373 @@
+use crate::iso::IsoImage;
+use crate::sfo::ParamSfo;
+use crate::types::Result;
+use crate::utils::{clean_title_id, compare_versions};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Where an installed game was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstallKind {
+    /// Installed to the (emulated) internal drive, e.g. PSN titles
+    Hdd,
+    /// Disc game extracted to a folder (`PS3_GAME/PARAM.SFO` layout)
+    Disc,
+    /// Disc image (`.iso`)
+    Iso,
+}
+
+/// A game found by one of the scanners, with its current version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledGame {
+    pub title_id: String,
+    pub title: String,
+    /// Highest `APP_VER` found for the title, including installed patches
+    pub app_version: Option<String>,
+    pub category: Option<String>,
+    pub kind: InstallKind,
+    /// Folder holding the game's `PARAM.SFO`
+    pub path: PathBuf,
+}
+
+/// List the games installed in an RPCS3 directory
+///
+/// Reads `dev_hdd0/game/*/PARAM.SFO` for HDD titles and installed patches,
+/// plus disc games under `games/` and those registered in `games.yml`. A
+/// title that appears several times (a disc game and its patch data, say) is
+/// reported once with the highest version found.
+pub fn scan_rpcs3(root: impl AsRef<Path>) -> Result<Vec<InstalledGame>> {
+    let root = root.as_ref();
+    let mut found = vec![];
+
+    for dir in subdirs(&root.join("dev_hdd0").join("game"))? {
+        found.extend(read_game(&dir, InstallKind::Hdd));
+    }
+
+    let mut disc_dirs = subdirs(&root.join("games"))?;
+    if let Ok(text) = std::fs::read_to_string(root.join("games.yml")) {
+        disc_dirs.extend(parse_games_yml(&text));
+    }
+    for dir in disc_dirs {
+        found.extend(read_disc_folder(&dir));
+    }
+
+    Ok(merge_installed(found))
+}
+
+/// List the games in every `.iso` file under `dir`
+///
+/// Title, version and category come from `PS3_GAME/PARAM.SFO`. Encrypted
+/// images whose `PARAM.SFO` can't be read are still listed by the title ID in
+/// `PS3_DISC.SFB`, without a version. Files that aren't PS3 images are skipped.
+pub fn scan_isos(dir: impl AsRef<Path>) -> Result<Vec<InstalledGame>> {
+    let mut found = vec![];
+    for path in find_files(dir.as_ref(), "iso")? {
+        found.extend(read_iso(&path));
+    }
+    Ok(merge_installed(found))
+}
+
+/// List the folder-format (JB) game dumps under `dir`
+///
+/// Any folder holding `PS3_GAME/PARAM.SFO` is a game, as laid out by
+/// multiMAN and webMAN in `GAMES`/`GAMEZ`; folders inside a game are not
+/// searched further.
+pub fn scan_game_folders(dir: impl AsRef<Path>) -> Result<Vec<InstalledGame>> {
+    let mut found = vec![];
+    let mut pending = vec![dir.as_ref().to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        match read_game(&dir.join("PS3_GAME"), InstallKind::Disc) {
+            Some(game) => found.push(game),
+            None => pending.extend(subdirs(&dir)?),
+        }
+    }
+    Ok(merge_installed(found))
+}
+
+/// Scan whatever `path` is: an RPCS3 directory, a single `.iso`, or a folder
+/// of ISOs and folder-format dumps (searched recursively)
+///
+/// An RPCS3 directory is recognised by its `dev_hdd0` folder.
+pub fn scan_path(path: impl AsRef<Path>) -> Result<Vec<InstalledGame>> {
+    let path = path.as_ref();
+    if path.is_file() {
+        return Ok(read_iso(path).into_iter().collect());
+    }
+    if path.join("dev_hdd0").is_dir() {
+        return scan_rpcs3(path);
+    }
+    let mut found = scan_isos(path)?;
+    found.extend(scan_game_folders(path)?);
+    Ok(merge_installed(found))
+}
+
+fn read_iso(path: &Path) -> Option<InstalledGame> {
+    let mut iso = IsoImage::open(path).ok()?;
+    if let Some(game) = iso
+        .param_sfo()
+        .ok()
+        .flatten()
+        .and_then(|sfo| installed_from_sfo(&sfo, InstallKind::Iso, path.to_path_buf()))
+    {
+        return Some(game);
+    }
+    let title_id = iso.title_id().ok().flatten()?;
+    Some(InstalledGame {
+        title_id,
+        title: String::new(),
+        app_version: None,
+        category: None,
+        kind: InstallKind::Iso,
+        path: path.to_path_buf(),
+    })
+}
+
+/// Read a disc game folder, with or without the `PS3_GAME` level
+pub(crate) fn read_disc_folder(dir: &Path) -> Option<InstalledGame> {
+    read_game(&dir.join("PS3_GAME"), InstallKind::Disc)
+        .or_else(|| read_game(dir, InstallKind::Disc))
+}
+
+fn read_game(dir: &Path, kind: InstallKind) -> Option<InstalledGame> {
+    let sfo = ParamSfo::load(dir.join("PARAM.SFO")).ok()?;
+    installed_from_sfo(&sfo, kind, dir.to_path_buf())
+}
+
+pub(crate) fn installed_from_sfo(
+    sfo: &ParamSfo,
+    kind: InstallKind,
+    path: PathBuf,
+) -> Option<InstalledGame> {
+    let title_id = clean_title_id(sfo.title_id()?);
+    if title_id.is_empty() {
+        return None;
+    }
+    Some(InstalledGame {
+        title_id,
+        title: sfo.title().unwrap_or_default().to_string(),
+        app_version: sfo.app_version().map(str::to_string),
+        category: sfo.category().map(str::to_string),
+        kind,
+        path,
+    })
+}
+
+/// Collapse entries sharing a title ID, keeping the highest version
+///
+/// Disc entries win over HDD ones for the title and path, since a disc
+/// game's HDD folder only holds its patch data.
+pub(crate) fn merge_installed(found: Vec<InstalledGame>) -> Vec<InstalledGame> {
+    let mut merged: BTreeMap<String, InstalledGame> = BTreeMap::new();
+    for game in found {
+        let Some(existing) = merged.get_mut(&game.title_id) else {
+            merged.insert(game.title_id.clone(), game);
+            continue;
+        };
+        let newer = match (&existing.app_version, &game.app_version) {
+            (Some(a), Some(b)) => compare_versions(b, a).is_gt(),
+            (None, Some(_)) => true,
+            _ => false,
+        };
+        let version = if newer {
+            game.app_version.clone()
+        } else {
+            existing.app_version.clone()
+        };
+        if game.kind == InstallKind::Disc && existing.kind != InstallKind::Disc {
+            *existing = game;
+        }
+        existing.app_version = version;
+    }
+    merged.into_values().collect()
+}
+
+fn subdirs(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+    let mut dirs = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            dirs.push(path);
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Files under `dir` with the given extension (case-insensitive), in path order
+fn find_files(dir: &Path, extension: &str) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for path in subdirs_and_files(dir)? {
+        if path.is_dir() {
+            files.extend(find_files(&path, extension)?);
+        } else if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+        {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+fn subdirs_and_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = std::fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+    Ok(paths)
+}
+
+/// Game paths from RPCS3's `games.yml` (`BLES00779: /path/to/game/` lines)
+fn parse_games_yml(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .filter_map(|line| {
+            let (_, path) = line.split_once(':')?;
+            let path = path.trim().trim_matches(|c| c == '"' || c == '\'');
+            (!path.is_empty()).then(|| PathBuf::from(path))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::iso::tests::sample_iso;
+    use crate::sfo::tests::sample_sfo;
+
+    pub(crate) fn write_sfo(dir: &Path, title_id: &str, category: &str, version: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        let sfo = sample_sfo(&[
+            ("APP_VER", version),
+            ("CATEGORY", category),
+            ("TITLE", "Test Game"),
+            ("TITLE_ID", title_id),
+        ]);
+        std::fs::write(dir.join("PARAM.SFO"), sfo).unwrap();
+    }
+
+    #[test]
+    fn test_parse_games_yml() {
+        let text = "BLES00779: /games/Test Game/\nBLUS30001: \"C:/Games/Other/\"\n";
+        assert_eq!(
+            parse_games_yml(text),
+            vec![
+                PathBuf::from("/games/Test Game/"),
+                PathBuf::from("C:/Games/Other/")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_rpcs3() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let hdd = root.join("dev_hdd0/game");
+        write_sfo(&hdd.join("NPEB00001"), "NPEB00001", "HG", "01.00");
+        // Patch data for the disc game below
+        write_sfo(&hdd.join("BLES00779"), "BLES00779", "GD", "01.05");
+        write_sfo(
+            &root.join("games/Test/PS3_GAME"),
+            "BLES00779",
+            "DG",
+            "01.00",
+        );
+        // Registered outside the RPCS3 folder
+        let external = root.join("elsewhere/Other");
+        write_sfo(&external.join("PS3_GAME"), "BLUS30001", "DG", "01.02");
+        std::fs::write(
+            root.join("games.yml"),
+            format!("BLUS30001: {}/\n", external.display()),
+        )
+        .unwrap();
+
+        let games = scan_rpcs3(root).unwrap();
+        let ids: Vec<_> = games.iter().map(|g| g.title_id.as_str()).collect();
+        assert_eq!(ids, ["BLES00779", "BLUS30001", "NPEB00001"]);
+        assert_eq!(games[0].kind, InstallKind::Disc);
+        assert_eq!(games[0].app_version.as_deref(), Some("01.05"));
+        assert_eq!(games[0].path, root.join("games/Test/PS3_GAME"));
+        assert_eq!(games[2].kind, InstallKind::Hdd);
+    }
+
+    #[test]
+    fn test_scan_game_folders() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        write_sfo(
+            &root.join("GAMES/Test Game [BLES00779]/PS3_GAME"),
+            "BLES00779",
+            "DG",
+            "01.00",
+        );
+        write_sfo(
+            &root.join("GAMEZ/BLUS30001/PS3_GAME"),
+            "BLUS30001",
+            "DG",
+            "01.02",
+        );
+        // Not a dump: PARAM.SFO without the PS3_GAME level
+        write_sfo(&root.join("GAMES/loose"), "NPEB00001", "HG", "01.00");
+
+        let games = scan_game_folders(root).unwrap();
+        let ids: Vec<_> = games.iter().map(|g| g.title_id.as_str()).collect();
+        assert_eq!(ids, ["BLES00779", "BLUS30001"]);
+        assert_eq!(
+            games[0].path,
+            root.join("GAMES/Test Game [BLES00779]/PS3_GAME")
+        );
+        assert_eq!(games[1].app_version.as_deref(), Some("01.02"));
+    }
+
+    #[test]
+    fn test_scan_isos() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        let sfo = sample_sfo(&[
+            ("APP_VER", "01.02"),
+            ("TITLE", "Test Game"),
+            ("TITLE_ID", "BLES00779"),
+        ]);
+        std::fs::write(root.join("game.ISO"), sample_iso("BLES-00779", Some(&sfo))).unwrap();
+        // Encrypted dump: only the SFB is readable
+        std::fs::write(root.join("sub/other.iso"), sample_iso("BLUS-30001", None)).unwrap();
+        std::fs::write(root.join("junk.iso"), b"not an image").unwrap();
+
+        let games = scan_isos(root).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].title, "Test Game");
+        assert_eq!(games[0].app_version.as_deref(), Some("01.02"));
+        assert_eq!(games[0].kind, InstallKind::Iso);
+        assert_eq!(games[1].title_id, "BLUS30001");
+        assert_eq!(games[1].app_version, None);
+        assert_eq!(games[1].path, root.join("sub/other.iso"));
+    }
+
+    #[test]
+    fn test_scan_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let iso = root.join("game.iso");
+        std::fs::write(&iso, sample_iso("BLUS-30001", None)).unwrap();
+        write_sfo(
+            &root.join("GAMES/BLES00779/PS3_GAME"),
+            "BLES00779",
+            "DG",
+            "01.00",
+        );
+
+        let ids: Vec<_> = scan_path(root)
+            .unwrap()
+            .into_iter()
+            .map(|g| g.title_id)
+            .collect();
+        assert_eq!(ids, ["BLES00779", "BLUS30001"]);
+        assert_eq!(scan_path(&iso).unwrap()[0].kind, InstallKind::Iso);
+    }
+}
@@ -0,0 +1,298 @@
+use crate::pkg::be_u32;
+use crate::sfo::ParamSfo;
+use crate::types::{PS3UpdateError, Result};
+use crate::utils::clean_title_id;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// ISO 9660 logical sector size
+pub const SECTOR_SIZE: u64 = 2048;
+
+const PVD_SECTOR: u64 = 16;
+const SFB_MAGIC: &[u8; 4] = b".SFB";
+
+/// A file or directory entry in an ISO 9660 directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsoEntry {
+    /// Name without the `;1` version suffix
+    pub name: String,
+    pub lba: u32,
+    pub size: u32,
+    pub is_dir: bool,
+}
+
+/// Read-only access to files in a PS3 disc image
+///
+/// Only the ISO 9660 file system is read. On encrypted (undecrypted) dumps
+/// `PARAM.SFO` may be unreadable, in which case [`IsoImage::title_id`] falls
+/// back to the plaintext `PS3_DISC.SFB`.
+pub struct IsoImage<R> {
+    reader: R,
+    root: IsoEntry,
+}
+
+impl IsoImage<File> {
+    /// Open an ISO file on disk
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> IsoImage<R> {
+    /// Read the primary volume descriptor from `reader`
+    pub fn new(mut reader: R) -> Result<Self> {
+        let pvd = read_sectors(&mut reader, PVD_SECTOR, SECTOR_SIZE as usize)?;
+        if pvd[0] != 1 || &pvd[1..6] != b"CD001" {
+            return Err(PS3UpdateError::InvalidIso(
+                "no primary volume descriptor".into(),
+            ));
+        }
+        let root = IsoEntry {
+            name: String::new(),
+            lba: u32::from_le_bytes(pvd[158..162].try_into().unwrap()),
+            size: u32::from_le_bytes(pvd[166..170].try_into().unwrap()),
+            is_dir: true,
+        };
+        Ok(Self { reader, root })
+    }
+
+    /// List a directory, e.g. `PS3_GAME`; the empty path is the root
+    pub fn read_dir(&mut self, path: &str) -> Result<Vec<IsoEntry>> {
+        let dir = self
+            .lookup(path)?
+            .filter(|e| e.is_dir)
+            .ok_or_else(|| PS3UpdateError::InvalidIso(format!("no such directory: {}", path)))?;
+        self.list(&dir)
+    }
+
+    /// Read a file by path (`/`-separated, case-insensitive)
+    pub fn read_file(&mut self, path: &str) -> Result<Option<Vec<u8>>> {
+        match self.lookup(path)? {
+            Some(entry) if !entry.is_dir => Ok(Some(read_sectors(
+                &mut self.reader,
+                entry.lba as u64,
+                entry.size as usize,
+            )?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Parse `PS3_GAME/PARAM.SFO`, if present and readable
+    pub fn param_sfo(&mut self) -> Result<Option<ParamSfo>> {
+        match self.read_file("PS3_GAME/PARAM.SFO")? {
+            Some(bytes) => Ok(ParamSfo::parse(&bytes).ok()),
+            None => Ok(None),
+        }
+    }
+
+    /// Title ID from `PARAM.SFO`, or `PS3_DISC.SFB` on encrypted images
+    pub fn title_id(&mut self) -> Result<Option<String>> {
+        let id = match self
+            .param_sfo()?
+            .and_then(|s| s.title_id().map(str::to_string))
+        {
+            Some(id) => Some(id),
+            None => self
+                .read_file("PS3_DISC.SFB")?
+                .and_then(|sfb| sfb_title_id(&sfb)),
+        };
+        Ok(id.map(|id| clean_title_id(&id)).filter(|id| !id.is_empty()))
+    }
+
+    fn lookup(&mut self, path: &str) -> Result<Option<IsoEntry>> {
+        let mut entry = self.root.clone();
+        for part in path.split('/').filter(|p| !p.is_empty()) {
+            if !entry.is_dir {
+                return Ok(None);
+            }
+            match self
+                .list(&entry)?
+                .into_iter()
+                .find(|e| e.name.eq_ignore_ascii_case(part))
+            {
+                Some(next) => entry = next,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(entry))
+    }
+
+    fn list(&mut self, dir: &IsoEntry) -> Result<Vec<IsoEntry>> {
+        let data = read_sectors(&mut self.reader, dir.lba as u64, dir.size as usize)?;
+        let mut entries = vec![];
+        let mut pos = 0;
+        while pos < data.len() {
+            let len = data[pos] as usize;
+            if len == 0 {
+                // Records never straddle sectors; skip the padding
+                pos = (pos / SECTOR_SIZE as usize + 1) * SECTOR_SIZE as usize;
+                continue;
+            }
+            let record = data
+                .get(pos..pos + len)
+                .ok_or_else(|| PS3UpdateError::InvalidIso("truncated directory".into()))?;
+            if let Some(entry) = parse_record(record) {
+                entries.push(entry);
+            }
+            pos += len;
+        }
+        Ok(entries)
+    }
+}
+
+/// Parse a directory record, skipping the `.` and `..` entries
+fn parse_record(record: &[u8]) -> Option<IsoEntry> {
+    if record.len() < 34 {
+        return None;
+    }
+    let name_len = record[32] as usize;
+    let name = record.get(33..33 + name_len)?;
+    let is_dir = record[25] & 0x02 != 0;
+    let name = match name {
+        [0] | [1] => return None,
+        _ => {
+            let name = String::from_utf8_lossy(name);
+            name.split(';').next().unwrap_or_default().to_string()
+        }
+    };
+    Some(IsoEntry {
+        name,
+        lba: u32::from_le_bytes(record[2..6].try_into().ok()?),
+        size: u32::from_le_bytes(record[10..14].try_into().ok()?),
+        is_dir,
+    })
+}
+
+fn read_sectors<R: Read + Seek>(reader: &mut R, lba: u64, len: usize) -> Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(lba * SECTOR_SIZE))?;
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Title ID from a `PS3_DISC.SFB` (`TITLE_ID` field, e.g. `BLES-00779`)
+fn sfb_title_id(sfb: &[u8]) -> Option<String> {
+    if sfb.get(0..4)? != SFB_MAGIC {
+        return None;
+    }
+    // Fixed-size 0x20-byte entries: key[0x10], offset, length, padding
+    sfb.get(0x20..0x200)?.chunks_exact(0x20).find_map(|entry| {
+        let key = entry[..0x10].split(|&b| b == 0).next()?;
+        if key != b"TITLE_ID" {
+            return None;
+        }
+        let offset = be_u32(entry, 0x10) as usize;
+        let len = be_u32(entry, 0x14) as usize;
+        let value = sfb.get(offset..offset + len)?;
+        let value = value.split(|&b| b == 0).next()?;
+        Some(String::from_utf8_lossy(value).trim().to_string())
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::sfo::tests::sample_sfo;
+    use std::io::Cursor;
+
+    fn record(name: &[u8], lba: u32, size: u32, is_dir: bool) -> Vec<u8> {
+        let mut len = 33 + name.len();
+        len += len % 2;
+        let mut rec = vec![0u8; len];
+        rec[0] = len as u8;
+        rec[2..6].copy_from_slice(&lba.to_le_bytes());
+        rec[10..14].copy_from_slice(&size.to_le_bytes());
+        rec[25] = if is_dir { 0x02 } else { 0 };
+        rec[32] = name.len() as u8;
+        rec[33..33 + name.len()].copy_from_slice(name);
+        rec
+    }
+
+    fn sample_sfb(title_id: &str) -> Vec<u8> {
+        let mut sfb = vec![0u8; 0x400];
+        sfb[0..4].copy_from_slice(SFB_MAGIC);
+        sfb[0x20..0x28].copy_from_slice(b"TITLE_ID");
+        sfb[0x30..0x34].copy_from_slice(&0x200u32.to_be_bytes());
+        sfb[0x34..0x38].copy_from_slice(&0x10u32.to_be_bytes());
+        sfb[0x200..0x200 + title_id.len()].copy_from_slice(title_id.as_bytes());
+        sfb
+    }
+
+    /// Build a minimal ISO with `PS3_DISC.SFB` and, optionally, `PS3_GAME/PARAM.SFO`
+    pub(crate) fn sample_iso(title_id: &str, sfo: Option<&[u8]>) -> Vec<u8> {
+        const ROOT: u32 = 20;
+        const GAME: u32 = 21;
+        const SFB: u32 = 22;
+        const SFO: u32 = 23;
+        let sector = SECTOR_SIZE as usize;
+        let mut iso = vec![0u8; 24 * sector + 0x1000];
+
+        let sfb = sample_sfb(title_id);
+        let sfo = sfo.unwrap_or_default();
+        let dir = |own: u32, parent: u32, entries: &[Vec<u8>]| {
+            let mut data = record(&[0], own, SECTOR_SIZE as u32, true);
+            data.extend(record(&[1], parent, SECTOR_SIZE as u32, true));
+            for e in entries {
+                data.extend(e);
+            }
+            data
+        };
+        let mut root = vec![record(b"PS3_DISC.SFB;1", SFB, sfb.len() as u32, false)];
+        if !sfo.is_empty() {
+            root.push(record(b"PS3_GAME", GAME, SECTOR_SIZE as u32, true));
+        }
+        let root = dir(ROOT, ROOT, &root);
+        let game = dir(
+            GAME,
+            ROOT,
+            &[record(b"PARAM.SFO;1", SFO, sfo.len() as u32, false)],
+        );
+
+        let pvd = 16 * sector;
+        iso[pvd] = 1;
+        iso[pvd + 1..pvd + 6].copy_from_slice(b"CD001");
+        let root_rec = record(&[0], ROOT, SECTOR_SIZE as u32, true);
+        iso[pvd + 156..pvd + 190].copy_from_slice(&root_rec);
+
+        let mut put = |lba: u32, data: &[u8]| {
+            let at = lba as usize * sector;
+            iso[at..at + data.len()].copy_from_slice(data);
+        };
+        put(ROOT, &root);
+        put(GAME, &game);
+        put(SFB, &sfb);
+        put(SFO, sfo);
+        iso
+    }
+
+    #[test]
+    fn test_read_iso() {
+        let sfo = sample_sfo(&[("TITLE_ID", "BLES00779"), ("APP_VER", "01.00")]);
+        let mut iso = IsoImage::new(Cursor::new(sample_iso("BLES-00779", Some(&sfo)))).unwrap();
+
+        let names: Vec<_> = iso
+            .read_dir("")
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, ["PS3_DISC.SFB", "PS3_GAME"]);
+        assert_eq!(iso.read_file("ps3_game/param.sfo").unwrap().unwrap(), sfo);
+        assert!(iso.read_file("PS3_GAME/MISSING").unwrap().is_none());
+        assert_eq!(
+            iso.param_sfo().unwrap().unwrap().app_version(),
+            Some("01.00")
+        );
+        assert_eq!(iso.title_id().unwrap().as_deref(), Some("BLES00779"));
+    }
+
+    #[test]
+    fn test_sfb_fallback() {
+        let mut iso = IsoImage::new(Cursor::new(sample_iso("BLES-00779", None))).unwrap();
+        assert!(iso.param_sfo().unwrap().is_none());
+        assert_eq!(iso.title_id().unwrap().as_deref(), Some("BLES00779"));
+
+        assert!(IsoImage::new(Cursor::new(vec![0u8; 64 * 1024])).is_err());
+    }
+}
@@ -7,7 +7,39 @@
 //! - Fetch available updates for any PS3 game by Title ID
 //! - Download update packages with progress tracking
 //! - Support for both single-threaded and multi-part downloads
+//! - Rate limiting, resume, retries and post-download verification
+//! - Export download lists for aria2c and wget, and Metalink (RFC 5854) files
 //! - Extract game metadata (title, version, size, SHA1 hash)
+//! - Inspect PKG headers of downloaded or unknown files and extract their PARAM.SFO
+//! - PARAM.SFO parsing
+//! - Split large PKGs into `.666XX` parts for FAT32 drives
+//! - Export packages to a PS3-ready USB drive layout
+//! - Verify PKGs against their trailing digest block
+//! - `sha1sum`-compatible checksum sidecars
+//! - Per-game `manifest.json` files describing downloaded packages
+//! - Configurable naming schemes for finished downloads
+//! - Library-wide verification against manifests, checksum files and the catalog
+//! - A `Library` type for managing a download directory: scan, status, sync and disk usage
+//! - Detect identical packages across regions and replace them with hard links
+//! - Retention policies that archive superseded update versions (zip/zstd with the `archive` feature)
+//! - Scan RPCS3 installations, ISO images, folder-format dumps and consoles (over FTP)
+//!   for games and their versions
+//! - Outdated-games report listing the packages each installed title is missing
+//! - CSV export of fetch results, outdated-games and verification reports
+//! - Watchlists that remember seen versions and report newly released updates,
+//!   with an Atom/RSS feed of the latest ones
+//! - Webhooks that POST JSON when downloads finish, verification fails or updates appear
+//! - Offline title ID → game name database
+//! - User-defined title ID aliases
+//! - Optional cover art / icon retrieval (`art` feature)
+//! - Optional SQLite catalog of every fetched package and download statistics (`catalog` feature)
+//! - Offline mirrors laid out like Sony's update server, optionally served over HTTP (`mirror-server` feature)
+//! - Push packages to a console running webMAN/multiMAN over FTP (`ftp` feature)
+//!   and queue them for installation through webMAN's web interface
+//! - A JSON API for listing updates and running downloads headless (`server` feature),
+//!   and the same over gRPC (`grpc` feature)
+//! - A mock update server serving canned update lists and packages, for tests
+//!   (`test-util` feature)
 //!
 //! ## Example
 //!
@@ -47,15 +79,127 @@
 //! }
 //! ```
 
+pub mod aliases;
+#[cfg(feature = "art")]
+pub mod art;
+#[cfg(feature = "catalog")]
+pub mod catalog;
+pub mod checksums;
+pub mod client;
+pub mod csv_export;
+pub mod dedup;
 pub mod downloader;
+pub mod export;
+pub mod feed;
 pub mod fetcher;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "ftp")]
+pub mod ftp;
+pub mod installed;
+pub mod iso;
+pub mod library;
+pub mod manifest;
+pub mod mirror;
+#[cfg(feature = "test-util")]
+pub mod mock_server;
+pub mod naming;
+#[cfg(feature = "mirror-server")]
+pub mod mirror_server;
+pub mod outdated;
+pub mod pkg;
+pub mod plan;
+pub mod queue;
+pub mod retention;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod sfo;
+pub mod split;
+pub mod title_id;
+pub mod titledb;
 pub mod types;
+pub mod usb;
 pub mod utils;
+pub mod verify;
+pub mod watch;
+pub mod webhook;
+pub mod webman;
 
 // Re-export main types for convenience
-pub use downloader::DownloadManager;
+pub use aliases::AliasMap;
+#[cfg(feature = "art")]
+pub use art::{ArtFetcher, ArtKind, ArtSource, Artwork};
+#[cfg(feature = "catalog")]
+pub use catalog::{
+    Catalog, CatalogPackage, CatalogSnapshot, CatalogTitle, DownloadRecord, DownloadStats,
+    ImportSummary, RecordSummary,
+};
+pub use checksums::{parse_sha1sums, write_sha1_sidecar, write_sha1sums};
+pub use client::{ClientOptions, PS3_USER_AGENT};
+pub use csv_export::write_fetch_csv;
+pub use dedup::{dedup_library, find_duplicates, DedupReport, DuplicateGroup};
+pub use downloader::{
+    ArchiveMirror, DownloadManager, DownloadOptions, PackageFilter, ProgressSubscription,
+};
+pub use export::{
+    aria2_input, metalink, plan_fetch, wget_script, ExportFormat, PlannedDownload,
+};
+pub use feed::{atom_feed, rss_feed, FeedFormat};
 pub use fetcher::UpdateFetcher;
+#[cfg(feature = "grpc")]
+pub use grpc::GrpcService;
+#[cfg(feature = "ftp")]
+pub use ftp::{ConsoleFtp, FtpUploader, CONSOLE_PACKAGES_DIR};
+pub use installed::{
+    scan_game_folders, scan_isos, scan_path, scan_rpcs3, InstallKind, InstalledGame,
+};
+pub use iso::{IsoEntry, IsoImage};
+#[cfg(feature = "catalog")]
+pub use library::verify_library_with_catalog;
+pub use library::{
+    verify_library, DiskUsage, FileCheck, FileStatus, Library, LibraryGame, LibraryReport,
+    LibraryStatus, LibrarySyncPlan, LibrarySyncReport, LibrarySyncTitle, TitleUsage,
+    VerifyProgress, VersionUsage,
+};
+pub use manifest::{record_download, GameManifest, ManifestEntry, ManifestPackage};
+pub use mirror::{
+    Mirror, MirrorCoverage, MirrorOptions, MirrorReport, MirrorTitleReport, TitleCoverage,
+};
+#[cfg(feature = "test-util")]
+pub use mock_server::{MockUpdateServer, RunningMockServer};
+pub use naming::{FolderTemplate, NamingContext, NamingScheme};
+#[cfg(feature = "mirror-server")]
+pub use mirror_server::MirrorServer;
+pub use outdated::{check_outdated, OutdatedReport, OutdatedTitle};
+pub use pkg::{check_package, PkgHeader, PkgItem, PkgPlatform, PkgReader, PkgRevision};
+pub use plan::{DownloadPlan, DownloadPlanReport, PlanItem};
+pub use queue::{QueueItem, QueueListener, QueueManager, QueueProgress, QueueStatus};
+pub use retention::{
+    apply_retention, plan_retention, ArchiveAction, RetentionPolicy, RetentionReport,
+    SupersededPackage,
+};
+#[cfg(feature = "server")]
+pub use server::{
+    ApiServer, DownloadRequest, JobEvent, JobEventKind, JobInfo, QueueRequest, QueueSnapshot,
+};
+pub use sfo::{ParamSfo, SfoValue};
+pub use split::{join_parts, split_pkg, split_pkg_to, DEFAULT_PART_SIZE};
+pub use title_id::{Region, TitleId};
+pub use titledb::{SuggestionReason, TitleDatabase, TitleEntry, TitleMatch, TitleSuggestion};
 pub use types::{
-    DownloadMode, FetchResult, PS3UpdateError, PackageInfo, ProgressInfo, Result,
+    DownloadMode, DownloadPhase, Failure, FetchResult, PS3UpdateError, PackageInfo, ProgressInfo,
+    Result,
+};
+pub use usb::{export_to_usb, TransferMethod, UsbExportOptions, UsbExportReport, UsbLayout};
+pub use utils::{
+    clean_title_id, compare_versions, format_size, format_size_with, parse_size, safe_dir_name,
+    unicode_dir_name, unix_timestamp, NameTemplate, SizeFormat, SizeUnits, MAX_DIR_NAME_BYTES,
+};
+pub use verify::{
+    file_sha1, file_sha1_with_progress, verify_download, verify_pkg, PkgVerification,
+};
+pub use watch::{
+    check_watchlist, DiscoveredUpdate, WatchReport, WatchState, WatchedTitle, FEED_ENTRIES,
 };
-pub use utils::{clean_title_id, format_size, safe_dir_name};
+pub use webhook::{Webhook, WebhookEvent, WebhookEventKind, WebhookFormat, WebhookNotifier};
+pub use webman::WebMan;
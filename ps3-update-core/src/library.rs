@@ -0,0 +1,1067 @@
+#[cfg(feature = "catalog")]
+use crate::catalog::Catalog;
+use crate::checksums::{parse_sha1sums, sidecar_path, SHA1SUMS};
+use crate::downloader::{DownloadManager, DownloadOptions};
+use crate::export::PlannedDownload;
+use crate::fetcher::UpdateFetcher;
+use crate::manifest::{GameManifest, ManifestPackage, MANIFEST_FILE};
+use crate::naming::{FolderTemplate, NamingScheme};
+use crate::pkg::PkgHeader;
+use crate::plan::{DownloadPlan, DownloadPlanReport, PlanItem};
+use crate::retention::{plan_retention, version_from_filename, RetentionPolicy};
+use crate::title_id::TitleId;
+use crate::types::{DownloadMode, Failure, FetchResult, PS3UpdateError, Result};
+use crate::utils::{clean_title_id, compare_versions};
+use crate::verify::{file_sha1, verify_pkg};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "typeshare")]
+use typeshare::typeshare;
+
+/// Outcome for one file in a library check
+#[cfg_attr(feature = "typeshare", typeshare)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileStatus {
+    /// Matches its recorded checksum
+    Ok,
+    /// Checksum, size or PKG digest doesn't match
+    Corrupt,
+    /// Listed in a manifest but not on disk
+    Missing,
+    /// Intact PKG with no reference data
+    Unknown,
+}
+
+/// Result of checking a single file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileCheck {
+    pub path: PathBuf,
+    pub status: FileStatus,
+    pub expected_sha1: Option<String>,
+    pub actual_sha1: Option<String>,
+    /// Why a file was flagged, when that isn't obvious from the hashes
+    pub detail: Option<String>,
+}
+
+/// Report produced by [`verify_library`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibraryReport {
+    pub files: Vec<FileCheck>,
+}
+
+impl LibraryReport {
+    pub fn count(&self, status: FileStatus) -> usize {
+        self.files.iter().filter(|f| f.status == status).count()
+    }
+
+    pub fn with_status(&self, status: FileStatus) -> impl Iterator<Item = &FileCheck> {
+        self.files.iter().filter(move |f| f.status == status)
+    }
+
+    /// True when nothing is corrupt or missing
+    pub fn is_healthy(&self) -> bool {
+        self.count(FileStatus::Corrupt) == 0 && self.count(FileStatus::Missing) == 0
+    }
+}
+
+/// Progress update passed to the [`verify_library`] callback before each file is hashed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyProgress {
+    /// Zero-based index of the file being checked
+    pub current: usize,
+    pub total: usize,
+    pub path: PathBuf,
+}
+
+/// Reference data for a file, from a manifest, SHA1SUMS list, sidecar or catalog
+#[derive(Debug, Clone, Default)]
+struct Expected {
+    sha1: Option<String>,
+    size: Option<u64>,
+}
+
+/// Re-hash every PKG under `root` against the library's own reference data
+///
+/// Checksums come from `manifest.json`, `SHA1SUMS` and `.sha1` sidecars. Files
+/// without any are checked against their internal PKG digest only and reported
+/// as [`FileStatus::Unknown`] when intact.
+pub fn verify_library(
+    root: impl AsRef<Path>,
+    progress: impl FnMut(&VerifyProgress),
+) -> Result<LibraryReport> {
+    verify_with(root.as_ref(), |_| None, progress)
+}
+
+/// Like [`verify_library`], but also uses the catalog for files without local reference data
+#[cfg(feature = "catalog")]
+pub fn verify_library_with_catalog(
+    root: impl AsRef<Path>,
+    catalog: &Catalog,
+    progress: impl FnMut(&VerifyProgress),
+) -> Result<LibraryReport> {
+    verify_with(
+        root.as_ref(),
+        |name| {
+            let pkg = catalog
+                .packages_by_filename(name)
+                .ok()?
+                .into_iter()
+                .next()?;
+            Some(Expected {
+                sha1: Some(pkg.sha1).filter(|s| !s.is_empty()),
+                size: Some(pkg.size_bytes).filter(|&s| s > 0),
+            })
+        },
+        progress,
+    )
+}
+
+fn verify_with(
+    root: &Path,
+    fallback: impl Fn(&str) -> Option<Expected>,
+    mut progress: impl FnMut(&VerifyProgress),
+) -> Result<LibraryReport> {
+    let mut pkgs = vec![];
+    let mut dirs = vec![];
+    walk(root, &mut pkgs, &mut dirs)?;
+
+    let mut expected: BTreeMap<PathBuf, Expected> = BTreeMap::new();
+    for dir in &dirs {
+        load_reference(dir, &mut expected);
+    }
+
+    let mut report = LibraryReport::default();
+    for (path, exp) in &expected {
+        if !path.is_file() {
+            report.files.push(FileCheck {
+                path: path.clone(),
+                status: FileStatus::Missing,
+                expected_sha1: exp.sha1.clone(),
+                actual_sha1: None,
+                detail: None,
+            });
+        }
+    }
+
+    let total = pkgs.len();
+    for (current, path) in pkgs.into_iter().enumerate() {
+        progress(&VerifyProgress {
+            current,
+            total,
+            path: path.clone(),
+        });
+        let exp = expected.get(&path).cloned().or_else(|| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(&fallback)
+        });
+        report.files.push(check_file(path, exp)?);
+    }
+    Ok(report)
+}
+
+fn check_file(path: PathBuf, expected: Option<Expected>) -> Result<FileCheck> {
+    let pkg = verify_pkg(&path);
+    let actual_sha1 = match &pkg {
+        Ok(v) => v.file_sha1.clone(),
+        Err(_) => file_sha1(&path)?,
+    };
+    let size = std::fs::metadata(&path)?.len();
+    let mut check = FileCheck {
+        path,
+        status: FileStatus::Ok,
+        expected_sha1: None,
+        actual_sha1: Some(actual_sha1),
+        detail: None,
+    };
+
+    let Some(expected) = expected.filter(|e| e.sha1.is_some() || e.size.is_some()) else {
+        // No reference data: rely on the package's own digest
+        match pkg {
+            Ok(v) if v.is_valid() => check.status = FileStatus::Unknown,
+            Ok(_) => {
+                check.status = FileStatus::Corrupt;
+                check.detail = Some("PKG digest mismatch".into());
+            }
+            Err(e) => {
+                check.status = FileStatus::Corrupt;
+                check.detail = Some(e.to_string());
+            }
+        }
+        return Ok(check);
+    };
+
+    check.expected_sha1 = expected.sha1.clone();
+    if let Some(want) = expected.size.filter(|&want| want != size) {
+        check.status = FileStatus::Corrupt;
+        check.detail = Some(format!("expected {} bytes, found {}", want, size));
+    } else if let Some(sha1) = &expected.sha1 {
+        let matches = match &pkg {
+            Ok(v) => v.matches_sha1(sha1),
+            Err(_) => check.actual_sha1.as_deref() == Some(sha1.to_lowercase().as_str()),
+        };
+        if !matches {
+            check.status = FileStatus::Corrupt;
+        }
+    }
+    Ok(check)
+}
+
+/// Collect `.pkg` files and every directory visited
+fn walk(dir: &Path, pkgs: &mut Vec<PathBuf>, dirs: &mut Vec<PathBuf>) -> Result<()> {
+    dirs.push(dir.to_path_buf());
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            walk(&path, pkgs, dirs)?;
+        } else if is_pkg(&path) {
+            pkgs.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Every `.pkg` file under `root`, in path order
+pub(crate) fn collect_pkgs(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut pkgs = vec![];
+    walk(root, &mut pkgs, &mut vec![])?;
+    Ok(pkgs)
+}
+
+pub(crate) fn is_pkg(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.eq_ignore_ascii_case("pkg"))
+        .unwrap_or(false)
+}
+
+/// Merge a folder's manifest, SHA1SUMS and sidecars into `expected`
+fn load_reference(dir: &Path, expected: &mut BTreeMap<PathBuf, Expected>) {
+    if dir.join(MANIFEST_FILE).is_file() {
+        if let Ok(manifest) = GameManifest::load(dir) {
+            for pkg in manifest.packages {
+                let entry = expected.entry(dir.join(&pkg.filename)).or_default();
+                entry.size = Some(pkg.size_bytes).filter(|&s| s > 0);
+                if !pkg.sha1.is_empty() {
+                    entry.sha1 = Some(pkg.sha1);
+                }
+            }
+        }
+    }
+
+    let mut set_sha1 = |name: &str, hash: String| {
+        let entry = expected.entry(dir.join(name)).or_default();
+        entry.sha1.get_or_insert(hash);
+    };
+    if let Ok(text) = std::fs::read_to_string(dir.join(SHA1SUMS)) {
+        for (hash, name) in parse_sha1sums(&text) {
+            set_sha1(&name, hash);
+        }
+    }
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for path in entries.flatten().map(|e| e.path()).filter(|p| is_pkg(p)) {
+            let sidecar = std::fs::read_to_string(sidecar_path(&path)).unwrap_or_default();
+            if let Some((hash, name)) = parse_sha1sums(&sidecar).into_iter().next() {
+                set_sha1(&name, hash);
+            }
+        }
+    }
+}
+
+/// A game folder inside a [`Library`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryGame {
+    pub dir: PathBuf,
+    /// From the manifest, the folder name or the first PKG's content ID
+    pub title_id: Option<String>,
+    pub game_name: String,
+    /// Packages recorded in the folder's manifest
+    pub packages: Vec<ManifestPackage>,
+    /// PKG files on disk that the manifest doesn't list
+    pub untracked: Vec<PathBuf>,
+}
+
+/// Summary of a [`Library`], computed without hashing anything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibraryStatus {
+    pub games: usize,
+    pub packages: usize,
+    pub size_bytes: u64,
+    pub untracked: Vec<PathBuf>,
+    /// Manifest entries whose file is gone
+    pub missing: Vec<PathBuf>,
+}
+
+/// Outcome of syncing one game in a [`Library`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibrarySyncTitle {
+    pub title_id: String,
+    pub downloaded: Vec<PathBuf>,
+    /// Files that failed, by URL
+    pub failed: Vec<Failure>,
+    pub error: Option<String>,
+    /// [`PS3UpdateError::code`] of `error`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+}
+
+/// What [`Library::sync_titles`] would download, from [`Library::sync_plan`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibrarySyncPlan {
+    pub downloads: Vec<PlannedDownload>,
+    /// Titles that couldn't be checked
+    pub errors: Vec<Failure>,
+}
+
+/// Outcome of [`Library::sync`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibrarySyncReport {
+    pub titles: Vec<LibrarySyncTitle>,
+}
+
+impl LibrarySyncReport {
+    /// True when every title was checked and every download succeeded
+    pub fn is_complete(&self) -> bool {
+        self.titles
+            .iter()
+            .all(|t| t.error.is_none() && t.failed.is_empty())
+    }
+
+    pub fn downloaded_count(&self) -> usize {
+        self.titles.iter().map(|t| t.downloaded.len()).sum()
+    }
+}
+
+/// Space taken by one package file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionUsage {
+    /// From the manifest or the file name, when known
+    pub version: Option<String>,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    /// A newer version of the same game is present, so this one can be archived
+    pub superseded: bool,
+}
+
+/// Space taken by one game folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TitleUsage {
+    pub title_id: Option<String>,
+    pub game_name: String,
+    pub dir: PathBuf,
+    pub size_bytes: u64,
+    /// Space held by superseded versions
+    pub superseded_bytes: u64,
+    /// Newest version first; files without a version come last
+    pub versions: Vec<VersionUsage>,
+}
+
+/// Result of [`Library::disk_usage`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiskUsage {
+    /// Largest game first
+    pub titles: Vec<TitleUsage>,
+    pub total_bytes: u64,
+    /// Space [`crate::apply_retention`] with `KeepLatest(1)` would free
+    pub superseded_bytes: u64,
+}
+
+/// A managed download directory with one `Game Name (TITLEID)` folder per game
+///
+/// Ties together manifests, verification and downloads so applications can
+/// treat the directory as a single collection.
+pub struct Library {
+    root: PathBuf,
+    options: DownloadOptions,
+    folder_template: FolderTemplate,
+    games: Vec<LibraryGame>,
+}
+
+impl Library {
+    /// Open (creating if needed) a library rooted at `root` and scan it
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        let mut library = Self {
+            root,
+            options: DownloadOptions::default(),
+            folder_template: FolderTemplate::default(),
+            games: vec![],
+        };
+        library.scan()?;
+        Ok(library)
+    }
+
+    /// Download mode used by [`Library::sync`]
+    pub fn with_download_mode(mut self, mode: DownloadMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    /// How folders are named for games not in the library yet
+    pub fn with_folder_template(mut self, template: FolderTemplate) -> Self {
+        self.folder_template = template;
+        self
+    }
+
+    /// File names given to packages downloaded by [`Library::sync`]
+    pub fn with_naming(mut self, scheme: NamingScheme) -> Self {
+        self.options.naming = scheme;
+        self
+    }
+
+    /// Base options for every download made by [`Library::sync`]
+    ///
+    /// Replaces the mode and naming scheme set so far. The title check and
+    /// manifest entry are always added per package.
+    pub fn with_download_options(mut self, options: DownloadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Games found by the last [`Library::scan`]
+    pub fn games(&self) -> &[LibraryGame] {
+        &self.games
+    }
+
+    pub fn game(&self, title_id: &str) -> Option<&LibraryGame> {
+        let title_id = clean_title_id(title_id);
+        self.games
+            .iter()
+            .find(|g| g.title_id.as_deref() == Some(title_id.as_str()))
+    }
+
+    /// Folder a game's packages are stored in, existing or not
+    pub fn game_dir(&self, title_id: &str, game_name: &str) -> PathBuf {
+        let title_id = clean_title_id(title_id);
+        match self.game(&title_id) {
+            Some(game) => game.dir.clone(),
+            None => self
+                .root
+                .join(self.folder_template.folder(game_name, &title_id)),
+        }
+    }
+
+    /// Re-read every game folder from disk
+    pub fn scan(&mut self) -> Result<&[LibraryGame]> {
+        let mut by_dir: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+        let mut dirs = vec![];
+        walk(&self.root, &mut vec![], &mut dirs)?;
+        for dir in dirs {
+            by_dir.entry(dir).or_default();
+        }
+        for pkg in collect_pkgs(&self.root)? {
+            if let Some(dir) = pkg.parent() {
+                by_dir.entry(dir.to_path_buf()).or_default().push(pkg);
+            }
+        }
+
+        self.games = by_dir
+            .into_iter()
+            .filter_map(|(dir, pkgs)| load_game(dir, pkgs))
+            .collect();
+        Ok(&self.games)
+    }
+
+    /// Counts and sizes from the last scan, plus files missing since
+    pub fn status(&self) -> LibraryStatus {
+        let mut status = LibraryStatus {
+            games: self.games.len(),
+            ..Default::default()
+        };
+        for game in &self.games {
+            for pkg in &game.packages {
+                let path = game.dir.join(&pkg.filename);
+                match std::fs::metadata(&path) {
+                    Ok(meta) => {
+                        status.packages += 1;
+                        status.size_bytes += meta.len();
+                    }
+                    Err(_) => status.missing.push(path),
+                }
+            }
+            for path in &game.untracked {
+                status.packages += 1;
+                status.size_bytes += std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+                status.untracked.push(path.clone());
+            }
+        }
+        status
+    }
+
+    /// Space used per game and per version, from the last scan
+    ///
+    /// Versions older than the newest of their title are flagged as
+    /// superseded, matching [`crate::plan_retention`] with `KeepLatest(1)`.
+    pub fn disk_usage(&self) -> Result<DiskUsage> {
+        let superseded: BTreeMap<PathBuf, String> =
+            plan_retention(&self.root, RetentionPolicy::KeepLatest(1))?
+                .into_iter()
+                .map(|p| (p.path, p.version))
+                .collect();
+
+        let mut usage = DiskUsage::default();
+        for game in &self.games {
+            let files = game
+                .packages
+                .iter()
+                .map(|p| (game.dir.join(&p.filename), Some(p.version.clone())))
+                .chain(game.untracked.iter().map(|path| {
+                    let name = path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or_default();
+                    (path.clone(), version_from_filename(name))
+                }));
+
+            let mut versions = vec![];
+            for (path, version) in files {
+                let Ok(meta) = std::fs::metadata(&path) else {
+                    continue;
+                };
+                versions.push(VersionUsage {
+                    superseded: superseded.contains_key(&path),
+                    version,
+                    path,
+                    size_bytes: meta.len(),
+                });
+            }
+            if versions.is_empty() {
+                continue;
+            }
+            versions.sort_by(|a, b| match (&a.version, &b.version) {
+                (Some(a), Some(b)) => compare_versions(b, a),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            });
+
+            let title = TitleUsage {
+                title_id: game.title_id.clone(),
+                game_name: game.game_name.clone(),
+                dir: game.dir.clone(),
+                size_bytes: versions.iter().map(|v| v.size_bytes).sum(),
+                superseded_bytes: versions
+                    .iter()
+                    .filter(|v| v.superseded)
+                    .map(|v| v.size_bytes)
+                    .sum(),
+                versions,
+            };
+            usage.total_bytes += title.size_bytes;
+            usage.superseded_bytes += title.superseded_bytes;
+            usage.titles.push(title);
+        }
+        usage
+            .titles
+            .sort_by_key(|t| std::cmp::Reverse(t.size_bytes));
+        Ok(usage)
+    }
+
+    /// Re-hash the library; see [`verify_library`]
+    pub fn verify(&self, progress: impl FnMut(&VerifyProgress)) -> Result<LibraryReport> {
+        verify_library(&self.root, progress)
+    }
+
+    /// Re-hash the library, falling back to the catalog; see [`verify_library_with_catalog`]
+    #[cfg(feature = "catalog")]
+    pub fn verify_with_catalog(
+        &self,
+        catalog: &Catalog,
+        progress: impl FnMut(&VerifyProgress),
+    ) -> Result<LibraryReport> {
+        verify_library_with_catalog(&self.root, catalog, progress)
+    }
+
+    /// Download every listed package each known game doesn't have yet
+    ///
+    /// Packages already on disk with the expected size are left alone. New
+    /// downloads are checked against the title and recorded in the game's
+    /// manifest. The library is rescanned afterwards.
+    pub async fn sync(
+        &mut self,
+        fetcher: &UpdateFetcher,
+        manager: &DownloadManager,
+    ) -> Result<LibrarySyncReport> {
+        let title_ids: Vec<String> = self
+            .games
+            .iter()
+            .filter_map(|g| g.title_id.clone())
+            .collect();
+        self.sync_titles(fetcher, manager, &title_ids).await
+    }
+
+    /// Like [`Library::sync`], for a list of titles that may not be in the library yet
+    ///
+    /// New titles get a `Game Name (TITLEID)` folder named after the update list.
+    pub async fn sync_titles(
+        &mut self,
+        fetcher: &UpdateFetcher,
+        manager: &DownloadManager,
+        title_ids: &[String],
+    ) -> Result<LibrarySyncReport> {
+        let mut report = LibrarySyncReport::default();
+        for title_id in title_ids {
+            let title = self.sync_one(fetcher, manager, title_id).await;
+            report.titles.push(title);
+        }
+        self.scan()?;
+        Ok(report)
+    }
+
+    /// Sync a single title and rescan the library
+    pub async fn sync_title(
+        &mut self,
+        fetcher: &UpdateFetcher,
+        manager: &DownloadManager,
+        title_id: &str,
+    ) -> Result<LibrarySyncTitle> {
+        let title = self.sync_one(fetcher, manager, title_id).await;
+        self.scan()?;
+        Ok(title)
+    }
+
+    async fn sync_one(
+        &self,
+        fetcher: &UpdateFetcher,
+        manager: &DownloadManager,
+        title_id: &str,
+    ) -> LibrarySyncTitle {
+        let title_id = clean_title_id(title_id);
+        match self.download_missing(fetcher, manager, &title_id).await {
+            Ok(title) => title,
+            Err(e) => LibrarySyncTitle {
+                title_id,
+                error: Some(e.to_string()),
+                error_code: Some(e.code().to_string()),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Fetch each title and list the packages a sync would download, without downloading
+    ///
+    /// The plan can be handed to other tools through [`crate::ExportFormat`].
+    pub async fn sync_plan(
+        &self,
+        fetcher: &UpdateFetcher,
+        title_ids: &[String],
+    ) -> LibrarySyncPlan {
+        let mut plan = LibrarySyncPlan::default();
+        for title_id in title_ids {
+            let title_id = clean_title_id(title_id);
+            match fetcher.fetch_updates(&title_id).await {
+                Ok(result) => plan.downloads.extend(self.plan_title(&result)),
+                Err(PS3UpdateError::NoUpdatesFound { .. }) => {}
+                Err(e) => plan.errors.push(Failure::new(title_id, &e)),
+            }
+        }
+        plan
+    }
+
+    /// Fetch each title and plan all of its packages, present ones included,
+    /// for a caller to confirm before [`Library::execute_plan`]
+    pub async fn download_plan(
+        &self,
+        fetcher: &UpdateFetcher,
+        title_ids: &[String],
+    ) -> DownloadPlan {
+        let mut items = vec![];
+        let mut errors = vec![];
+        for title_id in title_ids {
+            let title_id = clean_title_id(title_id);
+            match fetcher.fetch_updates(&title_id).await {
+                Ok(result) => items.extend(self.title_items(&result)),
+                Err(PS3UpdateError::NoUpdatesFound { .. }) => {}
+                Err(e) => errors.push(Failure::new(title_id, &e)),
+            }
+        }
+        DownloadPlan {
+            errors,
+            ..DownloadPlan::from_items(items)
+        }
+    }
+
+    /// Download a plan with the library's options, then rescan
+    pub async fn execute_plan(
+        &mut self,
+        plan: &DownloadPlan,
+        manager: &DownloadManager,
+    ) -> Result<DownloadPlanReport> {
+        let report = plan.execute(manager, &self.options).await?;
+        self.scan()?;
+        Ok(report)
+    }
+
+    /// Packages of a fetch that aren't in the library yet, and where they go
+    ///
+    /// A package counts as present when a file of the listed size exists
+    /// under its server name or under the name its manifest entry records.
+    pub fn plan_title(&self, result: &FetchResult) -> Vec<PlannedDownload> {
+        self.title_items(result)
+            .into_iter()
+            .filter(|item| !item.present)
+            .map(|item| item.download)
+            .collect()
+    }
+
+    fn title_items(&self, result: &FetchResult) -> Vec<PlanItem> {
+        let title_id = &result.cleaned_title_id;
+        let game_name = match self.game(title_id) {
+            Some(game) if !game.game_name.is_empty() => &game.game_name,
+            _ => &result.game_title,
+        };
+        let dir = self.game_dir(title_id, game_name);
+
+        // Renamed packages are found through the manifest
+        let recorded = GameManifest::load(&dir)
+            .map(|m| m.packages)
+            .unwrap_or_default();
+        result
+            .results
+            .iter()
+            .map(|pkg| PlanItem {
+                present: std::iter::once(dir.join(&pkg.filename))
+                    .chain(
+                        recorded
+                            .iter()
+                            .filter(|r| r.version == pkg.version)
+                            .map(|r| dir.join(&r.filename)),
+                    )
+                    .any(|path| {
+                        std::fs::metadata(path).map(|m| m.len()).ok() == Some(pkg.size_bytes)
+                    }),
+                download: PlannedDownload {
+                    title_id: title_id.clone(),
+                    game_title: game_name.clone(),
+                    package: pkg.clone(),
+                    dir: dir.clone(),
+                },
+            })
+            .collect()
+    }
+
+    async fn download_missing(
+        &self,
+        fetcher: &UpdateFetcher,
+        manager: &DownloadManager,
+        title_id: &str,
+    ) -> Result<LibrarySyncTitle> {
+        let result = match fetcher.fetch_updates(title_id).await {
+            Ok(result) => result,
+            // A title without patches has nothing to download
+            Err(PS3UpdateError::NoUpdatesFound { .. }) => {
+                return Ok(LibrarySyncTitle {
+                    title_id: title_id.to_string(),
+                    ..Default::default()
+                })
+            }
+            Err(e) => return Err(e),
+        };
+        let report = DownloadPlan::from_items(self.title_items(&result))
+            .execute(manager, &self.options)
+            .await?;
+        Ok(LibrarySyncTitle {
+            title_id: title_id.to_string(),
+            downloaded: report.downloaded,
+            failed: report.failed,
+            ..Default::default()
+        })
+    }
+}
+
+/// Describe a folder as a game, if it has a manifest or PKGs
+fn load_game(dir: PathBuf, pkgs: Vec<PathBuf>) -> Option<LibraryGame> {
+    let manifest = GameManifest::load(&dir).ok();
+    if manifest.is_none() && pkgs.is_empty() {
+        return None;
+    }
+
+    let folder = dir.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let (folder_name, folder_id) = split_folder_name(folder);
+    let title_id = manifest
+        .as_ref()
+        .map(|m| m.title_id.clone())
+        .or(folder_id)
+        .or_else(|| {
+            pkgs.iter().find_map(|p| {
+                let header = PkgHeader::read_from(p).ok()?;
+                Some(header.title_id()?.as_str().to_string())
+            })
+        });
+    let (game_name, packages) = match manifest {
+        Some(m) => (m.game_name, m.packages),
+        None => (folder_name.to_string(), vec![]),
+    };
+    let untracked = pkgs
+        .into_iter()
+        .filter(|p| {
+            let name = p.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            !packages.iter().any(|m| m.filename == name)
+        })
+        .collect();
+
+    Some(LibraryGame {
+        dir,
+        title_id,
+        game_name,
+        packages,
+        untracked,
+    })
+}
+
+/// Split `Game Name (BLES00779)` into its name and title ID
+fn split_folder_name(folder: &str) -> (&str, Option<String>) {
+    folder
+        .strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once(" ("))
+        .and_then(|(name, id)| Some((name, TitleId::parse(id).ok()?.as_str().to_string())))
+        .map(|(name, id)| (name, Some(id)))
+        .unwrap_or((folder, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksums::write_sha1_sidecar;
+    use crate::manifest::record_download;
+    use crate::types::PackageInfo;
+    use crate::verify::tests::sample_pkg;
+
+    fn package(filename: &str, data: &[u8], sha1: &str) -> PackageInfo {
+        PackageInfo {
+            version: "01.00".into(),
+            system_ver: String::new(),
+            size_bytes: data.len() as u64,
+            size_human: String::new(),
+            url: format!("http://example/{}", filename),
+            sha1: sha1.into(),
+            filename: filename.into(),
+        }
+    }
+
+    #[test]
+    fn test_verify_library() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let game = root.join("Test Game (BLES00779)");
+        std::fs::create_dir_all(&game).unwrap();
+        let data = sample_pkg("EP0001-BLES00779_00-PATCH00000000001");
+
+        // Tracked by the manifest and intact
+        std::fs::write(game.join("ok.pkg"), &data).unwrap();
+        let sha1 = file_sha1(game.join("ok.pkg")).unwrap();
+        record_download(
+            &game,
+            "BLES00779",
+            "Test",
+            &package("ok.pkg", &data, &sha1),
+            "",
+        )
+        .unwrap();
+
+        // Tracked but damaged on disk
+        let mut bad = data.clone();
+        bad[0xD0] ^= 1;
+        std::fs::write(game.join("bad.pkg"), &bad).unwrap();
+        record_download(
+            &game,
+            "BLES00779",
+            "Test",
+            &package("bad.pkg", &data, &sha1),
+            "",
+        )
+        .unwrap();
+
+        // Tracked but deleted
+        record_download(
+            &game,
+            "BLES00779",
+            "Test",
+            &package("gone.pkg", &data, &sha1),
+            "",
+        )
+        .unwrap();
+
+        // Only a sidecar
+        std::fs::write(root.join("side.pkg"), &data).unwrap();
+        write_sha1_sidecar(root.join("side.pkg")).unwrap();
+
+        // No reference data at all
+        std::fs::write(root.join("stray.pkg"), &data).unwrap();
+
+        let mut seen = 0;
+        let report = verify_library(root, |p| {
+            assert_eq!(p.total, 4);
+            seen += 1;
+        })
+        .unwrap();
+        assert_eq!(seen, 4);
+
+        let status = |name: &str| {
+            report
+                .files
+                .iter()
+                .find(|f| f.path.file_name().unwrap() == name)
+                .unwrap()
+                .status
+        };
+        assert_eq!(status("ok.pkg"), FileStatus::Ok);
+        assert_eq!(status("bad.pkg"), FileStatus::Corrupt);
+        assert_eq!(status("gone.pkg"), FileStatus::Missing);
+        assert_eq!(status("side.pkg"), FileStatus::Ok);
+        assert_eq!(status("stray.pkg"), FileStatus::Unknown);
+        assert!(!report.is_healthy());
+    }
+
+    #[test]
+    fn test_library_scan() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let tracked = root.join("Test Game (BLES00779)");
+        let named = root.join("Other Game (BLUS30001)");
+        std::fs::create_dir_all(&tracked).unwrap();
+        std::fs::create_dir_all(&named).unwrap();
+        std::fs::create_dir_all(root.join("empty")).unwrap();
+
+        std::fs::write(tracked.join("a.pkg"), b"abc").unwrap();
+        record_download(
+            &tracked,
+            "BLES00779",
+            "Test",
+            &package("a.pkg", b"abc", ""),
+            "",
+        )
+        .unwrap();
+        record_download(
+            &tracked,
+            "BLES00779",
+            "Test",
+            &package("gone.pkg", b"", ""),
+            "",
+        )
+        .unwrap();
+        std::fs::write(named.join("b.pkg"), b"hello").unwrap();
+
+        let library = Library::open(root).unwrap();
+        assert_eq!(library.games().len(), 2);
+        let other = library.game("BLUS-30001").unwrap();
+        assert_eq!(other.game_name, "Other Game");
+        assert_eq!(other.untracked, vec![named.join("b.pkg")]);
+        assert_eq!(library.game("BLES00779").unwrap().game_name, "Test");
+        assert_eq!(library.game_dir("BLES00779", "Ignored"), tracked);
+        assert_eq!(
+            library.game_dir("NPEB00001", "New: Game"),
+            root.join("New Game (NPEB00001)")
+        );
+
+        let status = library.status();
+        assert_eq!(status.games, 2);
+        assert_eq!(status.packages, 2);
+        assert_eq!(status.size_bytes, 8);
+        assert_eq!(status.missing, vec![tracked.join("gone.pkg")]);
+        assert_eq!(status.untracked.len(), 1);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_library_sync() {
+        let server = crate::MockUpdateServer::new()
+            .with_title("BLES00779", "Demon's Souls", &["01.01", "01.02"])
+            .start()
+            .await
+            .unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let patched = root.join("Demon's Souls (BLES00779)");
+        // BCUS98111 has no patches, so the server has no update list for it
+        let patchless = root.join("Patchless (BCUS98111)");
+        std::fs::create_dir_all(&patched).unwrap();
+        std::fs::create_dir_all(&patchless).unwrap();
+        std::fs::write(patched.join("old.pkg"), b"old").unwrap();
+        std::fs::write(patchless.join("game.pkg"), b"game").unwrap();
+
+        let mut library = Library::open(root).unwrap();
+        let manager = DownloadManager::new().unwrap();
+        let fetcher = server.fetcher().unwrap();
+        let report = library.sync(&fetcher, &manager).await.unwrap();
+
+        assert!(report.is_complete());
+        assert_eq!(report.downloaded_count(), 2);
+        let title = |id: &str| report.titles.iter().find(|t| t.title_id == id).unwrap();
+        assert!(title("BCUS98111").downloaded.is_empty());
+        assert!(title("BLES00779")
+            .downloaded
+            .iter()
+            .all(|path| path.starts_with(&patched)));
+        assert_eq!(library.game("BLES00779").unwrap().packages.len(), 2);
+
+        // A second sync finds everything present
+        let report = library.sync(&fetcher, &manager).await.unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.downloaded_count(), 0);
+    }
+
+    #[test]
+    fn test_disk_usage() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let big = root.join("Big Game (BLES00779)");
+        let small = root.join("Small Game (BLUS30001)");
+        std::fs::create_dir_all(&big).unwrap();
+        std::fs::create_dir_all(&small).unwrap();
+        for (v, size) in [("0101", 100), ("0102", 200)] {
+            let name = format!("EP0001-BLES00779_00-PATCH-A{}-V0100-PE.pkg", v);
+            std::fs::write(big.join(name), vec![0u8; size]).unwrap();
+        }
+        std::fs::write(big.join("extra.pkg"), vec![0u8; 5]).unwrap();
+        std::fs::write(small.join("a.pkg"), vec![0u8; 50]).unwrap();
+
+        let usage = Library::open(root).unwrap().disk_usage().unwrap();
+        assert_eq!(usage.total_bytes, 355);
+        assert_eq!(usage.superseded_bytes, 100);
+        assert_eq!(usage.titles[0].dir, big);
+        let versions: Vec<_> = usage.titles[0]
+            .versions
+            .iter()
+            .map(|v| (v.version.as_deref(), v.superseded))
+            .collect();
+        assert_eq!(
+            versions,
+            [(Some("01.02"), false), (Some("01.01"), true), (None, false)]
+        );
+        assert_eq!(usage.titles[1].superseded_bytes, 0);
+    }
+
+    #[test]
+    fn test_plan_title() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let dir = root.join("Demon's Souls (BLES00779)");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("EP0001-BLES00779_00-PATCH-A0101-V0100-PE.pkg"),
+            vec![0u8; 1024],
+        )
+        .unwrap();
+
+        let library = Library::open(root).unwrap();
+        let fetcher = crate::UpdateFetcher::new().unwrap();
+        let result = fetcher
+            .parse_updates("BLES00779", crate::fetcher::tests::SAMPLE_XML)
+            .unwrap();
+        let plan = library.plan_title(&result);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].package.version, "01.02");
+        assert_eq!(plan[0].game_title, "Demon's Souls");
+        assert_eq!(
+            plan[0].path(),
+            dir.join("EP0001-BLES00779_00-PATCH-A0102-V0100-PE.pkg")
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::title_id::{Region, TitleId};
+use crate::types::{PS3UpdateError, PackageInfo, Result};
+use crate::utils::{clean_title_id, unix_timestamp};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// File name of the per-game manifest
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+const MANIFEST_FORMAT_VERSION: u32 = 1;
+
+/// Serializes read-modify-write cycles when several downloads finish at once
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// A downloaded package as recorded in a manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestPackage {
+    pub version: String,
+    pub filename: String,
+    pub size_bytes: u64,
+    pub sha1: String,
+    pub system_ver: String,
+    /// URL the file was downloaded from (may be an archive mirror)
+    pub source_url: String,
+    /// Unix timestamp of the download
+    pub downloaded_at: u64,
+}
+
+/// What to record once a download finishes (see [`crate::DownloadOptions::with_manifest`])
+#[derive(Debug, Clone)]
+pub struct ManifestEntry {
+    pub title_id: String,
+    pub game_name: String,
+    pub package: PackageInfo,
+}
+
+/// Self-describing record of a game folder's contents (`manifest.json`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameManifest {
+    pub format_version: u32,
+    pub title_id: String,
+    pub game_name: String,
+    pub region: Region,
+    pub packages: Vec<ManifestPackage>,
+}
+
+impl GameManifest {
+    pub fn new(title_id: &str, game_name: &str) -> Self {
+        let title_id = clean_title_id(title_id);
+        let region = TitleId::parse(&title_id)
+            .map(|t| t.region())
+            .unwrap_or(Region::Unknown);
+        Self {
+            format_version: MANIFEST_FORMAT_VERSION,
+            title_id,
+            game_name: game_name.to_string(),
+            region,
+            packages: vec![],
+        }
+    }
+
+    /// Path of the manifest in a game folder
+    pub fn path_in(dir: &Path) -> PathBuf {
+        dir.join(MANIFEST_FILE)
+    }
+
+    /// Read the manifest from a game folder
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let text = std::fs::read_to_string(Self::path_in(dir.as_ref()))?;
+        serde_json::from_str(&text).map_err(PS3UpdateError::Manifest)
+    }
+
+    /// Write the manifest into a game folder
+    pub fn save(&self, dir: impl AsRef<Path>) -> Result<()> {
+        let text = serde_json::to_string_pretty(self).map_err(PS3UpdateError::Manifest)?;
+        std::fs::write(Self::path_in(dir.as_ref()), text)?;
+        Ok(())
+    }
+
+    /// Add a package, replacing any earlier entry with the same file name
+    pub fn record(&mut self, package: &PackageInfo, source_url: &str) {
+        self.packages.retain(|p| p.filename != package.filename);
+        self.packages.push(ManifestPackage {
+            version: package.version.clone(),
+            filename: package.filename.clone(),
+            size_bytes: package.size_bytes,
+            sha1: package.sha1.clone(),
+            system_ver: package.system_ver.clone(),
+            source_url: source_url.to_string(),
+            downloaded_at: unix_timestamp(),
+        });
+        self.packages.sort_by(|a, b| a.version.cmp(&b.version));
+    }
+
+    pub fn package(&self, filename: &str) -> Option<&ManifestPackage> {
+        self.packages.iter().find(|p| p.filename == filename)
+    }
+}
+
+/// Record a finished download in `dir/manifest.json`, creating it if needed
+///
+/// The game name is refreshed on every call; the title ID of an existing
+/// manifest is kept.
+pub fn record_download(
+    dir: impl AsRef<Path>,
+    title_id: &str,
+    game_name: &str,
+    package: &PackageInfo,
+    source_url: &str,
+) -> Result<GameManifest> {
+    let dir = dir.as_ref();
+    let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    let mut manifest = match GameManifest::load(dir) {
+        Ok(manifest) => manifest,
+        Err(PS3UpdateError::FileSystem(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            GameManifest::new(title_id, game_name)
+        }
+        Err(e) => return Err(e),
+    };
+    if !game_name.is_empty() {
+        manifest.game_name = game_name.to_string();
+    }
+    manifest.record(package, source_url);
+    manifest.save(dir)?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(version: &str) -> PackageInfo {
+        PackageInfo {
+            version: version.to_string(),
+            system_ver: "03.4100".to_string(),
+            size_bytes: 1024,
+            size_human: "1.00 KB".to_string(),
+            url: format!("http://example/{}.pkg", version),
+            sha1: "aa".to_string(),
+            filename: format!("{}.pkg", version),
+        }
+    }
+
+    #[test]
+    fn test_record_download() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+
+        record_download(
+            dir,
+            "bles-00779",
+            "Test Game",
+            &package("01.02"),
+            "http://a",
+        )
+        .unwrap();
+        record_download(dir, "BLES00779", "Test Game", &package("01.01"), "http://b").unwrap();
+        let manifest =
+            record_download(dir, "BLES00779", "Test Game", &package("01.02"), "http://c").unwrap();
+
+        assert_eq!(manifest, GameManifest::load(dir).unwrap());
+        assert_eq!(manifest.title_id, "BLES00779");
+        assert_eq!(manifest.region, Region::Europe);
+        assert_eq!(manifest.packages.len(), 2);
+        assert_eq!(manifest.packages[0].version, "01.01");
+        assert_eq!(
+            manifest.package("01.02.pkg").unwrap().source_url,
+            "http://c"
+        );
+    }
+}
@@ -0,0 +1,414 @@
+use crate::downloader::{DownloadManager, DownloadOptions};
+use crate::fetcher::UpdateFetcher;
+use crate::types::{Failure, PS3UpdateError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// Titles finished by an interrupted crawl, kept in the mirror root
+pub const MIRROR_PROGRESS_FILE: &str = ".mirror-progress.json";
+
+/// Options controlling what a [`Mirror`] downloads
+#[derive(Debug, Clone)]
+pub struct MirrorOptions {
+    /// Download the PKG files, not just the `-ver.xml` documents
+    pub packages: bool,
+    /// Skip packages already present with the expected size
+    pub skip_existing: bool,
+    /// Mode, rate limit, retries and checks for each package; the title
+    /// check and `resume` are added on top
+    pub download: DownloadOptions,
+    /// Pick up an interrupted crawl: skip titles it finished and continue
+    /// partial package downloads
+    pub resume: bool,
+}
+
+impl Default for MirrorOptions {
+    fn default() -> Self {
+        Self {
+            packages: true,
+            skip_existing: true,
+            download: DownloadOptions::default(),
+            resume: false,
+        }
+    }
+}
+
+/// Outcome of mirroring a single title
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MirrorTitleReport {
+    pub title_id: String,
+    pub xml_path: Option<PathBuf>,
+    pub downloaded: Vec<PathBuf>,
+    pub skipped: Vec<PathBuf>,
+    /// Files that failed, by URL
+    pub failed: Vec<Failure>,
+    pub error: Option<String>,
+    /// [`PS3UpdateError::code`] of `error`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+}
+
+/// Outcome of mirroring a list of titles
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MirrorReport {
+    pub titles: Vec<MirrorTitleReport>,
+    /// Titles skipped because an earlier, interrupted crawl finished them
+    #[serde(default)]
+    pub already_complete: Vec<String>,
+}
+
+impl MirrorReport {
+    /// True when every title and package was mirrored
+    pub fn is_complete(&self) -> bool {
+        self.titles
+            .iter()
+            .all(|t| t.error.is_none() && t.failed.is_empty())
+    }
+
+    pub fn downloaded_count(&self) -> usize {
+        self.titles.iter().map(|t| t.downloaded.len()).sum()
+    }
+}
+
+impl MirrorTitleReport {
+    fn is_complete(&self) -> bool {
+        self.error.is_none() && self.failed.is_empty()
+    }
+}
+
+/// How much of one title's update list is in the mirror
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TitleCoverage {
+    pub title_id: String,
+    pub has_xml: bool,
+    pub packages_listed: usize,
+    pub packages_present: usize,
+    pub bytes_listed: u64,
+    pub bytes_present: u64,
+}
+
+impl TitleCoverage {
+    pub fn is_complete(&self) -> bool {
+        self.has_xml && self.packages_present == self.packages_listed
+    }
+}
+
+/// How much of a title list a mirror holds, read from disk
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MirrorCoverage {
+    pub titles: Vec<TitleCoverage>,
+}
+
+impl MirrorCoverage {
+    pub fn complete_titles(&self) -> usize {
+        self.titles.iter().filter(|t| t.is_complete()).count()
+    }
+
+    pub fn packages_listed(&self) -> usize {
+        self.titles.iter().map(|t| t.packages_listed).sum()
+    }
+
+    pub fn packages_present(&self) -> usize {
+        self.titles.iter().map(|t| t.packages_present).sum()
+    }
+
+    /// Share of listed bytes present, from 0 to 100 (100 when nothing is listed)
+    pub fn percent(&self) -> f64 {
+        let listed: u64 = self.titles.iter().map(|t| t.bytes_listed).sum();
+        let present: u64 = self.titles.iter().map(|t| t.bytes_present).sum();
+        if listed == 0 {
+            100.0
+        } else {
+            present as f64 / listed as f64 * 100.0
+        }
+    }
+}
+
+/// Titles a crawl has finished, saved after each one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CrawlProgress {
+    completed: Vec<String>,
+}
+
+/// Builds an offline copy of Sony's update server for a set of titles
+///
+/// Files are written with the same paths they have on the server, so the root
+/// directory can be archived as-is or served by any static file server:
+/// `tpl/np/{id}/{id}-ver.xml` for the update lists and `tppkg/np/…` for packages.
+pub struct Mirror {
+    root: PathBuf,
+    fetcher: UpdateFetcher,
+    downloader: DownloadManager,
+    options: MirrorOptions,
+}
+
+impl Mirror {
+    /// Create a mirror rooted at `root` using default fetcher and downloader settings
+    pub fn new(root: impl Into<PathBuf>) -> Result<Self> {
+        Ok(Self {
+            root: root.into(),
+            fetcher: UpdateFetcher::new()?,
+            downloader: DownloadManager::new()?,
+            options: MirrorOptions::default(),
+        })
+    }
+
+    pub fn with_fetcher(mut self, fetcher: UpdateFetcher) -> Self {
+        self.fetcher = fetcher;
+        self
+    }
+
+    pub fn with_downloader(mut self, downloader: DownloadManager) -> Self {
+        self.downloader = downloader;
+        self
+    }
+
+    pub fn with_options(mut self, options: MirrorOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Mirror each title in turn; failures are recorded in the report rather than aborting
+    ///
+    /// Finished titles are noted in [`MIRROR_PROGRESS_FILE`] as the crawl
+    /// goes, so a crawl run again with [`MirrorOptions::resume`] skips them.
+    /// The file is removed once every title is complete.
+    pub async fn mirror_titles<I, S>(&self, title_ids: I) -> MirrorReport
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.mirror_titles_with(title_ids, |_| {}).await
+    }
+
+    /// Like [`Mirror::mirror_titles`], calling `on_title` as each title finishes
+    pub async fn mirror_titles_with<I, S>(
+        &self,
+        title_ids: I,
+        mut on_title: impl FnMut(&MirrorTitleReport),
+    ) -> MirrorReport
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let progress_path = self.root.join(MIRROR_PROGRESS_FILE);
+        let mut progress = if self.options.resume {
+            std::fs::read_to_string(&progress_path)
+                .ok()
+                .and_then(|text| serde_json::from_str(&text).ok())
+                .unwrap_or_default()
+        } else {
+            CrawlProgress::default()
+        };
+
+        let mut report = MirrorReport::default();
+        for title_id in title_ids {
+            let title_id = self.fetcher.resolve_title_id(title_id.as_ref());
+            if progress.completed.contains(&title_id) {
+                report.already_complete.push(title_id);
+                continue;
+            }
+            let title = match self.mirror_title(&title_id).await {
+                Ok(title) => title,
+                Err(e) => MirrorTitleReport {
+                    title_id,
+                    error: Some(e.to_string()),
+                    error_code: Some(e.code().to_string()),
+                    ..Default::default()
+                },
+            };
+            if title.is_complete() {
+                progress.completed.push(title.title_id.clone());
+                // Losing progress only costs a re-check, so write errors are ignored
+                if let Ok(text) = serde_json::to_string(&progress) {
+                    let _ = std::fs::create_dir_all(&self.root);
+                    let _ = std::fs::write(&progress_path, text);
+                }
+            }
+            on_title(&title);
+            report.titles.push(title);
+        }
+
+        if report.is_complete() {
+            let _ = std::fs::remove_file(&progress_path);
+        }
+        report
+    }
+
+    /// Check what the mirror holds for each title, without going online
+    ///
+    /// Packages count as present when their file has the listed size.
+    pub fn coverage<I, S>(&self, title_ids: I) -> MirrorCoverage
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut coverage = MirrorCoverage::default();
+        for title_id in title_ids {
+            let title_id = self.fetcher.resolve_title_id(title_id.as_ref());
+            let mut title = TitleCoverage {
+                title_id: title_id.clone(),
+                ..Default::default()
+            };
+            let text = std::fs::read_to_string(self.root.join(xml_path(&title_id)));
+            if let Ok(result) = text.and_then(|text| {
+                self.fetcher
+                    .parse_updates(&title_id, &text)
+                    .map_err(std::io::Error::other)
+            }) {
+                title.has_xml = true;
+                for pkg in result.results {
+                    title.packages_listed += 1;
+                    title.bytes_listed += pkg.size_bytes;
+                    let present = package_path(&pkg.url)
+                        .ok()
+                        .and_then(|path| std::fs::metadata(self.root.join(path)).ok())
+                        .is_some_and(|m| m.len() == pkg.size_bytes);
+                    if present {
+                        title.packages_present += 1;
+                        title.bytes_present += pkg.size_bytes;
+                    }
+                }
+            }
+            coverage.titles.push(title);
+        }
+        coverage
+    }
+
+    /// Download a title's `-ver.xml` and, if enabled, every package it lists
+    pub async fn mirror_title(&self, title_id: &str) -> Result<MirrorTitleReport> {
+        let cleaned = self.fetcher.resolve_title_id(title_id);
+        let text = self.fetcher.fetch_raw_xml(&cleaned).await?;
+
+        let xml_path = self.root.join(xml_path(&cleaned));
+        if let Some(parent) = xml_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&xml_path, &text).await?;
+
+        let mut report = MirrorTitleReport {
+            title_id: cleaned.clone(),
+            xml_path: Some(xml_path),
+            ..Default::default()
+        };
+        if !self.options.packages {
+            return Ok(report);
+        }
+
+        let result = self.fetcher.parse_updates(&cleaned, &text)?;
+        for pkg in result.results {
+            let dest = self.root.join(package_path(&pkg.url)?);
+
+            if self.options.skip_existing {
+                let existing = tokio::fs::metadata(&dest).await.ok().map(|m| m.len());
+                if existing.is_some() && existing == Some(pkg.size_bytes) {
+                    report.skipped.push(dest);
+                    continue;
+                }
+            }
+
+            let mut options = self.options.download.clone().expect_title(&cleaned);
+            if self.options.resume {
+                options = options.with_resume();
+            }
+            let job_id = self
+                .downloader
+                .start_download_with(&pkg.url, dest.clone(), options)
+                .await?;
+            let progress = self.downloader.wait_for(&job_id).await?;
+            self.downloader.remove_job(&job_id);
+
+            match progress.error {
+                Some(error) => report.failed.push(Failure {
+                    target: pkg.url,
+                    error,
+                    code: progress.error_code,
+                }),
+                None => report.downloaded.push(dest),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Server-relative path of a title's update list
+pub fn xml_path(title_id: &str) -> PathBuf {
+    Path::new("tpl")
+        .join("np")
+        .join(title_id)
+        .join(format!("{}-ver.xml", title_id))
+}
+
+/// Server-relative path of a package, taken from its URL
+pub fn package_path(url: &str) -> Result<PathBuf> {
+    let parsed = reqwest::Url::parse(url)?;
+    let path = PathBuf::from(parsed.path().trim_start_matches('/'));
+
+    let safe =
+        path.file_name().is_some() && path.components().all(|c| matches!(c, Component::Normal(_)));
+    if !safe {
+        return Err(PS3UpdateError::Download(format!(
+            "Refusing to mirror unsafe package path: {}",
+            url
+        )));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetcher::tests::SAMPLE_XML;
+
+    #[test]
+    fn test_xml_path() {
+        assert_eq!(
+            xml_path("BLES00779"),
+            PathBuf::from("tpl/np/BLES00779/BLES00779-ver.xml")
+        );
+    }
+
+    #[test]
+    fn test_package_path() {
+        assert_eq!(
+            package_path(
+                "http://b0.ww.np.dl.playstation.net/tppkg/np/BLES00779/BLES00779_T2/abc/EP0001-BLES00779_00-PATCH-A0102-V0100-PE.pkg"
+            )
+            .unwrap(),
+            PathBuf::from(
+                "tppkg/np/BLES00779/BLES00779_T2/abc/EP0001-BLES00779_00-PATCH-A0102-V0100-PE.pkg"
+            )
+        );
+        assert!(package_path("http://example/").is_err());
+        assert!(package_path("not a url").is_err());
+    }
+
+    #[test]
+    fn test_coverage() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let xml = root.join(xml_path("BLES00779"));
+        std::fs::create_dir_all(xml.parent().unwrap()).unwrap();
+        std::fs::write(&xml, SAMPLE_XML).unwrap();
+        let pkg = root.join("a/EP0001-BLES00779_00-PATCH-A0101-V0100-PE.pkg");
+        std::fs::create_dir_all(pkg.parent().unwrap()).unwrap();
+        std::fs::write(&pkg, vec![0u8; 1024]).unwrap();
+
+        let coverage = Mirror::new(root)
+            .unwrap()
+            .coverage(["BLES00779", "BLUS30109"]);
+        assert_eq!(coverage.titles.len(), 2);
+        let title = &coverage.titles[0];
+        assert!(title.has_xml);
+        assert_eq!((title.packages_present, title.packages_listed), (1, 2));
+        assert_eq!((title.bytes_present, title.bytes_listed), (1024, 3072));
+        assert!(!coverage.titles[1].has_xml);
+        assert_eq!(coverage.complete_titles(), 0);
+        assert!((coverage.percent() - 100.0 / 3.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::types::Result;
+use axum::Router;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use tower_http::services::ServeDir;
+
+/// Serves a directory produced by [`crate::Mirror`] with Sony-compatible paths
+///
+/// A PS3 whose DNS resolves the update hosts (`a0.ww.np.dl.playstation.net`,
+/// `b0.ww.np.dl.playstation.net`…) to this machine will fetch its `-ver.xml`
+/// and packages from the mirror. The server speaks plain HTTP; if the console
+/// requests the XML over HTTPS, terminate TLS in front of it.
+pub struct MirrorServer {
+    root: PathBuf,
+}
+
+impl MirrorServer {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Router serving the mirror, for embedding in a larger axum application
+    pub fn router(&self) -> Router {
+        Router::new().fallback_service(ServeDir::new(&self.root))
+    }
+
+    /// Listen on `addr` and serve until the process exits
+    pub async fn serve(&self, addr: SocketAddr) -> Result<()> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        axum::serve(listener, self.router()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mirror::xml_path;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_serves_sony_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let xml = root.join(xml_path("BLES00779"));
+        std::fs::create_dir_all(xml.parent().unwrap()).unwrap();
+        std::fs::write(&xml, "<titlepatch/>").unwrap();
+
+        let router = MirrorServer::new(root).router();
+        let resp = router
+            .clone()
+            .oneshot(
+                Request::get("/tpl/np/BLES00779/BLES00779-ver.xml")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = router
+            .oneshot(
+                Request::get("/tpl/np/BLUS30109/BLUS30109-ver.xml")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,674 @@
+use crate::fetcher::UpdateFetcher;
+use crate::pkg::{PkgHeader, PKG_MAGIC};
+use crate::types::Result;
+use crate::verify::to_hex;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use bytes::Bytes;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// Stands in for a URL in canned XML; replaced with the server's address
+pub const BASE_URL_PLACEHOLDER: &str = "{base}";
+
+/// A local stand-in for Sony's update server, for tests
+///
+/// Serves canned `-ver.xml` files at Sony's paths and package payloads with
+/// `Range` support, so fetches, direct, resumed and multi-part downloads can
+/// be exercised offline and deterministically:
+///
+/// ```no_run
+/// # async fn demo() -> ps3_update_core::Result<()> {
+/// use ps3_update_core::MockUpdateServer;
+///
+/// let server = MockUpdateServer::new()
+///     .with_title("BLES00779", "Demon's Souls", &["01.01", "01.02"])
+///     .start()
+///     .await?;
+/// let result = server.fetcher()?.fetch_updates("BLES00779").await?;
+/// assert_eq!(result.results.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockUpdateServer {
+    titles: Vec<MockTitle>,
+    xml: Vec<(String, String)>,
+    files: HashMap<String, Bytes>,
+    ranges: bool,
+    failing_range: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+struct MockTitle {
+    title_id: String,
+    game_title: String,
+    versions: Vec<String>,
+}
+
+impl Default for MockUpdateServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockUpdateServer {
+    pub fn new() -> Self {
+        Self {
+            titles: vec![],
+            xml: vec![],
+            files: HashMap::new(),
+            ranges: true,
+            failing_range: None,
+        }
+    }
+
+    /// A title with one generated package per version
+    ///
+    /// Each package is a small, structurally valid PKG with a correct digest
+    /// block, listed with its SHA1 and size, so it passes verification.
+    pub fn with_title(mut self, title_id: &str, game_title: &str, versions: &[&str]) -> Self {
+        self.titles.push(MockTitle {
+            title_id: title_id.to_string(),
+            game_title: game_title.to_string(),
+            versions: versions.iter().map(|v| v.to_string()).collect(),
+        });
+        self
+    }
+
+    /// Serve `xml` as a title's `-ver.xml`, with [`BASE_URL_PLACEHOLDER`]
+    /// replaced by the server's URL
+    pub fn with_xml(mut self, title_id: &str, xml: impl Into<String>) -> Self {
+        self.xml.push((title_id.to_string(), xml.into()));
+        self
+    }
+
+    /// Serve `data` at `path` (e.g. `/pkg/update.pkg`)
+    pub fn with_file(mut self, path: &str, data: impl Into<Bytes>) -> Self {
+        self.files.insert(path.to_string(), data.into());
+        self
+    }
+
+    /// Ignore `Range` headers and leave out `Accept-Ranges`, like a server
+    /// without range support
+    pub fn without_ranges(mut self) -> Self {
+        self.ranges = false;
+        self
+    }
+
+    /// Answer `Range` requests starting at byte `start` with a server error,
+    /// to break one part of a multi-part download
+    pub fn with_failing_range(mut self, start: u64) -> Self {
+        self.failing_range = Some(start);
+        self
+    }
+
+    /// Every path and its content, with URLs pointing at `base_url`
+    fn content(&self, base_url: &str) -> HashMap<String, Bytes> {
+        let mut content = self.files.clone();
+        for title in &self.titles {
+            let mut packages = String::new();
+            for (i, version) in title.versions.iter().enumerate() {
+                let digits = version.replace('.', "");
+                let content_id = format!("EP0001-{}_00-PATCH{:0>11}", title.title_id, digits);
+                let path = format!(
+                    "/tppkg/np/{}/{}-A{:0>4}-V0100-PE.pkg",
+                    title.title_id, content_id, digits
+                );
+                let data = mock_pkg(&content_id);
+                let digest = to_hex(&Sha1::digest(&data[..data.len() - 0x20]));
+                packages.push_str(&format!(
+                    "<package version=\"{}\" size=\"{}\" sha1=\"{}\" url=\"{}{}\" ps3_system_ver=\"03.5500\">",
+                    version,
+                    data.len(),
+                    digest,
+                    base_url,
+                    path
+                ));
+                // Sony names the title only in the newest package
+                if i + 1 == title.versions.len() {
+                    packages.push_str(&format!(
+                        "<paramsfo><TITLE>{}</TITLE></paramsfo>",
+                        quick_xml::escape::partial_escape(&title.game_title)
+                    ));
+                }
+                packages.push_str("</package>");
+                content.insert(path, data.into());
+            }
+            let xml = format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+                 <titlepatch titleid=\"{id}\"><tag name=\"{id}_T1\">{packages}</tag></titlepatch>",
+                id = title.title_id,
+                packages = packages,
+            );
+            content.insert(xml_route(&title.title_id), xml.into());
+        }
+        for (title_id, xml) in &self.xml {
+            content.insert(
+                xml_route(title_id),
+                xml.replace(BASE_URL_PLACEHOLDER, base_url).into(),
+            );
+        }
+        content
+    }
+
+    /// Bind to a free local port and serve in the background
+    ///
+    /// The server stops when the returned handle is dropped.
+    pub async fn start(self) -> Result<RunningMockServer> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let base_url = format!("http://{}", addr);
+        let state = Arc::new(MockState {
+            content: self.content(&base_url),
+            ranges: self.ranges,
+            failing_range: self.failing_range,
+        });
+        let router = Router::new().fallback(serve_path).with_state(state);
+        let task = tokio::spawn(async move {
+            let _ = axum::serve(listener, router).await;
+        });
+        Ok(RunningMockServer {
+            addr,
+            base_url,
+            task,
+        })
+    }
+}
+
+/// A started [`MockUpdateServer`]; stops serving when dropped
+#[derive(Debug)]
+pub struct RunningMockServer {
+    addr: SocketAddr,
+    base_url: String,
+    task: JoinHandle<()>,
+}
+
+impl RunningMockServer {
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// `http://127.0.0.1:PORT`, without a trailing slash
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The full URL of a served path
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// A fetcher that reads update lists from this server
+    pub fn fetcher(&self) -> Result<UpdateFetcher> {
+        Ok(UpdateFetcher::new()?.with_base_url(&self.base_url))
+    }
+}
+
+impl Drop for RunningMockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+struct MockState {
+    content: HashMap<String, Bytes>,
+    ranges: bool,
+    failing_range: Option<u64>,
+}
+
+fn xml_route(title_id: &str) -> String {
+    format!("/tpl/np/{id}/{id}-ver.xml", id = title_id)
+}
+
+/// A PKG with a 64-byte body and a valid digest block
+pub fn mock_pkg(content_id: &str) -> Vec<u8> {
+    let body: Vec<u8> = (0..0x40u8).collect();
+    let data_size = body.len() as u64;
+    let mut data = vec![0u8; PkgHeader::SIZE];
+    data[0x00..0x04].copy_from_slice(&PKG_MAGIC.to_be_bytes());
+    data[0x04..0x06].copy_from_slice(&0x8000u16.to_be_bytes());
+    data[0x06..0x08].copy_from_slice(&1u16.to_be_bytes());
+    let total = PkgHeader::SIZE as u64 + data_size + 0x20;
+    data[0x18..0x20].copy_from_slice(&total.to_be_bytes());
+    data[0x20..0x28].copy_from_slice(&(PkgHeader::SIZE as u64).to_be_bytes());
+    data[0x28..0x30].copy_from_slice(&data_size.to_be_bytes());
+    let id = content_id.as_bytes();
+    let len = id.len().min(0x24);
+    data[0x30..0x30 + len].copy_from_slice(&id[..len]);
+    data.extend_from_slice(&body);
+    let digest = Sha1::digest(&data);
+    data.extend_from_slice(&digest);
+    data.extend_from_slice(&[0u8; 12]);
+    data
+}
+
+async fn serve_path(State(state): State<Arc<MockState>>, uri: Uri, headers: HeaderMap) -> Response {
+    let Some(data) = state.content.get(uri.path()) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let len = data.len() as u64;
+    let mut response = Response::builder().header(header::CONTENT_TYPE, content_type(uri.path()));
+    if state.ranges {
+        response = response.header(header::ACCEPT_RANGES, "bytes");
+    }
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| state.ranges);
+    let Some(range) = range else {
+        return response
+            .header(header::CONTENT_LENGTH, len)
+            .body(Body::from(data.clone()))
+            .unwrap();
+    };
+    match parse_range(range, len) {
+        Some((start, _)) if state.failing_range == Some(start) => {
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Some((start, end)) => response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, len),
+            )
+            .header(header::CONTENT_LENGTH, end - start + 1)
+            .body(Body::from(data.slice(start as usize..=end as usize)))
+            .unwrap(),
+        None => response
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Body::empty())
+            .unwrap(),
+    }
+}
+
+fn content_type(path: &str) -> &'static str {
+    if path.ends_with(".xml") {
+        "application/xml"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+/// The inclusive byte range of a single-range `Range` header, if satisfiable
+///
+/// No range of an empty file is.
+fn parse_range(header: &str, len: u64) -> Option<(u64, u64)> {
+    let last = len.checked_sub(1)?;
+    let (start, end) = header.strip_prefix("bytes=")?.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => (len - suffix.parse::<u64>().ok()?.min(len), last),
+        (start, "") => (start.parse().ok()?, last),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(last)),
+    };
+    (start <= end && start < len).then_some((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::downloader::{DownloadManager, DownloadOptions};
+    use crate::titledb::{SuggestionReason, TitleDatabase};
+    use crate::types::{DownloadMode, PS3UpdateError, PackageInfo};
+    use crate::verify::verify_pkg;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-9", 100), Some((0, 9)));
+        assert_eq!(parse_range("bytes=90-", 100), Some((90, 99)));
+        assert_eq!(parse_range("bytes=-10", 100), Some((90, 99)));
+        assert_eq!(parse_range("bytes=50-500", 100), Some((50, 99)));
+        assert_eq!(parse_range("bytes=100-", 100), None);
+        assert_eq!(parse_range("bytes=9-0", 100), None);
+        assert_eq!(parse_range("items=0-9", 100), None);
+        assert_eq!(parse_range("bytes=0-9", 0), None);
+        assert_eq!(parse_range("bytes=0-", 0), None);
+        assert_eq!(parse_range("bytes=-10", 0), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_updates_batch() {
+        let server = MockUpdateServer::new()
+            .with_title("BLES00779", "Demon's Souls", &["01.01", "01.02"])
+            .with_title("BLUS30109", "Metal Gear Solid 4", &["02.00"])
+            .start()
+            .await
+            .unwrap();
+        let fetcher = server.fetcher().unwrap();
+
+        let mut reported = vec![];
+        let results = fetcher
+            .fetch_updates_batch(
+                &["blus-30109", "BLES00779", "BCUS98111", "BLUS30109"],
+                2,
+                |done, total, title_id, result| {
+                    reported.push((done, total, title_id.to_string(), result.is_ok()))
+                },
+            )
+            .await;
+
+        let ids: Vec<_> = results.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["BLUS30109", "BLES00779", "BCUS98111"]);
+        assert_eq!(results[1].1.as_ref().unwrap().results.len(), 2);
+        assert!(results[2].1.is_err());
+        assert_eq!(reported.len(), 3);
+        assert_eq!(reported.last().unwrap().0, 3);
+        assert!(reported.iter().all(|(_, total, _, _)| *total == 3));
+    }
+
+    #[tokio::test]
+    async fn test_not_found_suggestions() {
+        let server = MockUpdateServer::new()
+            .with_title("BLUS30109", "Metal Gear Solid 4", &["02.00"])
+            .start()
+            .await
+            .unwrap();
+        let fetcher = server
+            .fetcher()
+            .unwrap()
+            .with_title_database(TitleDatabase::parse("BLUS30109\tMetal Gear Solid 4\n"));
+        match fetcher.fetch_updates("BLUS30190").await {
+            Err(PS3UpdateError::NoUpdatesFound {
+                title_id,
+                suggestions,
+            }) => {
+                assert_eq!(title_id, "BLUS30190");
+                assert_eq!(suggestions.len(), 1);
+                assert_eq!(suggestions[0].title_id, "BLUS30109");
+                assert_eq!(suggestions[0].reason, SuggestionReason::TransposedDigits);
+            }
+            other => panic!("expected NoUpdatesFound, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_and_download() {
+        let server = MockUpdateServer::new()
+            .with_title("BLES00779", "Demon's Souls", &["01.01", "01.02"])
+            .with_xml("BLUS30109", "<titlepatch/>")
+            .start()
+            .await
+            .unwrap();
+        let fetcher = server.fetcher().unwrap();
+        let result = fetcher.fetch_updates("BLES00779").await.unwrap();
+        assert_eq!(result.game_title, "Demon's Souls");
+        assert_eq!(result.results.len(), 2);
+        assert!(fetcher.fetch_updates("BCUS98111").await.is_err());
+        assert!(fetcher.check_server_status().await);
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let manager = DownloadManager::new().unwrap();
+        for (i, mode) in [
+            DownloadMode::Direct,
+            DownloadMode::MultiPart { num_parts: 3 },
+            DownloadMode::Auto,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let pkg = &result.results[0];
+            let dest = dir.join(format!("{}-{}", i, pkg.filename));
+            let id = manager
+                .start_download_with(
+                    &pkg.url,
+                    dest.clone(),
+                    DownloadOptions::new(mode).expect_title("BLES00779"),
+                )
+                .await
+                .unwrap();
+            let progress = manager.wait_for(&id).await.unwrap();
+            assert_eq!(progress.error, None);
+            assert_eq!(progress.downloaded, pkg.size_bytes);
+            let check = verify_pkg(&dest).unwrap();
+            assert!(check.is_valid());
+            assert!(check.matches_sha1(&pkg.sha1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_and_no_ranges() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(10_000).collect();
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let manager = DownloadManager::new().unwrap();
+        for (server, ranges) in [
+            (MockUpdateServer::new(), true),
+            (MockUpdateServer::new().without_ranges(), false),
+        ] {
+            let server = server
+                .with_file("/file.bin", data.clone())
+                .start()
+                .await
+                .unwrap();
+            let dest = dir.join("file.bin");
+            std::fs::write(&dest, &data[..4000]).unwrap();
+            let id = manager
+                .start_download_with(
+                    &server.url("/file.bin"),
+                    dest.clone(),
+                    DownloadOptions::new(DownloadMode::Direct).with_resume(),
+                )
+                .await
+                .unwrap();
+            let progress = manager.wait_for(&id).await.unwrap();
+            assert_eq!(progress.error, None);
+            // Without ranges the whole file is sent again and overwrites the part
+            assert_eq!(std::fs::read(&dest).unwrap(), data);
+            assert_eq!(
+                progress.notes.iter().any(|n| n.starts_with("Resumed from")),
+                ranges
+            );
+            // The clock stops with the job
+            let later = manager.get_progress(&id).unwrap();
+            assert_eq!(later.elapsed_secs, progress.elapsed_secs);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_past_the_end() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(10_000).collect();
+        let server = MockUpdateServer::new()
+            .with_file("/file.bin", data.clone())
+            .start()
+            .await
+            .unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let dest = tmp.path().join("file.bin");
+        let manager = DownloadManager::new().unwrap();
+        // A finished copy is left alone; a longer, stale one is replaced
+        for (local, restarted) in [(data.clone(), false), (vec![1u8; 12_000], true)] {
+            std::fs::write(&dest, &local).unwrap();
+            let id = manager
+                .start_download_with(
+                    &server.url("/file.bin"),
+                    dest.clone(),
+                    DownloadOptions::new(DownloadMode::Direct).with_resume(),
+                )
+                .await
+                .unwrap();
+            let progress = manager.wait_for(&id).await.unwrap();
+            assert_eq!(progress.error, None);
+            assert_eq!(progress.downloaded, data.len() as u64);
+            assert_eq!(std::fs::read(&dest).unwrap(), data);
+            assert_eq!(
+                progress.notes.iter().any(|n| n.contains("started over")),
+                restarted
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_multipart_with_resume() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(10_000).collect();
+        // The first part fails after the others have written past it
+        let server = MockUpdateServer::new()
+            .with_file("/file.bin", data.clone())
+            .with_failing_range(0)
+            .start()
+            .await
+            .unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let dest = dir.join("file.bin");
+        let manager = DownloadManager::new().unwrap();
+        let id = manager
+            .start_download_with(
+                &server.url("/file.bin"),
+                dest.clone(),
+                DownloadOptions::new(DownloadMode::MultiPart { num_parts: 4 }).with_resume(),
+            )
+            .await
+            .unwrap();
+        let progress = manager.wait_for(&id).await.unwrap();
+        assert_eq!(progress.error, None);
+        assert!(progress
+            .notes
+            .iter()
+            .any(|n| n.starts_with("Multi-part download failed")));
+        assert!(!progress.notes.iter().any(|n| n.starts_with("Resumed from")));
+        assert_eq!(std::fs::read(&dest).unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_remove_job_cancels() {
+        let data = vec![7u8; 200_000];
+        let server = MockUpdateServer::new()
+            .with_file("/file.bin", data)
+            .start()
+            .await
+            .unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let package = PackageInfo {
+            version: "01.00".into(),
+            system_ver: "03.40".into(),
+            size_bytes: 200_000,
+            size_human: "195.31 KB".into(),
+            url: server.url("/file.bin"),
+            sha1: String::new(),
+            filename: "file.bin".into(),
+        };
+        // About a second at this speed, then the manifest records it
+        let options = DownloadOptions::new(DownloadMode::Direct)
+            .with_rate_limit(200_000)
+            .with_manifest("BLES00779", "Demon's Souls", package);
+        let manager = DownloadManager::new().unwrap();
+        let id = manager
+            .start_download_with(&server.url("/file.bin"), dir.join("file.bin"), options)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        manager.remove_job(&id);
+        assert!(manager.get_progress(&id).is_err());
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!dir.join(crate::manifest::MANIFEST_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn test_skip_existing_checks_sha1() {
+        let server = MockUpdateServer::new()
+            .with_title("BLES00779", "Demon's Souls", &["01.02"])
+            .start()
+            .await
+            .unwrap();
+        let pkg = server
+            .fetcher()
+            .unwrap()
+            .fetch_updates("BLES00779")
+            .await
+            .unwrap()
+            .results[0]
+            .clone();
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let dest = dir.join(&pkg.filename);
+        let manager = DownloadManager::new().unwrap();
+        let options = DownloadOptions::new(DownloadMode::Direct)
+            .with_manifest("BLES00779", "Demon's Souls", pkg.clone())
+            .with_skip_existing();
+
+        let mut outcomes = vec![];
+        for corrupt in [false, false, true] {
+            if corrupt {
+                let mut data = std::fs::read(&dest).unwrap();
+                data[0x100] ^= 0xFF;
+                std::fs::write(&dest, data).unwrap();
+            }
+            let id = manager
+                .start_download_with(&pkg.url, dest.clone(), options.clone())
+                .await
+                .unwrap();
+            let progress = manager.wait_for(&id).await.unwrap();
+            assert_eq!(progress.error, None);
+            outcomes.push(progress.already_downloaded);
+        }
+        // Fetched, kept, then fetched again once the copy no longer matched
+        assert_eq!(outcomes, [false, true, false]);
+        assert!(verify_pkg(&dest).unwrap().matches_sha1(&pkg.sha1));
+    }
+
+    #[cfg(feature = "catalog")]
+    #[tokio::test]
+    async fn test_downloads_logged_in_catalog() {
+        let server = MockUpdateServer::new()
+            .with_title("BLES00779", "Demon's Souls", &["01.02"])
+            .start()
+            .await
+            .unwrap();
+        let pkg = server
+            .fetcher()
+            .unwrap()
+            .fetch_updates("BLES00779")
+            .await
+            .unwrap()
+            .results[0]
+            .clone();
+        let missing = PackageInfo {
+            url: server.url("/missing.pkg"),
+            filename: "missing.pkg".into(),
+            ..pkg.clone()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let catalog = std::sync::Arc::new(crate::Catalog::open_in_memory().unwrap());
+        let manager = DownloadManager::new()
+            .unwrap()
+            .with_catalog(catalog.clone());
+
+        // Downloaded, then skipped as already present, then not found
+        for package in [&pkg, &pkg, &missing] {
+            let options = DownloadOptions::new(DownloadMode::Direct)
+                .with_manifest("BLES00779", "Demon's Souls", package.clone())
+                .with_skip_existing();
+            let id = manager
+                .start_download_with(&package.url, dir.join(&package.filename), options)
+                .await
+                .unwrap();
+            manager.wait_for(&id).await.unwrap();
+        }
+
+        let history = catalog.download_history("BLES00779").unwrap();
+        assert_eq!(history.len(), 2);
+        let ok = history.iter().find(|r| r.success).unwrap();
+        assert_eq!(ok.filename, pkg.filename);
+        assert_eq!(ok.bytes, pkg.size_bytes);
+        assert_eq!(ok.sha1, pkg.sha1);
+        assert_eq!(
+            ok.path.as_deref().map(std::path::Path::new),
+            Some(dir.join(&pkg.filename).as_path())
+        );
+        let failed = history.iter().find(|r| !r.success).unwrap();
+        assert_eq!(failed.filename, "missing.pkg");
+        assert!(failed.error.is_some());
+    }
+}
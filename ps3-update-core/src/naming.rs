@@ -0,0 +1,300 @@
+use crate::title_id::{Region, TitleId};
+use crate::types::{PS3UpdateError, Result};
+use crate::usb::fat32_name;
+use crate::utils::{clean_title_id, safe_dir_name, unicode_dir_name, NameTemplate};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// How finished downloads are named on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NamingScheme {
+    /// Keep the server's file name
+    #[default]
+    Original,
+    /// `Game Name [BLES00779] v01.02.pkg`
+    Descriptive,
+    /// `EP0001-BLES00779_00-PATCH00000000001-A0102.pkg`, from the PKG header
+    ContentId,
+}
+
+/// What a [`NamingScheme`] needs to know about a package
+#[derive(Debug, Clone, Default)]
+pub struct NamingContext<'a> {
+    pub original: &'a str,
+    pub title_id: &'a str,
+    pub game_name: &'a str,
+    pub version: &'a str,
+    pub content_id: Option<&'a str>,
+}
+
+impl NamingContext<'_> {
+    /// Placeholders a file [`NameTemplate`] may use: the server's file name,
+    /// game name, cleaned title ID, region code, version, content ID, and the
+    /// version as four digits (`01.02` is `0102`)
+    pub const PLACEHOLDERS: [&'static str; 7] = [
+        "original",
+        "title",
+        "title_id",
+        "region",
+        "version",
+        "content_id",
+        "app_ver",
+    ];
+
+    /// Fill in a template parsed with [`NamingContext::PLACEHOLDERS`]
+    ///
+    /// Missing values come out empty; the name is FAT32-safe.
+    pub fn render(&self, template: &NameTemplate) -> String {
+        let title_id = clean_title_id(self.title_id);
+        template.render_name(|name| match name {
+            "original" => self.original.to_string(),
+            "title" => self.game_name.trim().to_string(),
+            "region" => TitleId::parse(&title_id)
+                .map_or(Region::Unknown, |id| id.region())
+                .code()
+                .to_string(),
+            "version" => self.version.to_string(),
+            "content_id" => self.content_id.unwrap_or_default().to_string(),
+            "app_ver" => format!("{:0>4}", self.version_digits()),
+            _ => title_id.clone(),
+        })
+    }
+
+    fn version_digits(&self) -> String {
+        self.version.chars().filter(char::is_ascii_digit).collect()
+    }
+}
+
+impl NamingScheme {
+    /// File name for a package under this scheme
+    ///
+    /// Falls back to the original name when the scheme's inputs are missing
+    /// (no version, or no content ID for [`NamingScheme::ContentId`]). Names
+    /// are always FAT32-safe.
+    pub fn file_name(&self, ctx: &NamingContext) -> String {
+        let has_title_id = !clean_title_id(ctx.title_id).is_empty();
+        let template = match self {
+            NamingScheme::Original => None,
+            NamingScheme::Descriptive if ctx.version.is_empty() || !has_title_id => None,
+            NamingScheme::Descriptive if ctx.game_name.trim().is_empty() => {
+                Some("{title_id} v{version}.pkg")
+            }
+            NamingScheme::Descriptive => Some("{title} [{title_id}] v{version}.pkg"),
+            NamingScheme::ContentId => ctx
+                .content_id
+                .filter(|c| !c.is_empty() && !ctx.version_digits().is_empty())
+                .map(|_| "{content_id}-A{app_ver}.pkg"),
+        };
+        match template {
+            Some(template) => ctx.render(
+                &NameTemplate::parse(template, &NamingContext::PLACEHOLDERS)
+                    .expect("built-in name templates are valid"),
+            ),
+            None => fat32_name(ctx.original),
+        }
+    }
+}
+
+/// How the folder holding a game's packages is named
+///
+/// The template may use `{title}` (the game name, cleaned by
+/// [`safe_dir_name`](crate::safe_dir_name)), `{title_id}` and `{region}`
+/// (`EU`, `US`…). `/` splits it into nested folders, e.g.
+/// `{region}/{title} ({title_id})`. It's a [`NameTemplate`], so each folder
+/// name is made FAT32-safe and the result always stays under the root it's
+/// joined to.
+///
+/// Game names are ASCII-only unless [`with_unicode`](Self::with_unicode) is
+/// set. Only the template text is serialized, not that choice.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct FolderTemplate {
+    template: NameTemplate,
+    unicode: bool,
+}
+
+const FOLDER_PLACEHOLDERS: [&str; 3] = ["title", "title_id", "region"];
+
+impl FolderTemplate {
+    /// `Game Name (BLES00779)`
+    pub const DEFAULT: &'static str = "{title} ({title_id})";
+
+    /// Check a template; unknown or unclosed placeholders are rejected
+    pub fn parse(template: &str) -> Result<Self> {
+        Ok(Self {
+            template: NameTemplate::parse(template, &FOLDER_PLACEHOLDERS)?,
+            unicode: false,
+        })
+    }
+
+    /// Keep non-ASCII letters in `{title}`, cleaning it with
+    /// [`unicode_dir_name`](crate::unicode_dir_name) instead
+    pub fn with_unicode(mut self, unicode: bool) -> Self {
+        self.unicode = unicode;
+        self
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.template.as_str()
+    }
+
+    /// Folder for a game, relative to wherever its packages are kept
+    pub fn folder(&self, game_name: &str, title_id: &str) -> PathBuf {
+        let title_id = clean_title_id(title_id);
+        let region = TitleId::parse(&title_id).map_or(Region::Unknown, |id| id.region());
+        let title = if self.unicode {
+            unicode_dir_name(game_name)
+        } else {
+            safe_dir_name(game_name)
+        };
+        self.template.render_path(|name| match name {
+            "title" => title.clone(),
+            "title_id" => title_id.clone(),
+            _ => region.code().to_string(),
+        })
+    }
+
+    /// Where a package goes under `root`
+    pub fn package_path(
+        &self,
+        root: &Path,
+        game_name: &str,
+        title_id: &str,
+        filename: &str,
+    ) -> PathBuf {
+        root.join(self.folder(game_name, title_id)).join(filename)
+    }
+}
+
+impl Default for FolderTemplate {
+    fn default() -> Self {
+        Self::parse(Self::DEFAULT).expect("default folder template is valid")
+    }
+}
+
+impl fmt::Display for FolderTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FolderTemplate {
+    type Err = PS3UpdateError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for FolderTemplate {
+    type Error = PS3UpdateError;
+
+    fn try_from(s: String) -> Result<Self> {
+        Self::parse(&s)
+    }
+}
+
+impl From<FolderTemplate> for String {
+    fn from(template: FolderTemplate) -> Self {
+        template.as_str().to_string()
+    }
+}
+
+/// Rename a file within its folder, returning the new path
+///
+/// An existing file with the target name is replaced.
+pub fn rename_in_place(path: &Path, new_name: &str) -> Result<PathBuf> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| PS3UpdateError::Download(format!("no parent folder: {}", path.display())))?;
+    let dest = dir.join(new_name);
+    if dest != path {
+        std::fs::rename(path, &dest)?;
+    }
+    Ok(dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        let ctx = NamingContext {
+            original: "EP0001-BLES00779_00-PATCH-A0102-V0100-PE.pkg",
+            title_id: "bles-00779",
+            game_name: "Game: Subtitle",
+            version: "01.02",
+            content_id: Some("EP0001-BLES00779_00-PATCH00000000001"),
+        };
+        assert_eq!(NamingScheme::Original.file_name(&ctx), ctx.original);
+        assert_eq!(
+            NamingScheme::Descriptive.file_name(&ctx),
+            "Game_ Subtitle [BLES00779] v01.02.pkg"
+        );
+        assert_eq!(
+            NamingScheme::ContentId.file_name(&ctx),
+            "EP0001-BLES00779_00-PATCH00000000001-A0102.pkg"
+        );
+
+        let bare = NamingContext {
+            original: "update.pkg",
+            title_id: "BLES00779",
+            version: "1.5",
+            ..Default::default()
+        };
+        assert_eq!(
+            NamingScheme::Descriptive.file_name(&bare),
+            "BLES00779 v1.5.pkg"
+        );
+        assert_eq!(NamingScheme::ContentId.file_name(&bare), "update.pkg");
+    }
+
+    #[test]
+    fn test_folder_template() {
+        let default = FolderTemplate::default();
+        assert_eq!(
+            default.folder("Demon's Souls", "bles-00779"),
+            PathBuf::from("Demon s Souls (BLES00779)")
+        );
+        assert_eq!(
+            default.package_path(Path::new("/dl"), "", "BLES00779", "a.pkg"),
+            Path::new("/dl")
+                .join("PS3Updates (BLES00779)")
+                .join("a.pkg")
+        );
+
+        let nested: FolderTemplate = "/{region}/../{title_id}: {title}.".parse().unwrap();
+        assert_eq!(
+            nested.folder("Game", "NPUB30910"),
+            Path::new("US").join("_").join("NPUB30910_ Game")
+        );
+
+        assert!(FolderTemplate::parse("{title} [{version}]").is_err());
+        assert!(FolderTemplate::parse("{title").is_err());
+        assert!(FolderTemplate::parse("  ").is_err());
+        assert_eq!(
+            serde_json::from_str::<FolderTemplate>("\"{title_id}\"")
+                .unwrap()
+                .as_str(),
+            "{title_id}"
+        );
+        assert!(serde_json::from_str::<FolderTemplate>("\"{nope}\"").is_err());
+
+        let unicode = FolderTemplate::default().with_unicode(true);
+        assert_eq!(
+            unicode.folder("ドラゴンクエスト", "BLJM60001"),
+            PathBuf::from("ドラゴンクエスト (BLJM60001)")
+        );
+        assert_eq!(
+            unicode.folder("Pokémon: Rumble", "NPUB30910"),
+            PathBuf::from(if cfg!(windows) {
+                "Pokémon Rumble (NPUB30910)"
+            } else {
+                "Pokémon_ Rumble (NPUB30910)"
+            })
+        );
+    }
+}
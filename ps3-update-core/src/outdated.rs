@@ -0,0 +1,201 @@
+use crate::fetcher::UpdateFetcher;
+use crate::installed::InstalledGame;
+use crate::types::{FetchResult, PS3UpdateError, PackageInfo};
+use crate::utils::compare_versions;
+use serde::{Deserialize, Serialize};
+
+/// Update status of one installed title
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutdatedTitle {
+    pub title_id: String,
+    pub title: String,
+    pub installed_version: Option<String>,
+    pub latest_version: Option<String>,
+    /// Packages newer than the installed version, oldest first (install order)
+    pub packages: Vec<PackageInfo>,
+    pub total_bytes: u64,
+    /// Set when the update list couldn't be fetched
+    pub error: Option<String>,
+}
+
+impl OutdatedTitle {
+    /// Compare an installed game against its fetched update list
+    pub fn from_fetch(game: &InstalledGame, result: &FetchResult) -> Self {
+        let installed = game.app_version.as_deref();
+        let mut packages: Vec<PackageInfo> = result
+            .results
+            .iter()
+            .filter(|p| installed.is_none_or(|v| compare_versions(&p.version, v).is_gt()))
+            .cloned()
+            .collect();
+        packages.sort_by(|a, b| compare_versions(&a.version, &b.version));
+
+        let title = if game.title.is_empty() {
+            result.game_title.clone()
+        } else {
+            game.title.clone()
+        };
+        Self {
+            title_id: game.title_id.clone(),
+            title,
+            installed_version: game.app_version.clone(),
+            latest_version: result
+                .results
+                .iter()
+                .map(|p| &p.version)
+                .max_by(|a, b| compare_versions(a, b))
+                .cloned(),
+            total_bytes: packages.iter().map(|p| p.size_bytes).sum(),
+            packages,
+            error: None,
+        }
+    }
+
+    pub fn is_outdated(&self) -> bool {
+        !self.packages.is_empty()
+    }
+}
+
+/// Installed games compared against Sony's update server
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutdatedReport {
+    pub titles: Vec<OutdatedTitle>,
+}
+
+impl OutdatedReport {
+    /// Titles with at least one newer package
+    pub fn outdated(&self) -> impl Iterator<Item = &OutdatedTitle> {
+        self.titles.iter().filter(|t| t.is_outdated())
+    }
+
+    /// Download size needed to bring every title up to date
+    pub fn total_bytes(&self) -> u64 {
+        self.titles.iter().map(|t| t.total_bytes).sum()
+    }
+
+    /// All packages to download, grouped by title in install order
+    pub fn packages(&self) -> impl Iterator<Item = (&OutdatedTitle, &PackageInfo)> {
+        self.titles
+            .iter()
+            .flat_map(|t| t.packages.iter().map(move |p| (t, p)))
+    }
+}
+
+/// Fetch the update list for each installed game and work out what's missing
+///
+/// Fetch failures are recorded per title rather than aborting the report. A
+/// title the server has no updates for is up to date.
+pub async fn check_outdated(fetcher: &UpdateFetcher, games: &[InstalledGame]) -> OutdatedReport {
+    let mut report = OutdatedReport::default();
+    for game in games {
+        let title = match fetcher.fetch_updates(&game.title_id).await {
+            Ok(result) => OutdatedTitle::from_fetch(game, &result),
+            Err(PS3UpdateError::NoUpdatesFound { title_id, .. }) => OutdatedTitle::from_fetch(
+                game,
+                &FetchResult {
+                    results: vec![],
+                    error: None,
+                    game_title: fetcher.title_name(&title_id).unwrap_or_default(),
+                    cleaned_title_id: title_id,
+                },
+            ),
+            Err(e) => OutdatedTitle {
+                title_id: game.title_id.clone(),
+                title: game.title.clone(),
+                installed_version: game.app_version.clone(),
+                error: Some(e.to_string()),
+                ..Default::default()
+            },
+        };
+        report.titles.push(title);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::installed::InstallKind;
+    use std::path::PathBuf;
+
+    fn package(version: &str, size_bytes: u64) -> PackageInfo {
+        PackageInfo {
+            version: version.to_string(),
+            system_ver: String::new(),
+            size_bytes,
+            size_human: String::new(),
+            url: String::new(),
+            sha1: String::new(),
+            filename: format!("{}.pkg", version),
+        }
+    }
+
+    #[test]
+    fn test_from_fetch() {
+        let result = FetchResult {
+            results: vec![
+                package("01.10", 300),
+                package("01.02", 200),
+                package("01.01", 100),
+            ],
+            error: None,
+            game_title: "Server Title".into(),
+            cleaned_title_id: "BLES00779".into(),
+        };
+        let mut game = InstalledGame {
+            title_id: "BLES00779".into(),
+            title: String::new(),
+            app_version: Some("01.01".into()),
+            category: None,
+            kind: InstallKind::Disc,
+            path: PathBuf::new(),
+        };
+
+        let title = OutdatedTitle::from_fetch(&game, &result);
+        assert!(title.is_outdated());
+        assert_eq!(title.title, "Server Title");
+        assert_eq!(title.latest_version.as_deref(), Some("01.10"));
+        let versions: Vec<_> = title.packages.iter().map(|p| p.version.as_str()).collect();
+        assert_eq!(versions, ["01.02", "01.10"]);
+        assert_eq!(title.total_bytes, 500);
+
+        game.app_version = Some("01.10".into());
+        assert!(!OutdatedTitle::from_fetch(&game, &result).is_outdated());
+
+        game.app_version = None;
+        let report = OutdatedReport {
+            titles: vec![OutdatedTitle::from_fetch(&game, &result)],
+        };
+        assert_eq!(report.total_bytes(), 600);
+        assert_eq!(report.packages().count(), 3);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_check_outdated() {
+        let server = crate::MockUpdateServer::new()
+            .with_title("BLES00779", "Demon's Souls", &["01.01", "01.02"])
+            .start()
+            .await
+            .unwrap();
+        let game = |title_id: &str| InstalledGame {
+            title_id: title_id.into(),
+            title: String::new(),
+            app_version: Some("01.01".into()),
+            category: None,
+            kind: InstallKind::Disc,
+            path: PathBuf::new(),
+        };
+        // BCUS98111 has no patches, so the server has no update list for it
+        let games = [game("BLES00779"), game("BCUS98111")];
+        let report = check_outdated(&server.fetcher().unwrap(), &games).await;
+
+        assert_eq!(report.titles.len(), 2);
+        assert!(report.titles.iter().all(|t| t.error.is_none()));
+        assert_eq!(report.outdated().count(), 1);
+        let patchless = &report.titles[1];
+        assert_eq!(patchless.title_id, "BCUS98111");
+        assert!(!patchless.is_outdated());
+        assert_eq!(patchless.latest_version, None);
+    }
+}
@@ -0,0 +1,531 @@
+use crate::sfo::ParamSfo;
+use crate::title_id::TitleId;
+use crate::types::{PS3UpdateError, Result};
+use crate::utils::{clean_title_id, compare_versions};
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::Aes128;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// First four bytes of every PKG file (`\x7FPKG`)
+pub const PKG_MAGIC: u32 = 0x7F50_4B47;
+
+/// AES key used for the body of retail PS3 packages
+const PS3_PKG_KEY: [u8; 16] = [
+    0x2E, 0x7B, 0x71, 0xD7, 0xC9, 0xC9, 0xA1, 0x4E, 0xA3, 0x22, 0x1F, 0x18, 0x88, 0x28, 0xB8, 0xF8,
+];
+
+/// Size of one entry in the item table
+const ITEM_ENTRY_SIZE: u64 = 0x20;
+
+/// Build type recorded in the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PkgRevision {
+    /// Debug/devkit package (`0x0000`)
+    Debug,
+    /// Retail (finalized) package (`0x8000`)
+    Retail,
+    Unknown(u16),
+}
+
+impl From<u16> for PkgRevision {
+    fn from(value: u16) -> Self {
+        match value {
+            0x0000 => PkgRevision::Debug,
+            0x8000 => PkgRevision::Retail,
+            other => PkgRevision::Unknown(other),
+        }
+    }
+}
+
+/// Platform the package targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PkgPlatform {
+    Ps3,
+    Psp,
+    Unknown(u16),
+}
+
+impl From<u16> for PkgPlatform {
+    fn from(value: u16) -> Self {
+        match value {
+            0x0001 => PkgPlatform::Ps3,
+            0x0002 => PkgPlatform::Psp,
+            other => PkgPlatform::Unknown(other),
+        }
+    }
+}
+
+/// Fixed-size header at the start of a PKG file
+///
+/// All fields are stored big-endian. Only the unencrypted part of the header is
+/// read; the item table and body are left untouched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PkgHeader {
+    pub revision: PkgRevision,
+    pub platform: PkgPlatform,
+    pub metadata_offset: u32,
+    pub metadata_count: u32,
+    pub metadata_size: u32,
+    /// Number of entries (files and directories) in the package
+    pub item_count: u32,
+    /// Size of the whole file, including header and trailing digest
+    pub total_size: u64,
+    /// Offset of the encrypted body
+    pub data_offset: u64,
+    /// Size of the encrypted body
+    pub data_size: u64,
+    /// e.g. `EP0001-BLES00779_00-PATCH00000000001`
+    pub content_id: String,
+    pub digest: [u8; 16],
+    /// Initialisation vector for the body keystream
+    pub data_riv: [u8; 16],
+}
+
+impl PkgHeader {
+    /// Number of bytes needed to parse a header
+    pub const SIZE: usize = 0xC0;
+
+    /// Parse a header from the start of a PKG file
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < Self::SIZE {
+            return Err(PS3UpdateError::InvalidPkg(format!(
+                "header truncated ({} of {} bytes)",
+                bytes.len(),
+                Self::SIZE
+            )));
+        }
+        let magic = be_u32(bytes, 0x00);
+        if magic != PKG_MAGIC {
+            return Err(PS3UpdateError::InvalidPkg(format!(
+                "bad magic 0x{:08X}",
+                magic
+            )));
+        }
+
+        let content_id = bytes[0x30..0x60]
+            .iter()
+            .take_while(|&&b| b != 0)
+            .map(|&b| b as char)
+            .collect();
+
+        Ok(Self {
+            revision: be_u16(bytes, 0x04).into(),
+            platform: be_u16(bytes, 0x06).into(),
+            metadata_offset: be_u32(bytes, 0x08),
+            metadata_count: be_u32(bytes, 0x0C),
+            metadata_size: be_u32(bytes, 0x10),
+            item_count: be_u32(bytes, 0x14),
+            total_size: be_u64(bytes, 0x18),
+            data_offset: be_u64(bytes, 0x20),
+            data_size: be_u64(bytes, 0x28),
+            content_id,
+            digest: bytes[0x60..0x70].try_into().unwrap(),
+            data_riv: bytes[0x70..0x80].try_into().unwrap(),
+        })
+    }
+
+    /// Read and parse the header of a PKG file on disk
+    pub fn read_from(path: impl AsRef<Path>) -> Result<Self> {
+        let mut buf = Vec::with_capacity(Self::SIZE);
+        std::fs::File::open(path)?
+            .take(Self::SIZE as u64)
+            .read_to_end(&mut buf)?;
+        Self::parse(&buf)
+    }
+
+    /// Title ID embedded in the content ID (`EP0001-BLES00779_00-…` → `BLES00779`)
+    pub fn title_id(&self) -> Option<TitleId> {
+        self.content_id
+            .get(7..16)
+            .and_then(|id| TitleId::parse(id).ok())
+    }
+
+    /// True if the content ID belongs to `title_id` (any formatting)
+    pub fn matches_title(&self, title_id: &str) -> bool {
+        match (self.title_id(), TitleId::parse(title_id)) {
+            (Some(actual), Ok(expected)) => actual == expected,
+            _ => false,
+        }
+    }
+}
+
+/// Check that the PKG at `path` is `version` (when given) of `title_id`
+///
+/// The title comes from the content ID. The version is `APP_VER` from the
+/// package's PARAM.SFO; a package without a readable one is judged on its
+/// title alone. Fails with [`PS3UpdateError::PackageMismatch`].
+pub fn check_package(path: impl AsRef<Path>, title_id: &str, version: Option<&str>) -> Result<()> {
+    let path = path.as_ref();
+    let header = PkgHeader::read_from(path).map_err(|e| {
+        PS3UpdateError::PackageMismatch(format!("downloaded file is not a valid PKG ({})", e))
+    })?;
+    if !header.matches_title(title_id) {
+        return Err(PS3UpdateError::PackageMismatch(format!(
+            "expected {}, got content ID {}",
+            clean_title_id(title_id),
+            header.content_id
+        )));
+    }
+
+    let Some(version) = version else {
+        return Ok(());
+    };
+    let sfo = PkgReader::open(path).and_then(|mut reader| reader.read_param_sfo());
+    match sfo.ok().flatten().as_ref().and_then(ParamSfo::app_version) {
+        Some(actual) if compare_versions(actual, version).is_ne() => {
+            Err(PS3UpdateError::PackageMismatch(format!(
+                "expected {} version {}, got version {} ({})",
+                clean_title_id(title_id),
+                version,
+                actual,
+                header.content_id
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// An entry (file or directory) in a package's item table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PkgItem {
+    /// Path inside the package, e.g. `PARAM.SFO` or `USRDIR/EBOOT.BIN`
+    pub name: String,
+    /// Offset relative to the start of the body
+    pub offset: u64,
+    pub size: u64,
+    pub flags: u32,
+}
+
+/// Body keystream, selected by the header revision
+enum Keystream {
+    /// Retail: AES-128 over a counter starting at the header's data RIV
+    Retail { cipher: Box<Aes128>, riv: u128 },
+    /// Debug: SHA1 over a block derived from the header digest
+    Debug { digest: [u8; 16] },
+}
+
+impl Keystream {
+    fn for_header(header: &PkgHeader) -> Result<Self> {
+        match header.revision {
+            PkgRevision::Retail if header.platform == PkgPlatform::Ps3 => Ok(Keystream::Retail {
+                cipher: Box::new(Aes128::new(&PS3_PKG_KEY.into())),
+                riv: u128::from_be_bytes(header.data_riv),
+            }),
+            PkgRevision::Debug => Ok(Keystream::Debug {
+                digest: header.digest,
+            }),
+            _ => Err(PS3UpdateError::InvalidPkg(format!(
+                "unsupported package type ({:?}, {:?})",
+                header.revision, header.platform
+            ))),
+        }
+    }
+
+    fn block(&self, index: u64) -> [u8; 16] {
+        match self {
+            Keystream::Retail { cipher, riv } => {
+                let mut block = riv.wrapping_add(index as u128).to_be_bytes().into();
+                cipher.encrypt_block(&mut block);
+                block.into()
+            }
+            Keystream::Debug { digest } => {
+                let mut input = [0u8; 0x40];
+                input[0x00..0x08].copy_from_slice(&digest[0..8]);
+                input[0x08..0x10].copy_from_slice(&digest[0..8]);
+                input[0x10..0x18].copy_from_slice(&digest[8..16]);
+                input[0x18..0x20].copy_from_slice(&digest[8..16]);
+                input[0x38..0x40].copy_from_slice(&index.to_be_bytes());
+                let hash = Sha1::digest(input);
+                hash[..16].try_into().unwrap()
+            }
+        }
+    }
+
+    /// XOR `data` (found at `offset` into the body) with the keystream
+    fn apply(&self, offset: u64, data: &mut [u8]) {
+        let mut pos = 0;
+        while pos < data.len() {
+            let abs = offset + pos as u64;
+            let block = self.block(abs / 16);
+            let skip = (abs % 16) as usize;
+            let n = (16 - skip).min(data.len() - pos);
+            for i in 0..n {
+                data[pos + i] ^= block[skip + i];
+            }
+            pos += n;
+        }
+    }
+}
+
+/// Reads and decrypts entries from a PS3 package
+pub struct PkgReader<R> {
+    reader: R,
+    header: PkgHeader,
+    keystream: Keystream,
+    /// Length of the underlying file; the header's sizes aren't trusted past it
+    len: u64,
+}
+
+impl PkgReader<File> {
+    /// Open a PKG file on disk
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> PkgReader<R> {
+    /// Parse the header from `reader`
+    ///
+    /// Retail PS3 and debug packages are supported; other platforms are rejected.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut buf = Vec::with_capacity(PkgHeader::SIZE);
+        reader.seek(SeekFrom::Start(0))?;
+        (&mut reader)
+            .take(PkgHeader::SIZE as u64)
+            .read_to_end(&mut buf)?;
+        let header = PkgHeader::parse(&buf)?;
+        let keystream = Keystream::for_header(&header)?;
+        let len = reader.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            reader,
+            header,
+            keystream,
+            len,
+        })
+    }
+
+    pub fn header(&self) -> &PkgHeader {
+        &self.header
+    }
+
+    /// Decrypt and list the item table
+    pub fn items(&mut self) -> Result<Vec<PkgItem>> {
+        let count = self.header.item_count as u64;
+        let table = self.read_body(0, count * ITEM_ENTRY_SIZE)?;
+
+        let mut items = Vec::with_capacity(count as usize);
+        for entry in table.chunks_exact(ITEM_ENTRY_SIZE as usize) {
+            let name_offset = be_u32(entry, 0x00) as u64;
+            let name_size = be_u32(entry, 0x04) as u64;
+            let name = self.read_body(name_offset, name_size)?;
+            items.push(PkgItem {
+                name: String::from_utf8_lossy(&name)
+                    .trim_end_matches('\0')
+                    .to_string(),
+                offset: be_u64(entry, 0x08),
+                size: be_u64(entry, 0x10),
+                flags: be_u32(entry, 0x18),
+            });
+        }
+        Ok(items)
+    }
+
+    /// Decrypt an item's contents
+    pub fn read_item(&mut self, item: &PkgItem) -> Result<Vec<u8>> {
+        self.read_body(item.offset, item.size)
+    }
+
+    /// Locate and decrypt the top-level PARAM.SFO, if the package has one
+    pub fn param_sfo(&mut self) -> Result<Option<Vec<u8>>> {
+        let item = self
+            .items()?
+            .into_iter()
+            .find(|item| item.name.eq_ignore_ascii_case("PARAM.SFO"));
+        match item {
+            Some(item) => self.read_item(&item).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Locate and parse the top-level PARAM.SFO
+    pub fn read_param_sfo(&mut self) -> Result<Option<ParamSfo>> {
+        self.param_sfo()?
+            .map(|bytes| ParamSfo::parse(&bytes))
+            .transpose()
+    }
+
+    fn read_body(&mut self, offset: u64, size: u64) -> Result<Vec<u8>> {
+        let end = offset.saturating_add(size);
+        if end > self.header.data_size || self.header.data_offset.saturating_add(end) > self.len {
+            return Err(PS3UpdateError::InvalidPkg(format!(
+                "entry at 0x{:X} (+0x{:X}) lies outside the body",
+                offset, size
+            )));
+        }
+        let mut data = vec![0u8; size as usize];
+        self.reader
+            .seek(SeekFrom::Start(self.header.data_offset + offset))?;
+        self.reader.read_exact(&mut data)?;
+        self.keystream.apply(offset, &mut data);
+        Ok(data)
+    }
+}
+
+pub(crate) fn be_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+pub(crate) fn be_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+pub(crate) fn be_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_be_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const CONTENT_ID: &str = "EP0001-BLES00779_00-PATCH00000000001";
+
+    /// Build a minimal header with the given content ID and sizes
+    pub(crate) fn sample_header(content_id: &str, data_size: u64) -> Vec<u8> {
+        let mut buf = vec![0u8; PkgHeader::SIZE];
+        buf[0x00..0x04].copy_from_slice(&PKG_MAGIC.to_be_bytes());
+        buf[0x04..0x06].copy_from_slice(&0x8000u16.to_be_bytes());
+        buf[0x06..0x08].copy_from_slice(&1u16.to_be_bytes());
+        buf[0x14..0x18].copy_from_slice(&3u32.to_be_bytes());
+        let total = PkgHeader::SIZE as u64 + data_size + 0x20;
+        buf[0x18..0x20].copy_from_slice(&total.to_be_bytes());
+        buf[0x20..0x28].copy_from_slice(&(PkgHeader::SIZE as u64).to_be_bytes());
+        buf[0x28..0x30].copy_from_slice(&data_size.to_be_bytes());
+        buf[0x30..0x30 + content_id.len()].copy_from_slice(content_id.as_bytes());
+        buf
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let header = PkgHeader::parse(&sample_header(CONTENT_ID, 0x40)).unwrap();
+        assert_eq!(header.revision, PkgRevision::Retail);
+        assert_eq!(header.platform, PkgPlatform::Ps3);
+        assert_eq!(header.item_count, 3);
+        assert_eq!(header.data_offset, 0xC0);
+        assert_eq!(header.data_size, 0x40);
+        assert_eq!(header.total_size, 0xC0 + 0x40 + 0x20);
+        assert_eq!(header.content_id, CONTENT_ID);
+        assert_eq!(header.title_id().unwrap().as_str(), "BLES00779");
+        assert!(header.matches_title("bles-00779"));
+        assert!(!header.matches_title("BLUS30109"));
+    }
+
+    /// Build a package whose body holds the given files, encrypted like the real thing
+    pub(crate) fn sample_pkg_with_items(revision: u16, files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut table = vec![];
+        let mut blobs = vec![];
+        let mut cursor = files.len() as u64 * ITEM_ENTRY_SIZE;
+        for (name, data) in files {
+            let name_offset = cursor;
+            blobs.extend_from_slice(name.as_bytes());
+            blobs.resize(blobs.len().next_multiple_of(16), 0);
+            cursor = files.len() as u64 * ITEM_ENTRY_SIZE + blobs.len() as u64;
+            let data_offset = cursor;
+            blobs.extend_from_slice(data);
+            blobs.resize(blobs.len().next_multiple_of(16), 0);
+            cursor = files.len() as u64 * ITEM_ENTRY_SIZE + blobs.len() as u64;
+
+            let mut entry = [0u8; ITEM_ENTRY_SIZE as usize];
+            entry[0x00..0x04].copy_from_slice(&(name_offset as u32).to_be_bytes());
+            entry[0x04..0x08].copy_from_slice(&(name.len() as u32).to_be_bytes());
+            entry[0x08..0x10].copy_from_slice(&data_offset.to_be_bytes());
+            entry[0x10..0x18].copy_from_slice(&(data.len() as u64).to_be_bytes());
+            entry[0x18..0x1C].copy_from_slice(&3u32.to_be_bytes());
+            table.extend_from_slice(&entry);
+        }
+        let mut body = table;
+        body.extend_from_slice(&blobs);
+
+        let mut data = sample_header(CONTENT_ID, body.len() as u64);
+        data[0x04..0x06].copy_from_slice(&revision.to_be_bytes());
+        data[0x14..0x18].copy_from_slice(&(files.len() as u32).to_be_bytes());
+        data[0x60..0x70].copy_from_slice(&[0x11; 16]);
+        data[0x70..0x80].copy_from_slice(&[0x22; 16]);
+
+        let header = PkgHeader::parse(&data).unwrap();
+        Keystream::for_header(&header).unwrap().apply(0, &mut body);
+        data.extend_from_slice(&body);
+        let digest = Sha1::digest(&data);
+        data.extend_from_slice(&digest);
+        data.extend_from_slice(&[0u8; 12]);
+        data
+    }
+
+    #[test]
+    fn test_extract_param_sfo() {
+        let sfo = &crate::sfo::tests::sample_sfo(&[("APP_VER", "01.02")])[..];
+        for revision in [0x8000u16, 0x0000] {
+            let pkg = sample_pkg_with_items(
+                revision,
+                &[("USRDIR/EBOOT.BIN", b"eboot"), ("PARAM.SFO", sfo)],
+            );
+            let mut reader = PkgReader::new(std::io::Cursor::new(pkg)).unwrap();
+            let items = reader.items().unwrap();
+            assert_eq!(items.len(), 2);
+            assert_eq!(items[0].name, "USRDIR/EBOOT.BIN");
+            assert_eq!(reader.read_item(&items[0]).unwrap(), b"eboot");
+            assert_eq!(reader.param_sfo().unwrap().unwrap(), sfo);
+            let parsed = reader.read_param_sfo().unwrap().unwrap();
+            assert_eq!(parsed.app_version(), Some("01.02"));
+        }
+
+        let pkg = sample_pkg_with_items(0x8000, &[("USRDIR/EBOOT.BIN", b"eboot")]);
+        let mut reader = PkgReader::new(std::io::Cursor::new(pkg)).unwrap();
+        assert_eq!(reader.param_sfo().unwrap(), None);
+    }
+
+    #[test]
+    fn test_check_package() {
+        let sfo = &crate::sfo::tests::sample_sfo(&[("APP_VER", "01.02")])[..];
+        let path = crate::verify::tests::write_temp(
+            "check-package",
+            &sample_pkg_with_items(0x8000, &[("PARAM.SFO", sfo)]),
+        );
+        assert!(check_package(&path, "BLES00779", None).is_ok());
+        assert!(check_package(&path, "bles-00779", Some("01.02")).is_ok());
+        assert!(matches!(
+            check_package(&path, "BLES00779", Some("01.01")),
+            Err(PS3UpdateError::PackageMismatch(_))
+        ));
+        assert!(matches!(
+            check_package(&path, "BLUS30109", Some("01.02")),
+            Err(PS3UpdateError::PackageMismatch(_))
+        ));
+
+        // Without a PARAM.SFO only the title is checked
+        let path = crate::verify::tests::write_temp(
+            "check-package",
+            &crate::verify::tests::sample_pkg(CONTENT_ID),
+        );
+        assert!(check_package(&path, "BLES00779", Some("01.01")).is_ok());
+    }
+
+    #[test]
+    fn test_sizes_past_the_end_of_the_file() {
+        // The header claims a huge body and item table that the file doesn't have
+        let mut pkg = sample_header(CONTENT_ID, u64::MAX / 2);
+        pkg[0x14..0x18].copy_from_slice(&u32::MAX.to_be_bytes());
+        pkg.extend_from_slice(&[0u8; 0x40]);
+        let mut reader = PkgReader::new(std::io::Cursor::new(pkg)).unwrap();
+        assert!(matches!(reader.items(), Err(PS3UpdateError::InvalidPkg(_))));
+        let item = PkgItem {
+            name: "PARAM.SFO".into(),
+            offset: 0,
+            size: u64::MAX / 4,
+            flags: 0,
+        };
+        assert!(matches!(
+            reader.read_item(&item),
+            Err(PS3UpdateError::InvalidPkg(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert!(PkgHeader::parse(b"\x7FPKG").is_err());
+        assert!(PkgHeader::parse(&[0u8; PkgHeader::SIZE]).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use crate::downloader::{DownloadManager, DownloadOptions};
+use crate::export::PlannedDownload;
+use crate::queue::{QueueManager, QueueStatus};
+use crate::types::{Failure, Result};
+use crate::utils::format_size;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// One file of a [`DownloadPlan`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanItem {
+    #[serde(flatten)]
+    pub download: PlannedDownload,
+    /// A file of the listed size is already there, so it won't be downloaded
+    pub present: bool,
+}
+
+/// Everything a batch of downloads would do, for a caller to confirm before
+/// anything is fetched
+///
+/// Built from packages with [`DownloadPlan::new`] or from a library with
+/// [`crate::Library::download_plan`], then run as a unit with
+/// [`DownloadPlan::execute`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DownloadPlan {
+    pub items: Vec<PlanItem>,
+    /// Files to download, leaving out those already present
+    pub file_count: usize,
+    /// Bytes to download
+    pub total_bytes: u64,
+    pub total_human: String,
+    pub present_count: usize,
+    /// At the speed given to [`DownloadPlan::with_speed`], if any
+    pub estimated_secs: Option<f64>,
+    /// Titles that couldn't be checked
+    pub errors: Vec<Failure>,
+}
+
+/// Outcome of [`DownloadPlan::execute`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DownloadPlanReport {
+    /// Where each finished file ended up, after any rename
+    pub downloaded: Vec<PathBuf>,
+    /// Files that failed, by URL
+    pub failed: Vec<Failure>,
+    /// Files left alone because they were already present
+    pub skipped: usize,
+}
+
+impl DownloadPlan {
+    /// Plan `downloads`, marking files already at their destination with the
+    /// listed size
+    pub fn new(downloads: Vec<PlannedDownload>) -> Self {
+        let items = downloads
+            .into_iter()
+            .map(|download| {
+                let present = std::fs::metadata(download.path()).map(|m| m.len()).ok()
+                    == Some(download.package.size_bytes);
+                PlanItem { download, present }
+            })
+            .collect();
+        Self::from_items(items)
+    }
+
+    /// Plan items whose presence is already known
+    pub fn from_items(items: Vec<PlanItem>) -> Self {
+        let mut plan = Self {
+            items,
+            ..Default::default()
+        };
+        plan.update_totals();
+        plan
+    }
+
+    /// Estimate the time the plan takes at `bytes_per_sec`
+    pub fn with_speed(mut self, bytes_per_sec: u64) -> Self {
+        self.estimated_secs =
+            (bytes_per_sec > 0).then(|| self.total_bytes as f64 / bytes_per_sec as f64);
+        self
+    }
+
+    /// The estimate from [`DownloadPlan::with_speed`]
+    pub fn estimated_duration(&self) -> Option<Duration> {
+        self.estimated_secs.map(Duration::from_secs_f64)
+    }
+
+    /// Files that would be downloaded
+    pub fn to_download(&self) -> impl Iterator<Item = &PlannedDownload> {
+        self.items
+            .iter()
+            .filter(|item| !item.present)
+            .map(|item| &item.download)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.file_count == 0
+    }
+
+    /// `14 files, 22.30 GB`, for a confirmation prompt
+    pub fn summary(&self) -> String {
+        let files = if self.file_count == 1 {
+            "file"
+        } else {
+            "files"
+        };
+        format!("{} {}, {}", self.file_count, files, self.total_human)
+    }
+
+    /// Download every file that isn't present, one after another
+    ///
+    /// Runs through a [`QueueManager`], so each file gets `options` with its
+    /// title checked and its manifest entry recorded, as
+    /// [`crate::Library::sync`] does. Failures are collected rather than
+    /// stopping the rest.
+    pub async fn execute(
+        &self,
+        manager: &DownloadManager,
+        options: &DownloadOptions,
+    ) -> Result<DownloadPlanReport> {
+        let queue = QueueManager::new(manager.clone(), options.clone());
+        queue.enqueue_plan(self);
+        queue.wait().await;
+
+        let mut report = DownloadPlanReport {
+            skipped: self.present_count,
+            ..Default::default()
+        };
+        for item in queue.items() {
+            let download = item.download;
+            match item.status {
+                QueueStatus::Done => report.downloaded.push(
+                    item.progress
+                        .and_then(|p| p.filename)
+                        .map(|n| download.dir.join(n))
+                        .unwrap_or_else(|| download.path()),
+                ),
+                _ => report.failed.push(Failure {
+                    target: download.package.url,
+                    error: item.error.unwrap_or_default(),
+                    code: item.error_code,
+                }),
+            }
+        }
+        Ok(report)
+    }
+
+    fn update_totals(&mut self) {
+        let pending: Vec<_> = self.items.iter().filter(|item| !item.present).collect();
+        self.file_count = pending.len();
+        self.total_bytes = pending.iter().map(|i| i.download.package.size_bytes).sum();
+        self.total_human = format_size(self.total_bytes);
+        self.present_count = self.items.len() - self.file_count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PackageInfo;
+
+    fn download(dir: &std::path::Path, filename: &str, size_bytes: u64) -> PlannedDownload {
+        PlannedDownload {
+            title_id: "BLES00779".to_string(),
+            game_title: "Demon's Souls".to_string(),
+            package: PackageInfo {
+                version: "01.00".to_string(),
+                system_ver: "03.40".to_string(),
+                size_bytes,
+                size_human: format_size(size_bytes),
+                url: format!("http://example.com/{}", filename),
+                sha1: String::new(),
+                filename: filename.to_string(),
+            },
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    #[test]
+    fn test_download_plan() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join("a.pkg"), b"pkg").unwrap();
+        std::fs::write(dir.join("b.pkg"), b"partial").unwrap();
+
+        let plan = DownloadPlan::new(vec![
+            download(dir, "a.pkg", 3),
+            download(dir, "b.pkg", 1024),
+            download(dir, "c.pkg", 1024),
+        ])
+        .with_speed(1024);
+        assert_eq!(plan.present_count, 1);
+        assert_eq!(plan.file_count, 2);
+        assert_eq!(plan.total_bytes, 2048);
+        assert_eq!(plan.summary(), "2 files, 2.00 KB");
+        assert_eq!(plan.estimated_duration(), Some(Duration::from_secs(2)));
+        let names: Vec<_> = plan
+            .to_download()
+            .map(|d| d.package.filename.as_str())
+            .collect();
+        assert_eq!(names, ["b.pkg", "c.pkg"]);
+
+        assert!(DownloadPlan::new(vec![]).is_empty());
+        assert_eq!(DownloadPlan::new(vec![]).with_speed(0).estimated_secs, None);
+    }
+}
@@ -0,0 +1,719 @@
+use crate::downloader::{DownloadManager, DownloadOptions};
+use crate::export::PlannedDownload;
+use crate::plan::DownloadPlan;
+use crate::types::{DownloadMode, PS3UpdateError, ProgressInfo, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// How often running items check on their jobs
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// How long a finished item's job stays with the download manager, so event
+// streams and UIs polling the manager see it end before it goes
+const FINISHED_JOB_GRACE: Duration = Duration::from_secs(5);
+
+/// Called with an item each time the queue changes its status
+pub type QueueListener = Arc<dyn Fn(&QueueItem) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueStatus {
+    Queued,
+    Downloading,
+    Done,
+    Failed,
+}
+
+/// A package waiting for, taking or done with its turn in a [`QueueManager`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueItem {
+    pub id: String,
+    #[serde(flatten)]
+    pub download: PlannedDownload,
+    /// Shared by the items of one [`QueueManager::enqueue_group`] call
+    #[serde(default)]
+    pub group_id: Option<String>,
+    pub status: QueueStatus,
+    /// Download manager job, once the item has started
+    pub job_id: Option<String>,
+    /// Live while downloading, final once done or failed
+    pub progress: Option<ProgressInfo>,
+    pub error: Option<String>,
+    /// [`PS3UpdateError::code`] of `error`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    /// Times the item has been started
+    #[serde(default)]
+    pub attempts: u32,
+    /// Its job writes front to back, so a partial file can be continued
+    #[serde(default)]
+    pub contiguous: bool,
+    /// Continue the partial file at its destination when next started
+    #[serde(default)]
+    pub resume: bool,
+}
+
+/// Items of a queue, or of one group, added up
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueueProgress {
+    pub items: usize,
+    pub queued: usize,
+    pub downloading: usize,
+    pub done: usize,
+    pub failed: usize,
+    pub total_bytes: u64,
+    /// Finished items count in full, downloading ones by their progress
+    pub downloaded_bytes: u64,
+    pub percent: f64,
+    /// Of every item downloading, together
+    pub speed_bytes_per_sec: f64,
+}
+
+impl QueueProgress {
+    /// Add up `items`, e.g. the ones a caller queued
+    pub fn from_items<'a>(items: impl IntoIterator<Item = &'a QueueItem>) -> Self {
+        let mut progress = Self::default();
+        for item in items {
+            let size = item.download.package.size_bytes;
+            progress.items += 1;
+            progress.total_bytes += size;
+            match item.status {
+                QueueStatus::Queued => progress.queued += 1,
+                QueueStatus::Downloading => {
+                    progress.downloading += 1;
+                    if let Some(p) = &item.progress {
+                        progress.downloaded_bytes += p.downloaded;
+                        progress.speed_bytes_per_sec += p.speed_bytes_per_sec;
+                    }
+                }
+                QueueStatus::Done => {
+                    progress.done += 1;
+                    progress.downloaded_bytes += size;
+                }
+                QueueStatus::Failed => progress.failed += 1,
+            }
+        }
+        if progress.total_bytes > 0 {
+            progress.percent =
+                progress.downloaded_bytes as f64 / progress.total_bytes as f64 * 100.0;
+        }
+        progress
+    }
+
+    /// Whether nothing is left to start or finish
+    pub fn is_finished(&self) -> bool {
+        self.queued == 0 && self.downloading == 0
+    }
+}
+
+struct QueueState {
+    items: Vec<QueueItem>,
+    next_id: u64,
+    /// Tasks taking items off the queue
+    workers: usize,
+}
+
+/// Downloads packages in queue order, a few at a time, retrying failures
+///
+/// Items start as soon as they're queued, up to
+/// [`QueueManager::with_concurrency`] at once (one by default). Each gets the
+/// queue's [`DownloadOptions`] with its title checked and its manifest entry
+/// recorded. A failed item goes to the back of the queue until it has been
+/// retried [`QueueManager::with_retries`] times. Clones share the queue.
+///
+/// Follow it by polling [`QueueManager::items`] and [`QueueManager::progress`],
+/// or hear about each status change through [`QueueManager::with_listener`].
+/// A finished item's job stays with the [`DownloadManager`] for a few seconds,
+/// so those following the manager's jobs see it end. Needs a Tokio runtime.
+#[derive(Clone)]
+pub struct QueueManager {
+    downloads: DownloadManager,
+    options: Arc<Mutex<DownloadOptions>>,
+    state: Arc<Mutex<QueueState>>,
+    held: Arc<AtomicBool>,
+    concurrency: usize,
+    retries: u32,
+    listener: Option<QueueListener>,
+}
+
+impl QueueManager {
+    /// Queue downloads on `downloads` with `options`
+    pub fn new(downloads: DownloadManager, options: DownloadOptions) -> Self {
+        Self {
+            downloads,
+            options: Arc::new(Mutex::new(options)),
+            state: Arc::new(Mutex::new(QueueState {
+                items: vec![],
+                next_id: 1,
+                workers: 0,
+            })),
+            held: Arc::new(AtomicBool::new(false)),
+            concurrency: 1,
+            retries: 0,
+            listener: None,
+        }
+    }
+
+    /// Download up to `n` items at once
+    pub fn with_concurrency(mut self, n: usize) -> Self {
+        self.concurrency = n.max(1);
+        self
+    }
+
+    /// Queue a failed item again, at the back, up to `retries` times
+    ///
+    /// On top of [`DownloadOptions::with_retries`], which retries network
+    /// errors within a job.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Call `listener` whenever an item starts, finishes, fails or is queued
+    /// for another try
+    pub fn with_listener(mut self, listener: impl Fn(&QueueItem) + Send + Sync + 'static) -> Self {
+        self.listener = Some(Arc::new(listener));
+        self
+    }
+
+    /// Options for items started from now on
+    pub fn set_options(&self, options: DownloadOptions) {
+        *self.options.lock().unwrap() = options;
+    }
+
+    /// Add downloads to the back of the queue
+    pub fn enqueue(&self, downloads: Vec<PlannedDownload>) -> Vec<QueueItem> {
+        self.add(downloads, None)
+    }
+
+    /// Add downloads to the back of the queue as one group, returning its ID
+    /// for [`QueueManager::group_progress`]
+    pub fn enqueue_group(&self, downloads: Vec<PlannedDownload>) -> String {
+        let group_id = {
+            let mut state = self.state.lock().unwrap();
+            state.next_id += 1;
+            (state.next_id - 1).to_string()
+        };
+        self.add(downloads, Some(group_id.clone()));
+        group_id
+    }
+
+    /// Queue the files of a plan that aren't present, as one group
+    pub fn enqueue_plan(&self, plan: &DownloadPlan) -> String {
+        self.enqueue_group(plan.to_download().cloned().collect())
+    }
+
+    fn add(&self, downloads: Vec<PlannedDownload>, group_id: Option<String>) -> Vec<QueueItem> {
+        let added: Vec<QueueItem> = {
+            let mut state = self.state.lock().unwrap();
+            let first = state.next_id;
+            state.next_id += downloads.len() as u64;
+            let added: Vec<QueueItem> = downloads
+                .into_iter()
+                .zip(first..)
+                .map(|(download, id)| QueueItem {
+                    id: id.to_string(),
+                    download,
+                    group_id: group_id.clone(),
+                    status: QueueStatus::Queued,
+                    job_id: None,
+                    progress: None,
+                    error: None,
+                    error_code: None,
+                    attempts: 0,
+                    contiguous: false,
+                    resume: false,
+                })
+                .collect();
+            state.items.extend(added.iter().cloned());
+            added
+        };
+        self.start_workers();
+        added
+    }
+
+    /// Put back items saved from [`QueueManager::items`] by an earlier run,
+    /// after any already queued, and carry on downloading
+    ///
+    /// Items that were downloading wait again. Their partial file is continued
+    /// when it was written front to back, and deleted otherwise.
+    pub fn restore(&self, items: Vec<QueueItem>) {
+        {
+            let mut state = self.state.lock().unwrap();
+            for mut item in items {
+                if item.status == QueueStatus::Downloading {
+                    item.status = QueueStatus::Queued;
+                    item.job_id = None;
+                    item.progress = None;
+                    item.resume = item.contiguous;
+                    if !item.resume {
+                        let _ = std::fs::remove_file(item.download.path());
+                    }
+                }
+                if let Ok(id) = item.id.parse::<u64>() {
+                    state.next_id = state.next_id.max(id + 1);
+                }
+                state.items.push(item);
+            }
+        }
+        self.start_workers();
+    }
+
+    /// Every item in queue order, with live progress for those downloading
+    pub fn items(&self) -> Vec<QueueItem> {
+        let mut items = self.state.lock().unwrap().items.clone();
+        for item in items
+            .iter_mut()
+            .filter(|item| item.status == QueueStatus::Downloading)
+        {
+            if let Some(progress) = item
+                .job_id
+                .as_deref()
+                .and_then(|job_id| self.downloads.get_progress(job_id).ok())
+            {
+                item.progress = Some(progress);
+            }
+        }
+        items
+    }
+
+    pub fn get(&self, id: &str) -> Result<QueueItem> {
+        self.items()
+            .into_iter()
+            .find(|item| item.id == id)
+            .ok_or_else(|| PS3UpdateError::QueueItemNotFound(id.to_string()))
+    }
+
+    /// The whole queue added up
+    pub fn progress(&self) -> QueueProgress {
+        QueueProgress::from_items(&self.items())
+    }
+
+    /// The items queued together as `group_id` added up, if any are left
+    pub fn group_progress(&self, group_id: &str) -> Option<QueueProgress> {
+        let items = self.items();
+        let group: Vec<_> = items
+            .iter()
+            .filter(|item| item.group_id.as_deref() == Some(group_id))
+            .collect();
+        (!group.is_empty()).then(|| QueueProgress::from_items(group))
+    }
+
+    /// Download manager jobs of the items downloading
+    pub fn active_jobs(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .items
+            .iter()
+            .filter(|item| item.status == QueueStatus::Downloading)
+            .filter_map(|item| item.job_id.clone())
+            .collect()
+    }
+
+    /// Move an item to `index`; only the order of waiting items matters
+    pub fn move_item(&self, id: &str, index: usize) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let from = state
+            .items
+            .iter()
+            .position(|item| item.id == id)
+            .ok_or_else(|| PS3UpdateError::QueueItemNotFound(id.to_string()))?;
+        let item = state.items.remove(from);
+        let to = index.min(state.items.len());
+        state.items.insert(to, item);
+        Ok(())
+    }
+
+    /// Drop an item, cancelling its job and deleting its partial file if it
+    /// hasn't finished
+    pub fn remove(&self, id: &str) -> Result<QueueItem> {
+        let removed = {
+            let mut state = self.state.lock().unwrap();
+            let index = state
+                .items
+                .iter()
+                .position(|item| item.id == id)
+                .ok_or_else(|| PS3UpdateError::QueueItemNotFound(id.to_string()))?;
+            state.items.remove(index)
+        };
+        match removed.status {
+            QueueStatus::Downloading => {
+                if let Some(job_id) = &removed.job_id {
+                    self.downloads.remove_job(job_id);
+                }
+                let _ = std::fs::remove_file(removed.download.path());
+            }
+            QueueStatus::Queued if removed.resume => {
+                let _ = std::fs::remove_file(removed.download.path());
+            }
+            _ => {}
+        }
+        Ok(removed)
+    }
+
+    /// Queue a failed item again, at the back, with its retries renewed
+    pub fn retry(&self, id: &str) -> Result<()> {
+        {
+            let mut state = self.state.lock().unwrap();
+            let index = state
+                .items
+                .iter()
+                .position(|item| item.id == id && item.status == QueueStatus::Failed)
+                .ok_or_else(|| PS3UpdateError::QueueItemNotFound(id.to_string()))?;
+            let mut item = state.items.remove(index);
+            item.status = QueueStatus::Queued;
+            item.error = None;
+            item.error_code = None;
+            item.attempts = 0;
+            state.items.push(item);
+        }
+        self.start_workers();
+        Ok(())
+    }
+
+    /// Hold the queue, pausing its downloads and starting nothing new, or let
+    /// it carry on
+    pub fn set_held(&self, held: bool) {
+        if self.held.swap(held, Ordering::SeqCst) == held {
+            return;
+        }
+        for job_id in self.active_jobs() {
+            let _ = if held {
+                self.downloads.pause(&job_id)
+            } else {
+                self.downloads.resume(&job_id)
+            };
+        }
+        if !held {
+            self.start_workers();
+        }
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held.load(Ordering::SeqCst)
+    }
+
+    /// Wait until nothing is left to start or finish, and add up the queue
+    ///
+    /// A held queue with items left isn't finished, so this waits for it to
+    /// be let go.
+    pub async fn wait(&self) -> QueueProgress {
+        loop {
+            let idle = {
+                let state = self.state.lock().unwrap();
+                state.workers == 0
+                    && !state.items.iter().any(|item| {
+                        matches!(item.status, QueueStatus::Queued | QueueStatus::Downloading)
+                    })
+            };
+            if idle {
+                return self.progress();
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    // Start enough workers for the waiting items, up to the concurrency
+    fn start_workers(&self) {
+        if self.is_held() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        let queued = state
+            .items
+            .iter()
+            .filter(|item| item.status == QueueStatus::Queued)
+            .count();
+        let wanted = self.concurrency.saturating_sub(state.workers).min(queued);
+        for _ in 0..wanted {
+            state.workers += 1;
+            tokio::spawn(self.clone().work());
+        }
+    }
+
+    async fn work(self) {
+        while let Some(item) = self.take_next() {
+            self.run(item).await;
+        }
+    }
+
+    // Mark the first waiting item as downloading and hand it out; a worker
+    // that gets nothing stops
+    fn take_next(&self) -> Option<QueueItem> {
+        let item = {
+            let mut state = self.state.lock().unwrap();
+            let next = state
+                .items
+                .iter_mut()
+                .find(|item| item.status == QueueStatus::Queued)
+                .filter(|_| !self.is_held());
+            match next {
+                Some(item) => {
+                    item.status = QueueStatus::Downloading;
+                    item.attempts += 1;
+                    item.error = None;
+                    item.error_code = None;
+                    item.clone()
+                }
+                None => {
+                    state.workers -= 1;
+                    return None;
+                }
+            }
+        };
+        self.notify(&item);
+        Some(item)
+    }
+
+    // Update an item in place; false if it has been removed
+    fn update(&self, id: &str, change: impl FnOnce(&mut QueueItem)) -> bool {
+        let item = {
+            let mut state = self.state.lock().unwrap();
+            let Some(item) = state.items.iter_mut().find(|item| item.id == id) else {
+                return false;
+            };
+            change(item);
+            item.clone()
+        };
+        self.notify(&item);
+        true
+    }
+
+    fn notify(&self, item: &QueueItem) {
+        if let Some(listener) = &self.listener {
+            listener(item);
+        }
+    }
+
+    // Download one item, leaving its final status in the queue
+    async fn run(&self, item: QueueItem) {
+        let download = &item.download;
+        let mut options = self
+            .options
+            .lock()
+            .unwrap()
+            .clone()
+            .expect_title(&download.title_id)
+            .with_manifest(
+                &download.title_id,
+                download.game_title.as_str(),
+                download.package.clone(),
+            );
+        let dest = download.path();
+        let resuming = item.resume && std::fs::metadata(&dest).is_ok_and(|m| m.len() > 0);
+        if resuming {
+            options = options.with_resume();
+        }
+        // Continuing a contiguous file takes one connection and keeps it so
+        let contiguous = resuming || writes_in_order(options.mode, download.package.size_bytes);
+
+        let started = self
+            .downloads
+            .start_download_with(&download.package.url, dest.clone(), options)
+            .await;
+        let job_id = match started {
+            Ok(job_id) => job_id,
+            Err(e) => {
+                self.finish(&item.id, None, e.to_string(), Some(e.code()));
+                return;
+            }
+        };
+        let recorded = self.update(&item.id, |item| {
+            item.job_id = Some(job_id.clone());
+            item.contiguous = contiguous;
+            item.resume = false;
+        });
+        if !recorded {
+            // Removed while the download was starting
+            self.downloads.remove_job(&job_id);
+            let _ = std::fs::remove_file(&dest);
+            return;
+        }
+        // Held while the download was starting
+        if self.is_held() {
+            let _ = self.downloads.pause(&job_id);
+        }
+
+        let progress = loop {
+            match self.downloads.get_progress(&job_id) {
+                Ok(progress) if progress.done => break progress,
+                Ok(_) => tokio::time::sleep(POLL_INTERVAL).await,
+                // The job goes when the item is removed mid-download
+                Err(e) => {
+                    self.finish(&item.id, None, e.to_string(), Some(e.code()));
+                    return;
+                }
+            }
+        };
+        let downloads = self.downloads.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(FINISHED_JOB_GRACE).await;
+            downloads.remove_job(&job_id);
+        });
+        match progress.error.clone() {
+            Some(e) => {
+                let code = progress.error_code.clone();
+                self.finish(&item.id, Some(progress), e, code.as_deref());
+            }
+            None => {
+                self.update(&item.id, |item| {
+                    item.status = QueueStatus::Done;
+                    item.progress = Some(progress);
+                });
+            }
+        }
+    }
+
+    // Fail an item, or send it to the back of the queue while it has retries left
+    fn finish(&self, id: &str, progress: Option<ProgressInfo>, error: String, code: Option<&str>) {
+        let item = {
+            let mut state = self.state.lock().unwrap();
+            let Some(index) = state.items.iter().position(|item| item.id == id) else {
+                return;
+            };
+            let item = &mut state.items[index];
+            item.error = Some(error);
+            item.error_code = code.map(str::to_string);
+            item.progress = progress;
+            if item.attempts <= self.retries {
+                item.status = QueueStatus::Queued;
+                item.job_id = None;
+                let item = state.items.remove(index);
+                state.items.push(item.clone());
+                item
+            } else {
+                item.status = QueueStatus::Failed;
+                item.clone()
+            }
+        };
+        self.notify(&item);
+    }
+}
+
+// Whether a job in `mode` fills its file front to back, so it can be resumed
+fn writes_in_order(mode: DownloadMode, size_bytes: u64) -> bool {
+    match mode {
+        DownloadMode::Direct => true,
+        DownloadMode::Auto => size_bytes < DownloadMode::AUTO_MIN_SIZE,
+        DownloadMode::MultiPart { num_parts } => num_parts <= 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The mock server has none of these files, so every download fails quickly
+    #[cfg(feature = "test-util")]
+    fn download(
+        server: &crate::RunningMockServer,
+        dir: &std::path::Path,
+        filename: &str,
+    ) -> PlannedDownload {
+        PlannedDownload {
+            title_id: "BLES00779".to_string(),
+            game_title: "Demon's Souls".to_string(),
+            package: crate::types::PackageInfo {
+                version: "01.00".to_string(),
+                system_ver: "03.40".to_string(),
+                size_bytes: 1024,
+                size_human: crate::utils::format_size(1024),
+                url: server.url(&format!("/{}", filename)),
+                sha1: String::new(),
+                filename: filename.to_string(),
+            },
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_queue_order_and_retries() {
+        let server = crate::MockUpdateServer::new().start().await.unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let started = Arc::new(Mutex::new(vec![]));
+        let log = started.clone();
+        let queue = QueueManager::new(DownloadManager::new().unwrap(), DownloadOptions::default())
+            .with_retries(1)
+            .with_listener(move |item| {
+                if item.status == QueueStatus::Downloading && item.job_id.is_none() {
+                    log.lock()
+                        .unwrap()
+                        .push(item.download.package.filename.clone());
+                }
+            });
+
+        queue.set_held(true);
+        let group = queue.enqueue_group(vec![
+            download(&server, dir, "a.pkg"),
+            download(&server, dir, "b.pkg"),
+        ]);
+        let items = queue.enqueue(vec![download(&server, dir, "c.pkg")]);
+        queue.move_item(&items[0].id, 0).unwrap();
+        let b = queue.items()[2].id.clone();
+        queue.remove(&b).unwrap();
+        assert!(queue.remove(&b).is_err());
+
+        let progress = queue.progress();
+        assert_eq!(progress.items, 2);
+        assert_eq!(progress.queued, 2);
+        assert_eq!(progress.total_bytes, 2048);
+        assert!(started.lock().unwrap().is_empty());
+        // Held items still count as work to wait for
+        let waited = tokio::time::timeout(Duration::from_millis(200), queue.wait()).await;
+        assert!(waited.is_err());
+
+        queue.set_held(false);
+        let progress = queue.wait().await;
+        assert!(progress.is_finished());
+        assert_eq!(progress.failed, 2);
+        assert_eq!(progress.percent, 0.0);
+        // Each failed once, went to the back, and failed again
+        assert_eq!(
+            *started.lock().unwrap(),
+            ["c.pkg", "a.pkg", "c.pkg", "a.pkg"]
+        );
+        assert!(queue
+            .items()
+            .iter()
+            .all(|item| item.attempts == 2 && item.error.is_some()));
+
+        let group = queue.group_progress(&group).unwrap();
+        assert_eq!((group.items, group.failed), (1, 1));
+        assert_eq!(queue.group_progress("nope"), None);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_multipart_is_not_contiguous() {
+        let server = crate::MockUpdateServer::new().start().await.unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        // Resuming files in general doesn't make multi-part parts land in order
+        let options = DownloadOptions::new(DownloadMode::MultiPart { num_parts: 4 }).with_resume();
+        let queue = QueueManager::new(DownloadManager::new().unwrap(), options);
+        queue.enqueue(vec![download(&server, dir, "a.pkg")]);
+        queue.wait().await;
+        let items = queue.items();
+        assert_eq!(items[0].status, QueueStatus::Failed);
+        assert!(!items[0].contiguous);
+    }
+
+    #[test]
+    fn test_writes_in_order() {
+        assert!(writes_in_order(DownloadMode::Direct, u64::MAX));
+        assert!(writes_in_order(DownloadMode::Auto, 1024));
+        assert!(!writes_in_order(
+            DownloadMode::Auto,
+            DownloadMode::AUTO_MIN_SIZE
+        ));
+        assert!(!writes_in_order(
+            DownloadMode::MultiPart { num_parts: 4 },
+            1024
+        ));
+    }
+}
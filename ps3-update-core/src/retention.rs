@@ -0,0 +1,368 @@
+use crate::checksums::{parse_sha1sums, sidecar_path, SHA1SUMS};
+use crate::library::collect_pkgs;
+use crate::manifest::{GameManifest, MANIFEST_FILE};
+use crate::pkg::PkgHeader;
+use crate::title_id::TitleId;
+use crate::types::Result;
+use crate::utils::compare_versions;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Which package versions to keep in each game folder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RetentionPolicy {
+    #[default]
+    KeepAll,
+    /// Keep the newest N versions (at least one is always kept)
+    KeepLatest(usize),
+}
+
+/// What happens to superseded packages
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveAction {
+    /// Move the file under `dest`, keeping its path relative to the library root
+    Move { dest: PathBuf },
+    /// Store the file in a `.zip` under `dest` (`archive` feature)
+    Zip { dest: PathBuf },
+    /// Compress the file to `.zst` under `dest` (`archive` feature)
+    Zstd { dest: PathBuf },
+}
+
+/// A package that a retention policy would remove from the library
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupersededPackage {
+    pub path: PathBuf,
+    pub version: String,
+    pub size_bytes: u64,
+    /// Newest version of the same title in the same folder
+    pub latest_version: String,
+}
+
+/// Result of [`apply_retention`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionReport {
+    /// `(original path, archived path)` pairs
+    pub archived: Vec<(PathBuf, PathBuf)>,
+    pub bytes_moved: u64,
+}
+
+/// List the packages `policy` would archive, without touching anything
+///
+/// Versions come from each folder's `manifest.json`, falling back to the
+/// `-A0102-` marker in Sony's file names. Files whose version can't be
+/// determined are always kept. Packages of different titles sharing a folder
+/// are counted separately, by the title ID from the manifest, the PKG header
+/// or the file name.
+pub fn plan_retention(
+    root: impl AsRef<Path>,
+    policy: RetentionPolicy,
+) -> Result<Vec<SupersededPackage>> {
+    let RetentionPolicy::KeepLatest(keep) = policy else {
+        return Ok(vec![]);
+    };
+    let keep = keep.max(1);
+
+    let mut by_title: BTreeMap<_, Vec<(PathBuf, String)>> = BTreeMap::new();
+    let mut manifests: BTreeMap<PathBuf, Option<GameManifest>> = BTreeMap::new();
+    for path in collect_pkgs(root.as_ref())? {
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let manifest = manifests
+            .entry(dir.clone())
+            .or_insert_with(|| GameManifest::load(&dir).ok());
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let listed = manifest.as_ref().filter(|m| m.package(name).is_some());
+        let version = listed
+            .and_then(|m| m.package(name))
+            .map(|p| p.version.clone())
+            .or_else(|| version_from_filename(name));
+        let Some(version) = version else {
+            continue;
+        };
+        let title_id = listed
+            .map(|m| m.title_id.clone())
+            .or_else(|| pkg_title_id(&path, name));
+        by_title
+            .entry((dir, title_id))
+            .or_default()
+            .push((path, version));
+    }
+
+    let mut superseded = vec![];
+    for (_, mut pkgs) in by_title {
+        pkgs.sort_by(|a, b| compare_versions(&b.1, &a.1));
+        let latest = pkgs[0].1.clone();
+        for (path, version) in pkgs.into_iter().skip(keep) {
+            superseded.push(SupersededPackage {
+                size_bytes: std::fs::metadata(&path)?.len(),
+                path,
+                version,
+                latest_version: latest.clone(),
+            });
+        }
+    }
+    Ok(superseded)
+}
+
+// Title ID of a package from its header, or else its file name
+fn pkg_title_id(path: &Path, name: &str) -> Option<String> {
+    PkgHeader::read_from(path)
+        .ok()
+        .and_then(|header| header.title_id())
+        .or_else(|| TitleId::find_all(name).into_iter().next())
+        .map(|id| id.as_str().to_string())
+}
+
+/// Archive the packages `policy` marks as superseded
+///
+/// Archived files are dropped from the folder's `manifest.json` and
+/// `SHA1SUMS`, and their `.sha1` sidecars travel with them, so
+/// [`crate::verify_library`] stays clean afterwards.
+pub fn apply_retention(
+    root: impl AsRef<Path>,
+    policy: RetentionPolicy,
+    action: &ArchiveAction,
+) -> Result<RetentionReport> {
+    let root = root.as_ref();
+    let mut report = RetentionReport::default();
+    for pkg in plan_retention(root, policy)? {
+        let relative = pkg.path.strip_prefix(root).unwrap_or(&pkg.path);
+        let archived = archive_file(&pkg.path, relative, action)?;
+
+        let sidecar = sidecar_path(&pkg.path);
+        if sidecar.is_file() {
+            let mut dest = archived.as_os_str().to_owned();
+            dest.push(".sha1");
+            move_file(&sidecar, Path::new(&dest))?;
+        }
+        forget(&pkg.path)?;
+
+        report.bytes_moved += pkg.size_bytes;
+        report.archived.push((pkg.path, archived));
+    }
+    Ok(report)
+}
+
+fn archive_file(path: &Path, relative: &Path, action: &ArchiveAction) -> Result<PathBuf> {
+    let dest = match action {
+        ArchiveAction::Move { dest } => dest.join(relative),
+        ArchiveAction::Zip { dest } => with_suffix(&dest.join(relative), ".zip"),
+        ArchiveAction::Zstd { dest } => with_suffix(&dest.join(relative), ".zst"),
+    };
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match action {
+        ArchiveAction::Move { .. } => move_file(path, &dest)?,
+        ArchiveAction::Zip { .. } | ArchiveAction::Zstd { .. } => {
+            compress(path, &dest, action)?;
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(dest)
+}
+
+#[cfg(feature = "archive")]
+fn compress(path: &Path, dest: &Path, action: &ArchiveAction) -> Result<()> {
+    use std::io::Write;
+
+    let mut input = std::fs::File::open(path)?;
+    let output = std::fs::File::create(dest)?;
+    if let ArchiveAction::Zip { .. } = action {
+        let mut zip = zip::ZipWriter::new(output);
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("update.pkg");
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .large_file(true);
+        zip.start_file(name, options)?;
+        std::io::copy(&mut input, &mut zip)?;
+        zip.finish()?.flush()?;
+    } else {
+        zstd::stream::copy_encode(&mut input, output, 0)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "archive"))]
+fn compress(_path: &Path, _dest: &Path, _action: &ArchiveAction) -> Result<()> {
+    Err(crate::types::PS3UpdateError::Download(
+        "Compressed archives require the `archive` feature".into(),
+    ))
+}
+
+/// Rename, falling back to copy + delete across filesystems
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// Drop a file from its folder's manifest and SHA1SUMS
+fn forget(path: &Path) -> Result<()> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name().and_then(|n| n.to_str())) else {
+        return Ok(());
+    };
+
+    if dir.join(MANIFEST_FILE).is_file() {
+        let mut manifest = GameManifest::load(dir)?;
+        manifest.packages.retain(|p| p.filename != name);
+        manifest.save(dir)?;
+    }
+
+    let sums = dir.join(SHA1SUMS);
+    if let Ok(text) = std::fs::read_to_string(&sums) {
+        let kept: String = parse_sha1sums(&text)
+            .into_iter()
+            .filter(|(_, n)| n != name)
+            .map(|(hash, n)| format!("{}  {}\n", hash, n))
+            .collect();
+        std::fs::write(&sums, kept)?;
+    }
+    Ok(())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Version from a Sony package name (`…-A0102-V0100-PE.pkg` → `01.02`)
+pub fn version_from_filename(name: &str) -> Option<String> {
+    name.split(['-', '_', '.']).find_map(|part| {
+        let digits = part.strip_prefix('A')?;
+        (digits.len() == 4 && digits.chars().all(|c| c.is_ascii_digit()))
+            .then(|| format!("{}.{}", &digits[..2], &digits[2..]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_from_filename() {
+        assert_eq!(
+            version_from_filename("EP0001-BLES00779_00-PATCH-A0102-V0100-PE.pkg"),
+            Some("01.02".to_string())
+        );
+        assert_eq!(version_from_filename("update.pkg"), None);
+    }
+
+    #[test]
+    fn test_apply_retention() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let game = root.join("Game (BLES00779)");
+        std::fs::create_dir_all(&game).unwrap();
+        for v in ["0101", "0102", "0110"] {
+            let name = format!("EP0001-BLES00779_00-PATCH-A{}-V0100-PE.pkg", v);
+            std::fs::write(game.join(&name), v).unwrap();
+        }
+        crate::checksums::write_sha1sums(&game).unwrap();
+
+        assert!(plan_retention(root, RetentionPolicy::KeepAll)
+            .unwrap()
+            .is_empty());
+        let plan = plan_retention(root, RetentionPolicy::KeepLatest(2)).unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].version, "01.01");
+        assert_eq!(plan[0].latest_version, "01.10");
+
+        let archive = root.join("archive");
+        let report = apply_retention(
+            root,
+            RetentionPolicy::KeepLatest(1),
+            &ArchiveAction::Move {
+                dest: archive.clone(),
+            },
+        )
+        .unwrap();
+        assert_eq!(report.archived.len(), 2);
+        assert_eq!(report.bytes_moved, 8);
+        assert!(archive
+            .join("Game (BLES00779)/EP0001-BLES00779_00-PATCH-A0101-V0100-PE.pkg")
+            .is_file());
+        assert_eq!(
+            parse_sha1sums(&std::fs::read_to_string(game.join(SHA1SUMS)).unwrap()).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_retention_per_title() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let dir = root.join("Mixed");
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "EP0001-BLES00779_00-PATCH-A0101-V0100-PE.pkg",
+            "EP0001-BLES00779_00-PATCH-A0102-V0100-PE.pkg",
+            "UP0001-BLUS30001_00-PATCH-A0100-V0100-PE.pkg",
+        ] {
+            std::fs::write(dir.join(name), name).unwrap();
+        }
+        // Renamed, so only the manifest knows its title and version
+        std::fs::write(dir.join("renamed.pkg"), b"renamed").unwrap();
+        crate::manifest::record_download(
+            &dir,
+            "BLUS30001",
+            "Other Game",
+            &crate::types::PackageInfo {
+                version: "01.05".into(),
+                system_ver: String::new(),
+                size_bytes: 7,
+                size_human: String::new(),
+                url: String::new(),
+                sha1: String::new(),
+                filename: "renamed.pkg".into(),
+            },
+            "",
+        )
+        .unwrap();
+
+        let mut plan = plan_retention(root, RetentionPolicy::KeepLatest(1)).unwrap();
+        plan.sort_by(|a, b| a.version.cmp(&b.version));
+        assert_eq!(plan.len(), 2);
+        assert_eq!(
+            plan[0].path,
+            dir.join("UP0001-BLUS30001_00-PATCH-A0100-V0100-PE.pkg")
+        );
+        assert_eq!(plan[0].latest_version, "01.05");
+        assert_eq!(
+            plan[1].path,
+            dir.join("EP0001-BLES00779_00-PATCH-A0101-V0100-PE.pkg")
+        );
+        assert_eq!(plan[1].latest_version, "01.02");
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn test_compress() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let pkg = root.join("a.pkg");
+        std::fs::write(&pkg, b"package bytes").unwrap();
+
+        let dest = archive_file(
+            &pkg,
+            Path::new("a.pkg"),
+            &ArchiveAction::Zstd {
+                dest: root.join("archive"),
+            },
+        )
+        .unwrap();
+        assert_eq!(dest, root.join("archive/a.pkg.zst"));
+        assert!(!pkg.exists());
+        let data = zstd::stream::decode_all(std::fs::File::open(&dest).unwrap()).unwrap();
+        assert_eq!(data, b"package bytes");
+    }
+}
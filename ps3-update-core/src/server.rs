@@ -0,0 +1,869 @@
+use crate::downloader::{DownloadManager, DownloadOptions};
+use crate::export::PlannedDownload;
+use crate::feed::FeedFormat;
+use crate::fetcher::UpdateFetcher;
+use crate::queue::{QueueItem, QueueManager, QueueProgress};
+use crate::titledb::TitleSuggestion;
+use crate::types::{DownloadMode, FetchResult, PS3UpdateError, PackageInfo, ProgressInfo, Result};
+use crate::utils::clean_title_id;
+use crate::watch::WatchState;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::{header, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "typeshare")]
+use typeshare::typeshare;
+
+/// A JSON API for fetching update lists and running downloads
+///
+/// Lets the library run headless (on a NAS, say) and be driven by any HTTP
+/// client or web UI:
+///
+/// | Route | |
+/// | ----- | - |
+/// | `GET /api/updates/{title_id}` | the title's [`FetchResult`] |
+/// | `GET /api/downloads` | every job, oldest first |
+/// | `POST /api/downloads` | start a download from a [`DownloadRequest`] |
+/// | `GET /api/downloads/{id}` | a job's progress |
+/// | `DELETE /api/downloads/{id}` | stop tracking a job, cancelling it if it's running |
+/// | `GET /api/events` | a Server-Sent Events stream of [`JobEvent`]s |
+/// | `GET /api/queue` | the download queue, as a [`QueueSnapshot`] |
+/// | `POST /api/queue` | queue a list of [`QueueRequest`]s as one group |
+/// | `GET /api/queue/{id}` | a queued item |
+/// | `DELETE /api/queue/{id}` | drop a queued item, cancelling it if it's downloading |
+/// | `GET /api/feed.atom`, `/api/feed.rss` | a watcher's latest discoveries, see [`ApiServer::with_feed`] |
+///
+/// Packages are always saved under the server's download folder. Errors come
+/// back as `{"error": "...", "code": "..."}` with a matching status code;
+/// `code` is [`PS3UpdateError::code`] or `bad_request`. There is no
+/// authentication; bind to a trusted network or put a proxy in front.
+pub struct ApiServer {
+    pub(crate) state: Arc<ApiState>,
+    feed: Option<PathBuf>,
+}
+
+pub(crate) struct ApiState {
+    pub(crate) fetcher: UpdateFetcher,
+    pub(crate) downloads: DownloadManager,
+    pub(crate) queue: QueueManager,
+    download_dir: PathBuf,
+}
+
+/// Body of `POST /api/downloads`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DownloadRequest {
+    pub url: String,
+    /// Saves into a folder named after the title and rejects PKGs of other titles
+    #[serde(default)]
+    pub title_id: Option<String>,
+    /// Defaults to the last part of the URL
+    #[serde(default)]
+    pub filename: Option<String>,
+    /// Connections to use; more than 1 downloads in parts
+    #[serde(default)]
+    pub parts: Option<usize>,
+    /// `direct`, `auto` or `multipart:N`, in place of `parts`
+    #[serde(default)]
+    pub mode: Option<DownloadMode>,
+    #[serde(default)]
+    pub resume: bool,
+    #[serde(default)]
+    pub verify: bool,
+    #[serde(default)]
+    pub retries: u32,
+}
+
+/// One package for `POST /api/queue`, saved into a folder named after its title
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueRequest {
+    pub title_id: String,
+    #[serde(default)]
+    pub game_title: String,
+    pub package: PackageInfo,
+}
+
+/// Body of `GET /api/queue`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueSnapshot {
+    pub progress: QueueProgress,
+    pub items: Vec<QueueItem>,
+}
+
+/// A download job as returned by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: String,
+    #[serde(flatten)]
+    pub progress: ProgressInfo,
+}
+
+/// What happened to a job; the SSE event name
+#[cfg_attr(feature = "typeshare", typeshare)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobEventKind {
+    Started,
+    Progress,
+    Finished,
+    Failed,
+    Removed,
+}
+
+impl JobEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobEventKind::Started => "started",
+            JobEventKind::Progress => "progress",
+            JobEventKind::Finished => "finished",
+            JobEventKind::Failed => "failed",
+            JobEventKind::Removed => "removed",
+        }
+    }
+}
+
+/// A job lifecycle or progress change, streamed on `GET /api/events`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobEvent {
+    pub kind: JobEventKind,
+    /// The job as of this event; its last known state for `removed`
+    pub job: JobInfo,
+}
+
+/// How often event streams look for changes
+pub(crate) const EVENT_INTERVAL: Duration = Duration::from_millis(500);
+
+impl ApiServer {
+    /// Serve an API that saves downloads under `download_dir`
+    pub fn new(download_dir: impl Into<PathBuf>) -> Result<Self> {
+        Ok(Self::with_parts(
+            UpdateFetcher::new()?,
+            DownloadManager::new()?,
+            download_dir,
+        ))
+    }
+
+    /// Use a configured fetcher and download manager (aliases, mirrors…)
+    pub fn with_parts(
+        fetcher: UpdateFetcher,
+        downloads: DownloadManager,
+        download_dir: impl Into<PathBuf>,
+    ) -> Self {
+        let queue = QueueManager::new(downloads.clone(), DownloadOptions::default());
+        Self {
+            state: Arc::new(ApiState {
+                fetcher,
+                downloads,
+                queue,
+                download_dir: download_dir.into(),
+            }),
+            feed: None,
+        }
+    }
+
+    /// Publish the feed of a [`WatchState`] file, re-read on every request
+    ///
+    /// The state is usually kept up to date by a separate watcher process.
+    pub fn with_feed(mut self, watch_state: impl Into<PathBuf>) -> Self {
+        self.feed = Some(watch_state.into());
+        self
+    }
+
+    pub fn download_dir(&self) -> &Path {
+        &self.state.download_dir
+    }
+
+    /// Router serving the API, for embedding in a larger axum application
+    pub fn router(&self) -> Router {
+        let mut router = Router::new()
+            .route("/api/updates/{title_id}", get(list_updates))
+            .route("/api/downloads", get(list_jobs).post(start_download))
+            .route("/api/downloads/{id}", get(job_progress).delete(remove_job))
+            .route("/api/events", get(job_events))
+            .route("/api/queue", get(list_queue).post(enqueue))
+            .route("/api/queue/{id}", get(queue_item).delete(remove_queue_item));
+        if let Some(path) = &self.feed {
+            for (route, format) in [
+                ("/api/feed.atom", FeedFormat::Atom),
+                ("/api/feed.rss", FeedFormat::Rss),
+            ] {
+                let path = path.clone();
+                router = router.route(route, get(move || watch_feed(path, format)));
+            }
+        }
+        router.with_state(self.state.clone())
+    }
+
+    /// Listen on `addr` and serve until the process exits
+    pub async fn serve(&self, addr: SocketAddr) -> Result<()> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        axum::serve(listener, self.router()).await?;
+        Ok(())
+    }
+}
+
+/// An error status, code and message, sent as `{"error": "...", "code": "..."}`
+///
+/// A title with no updates also sends the known title IDs close to it as
+/// `"suggestions"`.
+pub(crate) struct ApiError(
+    pub(crate) StatusCode,
+    pub(crate) &'static str,
+    pub(crate) String,
+    pub(crate) Vec<TitleSuggestion>,
+);
+
+impl From<PS3UpdateError> for ApiError {
+    fn from(e: PS3UpdateError) -> Self {
+        let status = match &e {
+            PS3UpdateError::InvalidTitleId(_) => StatusCode::BAD_REQUEST,
+            PS3UpdateError::NoUpdatesFound { .. }
+            | PS3UpdateError::JobNotFound(_)
+            | PS3UpdateError::QueueItemNotFound(_) => StatusCode::NOT_FOUND,
+            PS3UpdateError::Network(_)
+            | PS3UpdateError::HttpStatus { .. }
+            | PS3UpdateError::NotFound(_) => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let (code, message) = (e.code(), e.to_string());
+        let suggestions = match e {
+            PS3UpdateError::NoUpdatesFound { suggestions, .. } => suggestions,
+            _ => vec![],
+        };
+        ApiError(status, code, message, suggestions)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut body = serde_json::json!({ "error": self.2, "code": self.1 });
+        if !self.3.is_empty() {
+            body["suggestions"] = serde_json::json!(self.3);
+        }
+        (self.0, Json(body)).into_response()
+    }
+}
+
+pub(crate) type ApiResult<T> = std::result::Result<T, ApiError>;
+
+async fn list_updates(
+    State(state): State<Arc<ApiState>>,
+    UrlPath(title_id): UrlPath<String>,
+) -> ApiResult<Json<FetchResult>> {
+    Ok(Json(state.fetcher.fetch_updates(&title_id).await?))
+}
+
+async fn watch_feed(path: PathBuf, format: FeedFormat) -> ApiResult<impl IntoResponse> {
+    // Reading the state file blocks; keep it off the runtime's workers
+    let state = tokio::task::spawn_blocking(move || WatchState::load(path))
+        .await
+        .map_err(PS3UpdateError::from)
+        .and_then(|r| r)?;
+    Ok((
+        [(header::CONTENT_TYPE, format.content_type())],
+        state.feed(format),
+    ))
+}
+
+async fn list_jobs(State(state): State<Arc<ApiState>>) -> Json<Vec<JobInfo>> {
+    Json(
+        state
+            .downloads
+            .jobs()
+            .into_iter()
+            .map(|(id, progress)| JobInfo { id, progress })
+            .collect(),
+    )
+}
+
+async fn start_download(
+    State(state): State<Arc<ApiState>>,
+    Json(request): Json<DownloadRequest>,
+) -> ApiResult<(StatusCode, Json<JobInfo>)> {
+    let job = state.start_download(&request).await?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+fn bad_request(message: String) -> ApiError {
+    ApiError(StatusCode::BAD_REQUEST, "bad_request", message, vec![])
+}
+
+// Refuse URLs that aren't HTTP and names that could leave the download folder
+fn check_download(url: &str, filename: &str) -> ApiResult<()> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(bad_request(format!("not an HTTP URL: {}", url)));
+    }
+    if filename.is_empty() || filename.starts_with('.') || filename.contains(['/', '\\']) {
+        return Err(bad_request(format!("invalid file name: {:?}", filename)));
+    }
+    Ok(())
+}
+
+impl ApiState {
+    /// Check a download request and start it; shared by the JSON and gRPC APIs
+    pub(crate) async fn start_download(&self, request: &DownloadRequest) -> ApiResult<JobInfo> {
+        let filename = match &request.filename {
+            Some(name) => name.clone(),
+            None => request
+                .url
+                .split(['?', '#'])
+                .next()
+                .and_then(|path| path.rsplit('/').next())
+                .unwrap_or_default()
+                .to_string(),
+        };
+        check_download(&request.url, &filename)?;
+
+        let mut dir = self.download_dir.clone();
+        let mode = request
+            .mode
+            .unwrap_or_else(|| DownloadMode::from_parts(request.parts.unwrap_or(1)));
+        let mut options = DownloadOptions::new(mode).with_retries(request.retries);
+        if let Some(raw) = &request.title_id {
+            let title_id = clean_title_id(raw);
+            if title_id.is_empty() {
+                return Err(PS3UpdateError::InvalidTitleId(raw.clone()).into());
+            }
+            dir.push(&title_id);
+            options = options.expect_title(title_id);
+        }
+        if request.resume {
+            options = options.with_resume();
+        }
+        if request.verify {
+            options = options.with_verification();
+        }
+
+        let id = self
+            .downloads
+            .start_download_with(&request.url, dir.join(&filename), options)
+            .await?;
+        let progress = self.downloads.get_progress(&id)?;
+        Ok(JobInfo { id, progress })
+    }
+}
+
+async fn list_queue(State(state): State<Arc<ApiState>>) -> Json<QueueSnapshot> {
+    let items = state.queue.items();
+    Json(QueueSnapshot {
+        progress: state.queue.progress(),
+        items,
+    })
+}
+
+async fn enqueue(
+    State(state): State<Arc<ApiState>>,
+    Json(requests): Json<Vec<QueueRequest>>,
+) -> ApiResult<(StatusCode, Json<Vec<QueueItem>>)> {
+    let mut downloads = vec![];
+    for request in requests {
+        check_download(&request.package.url, &request.package.filename)?;
+        let title_id = clean_title_id(&request.title_id);
+        if title_id.is_empty() {
+            return Err(PS3UpdateError::InvalidTitleId(request.title_id).into());
+        }
+        downloads.push(PlannedDownload {
+            dir: state.download_dir.join(&title_id),
+            title_id,
+            game_title: request.game_title,
+            package: request.package,
+        });
+    }
+    let group_id = state.queue.enqueue_group(downloads);
+    let items = state
+        .queue
+        .items()
+        .into_iter()
+        .filter(|item| item.group_id.as_deref() == Some(group_id.as_str()))
+        .collect();
+    Ok((StatusCode::ACCEPTED, Json(items)))
+}
+
+async fn queue_item(
+    State(state): State<Arc<ApiState>>,
+    UrlPath(id): UrlPath<String>,
+) -> ApiResult<Json<QueueItem>> {
+    Ok(Json(state.queue.get(&id)?))
+}
+
+async fn remove_queue_item(
+    State(state): State<Arc<ApiState>>,
+    UrlPath(id): UrlPath<String>,
+) -> ApiResult<StatusCode> {
+    state.queue.remove(&id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn job_progress(
+    State(state): State<Arc<ApiState>>,
+    UrlPath(id): UrlPath<String>,
+) -> ApiResult<Json<JobInfo>> {
+    let progress = state.downloads.get_progress(&id)?;
+    Ok(Json(JobInfo { id, progress }))
+}
+
+async fn remove_job(
+    State(state): State<Arc<ApiState>>,
+    UrlPath(id): UrlPath<String>,
+) -> ApiResult<StatusCode> {
+    state.downloads.get_progress(&id)?;
+    state.downloads.remove_job(&id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Events turning the job list `previous` into `current`
+///
+/// Jobs seen for the first time are `started`, and also `finished` or `failed`
+/// if they are already done. `progress` is sent while the byte count moves.
+fn diff_jobs(
+    previous: &HashMap<String, ProgressInfo>,
+    current: &[(String, ProgressInfo)],
+) -> Vec<JobEvent> {
+    let mut events = vec![];
+    let mut push = |kind, id: &str, progress: &ProgressInfo| {
+        events.push(JobEvent {
+            kind,
+            job: JobInfo {
+                id: id.to_string(),
+                progress: progress.clone(),
+            },
+        })
+    };
+    for (id, progress) in current {
+        let before = previous.get(id);
+        if before.is_none() {
+            push(JobEventKind::Started, id, progress);
+        }
+        if progress.done {
+            if !before.is_some_and(|b| b.done) {
+                let kind = if progress.error.is_some() {
+                    JobEventKind::Failed
+                } else {
+                    JobEventKind::Finished
+                };
+                push(kind, id, progress);
+            }
+        } else if before
+            .is_some_and(|b| b.downloaded != progress.downloaded || b.total != progress.total)
+        {
+            push(JobEventKind::Progress, id, progress);
+        }
+    }
+    for (id, progress) in previous {
+        if !current.iter().any(|(current_id, _)| current_id == id) {
+            push(JobEventKind::Removed, id, progress);
+        }
+    }
+    events
+}
+
+struct EventStream {
+    state: Arc<ApiState>,
+    seen: HashMap<String, ProgressInfo>,
+    pending: VecDeque<JobEvent>,
+    first: bool,
+}
+
+async fn job_events(
+    State(state): State<Arc<ApiState>>,
+) -> Sse<impl Stream<Item = std::result::Result<Event, axum::Error>>> {
+    let start = EventStream {
+        state,
+        seen: HashMap::new(),
+        pending: VecDeque::new(),
+        first: true,
+    };
+    let events = stream::unfold(start, |mut s| async move {
+        loop {
+            if let Some(event) = s.pending.pop_front() {
+                let sse = Event::default()
+                    .event(event.kind.as_str())
+                    .json_data(&event.job);
+                return Some((sse, s));
+            }
+            if !s.first {
+                tokio::time::sleep(EVENT_INTERVAL).await;
+            }
+            s.first = false;
+            let current = s.state.downloads.jobs();
+            s.pending.extend(diff_jobs(&s.seen, &current));
+            s.seen = current.into_iter().collect();
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "test-util")]
+    use crate::titledb::TitleDatabase;
+    use crate::types::DownloadPhase;
+    use axum::body::Body;
+    use axum::http::Request;
+    #[cfg(feature = "test-util")]
+    use futures_util::StreamExt;
+    use tower::ServiceExt;
+
+    #[cfg(feature = "test-util")]
+    async fn call(router: &Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let resp = router.clone().oneshot(request).await.unwrap();
+        let status = resp.status();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json = if body.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::from_slice(&body).unwrap()
+        };
+        (status, json)
+    }
+
+    fn job(downloaded: u64, done: bool, error: Option<&str>) -> ProgressInfo {
+        ProgressInfo {
+            filename: Some("a.pkg".into()),
+            total: 100,
+            downloaded,
+            remaining: 100 - downloaded,
+            percent: downloaded as f64,
+            speed_bytes_per_sec: 0.0,
+            speed_human: "0 B/s".into(),
+            done,
+            error: error.map(String::from),
+            error_code: None,
+            source: None,
+            verified: None,
+            already_downloaded: false,
+            rate_limit: None,
+            paused: false,
+            elapsed_secs: 0.0,
+            notes: vec![],
+            phase: if done {
+                DownloadPhase::Done
+            } else {
+                DownloadPhase::Downloading
+            },
+            attempts: 1,
+        }
+    }
+
+    #[test]
+    fn test_diff_jobs() {
+        let kinds = |events: Vec<JobEvent>| -> Vec<(JobEventKind, String)> {
+            events.into_iter().map(|e| (e.kind, e.job.id)).collect()
+        };
+        let mut seen = HashMap::new();
+        let current = vec![
+            ("a".to_string(), job(0, false, None)),
+            ("b".to_string(), job(100, true, None)),
+        ];
+        assert_eq!(
+            kinds(diff_jobs(&seen, &current)),
+            vec![
+                (JobEventKind::Started, "a".into()),
+                (JobEventKind::Started, "b".into()),
+                (JobEventKind::Finished, "b".into()),
+            ]
+        );
+        seen = current.into_iter().collect();
+
+        // No change, no events
+        let current = vec![
+            ("a".to_string(), job(0, false, None)),
+            ("b".to_string(), job(100, true, None)),
+        ];
+        assert!(diff_jobs(&seen, &current).is_empty());
+
+        let current = vec![("a".to_string(), job(50, false, None))];
+        assert_eq!(
+            kinds(diff_jobs(&seen, &current)),
+            vec![
+                (JobEventKind::Progress, "a".into()),
+                (JobEventKind::Removed, "b".into())
+            ]
+        );
+        seen = current.into_iter().collect();
+
+        let current = vec![("a".to_string(), job(50, true, Some("Network error: reset")))];
+        let events = diff_jobs(&seen, &current);
+        assert_eq!(
+            kinds(events.clone()),
+            vec![(JobEventKind::Failed, "a".into())]
+        );
+        assert_eq!(
+            events[0].job.progress.error.as_deref(),
+            Some("Network error: reset")
+        );
+    }
+
+    #[cfg(feature = "test-util")]
+    fn post(request: serde_json::Value) -> Request<Body> {
+        Request::post("/api/downloads")
+            .header("content-type", "application/json")
+            .body(Body::from(request.to_string()))
+            .unwrap()
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_download_jobs() {
+        let mock = crate::MockUpdateServer::new().start().await.unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let server = ApiServer::new(dir).unwrap();
+        let router = server.router();
+
+        let (status, jobs) = call(
+            &router,
+            Request::get("/api/downloads").body(Body::empty()).unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(jobs, serde_json::json!([]));
+
+        let (status, body) = call(
+            &router,
+            post(serde_json::json!({ "url": "file:///etc/passwd" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("not an HTTP URL"));
+        assert_eq!(body["code"], "bad_request");
+        let (status, _) = call(
+            &router,
+            post(serde_json::json!({ "url": mock.url("/a.pkg"), "filename": "../a.pkg" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // The mock server has no such file, so the job fails quickly
+        let (status, job) = call(
+            &router,
+            post(serde_json::json!({ "url": mock.url("/a.pkg?x=1"), "title_id": "bles-00779" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(job["filename"], "a.pkg");
+        let id = job["id"].as_str().unwrap().to_string();
+        let last = server.state.downloads.wait_for(&id).await.unwrap();
+        assert!(last.error.is_some());
+
+        // A new event stream starts with the jobs already tracked
+        let resp = router
+            .clone()
+            .oneshot(Request::get("/api/events").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.headers()["content-type"], "text/event-stream");
+        let mut body = resp.into_body().into_data_stream();
+        let frame = body.next().await.unwrap().unwrap();
+        let frame = String::from_utf8_lossy(&frame);
+        assert!(frame.starts_with("event: started\n"));
+        assert!(frame.contains(&format!("\"id\":\"{}\"", id)));
+
+        let (_, jobs) = call(
+            &router,
+            Request::get("/api/downloads").body(Body::empty()).unwrap(),
+        )
+        .await;
+        assert_eq!(jobs[0]["id"], id.as_str());
+        assert_eq!(jobs[0]["done"], true);
+
+        let uri = format!("/api/downloads/{}", id);
+        let (status, _) = call(&router, Request::delete(&uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, body) = call(&router, Request::get(&uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body["error"].as_str().unwrap().starts_with("Job not found"));
+        assert_eq!(body["code"], "job_not_found");
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_queue() {
+        let mock = crate::MockUpdateServer::new().start().await.unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let server = ApiServer::new(dir).unwrap();
+        let router = server.router();
+        let enqueue = |filename: &str| {
+            let request = serde_json::json!([{
+                "title_id": "bles-00779",
+                "package": {
+                    "version": "01.00",
+                    "system_ver": "03.40",
+                    "size_bytes": 1024,
+                    "size_human": "1.00 KB",
+                    "url": mock.url(&format!("/{}", filename)),
+                    "sha1": "",
+                    "filename": filename,
+                },
+            }]);
+            Request::post("/api/queue")
+                .header("content-type", "application/json")
+                .body(Body::from(request.to_string()))
+                .unwrap()
+        };
+
+        let (status, _) = call(&router, enqueue("../a.pkg")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, items) = call(&router, enqueue("a.pkg")).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(items[0]["title_id"], "BLES00779");
+        assert!(items[0]["group_id"].is_string());
+        let id = items[0]["id"].as_str().unwrap().to_string();
+
+        // The mock server has no such file, so the item fails quickly
+        server.state.queue.wait().await;
+        let (status, queue) = call(
+            &router,
+            Request::get("/api/queue").body(Body::empty()).unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(queue["progress"]["failed"], 1);
+        assert_eq!(queue["items"][0]["status"], "failed");
+
+        let uri = format!("/api/queue/{}", id);
+        let (status, item) = call(&router, Request::get(&uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(item["package"]["filename"], "a.pkg");
+        let (status, _) = call(&router, Request::delete(&uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, body) = call(&router, Request::get(&uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "queue_item_not_found");
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_not_found_suggestions() {
+        // A mock server without titles answers every -ver.xml with 404
+        let mock = crate::MockUpdateServer::new().start().await.unwrap();
+        let fetcher = mock
+            .fetcher()
+            .unwrap()
+            .with_title_database(TitleDatabase::parse("BLUS30109\tMGS4 US\n"));
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let router = ApiServer::with_parts(fetcher, DownloadManager::new().unwrap(), dir).router();
+
+        let (status, body) = call(
+            &router,
+            Request::get("/api/updates/BLUS30190")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "no_updates_found");
+        assert_eq!(
+            body["error"],
+            "No updates found for title ID: BLUS30190 (did you mean BLUS30109?)"
+        );
+        assert_eq!(body["suggestions"][0]["title_id"], "BLUS30109");
+        assert_eq!(body["suggestions"][0]["reason"], "TransposedDigits");
+
+        // Nothing close is known
+        let (status, body) = call(
+            &router,
+            Request::get("/api/updates/BCES99999")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.get("suggestions").is_none());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_queue_events() {
+        let mock = crate::MockUpdateServer::new()
+            .with_title("BLES00779", "Demon's Souls", &["01.02"])
+            .start()
+            .await
+            .unwrap();
+        let fetcher = mock.fetcher().unwrap();
+        let result = fetcher.fetch_updates("BLES00779").await.unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let router = ApiServer::with_parts(fetcher, DownloadManager::new().unwrap(), dir).router();
+
+        let resp = router
+            .clone()
+            .oneshot(Request::get("/api/events").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let mut events = resp.into_body().into_data_stream();
+        let request = serde_json::json!([{
+            "title_id": "BLES00779",
+            "package": result.results[0],
+        }]);
+        let (status, _) = call(
+            &router,
+            Request::post("/api/queue")
+                .header("content-type", "application/json")
+                .body(Body::from(request.to_string()))
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        // The queue's job finishes well within one event interval, and is
+        // still reported as finished rather than just gone
+        let mut kinds = vec![];
+        while !kinds.contains(&"finished") && !kinds.contains(&"removed") {
+            let frame = tokio::time::timeout(Duration::from_secs(10), events.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            let frame = String::from_utf8_lossy(&frame);
+            for kind in ["started", "progress", "finished", "failed", "removed"] {
+                if frame.starts_with(&format!("event: {}\n", kind)) {
+                    kinds.push(kind);
+                }
+            }
+        }
+        assert_eq!(kinds.last(), Some(&"finished"));
+    }
+
+    #[tokio::test]
+    async fn test_feed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("watch.json");
+        let router = ApiServer::new(dir).unwrap().router();
+        let resp = router
+            .clone()
+            .oneshot(Request::get("/api/feed.atom").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // A missing state file is an empty feed
+        let router = ApiServer::new(dir).unwrap().with_feed(&path).router();
+        let resp = router
+            .oneshot(Request::get("/api/feed.rss").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "application/rss+xml");
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("<channel>") && !body.contains("<item>"));
+    }
+}
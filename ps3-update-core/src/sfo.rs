@@ -0,0 +1,228 @@
+use crate::types::{PS3UpdateError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Magic at the start of every PARAM.SFO (`\0PSF`)
+pub const SFO_MAGIC: &[u8; 4] = b"\0PSF";
+
+const HEADER_SIZE: usize = 0x14;
+const INDEX_ENTRY_SIZE: usize = 0x10;
+
+const FMT_UTF8_SPECIAL: u16 = 0x0004;
+const FMT_UTF8: u16 = 0x0204;
+const FMT_INT32: u16 = 0x0404;
+
+/// A single PARAM.SFO value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SfoValue {
+    Text(String),
+    Integer(u32),
+    /// Raw data with an unrecognised format
+    Bytes(Vec<u8>),
+}
+
+impl SfoValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            SfoValue::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            SfoValue::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+/// Parsed PARAM.SFO key/value table
+///
+/// Entries keep the order they have in the file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamSfo {
+    entries: Vec<(String, SfoValue)>,
+}
+
+impl ParamSfo {
+    /// Parse a PARAM.SFO from bytes
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..4] != SFO_MAGIC {
+            return Err(PS3UpdateError::InvalidSfo("bad magic".into()));
+        }
+        let key_table = le_u32(bytes, 0x08) as usize;
+        let data_table = le_u32(bytes, 0x0C) as usize;
+        let count = le_u32(bytes, 0x10) as usize;
+
+        let mut entries = Vec::with_capacity(count);
+        for i in 0..count {
+            let index = HEADER_SIZE + i * INDEX_ENTRY_SIZE;
+            let entry = bytes
+                .get(index..index + INDEX_ENTRY_SIZE)
+                .ok_or_else(|| PS3UpdateError::InvalidSfo("index table truncated".into()))?;
+            let key_offset = key_table + le_u16(entry, 0x00) as usize;
+            let fmt = le_u16(entry, 0x02);
+            let len = le_u32(entry, 0x04) as usize;
+            let data_offset = data_table + le_u32(entry, 0x0C) as usize;
+
+            let key = bytes
+                .get(key_offset..)
+                .and_then(|rest| rest.split(|&b| b == 0).next())
+                .ok_or_else(|| PS3UpdateError::InvalidSfo(format!("key {} out of range", i)))?;
+            let key = String::from_utf8_lossy(key).into_owned();
+            let data = bytes.get(data_offset..data_offset + len).ok_or_else(|| {
+                PS3UpdateError::InvalidSfo(format!("value for {} out of range", key))
+            })?;
+
+            let value = match fmt {
+                FMT_UTF8 | FMT_UTF8_SPECIAL => {
+                    let text = data.split(|&b| b == 0).next().unwrap_or_default();
+                    SfoValue::Text(String::from_utf8_lossy(text).into_owned())
+                }
+                FMT_INT32 if len == 4 => SfoValue::Integer(le_u32(data, 0)),
+                _ => SfoValue::Bytes(data.to_vec()),
+            };
+            entries.push((key, value));
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Read and parse a PARAM.SFO file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&SfoValue> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(SfoValue::as_str)
+    }
+
+    pub fn get_u32(&self, key: &str) -> Option<u32> {
+        self.get(key).and_then(SfoValue::as_u32)
+    }
+
+    /// `TITLE`
+    pub fn title(&self) -> Option<&str> {
+        self.get_str("TITLE")
+    }
+
+    /// `TITLE_ID`
+    pub fn title_id(&self) -> Option<&str> {
+        self.get_str("TITLE_ID")
+    }
+
+    /// `APP_VER`, the version of the game or patch (e.g. `01.02`)
+    pub fn app_version(&self) -> Option<&str> {
+        self.get_str("APP_VER")
+    }
+
+    /// `PS3_SYSTEM_VER`, the minimum firmware (e.g. `03.4100`)
+    pub fn system_version(&self) -> Option<&str> {
+        self.get_str("PS3_SYSTEM_VER")
+    }
+
+    /// `CATEGORY`, e.g. `DG` for disc games or `GD` for game data/patches
+    pub fn category(&self) -> Option<&str> {
+        self.get_str("CATEGORY")
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SfoValue)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn le_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+fn le_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Build a PARAM.SFO holding the given text entries plus an integer `ATTRIBUTE`
+    pub(crate) fn sample_sfo(text: &[(&str, &str)]) -> Vec<u8> {
+        let count = text.len() + 1;
+        let mut keys = vec![];
+        let mut data = vec![];
+        let mut index = vec![];
+
+        let mut push = |key: &str, fmt: u16, value: &[u8], max_len: usize| {
+            index.extend_from_slice(&(keys.len() as u16).to_le_bytes());
+            index.extend_from_slice(&fmt.to_le_bytes());
+            index.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            index.extend_from_slice(&(max_len as u32).to_le_bytes());
+            index.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            keys.extend_from_slice(key.as_bytes());
+            keys.push(0);
+            data.extend_from_slice(value);
+            data.resize(data.len() + max_len - value.len(), 0);
+        };
+        push("ATTRIBUTE", FMT_INT32, &5u32.to_le_bytes(), 4);
+        for (key, value) in text {
+            let mut value = value.as_bytes().to_vec();
+            value.push(0);
+            let max_len = value.len().next_multiple_of(4);
+            push(key, FMT_UTF8, &value, max_len);
+        }
+        keys.resize(keys.len().next_multiple_of(4), 0);
+
+        let key_table = HEADER_SIZE + count * INDEX_ENTRY_SIZE;
+        let data_table = key_table + keys.len();
+        let mut sfo = SFO_MAGIC.to_vec();
+        sfo.extend_from_slice(&0x0101u32.to_le_bytes());
+        sfo.extend_from_slice(&(key_table as u32).to_le_bytes());
+        sfo.extend_from_slice(&(data_table as u32).to_le_bytes());
+        sfo.extend_from_slice(&(count as u32).to_le_bytes());
+        sfo.extend_from_slice(&index);
+        sfo.extend_from_slice(&keys);
+        sfo.extend_from_slice(&data);
+        sfo
+    }
+
+    #[test]
+    fn test_parse() {
+        let sfo = ParamSfo::parse(&sample_sfo(&[
+            ("APP_VER", "01.02"),
+            ("CATEGORY", "GD"),
+            ("PS3_SYSTEM_VER", "03.4100"),
+            ("TITLE", "Test Game"),
+            ("TITLE_ID", "BLES00779"),
+        ]))
+        .unwrap();
+        assert_eq!(sfo.len(), 6);
+        assert_eq!(sfo.title(), Some("Test Game"));
+        assert_eq!(sfo.title_id(), Some("BLES00779"));
+        assert_eq!(sfo.app_version(), Some("01.02"));
+        assert_eq!(sfo.system_version(), Some("03.4100"));
+        assert_eq!(sfo.category(), Some("GD"));
+        assert_eq!(sfo.get_u32("ATTRIBUTE"), Some(5));
+        assert_eq!(sfo.get("VERSION"), None);
+        assert_eq!(sfo.iter().next().unwrap().0, "ATTRIBUTE");
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert!(ParamSfo::parse(b"nope").is_err());
+
+        let mut truncated = sample_sfo(&[("TITLE", "Test Game")]);
+        truncated.truncate(truncated.len() - 8);
+        assert!(ParamSfo::parse(&truncated).is_err());
+    }
+}
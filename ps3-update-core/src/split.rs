@@ -0,0 +1,173 @@
+use crate::types::{PS3UpdateError, Result};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Largest file FAT32 can hold
+pub const FAT32_MAX_FILE_SIZE: u64 = 0xFFFF_FFFF;
+
+/// Part size used by common PS3 splitting tools (4 GiB minus 64 KiB)
+pub const DEFAULT_PART_SIZE: u64 = 0xFFFF_0000;
+
+/// The PS3 installer only recognises `.66600` through `.66699`
+const MAX_PARTS: usize = 100;
+
+/// Path of the `index`th part of a split package (`game.pkg` → `game.pkg.66600`)
+pub fn part_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".666{:02}", index));
+    PathBuf::from(name)
+}
+
+/// True if the file is too large to copy to a FAT32 drive whole
+pub fn needs_split(path: impl AsRef<Path>) -> Result<bool> {
+    Ok(std::fs::metadata(path)?.len() > FAT32_MAX_FILE_SIZE)
+}
+
+/// Existing parts of a split package, in order, stopping at the first gap
+pub fn find_parts(path: &Path) -> Vec<PathBuf> {
+    (0..MAX_PARTS)
+        .map(|i| part_path(path, i))
+        .take_while(|p| p.is_file())
+        .collect()
+}
+
+/// Split a package into `.666XX` parts of at most `part_size` bytes
+///
+/// The original file is left in place. `progress` is called with
+/// `(bytes_written, total_bytes)` as the split proceeds.
+pub fn split_pkg(
+    path: impl AsRef<Path>,
+    part_size: u64,
+    progress: impl FnMut(u64, u64),
+) -> Result<Vec<PathBuf>> {
+    split_pkg_to(&path, &path, part_size, progress)
+}
+
+/// Like [`split_pkg`], but writes the parts for `dest` (e.g. on a USB drive)
+/// instead of next to the source
+pub fn split_pkg_to(
+    path: impl AsRef<Path>,
+    dest: impl AsRef<Path>,
+    part_size: u64,
+    mut progress: impl FnMut(u64, u64),
+) -> Result<Vec<PathBuf>> {
+    let (path, dest) = (path.as_ref(), dest.as_ref());
+    if part_size == 0 || part_size > FAT32_MAX_FILE_SIZE {
+        return Err(PS3UpdateError::Download(format!(
+            "Invalid part size: {}",
+            part_size
+        )));
+    }
+    let total = std::fs::metadata(path)?.len();
+    let count = total.div_ceil(part_size).max(1) as usize;
+    if count > MAX_PARTS {
+        return Err(PS3UpdateError::Download(format!(
+            "{} would need {} parts; at most {} are supported",
+            path.display(),
+            count,
+            MAX_PARTS
+        )));
+    }
+
+    let mut input = File::open(path)?;
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut written = 0u64;
+    let mut parts = Vec::with_capacity(count);
+    for index in 0..count {
+        let part = part_path(dest, index);
+        let mut output = BufWriter::new(File::create(&part)?);
+        let mut remaining = part_size.min(total - written);
+        while remaining > 0 {
+            let want = remaining.min(buf.len() as u64) as usize;
+            input.read_exact(&mut buf[..want])?;
+            output.write_all(&buf[..want])?;
+            remaining -= want as u64;
+            written += want as u64;
+            progress(written, total);
+        }
+        output.flush()?;
+        parts.push(part);
+    }
+    Ok(parts)
+}
+
+/// Join the `.666XX` parts of `path` back into a single file at `dest`
+///
+/// Returns the size of the joined file. `progress` is called with
+/// `(bytes_written, total_bytes)`.
+pub fn join_parts(
+    path: impl AsRef<Path>,
+    dest: impl AsRef<Path>,
+    mut progress: impl FnMut(u64, u64),
+) -> Result<u64> {
+    let path = path.as_ref();
+    let parts = find_parts(path);
+    if parts.is_empty() {
+        return Err(PS3UpdateError::NotFound(
+            part_path(path, 0).display().to_string(),
+        ));
+    }
+    let total = parts
+        .iter()
+        .map(|p| std::fs::metadata(p).map(|m| m.len()))
+        .sum::<std::io::Result<u64>>()?;
+
+    let mut output = BufWriter::new(File::create(dest)?);
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut written = 0u64;
+    for part in &parts {
+        let mut input = File::open(part)?;
+        loop {
+            let n = input.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            output.write_all(&buf[..n])?;
+            written += n as u64;
+            progress(written, total);
+        }
+    }
+    output.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_path() {
+        assert_eq!(
+            part_path(Path::new("/usb/game.pkg"), 0),
+            PathBuf::from("/usb/game.pkg.66600")
+        );
+        assert_eq!(
+            part_path(Path::new("game.pkg"), 12),
+            PathBuf::from("game.pkg.66612")
+        );
+    }
+
+    #[test]
+    fn test_split_and_join() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let pkg = dir.join("game.pkg");
+        let data: Vec<u8> = (0..2500u32).map(|i| i as u8).collect();
+        std::fs::write(&pkg, &data).unwrap();
+
+        let mut last = (0, 0);
+        let parts = split_pkg(&pkg, 1000, |done, total| last = (done, total)).unwrap();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(last, (2500, 2500));
+        assert_eq!(std::fs::metadata(&parts[2]).unwrap().len(), 500);
+        assert_eq!(find_parts(&pkg), parts);
+
+        let joined = dir.join("joined.pkg");
+        assert_eq!(join_parts(&pkg, &joined, |_, _| {}).unwrap(), 2500);
+        assert_eq!(std::fs::read(&joined).unwrap(), data);
+
+        assert!(split_pkg(&pkg, 10, |_, _| {}).is_err());
+        assert!(join_parts(dir.join("missing.pkg"), &joined, |_, _| {}).is_err());
+    }
+}
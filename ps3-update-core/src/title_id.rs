@@ -0,0 +1,221 @@
+use crate::types::{PS3UpdateError, Result};
+use crate::utils::clean_title_id;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Release region, derived from the third letter of a title ID prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Region {
+    Europe,
+    America,
+    Japan,
+    Asia,
+    Korea,
+    HongKong,
+    Unknown,
+}
+
+impl Region {
+    /// Short code used for folder names and display (EU, US, JP…)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Region::Europe => "EU",
+            Region::America => "US",
+            Region::Japan => "JP",
+            Region::Asia => "AS",
+            Region::Korea => "KR",
+            Region::HongKong => "HK",
+            Region::Unknown => "XX",
+        }
+    }
+
+    /// Region letter used in title ID prefixes
+    pub fn letter(&self) -> Option<char> {
+        match self {
+            Region::Europe => Some('E'),
+            Region::America => Some('U'),
+            Region::Japan => Some('J'),
+            Region::Asia => Some('A'),
+            Region::Korea => Some('K'),
+            Region::HongKong => Some('H'),
+            Region::Unknown => None,
+        }
+    }
+
+    fn from_letter(c: char) -> Self {
+        match c {
+            'E' => Region::Europe,
+            'U' => Region::America,
+            'J' => Region::Japan,
+            'A' => Region::Asia,
+            'K' => Region::Korea,
+            'H' => Region::HongKong,
+            _ => Region::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// A validated PS3 title ID (four letters followed by five digits, e.g. `BLES00779`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TitleId(String);
+
+impl TitleId {
+    /// Parse and normalize a title ID; dashes, spaces and case are ignored
+    pub fn parse(raw: &str) -> Result<Self> {
+        let cleaned = clean_title_id(raw);
+        let valid = cleaned.len() == 9
+            && cleaned[..4].chars().all(|c| c.is_ascii_uppercase())
+            && cleaned[4..].chars().all(|c| c.is_ascii_digit());
+        if valid {
+            Ok(TitleId(cleaned))
+        } else {
+            Err(PS3UpdateError::InvalidTitleId(raw.trim().to_string()))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The four-letter prefix, e.g. `BLES`
+    pub fn prefix(&self) -> &str {
+        &self.0[..4]
+    }
+
+    /// The five-digit number, e.g. `00779`
+    pub fn number(&self) -> &str {
+        &self.0[4..]
+    }
+
+    pub fn region(&self) -> Region {
+        Region::from_letter(self.0.as_bytes()[2] as char)
+    }
+
+    /// Title IDs written out in free text, such as a copied game list
+    ///
+    /// Stricter than [`TitleId::parse`] to keep false hits down: only
+    /// `BLES00779` or `BLES-00779` standing on its own, in capitals, with a
+    /// disc (`B…`) or PSN (`N…`) prefix and a known region. Each ID is
+    /// returned once, in the order found.
+    pub fn find_all(text: &str) -> Vec<TitleId> {
+        let bytes = text.as_bytes();
+        let mut found: Vec<TitleId> = vec![];
+        let mut i = 0;
+        while i < bytes.len() {
+            let at_boundary = i == 0 || !bytes[i - 1].is_ascii_alphanumeric();
+            if let Some((id, len)) = at_boundary.then(|| Self::match_at(&bytes[i..])).flatten() {
+                if !found.contains(&id) {
+                    found.push(id);
+                }
+                i += len;
+            } else {
+                i += 1;
+            }
+        }
+        found
+    }
+
+    // A title ID at the start of `bytes`, and how many bytes it spans
+    fn match_at(bytes: &[u8]) -> Option<(TitleId, usize)> {
+        let prefix = bytes.get(..4)?;
+        if !matches!(prefix[0], b'B' | b'N') || !prefix.iter().all(u8::is_ascii_uppercase) {
+            return None;
+        }
+        let sep = usize::from(matches!(bytes.get(4), Some(b'-' | b' ')));
+        let digits = bytes.get(4 + sep..9 + sep)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        if bytes.get(9 + sep).is_some_and(u8::is_ascii_alphanumeric) {
+            return None;
+        }
+        let id = TitleId(prefix.iter().chain(digits).map(|&b| b as char).collect());
+        (id.region() != Region::Unknown).then_some((id, 9 + sep))
+    }
+}
+
+impl fmt::Display for TitleId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for TitleId {
+    type Err = PS3UpdateError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        TitleId::parse(s)
+    }
+}
+
+impl TryFrom<String> for TitleId {
+    type Error = PS3UpdateError;
+
+    fn try_from(s: String) -> Result<Self> {
+        TitleId::parse(&s)
+    }
+}
+
+impl From<TitleId> for String {
+    fn from(id: TitleId) -> Self {
+        id.0
+    }
+}
+
+impl AsRef<str> for TitleId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let id = TitleId::parse("bles-00779").unwrap();
+        assert_eq!(id.as_str(), "BLES00779");
+        assert_eq!(id.prefix(), "BLES");
+        assert_eq!(id.number(), "00779");
+        assert!(TitleId::parse("BLES0077").is_err());
+        assert!(TitleId::parse("12345ABCD").is_err());
+    }
+
+    #[test]
+    fn test_region() {
+        assert_eq!(
+            TitleId::parse("BLES00779").unwrap().region(),
+            Region::Europe
+        );
+        assert_eq!(
+            TitleId::parse("NPUB30910").unwrap().region(),
+            Region::America
+        );
+        assert_eq!(TitleId::parse("BCJS30001").unwrap().region(), Region::Japan);
+        assert_eq!(
+            TitleId::parse("BLZS00001").unwrap().region(),
+            Region::Unknown
+        );
+    }
+
+    #[test]
+    fn test_find_all() {
+        let text = "Demon's Souls (BLES-00779), MGS4 BLUS30109 and NPUB30910; \
+                    BLES00779 again, not ABCD12345, BLZS00001, XBLES00779 or BLUS301090";
+        let found: Vec<_> = TitleId::find_all(text)
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(found, ["BLES00779", "BLUS30109", "NPUB30910"]);
+        assert!(TitleId::find_all("bles00779").is_empty());
+    }
+}
@@ -0,0 +1,355 @@
+use crate::title_id::{Region, TitleId};
+use crate::types::Result;
+use crate::utils::clean_title_id;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Seed database shipped with the crate
+const EMBEDDED_TITLES: &str = include_str!("../data/titles.tsv");
+
+/// A single serial → name mapping
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TitleEntry {
+    pub title_id: String,
+    pub name: String,
+}
+
+/// A search hit for a name query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TitleMatch {
+    pub title_id: String,
+    pub name: String,
+    pub region: Region,
+    /// Relevance between 0.0 and 1.0 (1.0 is an exact name match)
+    pub score: f64,
+}
+
+/// Why a title ID was suggested as a correction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SuggestionReason {
+    /// Same number, different region letter (e.g. BLUS vs BLES)
+    WrongRegion,
+    /// Same number, different prefix (e.g. BCES vs BLES)
+    WrongPrefix,
+    /// Two adjacent digits swapped
+    TransposedDigits,
+    /// One digit mistyped
+    MistypedDigit,
+}
+
+/// A known title ID close to one that could not be found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TitleSuggestion {
+    pub title_id: String,
+    pub name: String,
+    pub reason: SuggestionReason,
+}
+
+/// Offline serial → game name database
+#[derive(Debug, Clone, Default)]
+pub struct TitleDatabase {
+    entries: HashMap<String, String>,
+}
+
+impl TitleDatabase {
+    /// Create an empty database
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Database containing the seed entries shipped with the crate
+    pub fn embedded() -> Self {
+        Self::parse(EMBEDDED_TITLES)
+    }
+
+    /// Load entries from a file (see [`TitleDatabase::parse`] for the format)
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(Self::parse(&text))
+    }
+
+    /// Parse `TITLE_ID<TAB>Name` or `TITLE_ID,Name` lines
+    ///
+    /// Blank lines and lines starting with `#` are ignored, as are lines
+    /// without a valid separator.
+    pub fn parse(text: &str) -> Self {
+        let mut db = Self::new();
+        db.extend_from_str(text);
+        db
+    }
+
+    /// Merge entries parsed from text into this database, replacing existing names
+    pub fn extend_from_str(&mut self, text: &str) {
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((id, name)) = line.split_once('\t').or_else(|| line.split_once(',')) else {
+                continue;
+            };
+            let name = name.trim().trim_matches('"');
+            if !name.is_empty() {
+                self.insert(id, name);
+            }
+        }
+    }
+
+    /// Add or replace an entry
+    pub fn insert(&mut self, title_id: &str, name: &str) {
+        let cleaned = clean_title_id(title_id);
+        if !cleaned.is_empty() {
+            self.entries.insert(cleaned, name.trim().to_string());
+        }
+    }
+
+    /// Look up the game name for a title ID
+    pub fn lookup(&self, title_id: &str) -> Option<&str> {
+        self.entries
+            .get(&clean_title_id(title_id))
+            .map(|s| s.as_str())
+    }
+
+    pub fn contains(&self, title_id: &str) -> bool {
+        self.entries.contains_key(&clean_title_id(title_id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Fuzzy search by game name, returning candidates across all regions
+    ///
+    /// Matches are ranked by score, then title ID. Word order, punctuation and
+    /// single-character typos in longer words are tolerated.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<TitleMatch> {
+        let query_tokens = tokenize(query);
+        if query_tokens.is_empty() {
+            return vec![];
+        }
+
+        let mut matches: Vec<TitleMatch> = self
+            .entries
+            .iter()
+            .filter_map(|(id, name)| {
+                let score = match_score(&query_tokens, &tokenize(name));
+                (score > 0.0).then(|| TitleMatch {
+                    title_id: id.clone(),
+                    name: name.clone(),
+                    region: TitleId::parse(id)
+                        .map(|t| t.region())
+                        .unwrap_or(Region::Unknown),
+                    score,
+                })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.title_id.cmp(&b.title_id))
+        });
+        matches.truncate(limit);
+        matches
+    }
+
+    /// Suggest known title IDs that are near misses of `title_id`
+    ///
+    /// Candidates are ordered by how likely the mistake is: wrong region,
+    /// then wrong prefix, transposed digits, and finally a single mistyped digit.
+    pub fn suggest(&self, title_id: &str, limit: usize) -> Vec<TitleSuggestion> {
+        let cleaned = clean_title_id(title_id);
+        let Ok(target) = TitleId::parse(&cleaned) else {
+            return vec![];
+        };
+
+        let mut suggestions: Vec<TitleSuggestion> = self
+            .entries
+            .iter()
+            .filter(|(id, _)| **id != cleaned)
+            .filter_map(|(id, name)| {
+                let candidate = TitleId::parse(id).ok()?;
+                let reason = suggestion_reason(&target, &candidate)?;
+                Some(TitleSuggestion {
+                    title_id: id.clone(),
+                    name: name.clone(),
+                    reason,
+                })
+            })
+            .collect();
+
+        suggestions.sort_by(|a, b| {
+            (a.reason as u8)
+                .cmp(&(b.reason as u8))
+                .then_with(|| a.title_id.cmp(&b.title_id))
+        });
+        suggestions.truncate(limit);
+        suggestions
+    }
+
+    /// Iterate over all entries in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = TitleEntry> + '_ {
+        self.entries.iter().map(|(id, name)| TitleEntry {
+            title_id: id.clone(),
+            name: name.clone(),
+        })
+    }
+}
+
+fn suggestion_reason(target: &TitleId, candidate: &TitleId) -> Option<SuggestionReason> {
+    if target.number() == candidate.number() {
+        let (t, c) = (target.prefix().as_bytes(), candidate.prefix().as_bytes());
+        if t[..2] == c[..2] && t[3] == c[3] {
+            return Some(SuggestionReason::WrongRegion);
+        }
+        return Some(SuggestionReason::WrongPrefix);
+    }
+    if target.prefix() != candidate.prefix() {
+        return None;
+    }
+
+    let (t, c) = (target.number().as_bytes(), candidate.number().as_bytes());
+    let diffs: Vec<usize> = (0..t.len()).filter(|&i| t[i] != c[i]).collect();
+    match diffs.as_slice() {
+        [_] => Some(SuggestionReason::MistypedDigit),
+        [i, j] if *j == i + 1 && t[*i] == c[*j] && t[*j] == c[*i] => {
+            Some(SuggestionReason::TransposedDigits)
+        }
+        _ => None,
+    }
+}
+
+fn tokenize(s: &str) -> Vec<String> {
+    s.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_string())
+        .collect()
+}
+
+/// Score how well the query tokens match a name's tokens
+fn match_score(query: &[String], name: &[String]) -> f64 {
+    if name.is_empty() {
+        return 0.0;
+    }
+    if query == name {
+        return 1.0;
+    }
+
+    let mut total = 0.0;
+    for q in query {
+        let best = name
+            .iter()
+            .map(|n| token_similarity(q, n))
+            .fold(0.0, f64::max);
+        if best == 0.0 {
+            // Every query word must match something
+            return 0.0;
+        }
+        total += best;
+    }
+
+    // Average token quality, scaled down for names with many unmatched words
+    let coverage = query.len().min(name.len()) as f64 / name.len() as f64;
+    (total / query.len() as f64) * (0.7 + 0.3 * coverage) * 0.99
+}
+
+fn token_similarity(query: &str, token: &str) -> f64 {
+    if query == token {
+        1.0
+    } else if token.starts_with(query) {
+        0.8
+    } else if query.len() >= 4 && edit_distance(query, token) <= 1 {
+        0.6
+    } else {
+        0.0
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut cur = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let db = TitleDatabase::parse(
+            "# comment\nBLES00246\tMetal Gear Solid 4\nbcus-98111,\"God of War III\"\n\ngarbage\n",
+        );
+        assert_eq!(db.len(), 2);
+        assert_eq!(db.lookup("bles00246"), Some("Metal Gear Solid 4"));
+        assert_eq!(db.lookup("BCUS98111"), Some("God of War III"));
+        assert_eq!(db.lookup("BLES00000"), None);
+    }
+
+    #[test]
+    fn test_search() {
+        let db = TitleDatabase::parse(
+            "BCES00510\tGod of War III\nBCUS98111\tGod of War III\nBCUS98174\tThe Last of Us\n",
+        );
+        let hits = db.search("god of war", 10);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].title_id, "BCES00510");
+        assert_eq!(hits[0].region, Region::Europe);
+        assert_eq!(hits[1].region, Region::America);
+
+        // Typos and partial words still match
+        assert_eq!(db.search("lastt of us", 10)[0].title_id, "BCUS98174");
+        assert_eq!(db.search("las", 10)[0].title_id, "BCUS98174");
+        assert!(db.search("gran turismo", 10).is_empty());
+        assert_eq!(db.search("god", 1).len(), 1);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let db = TitleDatabase::parse(
+            "BLES00246\tMGS4 EU\nBLUS30109\tMGS4 US\nBCES00510\tGoW3 EU\nBCUS98111\tGoW3 US\n",
+        );
+
+        let s = db.suggest("BLUS00246", 5);
+        assert_eq!(s[0].title_id, "BLES00246");
+        assert_eq!(s[0].reason, SuggestionReason::WrongRegion);
+
+        let s = db.suggest("BCES00501", 5);
+        assert_eq!(s[0].title_id, "BCES00510");
+        assert_eq!(s[0].reason, SuggestionReason::TransposedDigits);
+
+        let s = db.suggest("BCUS98112", 5);
+        assert_eq!(s[0].title_id, "BCUS98111");
+        assert_eq!(s[0].reason, SuggestionReason::MistypedDigit);
+
+        assert_eq!(
+            db.suggest("BLES00510", 5)[0].reason,
+            SuggestionReason::WrongPrefix
+        );
+        assert!(db.suggest("BLES99999", 5).is_empty());
+        assert!(db.suggest("not an id", 5).is_empty());
+    }
+
+    #[test]
+    fn test_embedded() {
+        let db = TitleDatabase::embedded();
+        assert!(!db.is_empty());
+        assert!(db.contains("BCUS98174"));
+    }
+}
@@ -1,10 +1,17 @@
+use crate::titledb::TitleSuggestion;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+#[cfg(feature = "typeshare")]
+use typeshare::typeshare;
 
 /// Represents a single PS3 update package
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typeshare", typeshare)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PackageInfo {
     pub version: String,
     pub system_ver: String,
+    #[cfg_attr(feature = "typeshare", typeshare(serialized_as = "number"))]
     pub size_bytes: u64,
     pub size_human: String,
     pub url: String,
@@ -13,54 +20,186 @@ pub struct PackageInfo {
 }
 
 /// Result of fetching updates for a title
+#[cfg_attr(feature = "typeshare", typeshare)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FetchResult {
     pub results: Vec<PackageInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
     pub game_title: String,
     pub cleaned_title_id: String,
 }
 
 /// Download progress information
+#[cfg_attr(feature = "typeshare", typeshare)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProgressInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub filename: Option<String>,
+    #[cfg_attr(feature = "typeshare", typeshare(serialized_as = "number"))]
     pub total: u64,
+    #[cfg_attr(feature = "typeshare", typeshare(serialized_as = "number"))]
     pub downloaded: u64,
+    /// Bytes still to come; 0 while the size is unknown
+    #[serde(default)]
+    #[cfg_attr(feature = "typeshare", typeshare(serialized_as = "number"))]
+    pub remaining: u64,
     pub percent: f64,
     pub speed_bytes_per_sec: f64,
     pub speed_human: String,
     pub done: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// [`PS3UpdateError::code`] of `error`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    /// URL that served (or is serving) the file, which may be an archive mirror
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Outcome of verification, once it ran: false means the file is corrupt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+    /// The destination already held an intact copy, so nothing was downloaded
+    #[serde(default)]
+    pub already_downloaded: bool,
+    /// Bytes per second the job is held to, if limited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "typeshare", typeshare(serialized_as = "number"))]
+    pub rate_limit: Option<u64>,
+    /// Held by `DownloadManager::pause` until resumed
+    #[serde(default)]
+    pub paused: bool,
+    /// Seconds since the job started, frozen once it's done
+    #[serde(default)]
+    pub elapsed_secs: f64,
+    /// Retries, fallbacks and the like, in the order they happened
+    #[serde(default)]
+    pub notes: Vec<String>,
+    /// What the job is doing now
+    #[serde(default)]
+    pub phase: DownloadPhase,
+    /// Tries at fetching the file so far, counting retries and mirrors
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+/// Stage a download job is in
+#[cfg_attr(feature = "typeshare", typeshare)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadPhase {
+    /// Waiting for the server to answer, including between retries
+    #[default]
+    Connecting,
+    /// Receiving (or, for uploads, sending) the file
+    Downloading,
+    /// Checking the finished file against its digest and listed SHA1
+    Verifying,
+    /// Finished, failed or cancelled; see `error`
+    Done,
 }
 
 /// Download mode: single-threaded or multi-part
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Written as `direct`, `auto` or `multipart:N` in config files, command
+/// lines and JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum DownloadMode {
+    #[default]
     Direct,
+    /// Multi-part for packages of [`DownloadMode::AUTO_MIN_SIZE`] or more,
+    /// with [`DownloadMode::AUTO_PARTS`] connections
+    Auto,
     MultiPart { num_parts: usize },
 }
 
-impl Default for DownloadMode {
-    fn default() -> Self {
-        DownloadMode::Direct
+impl DownloadMode {
+    /// Smallest package `Auto` splits
+    pub const AUTO_MIN_SIZE: u64 = 64 * 1024 * 1024;
+    /// Connections `Auto` opens for a large package
+    pub const AUTO_PARTS: usize = 4;
+
+    /// `MultiPart` over `num_parts` connections, or `Direct` for one or none
+    pub fn from_parts(num_parts: usize) -> Self {
+        if num_parts > 1 {
+            DownloadMode::MultiPart { num_parts }
+        } else {
+            DownloadMode::Direct
+        }
+    }
+
+    /// Read `direct`, `auto` or `multipart:N` (any case); `N` must be at least 1
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim().to_ascii_lowercase();
+        let invalid = || PS3UpdateError::InvalidDownloadMode(text.clone());
+        match text.split_once(':') {
+            None if text == "direct" => Ok(DownloadMode::Direct),
+            None if text == "auto" => Ok(DownloadMode::Auto),
+            Some(("multipart", parts)) => match parts.trim().parse() {
+                Ok(num_parts) if num_parts > 0 => Ok(DownloadMode::MultiPart { num_parts }),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for DownloadMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadMode::Direct => f.write_str("direct"),
+            DownloadMode::Auto => f.write_str("auto"),
+            DownloadMode::MultiPart { num_parts } => write!(f, "multipart:{}", num_parts),
+        }
+    }
+}
+
+impl FromStr for DownloadMode {
+    type Err = PS3UpdateError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for DownloadMode {
+    type Error = PS3UpdateError;
+
+    fn try_from(s: String) -> Result<Self> {
+        Self::parse(&s)
+    }
+}
+
+impl From<DownloadMode> for String {
+    fn from(mode: DownloadMode) -> Self {
+        mode.to_string()
     }
 }
 
 /// Error types for the library
+///
+/// Errors from other crates are kept whole and reachable through
+/// [`std::error::Error::source`], not flattened into the message. Each
+/// variant has a stable [`code`](PS3UpdateError::code) for APIs and logs.
 #[derive(Debug, thiserror::Error)]
 pub enum PS3UpdateError {
     #[error("Network error: {0}")]
     Network(#[from] reqwest::Error),
 
     #[error("XML parsing error: {0}")]
-    XmlParse(String),
+    XmlParse(#[from] quick_xml::DeError),
 
     #[error("Invalid title ID: {0}")]
     InvalidTitleId(String),
 
-    #[error("No updates found for title ID: {0}")]
-    NoUpdatesFound(String),
+    /// With known title IDs close to the one asked for, when a title
+    /// database is configured
+    #[error("No updates found for title ID: {title_id}{}", did_you_mean(.suggestions))]
+    NoUpdatesFound {
+        title_id: String,
+        suggestions: Vec<TitleSuggestion>,
+    },
 
     #[error("File system error: {0}")]
     FileSystem(#[from] std::io::Error),
@@ -68,8 +207,247 @@ pub enum PS3UpdateError {
     #[error("Download error: {0}")]
     Download(String),
 
+    /// The server answered a transfer with an error status
+    #[error("Download error: HTTP error: {status}")]
+    HttpStatus {
+        status: reqwest::StatusCode,
+        url: String,
+    },
+
+    #[error("Not found on server: {0}")]
+    NotFound(String),
+
     #[error("Job not found: {0}")]
     JobNotFound(String),
+
+    #[error("Queue item not found: {0}")]
+    QueueItemNotFound(String),
+
+    #[error("Invalid header: {0}")]
+    InvalidHeader(String),
+
+    #[error("Alias file error: {0}")]
+    AliasParse(String),
+
+    #[error("Alias file error: {0}")]
+    AliasToml(#[from] toml::de::Error),
+
+    #[cfg(feature = "catalog")]
+    #[error("Catalog error: {0}")]
+    Catalog(#[from] rusqlite::Error),
+
+    #[error("Snapshot error: {0}")]
+    Snapshot(String),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("Artwork not available for title ID: {0}")]
+    ArtNotFound(String),
+
+    #[error("Invalid PKG file: {0}")]
+    InvalidPkg(String),
+
+    #[error("Package does not match request: {0}")]
+    PackageMismatch(String),
+
+    #[error("Verification failed: {0}")]
+    Verification(String),
+
+    #[error("Invalid PARAM.SFO: {0}")]
+    InvalidSfo(String),
+
+    #[error("Manifest error: {0}")]
+    Manifest(#[source] serde_json::Error),
+
+    #[error("Invalid ISO image: {0}")]
+    InvalidIso(String),
+
+    #[error("FTP error: {0}")]
+    Ftp(String),
+
+    #[cfg(feature = "ftp")]
+    #[error("FTP error: {0}")]
+    FtpProtocol(#[from] suppaftp::FtpError),
+
+    #[error("Watch state error: {0}")]
+    Watch(#[source] serde_json::Error),
+
+    #[error("Invalid folder template: {0}")]
+    InvalidTemplate(String),
+
+    #[error("Invalid size: {0}")]
+    InvalidSize(String),
+
+    #[error("Invalid download mode (expected direct, auto or multipart:N): {0}")]
+    InvalidDownloadMode(String),
+
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+
+    #[cfg(feature = "archive")]
+    #[error("Archive error: {0}")]
+    Archive(#[from] zip::result::ZipError),
+
+    /// The gRPC server couldn't bind its address or stopped serving
+    #[cfg(feature = "grpc")]
+    #[error("Server error: {0}")]
+    Server(#[from] tonic::transport::Error),
+
+    /// A blocking task (hashing, copying) panicked or was cancelled
+    #[error("Background task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+impl PS3UpdateError {
+    /// Stable, machine-readable name of the error's kind, e.g. `network` or
+    /// `verification`
+    ///
+    /// Codes don't change between releases; messages may. Variants that differ
+    /// only in what they wrap share a code (`alias_parse`, `ftp`).
+    pub fn code(&self) -> &'static str {
+        match self {
+            PS3UpdateError::Network(_) => "network",
+            PS3UpdateError::XmlParse(_) => "xml_parse",
+            PS3UpdateError::InvalidTitleId(_) => "invalid_title_id",
+            PS3UpdateError::NoUpdatesFound { .. } => "no_updates_found",
+            PS3UpdateError::FileSystem(_) => "file_system",
+            PS3UpdateError::Download(_) => "download",
+            PS3UpdateError::HttpStatus { .. } => "http_status",
+            PS3UpdateError::NotFound(_) => "not_found",
+            PS3UpdateError::JobNotFound(_) => "job_not_found",
+            PS3UpdateError::QueueItemNotFound(_) => "queue_item_not_found",
+            PS3UpdateError::InvalidHeader(_) => "invalid_header",
+            PS3UpdateError::AliasParse(_) | PS3UpdateError::AliasToml(_) => "alias_parse",
+            #[cfg(feature = "catalog")]
+            PS3UpdateError::Catalog(_) => "catalog",
+            PS3UpdateError::Snapshot(_) => "snapshot",
+            PS3UpdateError::Json(_) => "json",
+            PS3UpdateError::Csv(_) => "csv",
+            PS3UpdateError::ArtNotFound(_) => "art_not_found",
+            PS3UpdateError::InvalidPkg(_) => "invalid_pkg",
+            PS3UpdateError::PackageMismatch(_) => "package_mismatch",
+            PS3UpdateError::Verification(_) => "verification",
+            PS3UpdateError::InvalidSfo(_) => "invalid_sfo",
+            PS3UpdateError::Manifest(_) => "manifest",
+            PS3UpdateError::InvalidIso(_) => "invalid_iso",
+            PS3UpdateError::Ftp(_) => "ftp",
+            #[cfg(feature = "ftp")]
+            PS3UpdateError::FtpProtocol(_) => "ftp",
+            PS3UpdateError::Watch(_) => "watch",
+            PS3UpdateError::InvalidTemplate(_) => "invalid_template",
+            PS3UpdateError::InvalidSize(_) => "invalid_size",
+            PS3UpdateError::InvalidDownloadMode(_) => "invalid_download_mode",
+            PS3UpdateError::InvalidUrl(_) => "invalid_url",
+            #[cfg(feature = "archive")]
+            PS3UpdateError::Archive(_) => "archive",
+            #[cfg(feature = "grpc")]
+            PS3UpdateError::Server(_) => "server",
+            PS3UpdateError::Task(_) => "task",
+        }
+    }
+}
+
+/// An error attached to a title or file in a report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Failure {
+    /// Title ID or URL the error is about
+    pub target: String,
+    pub error: String,
+    /// [`PS3UpdateError::code`] of the error, when it came from the library
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+impl Failure {
+    pub fn new(target: impl Into<String>, error: &PS3UpdateError) -> Self {
+        Self {
+            target: target.into(),
+            error: error.to_string(),
+            code: Some(error.code().to_string()),
+        }
+    }
+}
+
+// " (did you mean BLUS30109 or BLES00246?)", or nothing without suggestions
+fn did_you_mean(suggestions: &[TitleSuggestion]) -> String {
+    if suggestions.is_empty() {
+        return String::new();
+    }
+    let ids: Vec<&str> = suggestions.iter().map(|s| s.title_id.as_str()).collect();
+    format!(" (did you mean {}?)", ids.join(" or "))
 }
 
 pub type Result<T> = std::result::Result<T, PS3UpdateError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_mode_strings() {
+        assert_eq!(
+            "direct".parse::<DownloadMode>().unwrap(),
+            DownloadMode::Direct
+        );
+        assert_eq!(
+            " Auto ".parse::<DownloadMode>().unwrap(),
+            DownloadMode::Auto
+        );
+        assert_eq!(
+            "multipart:8".parse::<DownloadMode>().unwrap(),
+            DownloadMode::MultiPart { num_parts: 8 }
+        );
+        for bad in [
+            "",
+            "fast",
+            "multipart",
+            "multipart:0",
+            "multipart:x",
+            "direct:2",
+        ] {
+            assert!(bad.parse::<DownloadMode>().is_err(), "{}", bad);
+        }
+
+        let mode = DownloadMode::MultiPart { num_parts: 4 };
+        assert_eq!(mode.to_string(), "multipart:4");
+        let json = serde_json::to_string(&mode).unwrap();
+        assert_eq!(json, "\"multipart:4\"");
+        assert_eq!(serde_json::from_str::<DownloadMode>(&json).unwrap(), mode);
+        assert!(serde_json::from_str::<DownloadMode>("\"turbo\"").is_err());
+
+        assert_eq!(DownloadMode::from_parts(1), DownloadMode::Direct);
+        assert_eq!(
+            DownloadMode::from_parts(3),
+            DownloadMode::MultiPart { num_parts: 3 }
+        );
+    }
+
+    #[test]
+    fn test_error_codes_keep_sources() {
+        use std::error::Error;
+
+        let json = serde_json::from_str::<u32>("x").unwrap_err();
+        let e = PS3UpdateError::Manifest(json);
+        assert_eq!(e.code(), "manifest");
+        assert!(e.to_string().starts_with("Manifest error: "));
+        assert!(e.source().unwrap().is::<serde_json::Error>());
+
+        let e: PS3UpdateError = std::io::Error::other("disk full").into();
+        assert_eq!(e.code(), "file_system");
+        assert!(e.source().unwrap().is::<std::io::Error>());
+
+        let e = PS3UpdateError::HttpStatus {
+            status: reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            url: "http://example.com/a.pkg".to_string(),
+        };
+        assert_eq!(e.code(), "http_status");
+        assert_eq!(
+            e.to_string(),
+            "Download error: HTTP error: 503 Service Unavailable"
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use crate::split::{split_pkg_to, DEFAULT_PART_SIZE, FAT32_MAX_FILE_SIZE};
+use crate::types::{PS3UpdateError, Result};
+use crate::utils::NameTemplate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Longest file name FAT32 long-name entries allow
+const FAT32_MAX_NAME: usize = 255;
+
+/// Where packages are placed on the drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UsbLayout {
+    /// Drive root, where the XMB "Install Package Files" option looks
+    #[default]
+    Root,
+    /// A `PACKAGES` folder, as used by package managers on modified firmware
+    Packages,
+}
+
+impl UsbLayout {
+    pub fn dir(&self, usb_root: &Path) -> PathBuf {
+        match self {
+            UsbLayout::Root => usb_root.to_path_buf(),
+            UsbLayout::Packages => usb_root.join("PACKAGES"),
+        }
+    }
+}
+
+/// How packages get onto the drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TransferMethod {
+    #[default]
+    Copy,
+    /// Hard link when source and destination share a filesystem (e.g. a
+    /// staging folder), copying otherwise
+    HardLink,
+}
+
+/// Options for [`export_to_usb`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsbExportOptions {
+    pub layout: UsbLayout,
+    pub method: TransferMethod,
+    /// Split packages larger than FAT32 allows into `.666XX` parts
+    pub split_large: bool,
+    /// Rename packages on the drive, e.g. `{stem}_usb.{ext}`; see
+    /// [`UsbExportOptions::RENAME_PLACEHOLDERS`]
+    #[serde(default)]
+    pub rename: Option<String>,
+}
+
+impl UsbExportOptions {
+    /// Placeholders `rename` may use: the package's file name, the name
+    /// without its extension, and the extension
+    pub const RENAME_PLACEHOLDERS: [&'static str; 3] = ["filename", "stem", "ext"];
+}
+
+impl Default for UsbExportOptions {
+    fn default() -> Self {
+        Self {
+            layout: UsbLayout::Root,
+            method: TransferMethod::Copy,
+            split_large: true,
+            rename: None,
+        }
+    }
+}
+
+/// Files written by [`export_to_usb`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsbExportReport {
+    pub files: Vec<PathBuf>,
+    pub bytes: u64,
+}
+
+/// Copy packages onto a USB drive in the layout the PS3 installer expects
+///
+/// Names are made FAT32-safe and de-duplicated. Packages over 4 GB are split
+/// when `split_large` is set and rejected otherwise. A bad `rename` template
+/// is rejected before anything is copied.
+pub fn export_to_usb(
+    packages: &[PathBuf],
+    usb_root: impl AsRef<Path>,
+    options: &UsbExportOptions,
+) -> Result<UsbExportReport> {
+    let rename = options
+        .rename
+        .as_deref()
+        .map(|t| NameTemplate::parse(t, &UsbExportOptions::RENAME_PLACEHOLDERS))
+        .transpose()?;
+    let dir = options.layout.dir(usb_root.as_ref());
+    std::fs::create_dir_all(&dir)?;
+
+    let mut report = UsbExportReport::default();
+    let mut used = HashSet::new();
+    for src in packages {
+        let size = std::fs::metadata(src)?.len();
+        let name = src
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|name| match &rename {
+                Some(template) => template.render_name(|field| {
+                    let (stem, ext) = name.rsplit_once('.').unwrap_or((name, ""));
+                    match field {
+                        "stem" => stem.to_string(),
+                        "ext" => ext.to_string(),
+                        _ => name.to_string(),
+                    }
+                }),
+                None => fat32_name(name),
+            })
+            .unwrap_or_else(|| "update.pkg".to_string());
+        let dest = dir.join(unique_name(&name, &mut used));
+
+        if size > FAT32_MAX_FILE_SIZE {
+            if !options.split_large {
+                return Err(PS3UpdateError::Download(format!(
+                    "{} is larger than FAT32 allows; enable splitting",
+                    src.display()
+                )));
+            }
+            report
+                .files
+                .extend(split_pkg_to(src, &dest, DEFAULT_PART_SIZE, |_, _| {})?);
+        } else {
+            transfer(src, &dest, options.method)?;
+            report.files.push(dest);
+        }
+        report.bytes += size;
+    }
+    Ok(report)
+}
+
+fn transfer(src: &Path, dest: &Path, method: TransferMethod) -> Result<()> {
+    if dest.exists() {
+        std::fs::remove_file(dest)?;
+    }
+    if method == TransferMethod::HardLink && std::fs::hard_link(src, dest).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(src, dest)?;
+    Ok(())
+}
+
+/// Make a file name valid on FAT32
+///
+/// Reserved characters and control characters become `_`, trailing dots and
+/// spaces are dropped, and long names are shortened while keeping the extension.
+pub fn fat32_name(name: &str) -> String {
+    let mut cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    cleaned.truncate(cleaned.trim_end_matches(['.', ' ']).len());
+    if cleaned.is_empty() {
+        cleaned = "_".to_string();
+    }
+
+    if cleaned.chars().count() > FAT32_MAX_NAME {
+        let (stem, ext) = match cleaned.rsplit_once('.') {
+            Some((stem, ext)) if ext.len() < 16 => (stem.to_string(), format!(".{}", ext)),
+            _ => (cleaned.clone(), String::new()),
+        };
+        let keep = FAT32_MAX_NAME - ext.chars().count();
+        cleaned = stem.chars().take(keep).collect::<String>() + &ext;
+    }
+    cleaned
+}
+
+/// Pick a name not already used on the drive (FAT32 is case-insensitive)
+fn unique_name(name: &str, used: &mut HashSet<String>) -> String {
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) => (stem, format!(".{}", ext)),
+        None => (name, String::new()),
+    };
+    let mut candidate = name.to_string();
+    let mut n = 2;
+    while !used.insert(candidate.to_lowercase()) {
+        candidate = format!("{} ({}){}", stem, n, ext);
+        n += 1;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fat32_name() {
+        assert_eq!(fat32_name("EP0001-BLES00779.pkg"), "EP0001-BLES00779.pkg");
+        assert_eq!(fat32_name("What?: A \"Game\".pkg"), "What__ A _Game_.pkg");
+        assert_eq!(fat32_name("trailing. . "), "trailing");
+
+        let long = format!("{}.pkg", "a".repeat(300));
+        let short = fat32_name(&long);
+        assert_eq!(short.len(), FAT32_MAX_NAME);
+        assert!(short.ends_with(".pkg"));
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_name("a.pkg", &mut used), "a.pkg");
+        assert_eq!(unique_name("A.PKG", &mut used), "A (2).PKG");
+        assert_eq!(unique_name("a.pkg", &mut used), "a (3).pkg");
+    }
+
+    #[test]
+    fn test_export_to_usb() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let src = dir.join("library");
+        std::fs::create_dir_all(&src).unwrap();
+        let pkg = src.join("update.pkg");
+        std::fs::write(&pkg, b"pkg").unwrap();
+
+        let usb = dir.join("usb");
+        let options = UsbExportOptions {
+            layout: UsbLayout::Packages,
+            method: TransferMethod::HardLink,
+            ..Default::default()
+        };
+        let report = export_to_usb(&[pkg.clone(), pkg], &usb, &options).unwrap();
+        assert_eq!(
+            report.files,
+            vec![
+                usb.join("PACKAGES/update.pkg"),
+                usb.join("PACKAGES/update (2).pkg")
+            ]
+        );
+        assert_eq!(report.bytes, 6);
+        assert_eq!(std::fs::read(&report.files[1]).unwrap(), b"pkg");
+
+        let options = UsbExportOptions {
+            rename: Some("{stem} [usb].{ext}".into()),
+            ..Default::default()
+        };
+        let report = export_to_usb(&[src.join("update.pkg")], &usb, &options).unwrap();
+        assert_eq!(report.files, vec![usb.join("update [usb].pkg")]);
+        let options = UsbExportOptions {
+            rename: Some("{size}.pkg".into()),
+            ..Default::default()
+        };
+        assert!(export_to_usb(&[src.join("update.pkg")], &usb, &options).is_err());
+    }
+}
@@ -1,16 +1,95 @@
-/// Format bytes to human-readable size
-pub fn format_size(n: u64) -> String {
-    if n == 0 {
-        return "Unknown".to_string();
+use crate::types::{PS3UpdateError, Result};
+use crate::usb::fat32_name;
+use std::path::PathBuf;
+
+/// Which units [`format_size_with`] counts in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SizeUnits {
+    /// Steps of 1024 labelled KB, MB, GB, as the PS3 and Windows show sizes
+    #[default]
+    Binary,
+    /// Steps of 1000 labelled kB, MB, GB, as drive makers and macOS count
+    Si,
+}
+
+impl SizeUnits {
+    fn step(self) -> f64 {
+        match self {
+            SizeUnits::Binary => 1024.0,
+            SizeUnits::Si => 1000.0,
+        }
+    }
+
+    fn labels(self) -> [&'static str; 5] {
+        match self {
+            SizeUnits::Binary => ["B", "KB", "MB", "GB", "TB"],
+            SizeUnits::Si => ["B", "kB", "MB", "GB", "TB"],
+        }
+    }
+}
+
+/// How [`format_size_with`] writes a size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeFormat {
+    pub units: SizeUnits,
+    /// Digits after the decimal point
+    pub precision: usize,
+}
+
+impl Default for SizeFormat {
+    /// What [`format_size`] uses: binary units, two decimals
+    fn default() -> Self {
+        SizeFormat {
+            units: SizeUnits::Binary,
+            precision: 2,
+        }
     }
-    let units = ["B", "KB", "MB", "GB", "TB"];
+}
+
+/// Format bytes to human-readable size, in binary units with two decimals
+pub fn format_size(n: u64) -> String {
+    format_size_with(n, SizeFormat::default())
+}
+
+/// Format bytes to human-readable size in the given units and precision
+pub fn format_size_with(n: u64, format: SizeFormat) -> String {
+    let units = format.units.labels();
+    let step = format.units.step();
     let mut size = n as f64;
     let mut i = 0;
-    while size >= 1024.0 && i < units.len() - 1 {
-        size /= 1024.0;
+    while size >= step && i < units.len() - 1 {
+        size /= step;
         i += 1;
     }
-    format!("{:.2} {}", size, units[i])
+    format!("{:.*} {}", format.precision, size, units[i])
+}
+
+/// Read a size such as `1.5 GB`, `700M`, `4096` or `2 GiB` as bytes
+///
+/// Units are case-insensitive and the `B` may be left off. `KB`, `MB`… are
+/// steps of 1024, matching [`format_size`], as are `KiB`, `MiB`…; a bare
+/// number is bytes.
+pub fn parse_size(text: &str) -> Result<u64> {
+    let invalid = || PS3UpdateError::InvalidSize(text.to_string());
+    let trimmed = text.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    let power = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 0,
+        "k" | "kb" | "kib" => 1,
+        "m" | "mb" | "mib" => 2,
+        "g" | "gb" | "gib" => 3,
+        "t" | "tb" | "tib" => 4,
+        _ => return Err(invalid()),
+    };
+    let bytes = number * 1024f64.powi(power);
+    if !bytes.is_finite() || bytes > u64::MAX as f64 {
+        return Err(invalid());
+    }
+    Ok(bytes.round() as u64)
 }
 
 /// Clean and normalize a PS3 title ID
@@ -21,7 +100,22 @@ pub fn clean_title_id(raw: &str) -> String {
         .to_uppercase()
 }
 
+/// Longest name [`safe_dir_name`] returns, in bytes
+pub const MAX_DIR_NAME_BYTES: usize = 64;
+
+// Device names Windows won't create a file or folder under, in any case
+const WINDOWS_RESERVED: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
 /// Create a safe directory name from a string
+///
+/// Only ASCII letters, digits, spaces, `-` and `_` are kept; see
+/// [`unicode_dir_name`] to keep accented and Japanese titles readable. The
+/// result can be created on Windows as well as elsewhere: no trailing dots or
+/// spaces, no reserved device name such as `CON` (which gets a `_` appended),
+/// and at most [`MAX_DIR_NAME_BYTES`] bytes.
 pub fn safe_dir_name(raw: &str) -> String {
     // Allow letters, numbers, space, dash, underscore; collapse whitespace
     let cleaned: String = raw
@@ -34,16 +128,204 @@ pub fn safe_dir_name(raw: &str) -> String {
             }
         })
         .collect();
-    let cleaned = cleaned
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join(" ")
-        .trim()
-        .to_string();
+    finish_dir_name(cleaned)
+}
+
+/// Like [`safe_dir_name`], but keeps every printable character the platform
+/// allows in a file name, so `ドラゴンクエスト` or `Pokémon` stay as they are
+///
+/// On Windows `< > : " / \ | ? *` become spaces; elsewhere only `/` does.
+/// Control characters are dropped everywhere. The byte limit still applies,
+/// so long names in multi-byte scripts are cut shorter in characters.
+pub fn unicode_dir_name(raw: &str) -> String {
+    let cleaned: String = raw
+        .chars()
+        .filter(|c| !c.is_control())
+        .map(|c| if is_illegal_in_name(c) { ' ' } else { c })
+        .collect();
+    finish_dir_name(cleaned)
+}
+
+#[cfg(windows)]
+fn is_illegal_in_name(c: char) -> bool {
+    matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*')
+}
+
+#[cfg(not(windows))]
+fn is_illegal_in_name(c: char) -> bool {
+    c == '/'
+}
+
+// Collapse whitespace, keep under the byte limit and steer clear of names
+// Windows can't create
+fn finish_dir_name(cleaned: String) -> String {
+    let mut cleaned = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    if cleaned.len() > MAX_DIR_NAME_BYTES {
+        let mut end = MAX_DIR_NAME_BYTES;
+        while !cleaned.is_char_boundary(end) {
+            end -= 1;
+        }
+        cleaned.truncate(end);
+    }
+    // Cutting it short can leave a space at the end
+    cleaned.truncate(cleaned.trim_end_matches(['.', ' ']).len());
+
     if cleaned.is_empty() {
         "PS3Updates".to_string()
+    } else if WINDOWS_RESERVED
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(&cleaned))
+    {
+        cleaned + "_"
     } else {
-        cleaned.chars().take(64).collect()
+        cleaned
+    }
+}
+
+/// Current time as seconds since the Unix epoch
+pub fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Compare package versions such as `01.02` numerically
+///
+/// Unparseable versions sort as `0`.
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let va = a.trim().parse::<f32>().unwrap_or(0.0);
+    let vb = b.trim().parse::<f32>().unwrap_or(0.0);
+    va.partial_cmp(&vb).unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// A name built from `{placeholder}` fields, such as `{title} ({title_id})`
+///
+/// Every place the crate names things on disk goes through this: game
+/// folders ([`FolderTemplate`](crate::FolderTemplate)), renamed downloads
+/// ([`NamingScheme`](crate::NamingScheme) and
+/// [`DownloadOptions::with_name_template`](crate::DownloadOptions::with_name_template))
+/// and files copied to USB ([`UsbExportOptions::rename`](crate::UsbExportOptions)).
+///
+/// Parsing checks each placeholder against the ones the caller fills in, so
+/// a typo fails up front instead of ending up on disk. `{{` and `}}` stand
+/// for literal braces. `/` (or `\`) in the template starts a new folder, but
+/// a `/` inside a value never does. Each folder and file name is made
+/// FAT32-safe, and `.` or `..` can't climb out of where it's joined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameTemplate {
+    text: String,
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Literal(String),
+    Field(String),
+    Separator,
+}
+
+impl NameTemplate {
+    /// Check `text`, allowing only the placeholders in `allowed`
+    pub fn parse(text: &str, allowed: &[&str]) -> Result<Self> {
+        let invalid = |reason: String| PS3UpdateError::InvalidTemplate(reason);
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(invalid("empty".to_string()));
+        }
+
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => {
+                                return Err(invalid(format!("unclosed {{ in {}", text)))
+                            }
+                            Some(c) => name.push(c),
+                        }
+                    }
+                    if !allowed.contains(&name.as_str()) {
+                        return Err(invalid(format!("unknown placeholder {{{}}}", name)));
+                    }
+                    parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    parts.push(TemplatePart::Field(name));
+                }
+                '/' | '\\' => {
+                    parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    parts.push(TemplatePart::Separator);
+                }
+                c => literal.push(c),
+            }
+        }
+        parts.push(TemplatePart::Literal(literal));
+        parts.retain(|part| !matches!(part, TemplatePart::Literal(l) if l.is_empty()));
+
+        Ok(Self {
+            text: text.to_string(),
+            parts,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Placeholders the template uses, in order, repeats included
+    pub fn placeholders(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            TemplatePart::Field(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    // Each folder level with its placeholders filled in, before cleaning
+    fn segments(&self, value: impl Fn(&str) -> String) -> Vec<String> {
+        let mut segments = vec![String::new()];
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(text) => segments.last_mut().unwrap().push_str(text),
+                TemplatePart::Field(name) => segments.last_mut().unwrap().push_str(&value(name)),
+                TemplatePart::Separator => segments.push(String::new()),
+            }
+        }
+        segments
+    }
+
+    /// A relative path, one FAT32-safe name per folder level
+    ///
+    /// `value` gives each placeholder's text. Levels that come out empty
+    /// are dropped.
+    pub fn render_path(&self, value: impl Fn(&str) -> String) -> PathBuf {
+        self.segments(value)
+            .into_iter()
+            .filter(|segment| !segment.trim().is_empty())
+            .map(|segment| fat32_name(segment.trim()))
+            .collect()
+    }
+
+    /// A single FAT32-safe file name; folder separators become `_`
+    pub fn render_name(&self, value: impl Fn(&str) -> String) -> String {
+        fat32_name(self.segments(value).join("/").trim())
+    }
+}
+
+impl std::fmt::Display for NameTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
     }
 }
 
@@ -53,12 +335,51 @@ mod tests {
 
     #[test]
     fn test_format_size() {
-        assert_eq!(format_size(0), "Unknown");
+        assert_eq!(format_size(0), "0.00 B");
         assert_eq!(format_size(512), "512.00 B");
         assert_eq!(format_size(1024), "1.00 KB");
         assert_eq!(format_size(1048576), "1.00 MB");
     }
 
+    #[test]
+    fn test_format_size_with() {
+        let si = SizeFormat {
+            units: SizeUnits::Si,
+            precision: 1,
+        };
+        assert_eq!(format_size_with(1000, si), "1.0 kB");
+        assert_eq!(format_size_with(1_500_000_000, si), "1.5 GB");
+        let whole = SizeFormat {
+            precision: 0,
+            ..SizeFormat::default()
+        };
+        assert_eq!(format_size_with(0, whole), "0 B");
+        assert_eq!(format_size_with(3 * 1024 * 1024, whole), "3 MB");
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size("1.5 GB").unwrap(), 1536 * 1024 * 1024);
+        assert_eq!(parse_size("700m").unwrap(), 700 * 1024 * 1024);
+        assert_eq!(parse_size(" 2 GiB ").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert_eq!(parse_size("512 B").unwrap(), 512);
+        assert_eq!(parse_size(&format_size(1048576)).unwrap(), 1048576);
+        assert!(parse_size("").is_err());
+        assert!(parse_size("GB").is_err());
+        assert!(parse_size("1.5 PB").is_err());
+        assert!(parse_size("1.2.3 MB").is_err());
+        assert!(parse_size("-1 MB").is_err());
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        use std::cmp::Ordering;
+        assert_eq!(compare_versions("01.02", "01.10"), Ordering::Less);
+        assert_eq!(compare_versions("1.2", "01.20"), Ordering::Equal);
+        assert_eq!(compare_versions("02.00", "bogus"), Ordering::Greater);
+    }
+
     #[test]
     fn test_clean_title_id() {
         assert_eq!(clean_title_id("BLES00779"), "BLES00779");
@@ -71,5 +392,72 @@ mod tests {
         assert_eq!(safe_dir_name("God of War"), "God of War");
         assert_eq!(safe_dir_name("Game/Title: Test"), "Game Title Test");
         assert_eq!(safe_dir_name(""), "PS3Updates");
+        assert_eq!(safe_dir_name("..."), "PS3Updates");
+    }
+
+    #[test]
+    fn test_safe_dir_name_windows() {
+        assert_eq!(safe_dir_name("CON"), "CON_");
+        assert_eq!(safe_dir_name("com1"), "com1_");
+        assert_eq!(safe_dir_name("Lpt9."), "Lpt9_");
+        assert_eq!(safe_dir_name("Aux Games"), "Aux Games");
+        assert_eq!(safe_dir_name("COM10"), "COM10");
+
+        // A cut that lands on a space doesn't leave it at the end
+        let long = format!("{} {}", "a".repeat(MAX_DIR_NAME_BYTES - 1), "b".repeat(10));
+        let name = safe_dir_name(&long);
+        assert_eq!(name, "a".repeat(MAX_DIR_NAME_BYTES - 1));
+        assert!(safe_dir_name(&"x".repeat(200)).len() <= MAX_DIR_NAME_BYTES);
+    }
+
+    #[test]
+    fn test_unicode_dir_name() {
+        assert_eq!(unicode_dir_name("Pokémon Rumble"), "Pokémon Rumble");
+        assert_eq!(unicode_dir_name("ドラゴンクエスト"), "ドラゴンクエスト");
+        assert_eq!(unicode_dir_name("Demon's Souls"), "Demon's Souls");
+        assert_eq!(unicode_dir_name("A/B."), "A B");
+        assert_eq!(unicode_dir_name("A\u{7}B"), "AB");
+        assert_eq!(unicode_dir_name("nul"), "nul_");
+        assert_eq!(unicode_dir_name("\u{1}"), "PS3Updates");
+
+        // Cut on a character boundary, never mid-character
+        let name = unicode_dir_name(&"テ".repeat(40));
+        assert!(name.len() <= MAX_DIR_NAME_BYTES);
+        assert_eq!(name, "テ".repeat(MAX_DIR_NAME_BYTES / 3));
+    }
+
+    #[test]
+    fn test_name_template() {
+        let allowed = ["title", "id", "version"];
+        let template = NameTemplate::parse("{title} ({id})/v{version}", &allowed).unwrap();
+        assert_eq!(
+            template.placeholders().collect::<Vec<_>>(),
+            ["title", "id", "version"]
+        );
+        let value = |name: &str| match name {
+            "title" => "AC/DC: Live".to_string(),
+            "id" => "BLUS30001".to_string(),
+            _ => "01.02".to_string(),
+        };
+        assert_eq!(
+            template.render_path(value),
+            PathBuf::from("AC_DC_ Live (BLUS30001)").join("v01.02")
+        );
+        assert_eq!(
+            template.render_name(value),
+            "AC_DC_ Live (BLUS30001)_v01.02"
+        );
+
+        // Escaped braces, and levels that can't climb out
+        let braces = NameTemplate::parse("{{{id}}}/../.", &allowed).unwrap();
+        assert_eq!(
+            braces.render_path(value),
+            PathBuf::from("{BLUS30001}").join("_").join("_")
+        );
+
+        assert!(NameTemplate::parse("", &allowed).is_err());
+        assert!(NameTemplate::parse("{title", &allowed).is_err());
+        assert!(NameTemplate::parse("{ti{tle}", &allowed).is_err());
+        assert!(NameTemplate::parse("{region}", &allowed).is_err());
     }
 }
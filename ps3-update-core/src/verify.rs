@@ -0,0 +1,214 @@
+use crate::pkg::PkgHeader;
+use crate::types::{PS3UpdateError, Result};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Size of the digest block at the end of every PKG
+///
+/// The first 20 bytes are the SHA1 of everything before the block; the rest is padding.
+pub const PKG_DIGEST_SIZE: u64 = 0x20;
+
+/// Result of checking a PKG against its own integrity data
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PkgVerification {
+    pub content_id: String,
+    /// File size matches the size recorded in the header
+    pub size_ok: bool,
+    /// SHA1 stored in the trailing digest block (hex)
+    pub stored_digest: String,
+    /// SHA1 of the file up to the digest block (hex)
+    pub computed_digest: String,
+    /// SHA1 of the whole file (hex)
+    pub file_sha1: String,
+}
+
+impl PkgVerification {
+    /// True when the package is intact and would pass the console's own check
+    pub fn is_valid(&self) -> bool {
+        self.size_ok && self.stored_digest == self.computed_digest
+    }
+
+    /// Compare against a SHA1 from the update XML
+    ///
+    /// Accepts either the digest-block SHA1 or the whole-file SHA1, since both
+    /// conventions appear in the wild.
+    pub fn matches_sha1(&self, expected: &str) -> bool {
+        let expected = expected.trim();
+        !expected.is_empty()
+            && (expected.eq_ignore_ascii_case(&self.computed_digest)
+                || expected.eq_ignore_ascii_case(&self.file_sha1))
+    }
+}
+
+/// Verify a PKG's header size and trailing digest block
+pub fn verify_pkg(path: impl AsRef<Path>) -> Result<PkgVerification> {
+    let path = path.as_ref();
+    let header = PkgHeader::read_from(path)?;
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len < PkgHeader::SIZE as u64 + PKG_DIGEST_SIZE {
+        return Err(PS3UpdateError::InvalidPkg(format!(
+            "file too small ({} bytes)",
+            len
+        )));
+    }
+
+    let mut body_hasher = Sha1::new();
+    let mut file_hasher = Sha1::new();
+    let mut remaining = len - PKG_DIGEST_SIZE;
+    let mut buf = vec![0u8; 1024 * 1024];
+    while remaining > 0 {
+        let want = remaining.min(buf.len() as u64) as usize;
+        file.read_exact(&mut buf[..want])?;
+        body_hasher.update(&buf[..want]);
+        file_hasher.update(&buf[..want]);
+        remaining -= want as u64;
+    }
+
+    let mut footer = [0u8; PKG_DIGEST_SIZE as usize];
+    file.read_exact(&mut footer)?;
+    file_hasher.update(footer);
+
+    Ok(PkgVerification {
+        content_id: header.content_id,
+        size_ok: header.total_size == len,
+        stored_digest: to_hex(&footer[..20]),
+        computed_digest: to_hex(&body_hasher.finalize()),
+        file_sha1: to_hex(&file_hasher.finalize()),
+    })
+}
+
+/// Check a downloaded PKG is intact and, when given, matches the listed SHA1
+///
+/// Fails with [`PS3UpdateError::Verification`] naming the file when it doesn't.
+pub fn verify_download(path: impl AsRef<Path>, expected_sha1: &str) -> Result<PkgVerification> {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let verification = verify_pkg(path)?;
+    if !verification.is_valid() {
+        return Err(PS3UpdateError::Verification(format!(
+            "{} is corrupt (digest mismatch or wrong size)",
+            name
+        )));
+    }
+    if !expected_sha1.trim().is_empty() && !verification.matches_sha1(expected_sha1) {
+        return Err(PS3UpdateError::Verification(format!(
+            "{} does not match the listed SHA1 {}",
+            name, expected_sha1
+        )));
+    }
+    Ok(verification)
+}
+
+/// SHA1 of a whole file (hex)
+pub fn file_sha1(path: impl AsRef<Path>) -> Result<String> {
+    file_sha1_with_progress(path, |_, _| {})
+}
+
+/// SHA1 of a whole file (hex), calling `on_progress(hashed, total)` after
+/// each megabyte so long hashes can show how far along they are
+pub fn file_sha1_with_progress(
+    path: impl AsRef<Path>,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<String> {
+    let mut file = File::open(path)?;
+    let total = file.metadata()?.len();
+    let mut hasher = Sha1::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut hashed = 0u64;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        hashed += n as u64;
+        on_progress(hashed, total);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::pkg::tests::{sample_header, CONTENT_ID};
+
+    /// A structurally valid PKG: header, body and a correct digest block
+    pub(crate) fn sample_pkg(content_id: &str) -> Vec<u8> {
+        let body = vec![0xA5u8; 0x40];
+        let mut data = sample_header(content_id, body.len() as u64);
+        data.extend_from_slice(&body);
+        let digest = Sha1::digest(&data);
+        data.extend_from_slice(&digest);
+        data.extend_from_slice(&[0u8; 12]);
+        data
+    }
+
+    /// Write `data` to a `.pkg` file that is deleted when the path is dropped
+    pub(crate) fn write_temp(name: &str, data: &[u8]) -> tempfile::TempPath {
+        let path = tempfile::Builder::new()
+            .prefix(&format!("ps3-{}-", name))
+            .suffix(".pkg")
+            .tempfile()
+            .unwrap()
+            .into_temp_path();
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_verify_download() {
+        let path = write_temp("verify-download", &sample_pkg(CONTENT_ID));
+        let verification = verify_download(&path, "").unwrap();
+        assert!(verify_download(&path, &verification.file_sha1).is_ok());
+
+        let err = verify_download(&path, "0123456789abcdef0123456789abcdef01234567").unwrap_err();
+        assert!(matches!(err, PS3UpdateError::Verification(_)));
+    }
+
+    #[test]
+    fn test_file_sha1_with_progress() {
+        let data = vec![0x5Au8; 1024 * 1024 + 10];
+        let path = write_temp("sha1-progress", &data);
+        let mut calls = vec![];
+        let sha1 =
+            file_sha1_with_progress(&path, |hashed, total| calls.push((hashed, total))).unwrap();
+        assert_eq!(sha1, to_hex(&Sha1::digest(&data)));
+        assert_eq!(sha1, file_sha1(&path).unwrap());
+        let total = data.len() as u64;
+        assert_eq!(calls.last(), Some(&(total, total)));
+        assert!(calls.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn test_verify_pkg() {
+        let data = sample_pkg(CONTENT_ID);
+        let path = write_temp("verify", &data);
+        let result = verify_pkg(&path).unwrap();
+        assert!(result.is_valid());
+        assert_eq!(result.content_id, CONTENT_ID);
+        assert!(result.matches_sha1(&result.computed_digest.to_uppercase()));
+        assert!(result.matches_sha1(&file_sha1(&path).unwrap()));
+        assert!(!result.matches_sha1(""));
+
+        // Flip a body byte: digest no longer matches
+        let mut corrupt = data.clone();
+        corrupt[0xD0] ^= 0xFF;
+        std::fs::write(&path, &corrupt).unwrap();
+        assert!(!verify_pkg(&path).unwrap().is_valid());
+
+        // Truncated download
+        std::fs::write(&path, &data[..data.len() - 1]).unwrap();
+        assert!(!verify_pkg(&path).unwrap().is_valid());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::feed::FeedFormat;
+use crate::fetcher::UpdateFetcher;
+use crate::types::{Failure, FetchResult, PS3UpdateError, PackageInfo, Result};
+use crate::utils::{clean_title_id, unix_timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// What a watcher knows about one title
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchedTitle {
+    pub game_title: String,
+    /// Every version seen so far
+    pub versions: Vec<String>,
+    /// Unix timestamp of the last successful check
+    pub last_checked: u64,
+}
+
+/// Discoveries a [`WatchState`] keeps for its feed
+pub const FEED_ENTRIES: usize = 50;
+
+/// A version seen for the first time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiscoveredUpdate {
+    pub title_id: String,
+    pub game_title: String,
+    pub package: PackageInfo,
+    /// Unix timestamp of the check that found it
+    pub discovered_at: u64,
+}
+
+/// Versions seen per title, kept between runs of a watcher
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchState {
+    pub titles: BTreeMap<String, WatchedTitle>,
+    /// The latest discoveries, newest first, for [`WatchState::feed`]
+    #[serde(default)]
+    pub recent: Vec<DiscoveredUpdate>,
+}
+
+impl WatchState {
+    /// Read a state file; a missing file is an empty state
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(PS3UpdateError::Watch),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let text = serde_json::to_string_pretty(self).map_err(PS3UpdateError::Watch)?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Record a fetch and return the versions not seen before
+    ///
+    /// The first fetch of a title only sets the baseline, so starting to watch
+    /// a game doesn't report its whole update history.
+    pub fn record(&mut self, result: &FetchResult) -> Vec<DiscoveredUpdate> {
+        let title_id = clean_title_id(&result.cleaned_title_id);
+        let now = unix_timestamp();
+        let first = !self.titles.contains_key(&title_id);
+        let watched = self.titles.entry(title_id.clone()).or_default();
+        watched.game_title = result.game_title.clone();
+        watched.last_checked = now;
+
+        let mut discovered = vec![];
+        for pkg in &result.results {
+            if watched.versions.contains(&pkg.version) {
+                continue;
+            }
+            watched.versions.push(pkg.version.clone());
+            if !first {
+                discovered.push(DiscoveredUpdate {
+                    title_id: title_id.clone(),
+                    game_title: result.game_title.clone(),
+                    package: pkg.clone(),
+                    discovered_at: now,
+                });
+            }
+        }
+        self.recent.splice(0..0, discovered.iter().cloned());
+        self.recent.truncate(FEED_ENTRIES);
+        discovered
+    }
+
+    /// The latest discoveries as an Atom or RSS document
+    pub fn feed(&self, format: FeedFormat) -> String {
+        format.render(&self.recent)
+    }
+}
+
+/// Outcome of one pass over a watchlist
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatchReport {
+    pub discovered: Vec<DiscoveredUpdate>,
+    /// Titles checked for the first time (baseline recorded, nothing reported)
+    pub new_titles: Vec<String>,
+    /// Titles that couldn't be checked
+    pub errors: Vec<Failure>,
+}
+
+/// Fetch every title on a watchlist and record what changed in `state`
+///
+/// A title with no updates on the server is recorded with no versions, so a
+/// first patch released later is reported.
+pub async fn check_watchlist(
+    fetcher: &UpdateFetcher,
+    state: &mut WatchState,
+    title_ids: &[String],
+) -> WatchReport {
+    let mut report = WatchReport::default();
+    for title_id in title_ids {
+        let title_id = clean_title_id(title_id);
+        let result = match fetcher.fetch_updates(&title_id).await {
+            Ok(result) => result,
+            Err(PS3UpdateError::NoUpdatesFound { .. }) => FetchResult {
+                results: vec![],
+                error: None,
+                game_title: fetcher.title_name(&title_id).unwrap_or_default(),
+                cleaned_title_id: title_id.clone(),
+            },
+            Err(e) => {
+                report.errors.push(Failure::new(title_id, &e));
+                continue;
+            }
+        };
+        if !state.titles.contains_key(&title_id) {
+            report.new_titles.push(title_id);
+        }
+        report.discovered.extend(state.record(&result));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fetch_result(versions: &[&str]) -> FetchResult {
+        FetchResult {
+            results: versions
+                .iter()
+                .map(|v| PackageInfo {
+                    version: v.to_string(),
+                    system_ver: String::new(),
+                    size_bytes: 100,
+                    size_human: String::new(),
+                    url: format!("http://example/{}.pkg", v),
+                    sha1: String::new(),
+                    filename: format!("{}.pkg", v),
+                })
+                .collect(),
+            error: None,
+            game_title: "Game".into(),
+            cleaned_title_id: "BLES00779".into(),
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        let mut state = WatchState::default();
+        assert!(state.record(&fetch_result(&["01.01"])).is_empty());
+
+        let discovered = state.record(&fetch_result(&["01.02", "01.01"]));
+        assert_eq!(discovered.len(), 1);
+        assert_eq!(discovered[0].package.version, "01.02");
+        assert!(state.record(&fetch_result(&["01.02", "01.01"])).is_empty());
+        state.record(&fetch_result(&["01.03", "01.02", "01.01"]));
+        let versions: Vec<&str> = state
+            .recent
+            .iter()
+            .map(|u| u.package.version.as_str())
+            .collect();
+        assert_eq!(versions, ["01.03", "01.02"]);
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("watch.json");
+        assert_eq!(WatchState::load(&path).unwrap(), WatchState::default());
+        state.save(&path).unwrap();
+        assert_eq!(WatchState::load(&path).unwrap(), state);
+    }
+}
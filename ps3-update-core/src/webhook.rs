@@ -0,0 +1,466 @@
+use crate::client::{client_builder, ClientOptions};
+use crate::retention::version_from_filename;
+use crate::types::{PS3UpdateError, PackageInfo, Result};
+use crate::utils::{format_size, unix_timestamp};
+use crate::watch::DiscoveredUpdate;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+#[cfg(feature = "typeshare")]
+use typeshare::typeshare;
+
+/// Something worth telling another service about
+///
+/// Sent as a JSON object with an `event` field naming the variant, a
+/// `timestamp`, and the variant's fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A package finished downloading
+    DownloadComplete {
+        title_id: String,
+        game_title: String,
+        version: Option<String>,
+        path: PathBuf,
+        size_bytes: u64,
+        /// Whether the package was checked against its digest after download
+        verified: bool,
+    },
+    /// A package is corrupt, isn't a PKG, or belongs to another title
+    VerificationFailed {
+        title_id: Option<String>,
+        game_title: String,
+        /// File or URL that failed
+        target: String,
+        error: String,
+    },
+    /// Watch mode found a version it hadn't seen before
+    UpdateDiscovered {
+        title_id: String,
+        game_title: String,
+        package: PackageInfo,
+    },
+}
+
+/// Names of [`WebhookEvent`] variants, for filtering
+#[cfg_attr(feature = "typeshare", typeshare)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventKind {
+    DownloadComplete,
+    VerificationFailed,
+    UpdateDiscovered,
+}
+
+impl WebhookEvent {
+    /// A finished download, with its size read from disk and its version
+    /// taken from Sony's file name
+    pub fn download_complete(
+        title_id: impl Into<String>,
+        game_title: impl Into<String>,
+        path: impl Into<PathBuf>,
+        verified: bool,
+    ) -> Self {
+        let path = path.into();
+        let version = path
+            .file_name()
+            .and_then(|name| version_from_filename(&name.to_string_lossy()));
+        let size_bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        WebhookEvent::DownloadComplete {
+            title_id: title_id.into(),
+            game_title: game_title.into(),
+            version,
+            path,
+            size_bytes,
+            verified,
+        }
+    }
+
+    pub fn kind(&self) -> WebhookEventKind {
+        match self {
+            WebhookEvent::DownloadComplete { .. } => WebhookEventKind::DownloadComplete,
+            WebhookEvent::VerificationFailed { .. } => WebhookEventKind::VerificationFailed,
+            WebhookEvent::UpdateDiscovered { .. } => WebhookEventKind::UpdateDiscovered,
+        }
+    }
+}
+
+/// `01.04` → `1.04`, as people write versions
+fn short_version(version: &str) -> &str {
+    match version.strip_prefix('0') {
+        Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit()) => rest,
+        _ => version,
+    }
+}
+
+impl WebhookEvent {
+    /// A one-line summary for chat, e.g. "Uncharted 1.04 downloaded and
+    /// verified, 1.1 GB"
+    pub fn message(&self) -> String {
+        match self {
+            WebhookEvent::DownloadComplete {
+                title_id,
+                game_title,
+                version,
+                path,
+                size_bytes,
+                verified,
+            } => {
+                let mut name = if game_title.is_empty() {
+                    title_id.clone()
+                } else {
+                    game_title.clone()
+                };
+                match version {
+                    Some(version) => name = format!("{} {}", name, short_version(version)),
+                    None => {
+                        let file = path.file_name().unwrap_or_default().to_string_lossy();
+                        name = format!("{} ({})", name, file);
+                    }
+                }
+                format!(
+                    "{} downloaded{}, {}",
+                    name,
+                    if *verified { " and verified" } else { "" },
+                    format_size(*size_bytes)
+                )
+            }
+            WebhookEvent::VerificationFailed {
+                title_id,
+                game_title,
+                target,
+                error,
+            } => {
+                let name = if !game_title.is_empty() {
+                    format!("{}: ", game_title)
+                } else if let Some(title_id) = title_id {
+                    format!("{}: ", title_id)
+                } else {
+                    String::new()
+                };
+                let file = target.rsplit(['/', '\\']).next().unwrap_or(target);
+                format!("{}{} failed verification ({})", name, file, error)
+            }
+            WebhookEvent::UpdateDiscovered {
+                title_id,
+                game_title,
+                package,
+            } => format!(
+                "{} {} is out ({}, {})",
+                if game_title.is_empty() {
+                    title_id
+                } else {
+                    game_title
+                },
+                short_version(&package.version),
+                title_id,
+                format_size(package.size_bytes)
+            ),
+        }
+    }
+}
+
+impl From<&DiscoveredUpdate> for WebhookEvent {
+    fn from(update: &DiscoveredUpdate) -> Self {
+        WebhookEvent::UpdateDiscovered {
+            title_id: update.title_id.clone(),
+            game_title: update.game_title.clone(),
+            package: update.package.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    #[serde(flatten)]
+    event: &'a WebhookEvent,
+    timestamp: u64,
+}
+
+/// What a webhook's requests look like
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// The event itself, for scripts and home automation
+    #[default]
+    Json,
+    /// A Discord webhook message with [`WebhookEvent::message`] as content
+    Discord,
+    /// A Slack incoming-webhook message (also understood by Mattermost)
+    Slack,
+}
+
+impl WebhookFormat {
+    /// Discord and Slack for their webhook URLs, JSON for anything else
+    pub fn for_url(url: &str) -> Self {
+        let host = url
+            .split("://")
+            .nth(1)
+            .and_then(|rest| rest.split(['/', ':']).next())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if [
+            "discord.com",
+            "discordapp.com",
+            "ptb.discord.com",
+            "canary.discord.com",
+        ]
+        .contains(&host.as_str())
+        {
+            WebhookFormat::Discord
+        } else if host == "hooks.slack.com" {
+            WebhookFormat::Slack
+        } else {
+            WebhookFormat::Json
+        }
+    }
+}
+
+/// A URL that receives events as `POST`s
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// Events to send; every event when empty
+    #[serde(default)]
+    pub events: Vec<WebhookEventKind>,
+    #[serde(default)]
+    pub format: WebhookFormat,
+}
+
+impl Webhook {
+    /// A hook whose format is picked from its URL (see [`WebhookFormat::for_url`])
+    pub fn new(url: impl Into<String>) -> Self {
+        let url = url.into();
+        Self {
+            format: WebhookFormat::for_url(&url),
+            url,
+            events: vec![],
+        }
+    }
+
+    pub fn with_format(mut self, format: WebhookFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Only send these events
+    pub fn with_events(mut self, events: impl IntoIterator<Item = WebhookEventKind>) -> Self {
+        self.events = events.into_iter().collect();
+        self
+    }
+
+    pub fn wants(&self, kind: WebhookEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+}
+
+/// Posts [`WebhookEvent`]s to a set of [`Webhook`]s
+///
+/// Failures are returned rather than retried, so a dead endpoint never holds
+/// up downloads.
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    hooks: Vec<Webhook>,
+}
+
+impl WebhookNotifier {
+    pub fn new(hooks: Vec<Webhook>) -> Result<Self> {
+        Self::with_client_options(hooks, &ClientOptions::default())
+    }
+
+    /// Post with tuned connection settings, e.g. a User-Agent or proxy headers
+    pub fn with_client_options(hooks: Vec<Webhook>, options: &ClientOptions) -> Result<Self> {
+        // Unlike Sony's servers, webhook endpoints have valid certificates
+        let client = client_builder(options)?
+            .danger_accept_invalid_certs(false)
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        Ok(Self { client, hooks })
+    }
+
+    pub fn hooks(&self) -> &[Webhook] {
+        &self.hooks
+    }
+
+    /// Send `event` to every hook that wants it, returning `(url, error)` for
+    /// each that failed
+    pub async fn notify(&self, event: &WebhookEvent) -> Vec<(String, PS3UpdateError)> {
+        let payload = Payload {
+            event,
+            timestamp: unix_timestamp(),
+        };
+        let mut failures = vec![];
+        for hook in self.hooks.iter().filter(|h| h.wants(event.kind())) {
+            let body = match hook.format {
+                WebhookFormat::Json => serde_json::to_value(&payload).unwrap_or_default(),
+                WebhookFormat::Discord => serde_json::json!({
+                    "username": "ps3-update",
+                    "content": event.message(),
+                }),
+                WebhookFormat::Slack => serde_json::json!({ "text": event.message() }),
+            };
+            if let Err(e) = self.post(&hook.url, &body).await {
+                failures.push((hook.url.clone(), e));
+            }
+        }
+        failures
+    }
+
+    async fn post(&self, url: &str, body: &serde_json::Value) -> Result<()> {
+        self.client
+            .post(url)
+            .json(body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    /// Accept one request, answer `status` and hand back the request body
+    fn one_shot_server(status: u16) -> (String, std::thread::JoinHandle<String>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buf = [0; 4096];
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text[..end]
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length: ")
+                                .map(|v| v.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        let body = String::from_utf8_lossy(&request[end + 4..]).into_owned();
+                        write!(stream, "HTTP/1.1 {} X\r\ncontent-length: 0\r\n\r\n", status)
+                            .unwrap();
+                        return body;
+                    }
+                }
+            }
+        });
+        (url, handle)
+    }
+
+    fn discovered() -> WebhookEvent {
+        WebhookEvent::UpdateDiscovered {
+            title_id: "BLES00779".into(),
+            game_title: "Demon's Souls".into(),
+            package: PackageInfo {
+                version: "01.04".into(),
+                system_ver: "03.4100".into(),
+                size_bytes: 1024,
+                size_human: "1.00 KB".into(),
+                url: "http://example/a.pkg".into(),
+                sha1: "aa".into(),
+                filename: "a.pkg".into(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_posts_events() {
+        let (url, server) = one_shot_server(204);
+        let notifier = WebhookNotifier::new(vec![
+            Webhook::new(&url),
+            // Filtered out, so never contacted
+            Webhook::new("http://127.0.0.1:9/").with_events([WebhookEventKind::DownloadComplete]),
+        ])
+        .unwrap();
+        assert!(notifier.notify(&discovered()).await.is_empty());
+
+        let body: serde_json::Value = serde_json::from_str(&server.join().unwrap()).unwrap();
+        assert_eq!(body["event"], "update_discovered");
+        assert_eq!(body["title_id"], "BLES00779");
+        assert_eq!(body["package"]["version"], "01.04");
+        assert!(body["timestamp"].as_u64().unwrap() > 0);
+
+        let (url, server) = one_shot_server(500);
+        let failures = WebhookNotifier::new(vec![Webhook::new(&url)])
+            .unwrap()
+            .notify(&discovered())
+            .await;
+        server.join().unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, url);
+    }
+
+    #[tokio::test]
+    async fn test_chat_formats() {
+        assert_eq!(
+            discovered().message(),
+            "Demon's Souls 1.04 is out (BLES00779, 1.00 KB)"
+        );
+        let failed = WebhookEvent::VerificationFailed {
+            title_id: Some("BCUS98174".into()),
+            game_title: String::new(),
+            target: "/srv/ps3/EP9000-BCUS98174_00-A0104.pkg".into(),
+            error: "Invalid PKG file: bad magic".into(),
+        };
+        assert_eq!(
+            failed.message(),
+            "BCUS98174: EP9000-BCUS98174_00-A0104.pkg failed verification (Invalid PKG file: bad magic)"
+        );
+
+        assert_eq!(
+            WebhookFormat::for_url("https://discord.com/api/webhooks/1/abc"),
+            WebhookFormat::Discord
+        );
+        assert_eq!(
+            WebhookFormat::for_url("https://hooks.slack.com/services/T0/B0/x"),
+            WebhookFormat::Slack
+        );
+        assert_eq!(
+            WebhookFormat::for_url("http://discord.com.example.org/hook"),
+            WebhookFormat::Json
+        );
+
+        let (url, server) = one_shot_server(204);
+        let notifier =
+            WebhookNotifier::new(vec![Webhook::new(&url).with_format(WebhookFormat::Slack)])
+                .unwrap();
+        assert!(notifier.notify(&failed).await.is_empty());
+        let body: serde_json::Value = serde_json::from_str(&server.join().unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!({ "text": failed.message() }));
+    }
+
+    #[test]
+    fn test_download_complete() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("EP0001-BLES00779_00-PATCH-A0104-V0100-PE.pkg");
+        std::fs::write(&path, [0u8; 10]).unwrap();
+
+        let event = WebhookEvent::download_complete("BLES00779", "Demon's Souls", &path, true);
+        assert_eq!(event.kind(), WebhookEventKind::DownloadComplete);
+        match event {
+            WebhookEvent::DownloadComplete {
+                version,
+                size_bytes,
+                ..
+            } => {
+                assert_eq!(version.as_deref(), Some("01.04"));
+                assert_eq!(size_bytes, 10);
+            }
+            _ => unreachable!(),
+        }
+        let event = WebhookEvent::download_complete("BLES00779", "Demon's Souls", &path, true);
+        assert_eq!(
+            event.message(),
+            "Demon's Souls 1.04 downloaded and verified, 10.00 B"
+        );
+    }
+}
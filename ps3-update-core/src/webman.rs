@@ -0,0 +1,94 @@
+use crate::client::{client_builder, ClientOptions};
+use crate::types::{PS3UpdateError, Result};
+use std::time::Duration;
+
+/// Client for the HTTP interface of a console running webMAN MOD
+#[derive(Debug, Clone)]
+pub struct WebMan {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl WebMan {
+    /// Connect to webMAN on `host` (an IP or `host:port`)
+    pub fn new(host: &str) -> Result<Self> {
+        let client = client_builder(&ClientOptions::default())?
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        let host = host.trim_end_matches('/');
+        let base_url = if host.contains("://") {
+            host.to_string()
+        } else {
+            format!("http://{}", host)
+        };
+        Ok(Self { client, base_url })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// URL that asks webMAN to install a package already on the console
+    pub fn install_url(&self, remote_path: &str) -> String {
+        let path = remote_path
+            .split('/')
+            .map(|part| {
+                part.bytes()
+                    .map(|b| match b {
+                        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                            (b as char).to_string()
+                        }
+                        _ => format!("%{:02X}", b),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        format!("{}/install.ps3{}", self.base_url, path)
+    }
+
+    /// Queue a package on the console's drive (e.g. `/dev_hdd0/packages/x.pkg`) for installation
+    pub async fn install(&self, remote_path: &str) -> Result<()> {
+        let response = self
+            .client
+            .get(self.install_url(remote_path))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(PS3UpdateError::Download(format!(
+                "webMAN install of {} failed: HTTP {}",
+                remote_path,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check that webMAN answers at all
+    pub async fn is_reachable(&self) -> bool {
+        self.client
+            .get(format!("{}/cpursx.ps3", self.base_url))
+            .send()
+            .await
+            .map(|r| r.status().is_success())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_url() {
+        let webman = WebMan::new("192.168.1.20").unwrap();
+        assert_eq!(
+            webman.install_url("/dev_hdd0/packages/Game Update.pkg"),
+            "http://192.168.1.20/install.ps3/dev_hdd0/packages/Game%20Update.pkg"
+        );
+        assert_eq!(
+            WebMan::new("http://ps3:8080/").unwrap().base_url(),
+            "http://ps3:8080"
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! UniFFI bindings for `ps3-update-core`
+//!
+//! Exposes [`UpdateFetcher`] and [`DownloadManager`] to Kotlin, Swift and the
+//! other languages UniFFI generates code for. The types here mirror the core
+//! ones with plain fields so they cross the FFI boundary as records.
+
+use ps3_update_core::PS3UpdateError;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+uniffi::setup_scaffolding!();
+
+/// Errors seen by foreign code; each carries the core error's message
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum UpdateError {
+    #[error("{0}")]
+    NoUpdatesFound(String),
+    #[error("{0}")]
+    InvalidTitleId(String),
+    #[error("{0}")]
+    Network(String),
+    #[error("{0}")]
+    Verification(String),
+    #[error("{0}")]
+    JobNotFound(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<PS3UpdateError> for UpdateError {
+    fn from(e: PS3UpdateError) -> Self {
+        let message = e.to_string();
+        match e {
+            PS3UpdateError::NoUpdatesFound { .. } => UpdateError::NoUpdatesFound(message),
+            PS3UpdateError::InvalidTitleId(_) => UpdateError::InvalidTitleId(message),
+            PS3UpdateError::Network(_) | PS3UpdateError::NotFound(_) => {
+                UpdateError::Network(message)
+            }
+            PS3UpdateError::Verification(_) | PS3UpdateError::PackageMismatch(_) => {
+                UpdateError::Verification(message)
+            }
+            PS3UpdateError::JobNotFound(_) => UpdateError::JobNotFound(message),
+            _ => UpdateError::Other(message),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, UpdateError>;
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct PackageInfo {
+    pub version: String,
+    pub system_ver: String,
+    pub size_bytes: u64,
+    pub size_human: String,
+    pub url: String,
+    pub sha1: String,
+    pub filename: String,
+}
+
+impl From<ps3_update_core::PackageInfo> for PackageInfo {
+    fn from(pkg: ps3_update_core::PackageInfo) -> Self {
+        PackageInfo {
+            version: pkg.version,
+            system_ver: pkg.system_ver,
+            size_bytes: pkg.size_bytes,
+            size_human: pkg.size_human,
+            url: pkg.url,
+            sha1: pkg.sha1,
+            filename: pkg.filename,
+        }
+    }
+}
+
+impl From<PackageInfo> for ps3_update_core::PackageInfo {
+    fn from(pkg: PackageInfo) -> Self {
+        ps3_update_core::PackageInfo {
+            version: pkg.version,
+            system_ver: pkg.system_ver,
+            size_bytes: pkg.size_bytes,
+            size_human: pkg.size_human,
+            url: pkg.url,
+            sha1: pkg.sha1,
+            filename: pkg.filename,
+        }
+    }
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FetchResult {
+    pub results: Vec<PackageInfo>,
+    pub error: Option<String>,
+    pub game_title: String,
+    pub cleaned_title_id: String,
+}
+
+impl From<ps3_update_core::FetchResult> for FetchResult {
+    fn from(result: ps3_update_core::FetchResult) -> Self {
+        FetchResult {
+            results: result.results.into_iter().map(|p| p.into()).collect(),
+            error: result.error,
+            game_title: result.game_title,
+            cleaned_title_id: result.cleaned_title_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ProgressInfo {
+    pub filename: Option<String>,
+    pub total: u64,
+    pub downloaded: u64,
+    pub percent: f64,
+    pub speed_bytes_per_sec: f64,
+    pub speed_human: String,
+    pub done: bool,
+    pub error: Option<String>,
+    pub source: Option<String>,
+}
+
+impl From<ps3_update_core::ProgressInfo> for ProgressInfo {
+    fn from(progress: ps3_update_core::ProgressInfo) -> Self {
+        ProgressInfo {
+            filename: progress.filename,
+            total: progress.total,
+            downloaded: progress.downloaded,
+            percent: progress.percent,
+            speed_bytes_per_sec: progress.speed_bytes_per_sec,
+            speed_human: progress.speed_human,
+            done: progress.done,
+            error: progress.error,
+            source: progress.source,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum DownloadMode {
+    Direct,
+    MultiPart { num_parts: u32 },
+}
+
+impl From<DownloadMode> for ps3_update_core::DownloadMode {
+    fn from(mode: DownloadMode) -> Self {
+        match mode {
+            DownloadMode::Direct => ps3_update_core::DownloadMode::Direct,
+            DownloadMode::MultiPart { num_parts } => ps3_update_core::DownloadMode::MultiPart {
+                num_parts: num_parts as usize,
+            },
+        }
+    }
+}
+
+/// A package to record in the destination folder's `manifest.json`
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ManifestEntry {
+    pub title_id: String,
+    pub game_name: String,
+    pub package: PackageInfo,
+}
+
+/// Per-download settings; see `ps3_update_core::DownloadOptions`
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct DownloadOptions {
+    pub mode: DownloadMode,
+    /// Fail the download if the PKG belongs to another title
+    #[uniffi(default = None)]
+    pub expected_title_id: Option<String>,
+    /// Record the download in `manifest.json`; its SHA1 is checked by `verify`
+    #[uniffi(default = None)]
+    pub manifest: Option<ManifestEntry>,
+    #[uniffi(default = false)]
+    pub write_sha1_sidecar: bool,
+    /// Bytes per second, or `None` for no limit
+    #[uniffi(default = None)]
+    pub rate_limit: Option<u64>,
+    /// Continue a partial file instead of starting over
+    #[uniffi(default = false)]
+    pub resume: bool,
+    #[uniffi(default = 0)]
+    pub retries: u32,
+    /// Check the finished PKG against its digest block
+    #[uniffi(default = false)]
+    pub verify: bool,
+    /// Keep an existing destination file of the right size
+    #[uniffi(default = false)]
+    pub skip_existing: bool,
+}
+
+impl From<DownloadOptions> for ps3_update_core::DownloadOptions {
+    fn from(options: DownloadOptions) -> Self {
+        let mut core = ps3_update_core::DownloadOptions::new(options.mode.into())
+            .with_retries(options.retries);
+        if let Some(title_id) = options.expected_title_id {
+            core = core.expect_title(title_id);
+        }
+        if let Some(entry) = options.manifest {
+            core = core.with_manifest(entry.title_id, entry.game_name, entry.package.into());
+        }
+        if options.write_sha1_sidecar {
+            core = core.with_sha1_sidecar();
+        }
+        if let Some(rate) = options.rate_limit {
+            core = core.with_rate_limit(rate);
+        }
+        if options.resume {
+            core = core.with_resume();
+        }
+        if options.verify {
+            core = core.with_verification();
+        }
+        if options.skip_existing {
+            core = core.with_skip_existing();
+        }
+        core
+    }
+}
+
+/// Fetches update lists from Sony's servers
+#[derive(uniffi::Object)]
+pub struct UpdateFetcher {
+    inner: ps3_update_core::UpdateFetcher,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl UpdateFetcher {
+    #[uniffi::constructor]
+    pub fn new() -> Result<Arc<Self>> {
+        Ok(Arc::new(Self {
+            inner: ps3_update_core::UpdateFetcher::new()?,
+        }))
+    }
+
+    /// List the updates available for a title ID such as `BLES00779`
+    pub async fn fetch_updates(&self, title_id: String) -> Result<FetchResult> {
+        Ok(self.inner.fetch_updates(&title_id).await?.into())
+    }
+
+    /// Game name from the offline title database, if known
+    pub fn title_name(&self, title_id: String) -> Option<String> {
+        self.inner.title_name(&title_id)
+    }
+}
+
+/// Runs downloads in the background and tracks them by job ID
+#[derive(uniffi::Object)]
+pub struct DownloadManager {
+    inner: ps3_update_core::DownloadManager,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl DownloadManager {
+    #[uniffi::constructor]
+    pub fn new() -> Result<Arc<Self>> {
+        Ok(Arc::new(Self {
+            inner: ps3_update_core::DownloadManager::new()?,
+        }))
+    }
+
+    /// Start downloading `url` to `dest_path` and return the job ID
+    pub async fn start_download(
+        &self,
+        url: String,
+        dest_path: String,
+        mode: DownloadMode,
+    ) -> Result<String> {
+        Ok(self
+            .inner
+            .start_download(&url, PathBuf::from(dest_path), mode.into())
+            .await?)
+    }
+
+    /// Like `start_download`, with rate limiting, retries and verification
+    pub async fn start_download_with(
+        &self,
+        url: String,
+        dest_path: String,
+        options: DownloadOptions,
+    ) -> Result<String> {
+        Ok(self
+            .inner
+            .start_download_with(&url, PathBuf::from(dest_path), options.into())
+            .await?)
+    }
+
+    pub fn get_progress(&self, job_id: String) -> Result<ProgressInfo> {
+        Ok(self.inner.get_progress(&job_id)?.into())
+    }
+
+    /// Wait until a job is done and return its final progress
+    pub async fn wait_for(&self, job_id: String) -> Result<ProgressInfo> {
+        Ok(self.inner.wait_for(&job_id).await?.into())
+    }
+
+    /// Stop tracking a job, cancelling it if it's still running
+    pub fn remove_job(&self, job_id: String) {
+        self.inner.remove_job(&job_id);
+    }
+}
+
+/// Normalize a title ID (`bles-00779` → `BLES00779`)
+#[uniffi::export]
+pub fn clean_title_id(title_id: String) -> String {
+    ps3_update_core::clean_title_id(&title_id)
+}
+
+/// Human-readable size, e.g. `1.5 GB`
+#[uniffi::export]
+pub fn format_size(bytes: u64) -> String {
+    ps3_update_core::format_size(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_mapping() {
+        let e = UpdateError::from(PS3UpdateError::NoUpdatesFound {
+            title_id: "BLES00779".into(),
+            suggestions: vec![],
+        });
+        assert!(matches!(e, UpdateError::NoUpdatesFound(_)));
+        assert_eq!(e.to_string(), "No updates found for title ID: BLES00779");
+        assert!(matches!(
+            UpdateError::from(PS3UpdateError::Verification("bad".into())),
+            UpdateError::Verification(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_missing_job() {
+        let manager = DownloadManager::new().unwrap();
+        assert!(matches!(
+            manager.get_progress("nope".into()),
+            Err(UpdateError::JobNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,254 @@
+//! Node.js bindings for `ps3-update-core`
+//!
+//! Exposes [`UpdateFetcher`] and [`DownloadManager`] with Promise-based
+//! methods, for Node and Electron apps. Field names are camelCased by
+//! napi-rs; sizes are plain numbers, which is exact up to 8 PB.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use ps3_update_core::PS3UpdateError;
+use std::path::PathBuf;
+
+/// Errors reach JS with the kind of failure as a message prefix, e.g.
+/// `NETWORK: Network error: ...`. napi-rs only supports custom error codes on
+/// synchronous methods, so the prefix is used everywhere for consistency.
+fn to_napi_err(e: PS3UpdateError) -> Error {
+    let code = match &e {
+        PS3UpdateError::NoUpdatesFound { .. } => "NO_UPDATES_FOUND",
+        PS3UpdateError::InvalidTitleId(_) => "INVALID_TITLE_ID",
+        PS3UpdateError::Network(_) | PS3UpdateError::NotFound(_) => "NETWORK",
+        PS3UpdateError::Verification(_)
+        | PS3UpdateError::PackageMismatch(_)
+        | PS3UpdateError::InvalidPkg(_) => "VERIFICATION",
+        PS3UpdateError::JobNotFound(_) => "JOB_NOT_FOUND",
+        _ => "UPDATE_ERROR",
+    };
+    Error::new(Status::GenericFailure, format!("{}: {}", code, e))
+}
+
+#[napi(object)]
+pub struct PackageInfo {
+    pub version: String,
+    pub system_ver: String,
+    pub size_bytes: f64,
+    pub size_human: String,
+    pub url: String,
+    pub sha1: String,
+    pub filename: String,
+}
+
+impl From<ps3_update_core::PackageInfo> for PackageInfo {
+    fn from(pkg: ps3_update_core::PackageInfo) -> Self {
+        PackageInfo {
+            version: pkg.version,
+            system_ver: pkg.system_ver,
+            size_bytes: pkg.size_bytes as f64,
+            size_human: pkg.size_human,
+            url: pkg.url,
+            sha1: pkg.sha1,
+            filename: pkg.filename,
+        }
+    }
+}
+
+#[napi(object)]
+pub struct FetchResult {
+    pub results: Vec<PackageInfo>,
+    pub error: Option<String>,
+    pub game_title: String,
+    pub cleaned_title_id: String,
+}
+
+impl From<ps3_update_core::FetchResult> for FetchResult {
+    fn from(result: ps3_update_core::FetchResult) -> Self {
+        FetchResult {
+            results: result.results.into_iter().map(|p| p.into()).collect(),
+            error: result.error,
+            game_title: result.game_title,
+            cleaned_title_id: result.cleaned_title_id,
+        }
+    }
+}
+
+#[napi(object)]
+pub struct ProgressInfo {
+    pub filename: Option<String>,
+    pub total: f64,
+    pub downloaded: f64,
+    pub percent: f64,
+    pub speed_bytes_per_sec: f64,
+    pub speed_human: String,
+    pub done: bool,
+    pub error: Option<String>,
+    pub source: Option<String>,
+}
+
+impl From<ps3_update_core::ProgressInfo> for ProgressInfo {
+    fn from(progress: ps3_update_core::ProgressInfo) -> Self {
+        ProgressInfo {
+            filename: progress.filename,
+            total: progress.total as f64,
+            downloaded: progress.downloaded as f64,
+            percent: progress.percent,
+            speed_bytes_per_sec: progress.speed_bytes_per_sec,
+            speed_human: progress.speed_human,
+            done: progress.done,
+            error: progress.error,
+            source: progress.source,
+        }
+    }
+}
+
+/// Per-download settings; every field is optional
+#[napi(object)]
+#[derive(Default)]
+pub struct DownloadOptions {
+    /// Connections per download; more than 1 uses multi-part mode
+    pub parts: Option<u32>,
+    /// Fail the download if the PKG belongs to another title
+    pub expected_title_id: Option<String>,
+    /// Bytes per second
+    pub rate_limit: Option<f64>,
+    /// Continue a partial file instead of starting over
+    pub resume: Option<bool>,
+    pub retries: Option<u32>,
+    /// Check the finished PKG against its digest block
+    pub verify: Option<bool>,
+    /// Keep an existing destination file of the right size
+    pub skip_existing: Option<bool>,
+    pub write_sha1_sidecar: Option<bool>,
+}
+
+impl From<DownloadOptions> for ps3_update_core::DownloadOptions {
+    fn from(options: DownloadOptions) -> Self {
+        let mode = match options.parts {
+            Some(parts) if parts > 1 => ps3_update_core::DownloadMode::MultiPart {
+                num_parts: parts as usize,
+            },
+            _ => ps3_update_core::DownloadMode::Direct,
+        };
+        let mut core = ps3_update_core::DownloadOptions::new(mode)
+            .with_retries(options.retries.unwrap_or_default());
+        if let Some(title_id) = options.expected_title_id {
+            core = core.expect_title(title_id);
+        }
+        if let Some(rate) = options.rate_limit {
+            core = core.with_rate_limit(rate as u64);
+        }
+        if options.resume.unwrap_or_default() {
+            core = core.with_resume();
+        }
+        if options.verify.unwrap_or_default() {
+            core = core.with_verification();
+        }
+        if options.skip_existing.unwrap_or_default() {
+            core = core.with_skip_existing();
+        }
+        if options.write_sha1_sidecar.unwrap_or_default() {
+            core = core.with_sha1_sidecar();
+        }
+        core
+    }
+}
+
+/// Fetches update lists from Sony's servers
+#[napi]
+pub struct UpdateFetcher {
+    inner: ps3_update_core::UpdateFetcher,
+}
+
+#[napi]
+impl UpdateFetcher {
+    #[napi(constructor)]
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            inner: ps3_update_core::UpdateFetcher::new().map_err(to_napi_err)?,
+        })
+    }
+
+    /// List the updates available for a title ID such as `BLES00779`
+    #[napi]
+    pub async fn fetch_updates(&self, title_id: String) -> Result<FetchResult> {
+        self.inner
+            .fetch_updates(&title_id)
+            .await
+            .map(FetchResult::from)
+            .map_err(to_napi_err)
+    }
+
+    /// Game name from the offline title database, if known
+    #[napi]
+    pub fn title_name(&self, title_id: String) -> Option<String> {
+        self.inner.title_name(&title_id)
+    }
+}
+
+/// Runs downloads in the background and tracks them by job ID
+#[napi]
+pub struct DownloadManager {
+    inner: ps3_update_core::DownloadManager,
+}
+
+#[napi]
+impl DownloadManager {
+    #[napi(constructor)]
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            inner: ps3_update_core::DownloadManager::new().map_err(to_napi_err)?,
+        })
+    }
+
+    /// Start downloading `url` to `destPath` and resolve with the job ID
+    #[napi]
+    pub async fn start_download(
+        &self,
+        url: String,
+        dest_path: String,
+        options: Option<DownloadOptions>,
+    ) -> Result<String> {
+        self.inner
+            .start_download_with(
+                &url,
+                PathBuf::from(dest_path),
+                options.unwrap_or_default().into(),
+            )
+            .await
+            .map_err(to_napi_err)
+    }
+
+    #[napi]
+    pub fn get_progress(&self, job_id: String) -> Result<ProgressInfo> {
+        self.inner
+            .get_progress(&job_id)
+            .map(ProgressInfo::from)
+            .map_err(to_napi_err)
+    }
+
+    /// Resolve with the final progress once a job is done
+    #[napi]
+    pub async fn wait_for(&self, job_id: String) -> Result<ProgressInfo> {
+        self.inner
+            .wait_for(&job_id)
+            .await
+            .map(ProgressInfo::from)
+            .map_err(to_napi_err)
+    }
+
+    /// Stop tracking a job, cancelling it if it's still running
+    #[napi]
+    pub fn remove_job(&self, job_id: String) {
+        self.inner.remove_job(&job_id);
+    }
+}
+
+/// Normalize a title ID (`bles-00779` → `BLES00779`)
+#[napi]
+pub fn clean_title_id(title_id: String) -> String {
+    ps3_update_core::clean_title_id(&title_id)
+}
+
+/// Human-readable size, e.g. `1.5 GB`
+#[napi]
+pub fn format_size(bytes: f64) -> String {
+    ps3_update_core::format_size(bytes as u64)
+}
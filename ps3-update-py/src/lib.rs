@@ -0,0 +1,401 @@
+//! The `ps3_update` Python module
+//!
+//! A blocking API over `ps3-update-core` for scripts: fetch update lists,
+//! download packages with a progress callback, and verify PKGs. Network work
+//! runs on an internal Tokio runtime with the GIL released.
+
+use ps3_update_core::{DownloadManager, DownloadMode, DownloadOptions, PS3UpdateError};
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+create_exception!(
+    ps3_update,
+    UpdateError,
+    PyException,
+    "Base class for ps3_update errors"
+);
+create_exception!(
+    ps3_update,
+    NoUpdatesFound,
+    UpdateError,
+    "The title has no updates"
+);
+create_exception!(
+    ps3_update,
+    NetworkError,
+    UpdateError,
+    "The server couldn't be reached"
+);
+create_exception!(
+    ps3_update,
+    VerificationError,
+    UpdateError,
+    "A package failed its checks"
+);
+
+fn to_py_err(e: PS3UpdateError) -> PyErr {
+    error_from_code(e.to_string(), Some(e.code()))
+}
+
+/// Pick the exception for an error by its `PS3UpdateError::code`
+///
+/// Errors from download jobs arrive as a message and code rather than the
+/// error itself.
+fn error_from_code(message: String, code: Option<&str>) -> PyErr {
+    match code {
+        Some("no_updates_found") => NoUpdatesFound::new_err(message),
+        Some("network" | "not_found") => NetworkError::new_err(message),
+        Some("verification" | "package_mismatch" | "invalid_pkg") => {
+            VerificationError::new_err(message)
+        }
+        _ => UpdateError::new_err(message),
+    }
+}
+
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start the Tokio runtime")
+    })
+}
+
+/// One update package
+#[pyclass(get_all, frozen, skip_from_py_object)]
+#[derive(Clone)]
+pub struct Package {
+    version: String,
+    system_ver: String,
+    size_bytes: u64,
+    size_human: String,
+    url: String,
+    sha1: String,
+    filename: String,
+}
+
+#[pymethods]
+impl Package {
+    fn __repr__(&self) -> String {
+        format!(
+            "Package(version={:?}, filename={:?})",
+            self.version, self.filename
+        )
+    }
+}
+
+impl From<ps3_update_core::PackageInfo> for Package {
+    fn from(pkg: ps3_update_core::PackageInfo) -> Self {
+        Package {
+            version: pkg.version,
+            system_ver: pkg.system_ver,
+            size_bytes: pkg.size_bytes,
+            size_human: pkg.size_human,
+            url: pkg.url,
+            sha1: pkg.sha1,
+            filename: pkg.filename,
+        }
+    }
+}
+
+/// Updates listed for a title, newest first
+#[pyclass(get_all, frozen)]
+pub struct FetchResult {
+    title_id: String,
+    game_title: String,
+    packages: Vec<Package>,
+}
+
+#[pymethods]
+impl FetchResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "FetchResult(title_id={:?}, game_title={:?}, packages={})",
+            self.title_id,
+            self.game_title,
+            self.packages.len()
+        )
+    }
+}
+
+impl From<ps3_update_core::FetchResult> for FetchResult {
+    fn from(result: ps3_update_core::FetchResult) -> Self {
+        FetchResult {
+            title_id: result.cleaned_title_id,
+            game_title: result.game_title,
+            packages: result.results.into_iter().map(Package::from).collect(),
+        }
+    }
+}
+
+/// Progress of a download, passed to the `progress` callback
+#[pyclass(get_all, frozen, skip_from_py_object)]
+#[derive(Clone)]
+pub struct Progress {
+    filename: Option<String>,
+    total: u64,
+    downloaded: u64,
+    percent: f64,
+    speed_bytes_per_sec: f64,
+    speed_human: String,
+    done: bool,
+    error: Option<String>,
+    /// ``PS3UpdateError`` code of ``error``, such as ``"verification"``
+    error_code: Option<String>,
+}
+
+#[pymethods]
+impl Progress {
+    fn __repr__(&self) -> String {
+        format!(
+            "Progress(downloaded={}, total={}, done={})",
+            self.downloaded,
+            self.total,
+            if self.done { "True" } else { "False" }
+        )
+    }
+}
+
+impl From<ps3_update_core::ProgressInfo> for Progress {
+    fn from(progress: ps3_update_core::ProgressInfo) -> Self {
+        Progress {
+            filename: progress.filename,
+            total: progress.total,
+            downloaded: progress.downloaded,
+            percent: progress.percent,
+            speed_bytes_per_sec: progress.speed_bytes_per_sec,
+            speed_human: progress.speed_human,
+            done: progress.done,
+            error: progress.error,
+            error_code: progress.error_code,
+        }
+    }
+}
+
+/// Result of checking a PKG against its own digest block
+#[pyclass(get_all, frozen)]
+pub struct Verification {
+    content_id: String,
+    size_ok: bool,
+    stored_digest: String,
+    computed_digest: String,
+    file_sha1: String,
+    /// Intact and matching `sha1`, when one was given
+    valid: bool,
+}
+
+#[pymethods]
+impl Verification {
+    fn __repr__(&self) -> String {
+        format!(
+            "Verification(content_id={:?}, valid={})",
+            self.content_id,
+            if self.valid { "True" } else { "False" }
+        )
+    }
+
+    fn __bool__(&self) -> bool {
+        self.valid
+    }
+}
+
+/// List the updates for a title ID such as ``"BLES00779"``
+///
+/// Raises ``NoUpdatesFound`` when the title has none.
+#[pyfunction]
+fn fetch_updates(py: Python<'_>, title_id: &str) -> PyResult<FetchResult> {
+    py.detach(|| {
+        runtime().block_on(async {
+            let fetcher = ps3_update_core::UpdateFetcher::new()?;
+            fetcher.fetch_updates(title_id).await
+        })
+    })
+    .map(FetchResult::from)
+    .map_err(to_py_err)
+}
+
+/// Download ``url`` to ``dest`` and return the final ``Progress``
+///
+/// ``progress`` is called with a ``Progress`` about four times a second.
+/// ``parts`` > 1 uses that many connections. With ``verify`` the finished PKG
+/// is checked against its digest block and ``sha1``; ``title_id`` makes the
+/// download fail if the PKG belongs to another game. Failures raise
+/// ``UpdateError`` (or a subclass) after the file is closed.
+#[pyfunction]
+#[pyo3(signature = (
+    url,
+    dest,
+    *,
+    progress = None,
+    parts = 1,
+    rate_limit = None,
+    resume = false,
+    retries = 0,
+    verify = false,
+    sha1 = None,
+    title_id = None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn download(
+    py: Python<'_>,
+    url: &str,
+    dest: PathBuf,
+    progress: Option<Py<PyAny>>,
+    parts: usize,
+    rate_limit: Option<u64>,
+    resume: bool,
+    retries: u32,
+    verify: bool,
+    sha1: Option<String>,
+    title_id: Option<String>,
+) -> PyResult<Progress> {
+    let mode = if parts > 1 {
+        DownloadMode::MultiPart { num_parts: parts }
+    } else {
+        DownloadMode::Direct
+    };
+    let mut options = DownloadOptions::new(mode).with_retries(retries);
+    if let Some(rate) = rate_limit {
+        options = options.with_rate_limit(rate);
+    }
+    if resume {
+        options = options.with_resume();
+    }
+    if let Some(title_id) = &title_id {
+        options = options.expect_title(title_id.as_str());
+    }
+    if verify {
+        options = options.with_verification();
+    }
+
+    let (manager, job_id) = py
+        .detach(|| {
+            runtime().block_on(async {
+                let manager = DownloadManager::new()?;
+                let job_id = manager
+                    .start_download_with(url, dest.clone(), options)
+                    .await?;
+                Ok::<_, PS3UpdateError>((manager, job_id))
+            })
+        })
+        .map_err(to_py_err)?;
+
+    let last = loop {
+        let info = manager.get_progress(&job_id).map_err(to_py_err)?;
+        let done = info.done;
+        let current = Progress::from(info);
+        if let Some(callback) = &progress {
+            callback.call1(py, (current.clone(),))?;
+        }
+        if done {
+            break current;
+        }
+        // Lets Ctrl-C interrupt a long download
+        py.check_signals()?;
+        py.detach(|| std::thread::sleep(Duration::from_millis(250)));
+    };
+    manager.remove_job(&job_id);
+
+    if let Some(e) = &last.error {
+        return Err(error_from_code(e.clone(), last.error_code.as_deref()));
+    }
+    if let Some(expected) = sha1.filter(|_| verify) {
+        let check = ps3_update_core::verify_pkg(&dest).map_err(to_py_err)?;
+        if !check.matches_sha1(&expected) {
+            return Err(VerificationError::new_err(format!(
+                "{} does not match the listed SHA1 {}",
+                dest.display(),
+                expected
+            )));
+        }
+    }
+    Ok(last)
+}
+
+/// Check a PKG against its digest block, and against ``sha1`` if given
+///
+/// ``sha1`` may be the hash from the update list or of the whole file.
+#[pyfunction(name = "verify")]
+#[pyo3(signature = (path, sha1 = None))]
+fn verify_pkg(py: Python<'_>, path: PathBuf, sha1: Option<String>) -> PyResult<Verification> {
+    let check = py
+        .detach(|| ps3_update_core::verify_pkg(&path))
+        .map_err(to_py_err)?;
+    let valid = check.is_valid()
+        && sha1
+            .as_deref()
+            .filter(|s| !s.trim().is_empty())
+            .is_none_or(|s| check.matches_sha1(s));
+    Ok(Verification {
+        content_id: check.content_id,
+        size_ok: check.size_ok,
+        stored_digest: check.stored_digest,
+        computed_digest: check.computed_digest,
+        file_sha1: check.file_sha1,
+        valid,
+    })
+}
+
+/// Normalize a title ID (``"bles-00779"`` → ``"BLES00779"``)
+#[pyfunction]
+fn clean_title_id(title_id: &str) -> String {
+    ps3_update_core::clean_title_id(title_id)
+}
+
+/// Human-readable size, e.g. ``"1.5 GB"``
+#[pyfunction]
+fn format_size(bytes: u64) -> String {
+    ps3_update_core::format_size(bytes)
+}
+
+#[pymodule]
+fn ps3_update(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("UpdateError", py.get_type::<UpdateError>())?;
+    m.add("NoUpdatesFound", py.get_type::<NoUpdatesFound>())?;
+    m.add("NetworkError", py.get_type::<NetworkError>())?;
+    m.add("VerificationError", py.get_type::<VerificationError>())?;
+    m.add_class::<Package>()?;
+    m.add_class::<FetchResult>()?;
+    m.add_class::<Progress>()?;
+    m.add_class::<Verification>()?;
+    m.add_function(wrap_pyfunction!(fetch_updates, m)?)?;
+    m.add_function(wrap_pyfunction!(download, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_pkg, m)?)?;
+    m.add_function(wrap_pyfunction!(clean_title_id, m)?)?;
+    m.add_function(wrap_pyfunction!(format_size, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_errors() {
+        Python::initialize();
+        Python::attach(|py| {
+            let e = error_from_code(
+                "Verification failed: a.pkg is corrupt".into(),
+                Some("verification"),
+            );
+            assert!(e.is_instance_of::<VerificationError>(py));
+            assert!(e.is_instance_of::<UpdateError>(py));
+            assert!(
+                error_from_code("Network error: timed out".into(), Some("network"))
+                    .is_instance_of::<NetworkError>(py)
+            );
+            assert!(error_from_code("Cancelled".into(), None).is_instance_of::<UpdateError>(py));
+            let e = to_py_err(PS3UpdateError::NoUpdatesFound {
+                title_id: "BLES00779".into(),
+                suggestions: vec![],
+            });
+            assert!(e.is_instance_of::<NoUpdatesFound>(py));
+        });
+    }
+}
@@ -0,0 +1,93 @@
+use clap::Args;
+use ps3_update_core::{parse_size, DownloadMode, DownloadOptions};
+
+/// Download tuning flags shared by subcommands that download
+#[derive(Args, Debug, Clone)]
+pub struct DownloadArgs {
+    /// Download each package over N connections
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    parts: usize,
+
+    /// direct, auto (split large packages) or multipart:N, in place of --parts
+    #[arg(long, value_name = "MODE", conflicts_with = "parts")]
+    mode: Option<DownloadMode>,
+
+    /// Cap the download speed in bytes per second, e.g. 500K or 2M
+    #[arg(long, value_name = "RATE", value_parser = parse_rate)]
+    limit_rate: Option<u64>,
+
+    /// Continue partial files instead of starting over
+    #[arg(long)]
+    resume: bool,
+
+    /// Retry each package this many times after network errors
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    retries: u32,
+
+    /// Check each finished package's digest and listed SHA1
+    #[arg(long)]
+    verify: bool,
+
+    /// Keep files that are already complete instead of downloading them again
+    #[arg(long)]
+    skip_existing: bool,
+}
+
+impl DownloadArgs {
+    pub fn mode(&self) -> DownloadMode {
+        self.mode
+            .unwrap_or_else(|| DownloadMode::from_parts(self.parts))
+    }
+
+    /// Whether partial files are continued
+    pub fn resumes(&self) -> bool {
+        self.resume
+    }
+
+    /// Whether finished packages are checked
+    pub fn verifies(&self) -> bool {
+        self.verify
+    }
+
+    pub fn options(&self) -> DownloadOptions {
+        let mut options = DownloadOptions::new(self.mode()).with_retries(self.retries);
+        if let Some(rate) = self.limit_rate {
+            options = options.with_rate_limit(rate);
+        }
+        if self.resume {
+            options = options.with_resume();
+        }
+        if self.verify {
+            options = options.with_verification();
+        }
+        if self.skip_existing {
+            options = options.with_skip_existing();
+        }
+        options
+    }
+}
+
+/// Parse a rate like `750`, `500K`, `1.5M` or `1G` (binary multiples, like curl)
+pub fn parse_rate(s: &str) -> Result<u64, String> {
+    let rate = parse_size(s).map_err(|e| e.to_string())?;
+    if rate == 0 {
+        return Err("rate must be greater than zero".to_string());
+    }
+    Ok(rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("750"), Ok(750));
+        assert_eq!(parse_rate("500k"), Ok(500 * 1024));
+        assert_eq!(parse_rate("2M"), Ok(2 * 1024 * 1024));
+        assert_eq!(parse_rate("1.5MB"), Ok(1536 * 1024));
+        assert!(parse_rate("fast").is_err());
+        assert!(parse_rate("2X").is_err());
+        assert!(parse_rate("0").is_err());
+    }
+}
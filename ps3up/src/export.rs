@@ -0,0 +1,125 @@
+use crate::outcome::{ErrorLog, Outcome};
+use crate::output::OutputMode;
+use crate::titles::read_title_list;
+use crate::CliResult;
+use clap::{Args, ValueEnum};
+use ps3_update_core::{
+    clean_title_id, plan_fetch, ExportFormat, Failure, Library, LibrarySyncPlan, UpdateFetcher,
+};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// Titles to export (one per line, `#` comments; `-` reads stdin)
+    #[arg(short, long, value_name = "FILE")]
+    titles: Option<PathBuf>,
+
+    /// Title IDs to export, in addition to those in `--titles`
+    #[arg(value_name = "TITLE_ID")]
+    title_ids: Vec<String>,
+
+    /// Download tool (or Metalink document) to write the list for
+    #[arg(short, long, value_enum, default_value_t = Format::Aria2)]
+    format: Format,
+
+    /// Only list packages missing from this library, saved into its game
+    /// folders (defaults to every game in it when no titles are given)
+    #[arg(short, long, value_name = "DIR", conflicts_with = "dir")]
+    library: Option<PathBuf>,
+
+    /// Folder every package is saved into, without a library
+    #[arg(short, long, value_name = "DIR", default_value = ".")]
+    dir: PathBuf,
+
+    /// Write the list to FILE instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// Input file for `aria2c -i`
+    Aria2,
+    /// Shell script calling `wget -c`
+    Wget,
+    /// RFC 5854 Metalink document
+    Metalink,
+}
+
+impl From<Format> for ExportFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Aria2 => ExportFormat::Aria2,
+            Format::Wget => ExportFormat::Wget,
+            Format::Metalink => ExportFormat::Metalink,
+        }
+    }
+}
+
+pub async fn run(
+    args: ExportArgs,
+    output: OutputMode,
+    errors: &mut ErrorLog,
+) -> CliResult<Outcome> {
+    let mut title_ids: Vec<String> = args.title_ids.iter().map(|t| clean_title_id(t)).collect();
+    if let Some(path) = &args.titles {
+        title_ids.extend(read_title_list(path)?);
+    }
+    let library = match &args.library {
+        Some(dir) => Some(Library::open(dir)?),
+        None => None,
+    };
+    if title_ids.is_empty() {
+        if let Some(library) = &library {
+            title_ids = library
+                .games()
+                .iter()
+                .filter_map(|g| g.title_id.clone())
+                .collect();
+        }
+    }
+    let mut seen = HashSet::new();
+    title_ids.retain(|t| !t.is_empty() && seen.insert(t.clone()));
+    if title_ids.is_empty() {
+        return Err("no title IDs given; pass them as arguments or with --titles".into());
+    }
+
+    let fetcher = UpdateFetcher::new()?;
+    let plan = match &library {
+        Some(library) => library.sync_plan(&fetcher, &title_ids).await,
+        None => {
+            let mut plan = LibrarySyncPlan::default();
+            for title_id in &title_ids {
+                match fetcher.fetch_updates(title_id).await {
+                    Ok(result) => plan.downloads.extend(plan_fetch(&result, &args.dir)),
+                    Err(e) => plan.errors.push(Failure::new(title_id.as_str(), &e)),
+                }
+            }
+            plan
+        }
+    };
+    for failure in &plan.errors {
+        eprintln!("{}: {}", failure.target, failure.error);
+        errors.add_title(failure);
+    }
+
+    let list = ExportFormat::from(args.format).render(&plan.downloads);
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, &list)?;
+            eprintln!(
+                "wrote {} download{} to {}",
+                plan.downloads.len(),
+                if plan.downloads.len() == 1 { "" } else { "s" },
+                path.display()
+            );
+        }
+        None if output.is_human() => print!("{}", list),
+        None => {}
+    }
+    output.report(&plan);
+
+    let succeeded = title_ids.len() - plan.errors.len();
+    Ok(errors.outcome(succeeded, plan.downloads.is_empty()))
+}
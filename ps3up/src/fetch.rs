@@ -0,0 +1,396 @@
+use crate::download::DownloadArgs;
+use crate::notify::{Notifier, NotifyArgs};
+use crate::outcome::{ErrorLog, Outcome};
+use crate::output::{create_output, OutputMode};
+use crate::table::Table;
+use crate::titles::read_title_list;
+use crate::CliResult;
+use clap::Args;
+use ps3_update_core::{
+    clean_title_id, format_size, write_fetch_csv, DownloadManager, Failure, FetchResult, Library,
+    PS3UpdateError, PackageInfo, PlannedDownload, ProgressInfo, QueueItem, QueueManager,
+    QueueProgress, QueueStatus, UpdateFetcher, WebhookEvent,
+};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Args)]
+pub struct FetchArgs {
+    /// Title IDs to check, e.g. BLES00779
+    titles: Vec<String>,
+
+    /// Read title IDs from a file (one per line, `#` comments); `-` reads stdin
+    #[arg(short, long, value_name = "FILE")]
+    input: Option<PathBuf>,
+
+    /// Download every listed package into this library folder
+    #[arg(short, long, value_name = "DIR")]
+    download: Option<PathBuf>,
+
+    /// Also write every package as a CSV row to FILE (`-` for stdout)
+    #[arg(long, value_name = "FILE")]
+    csv: Option<PathBuf>,
+
+    /// Download up to N of a title's packages at once
+    ///
+    /// Packages start oldest first, but above 1 they can finish out of order,
+    /// so an interrupted run may leave a gap in the install chain.
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "download")]
+    jobs: usize,
+
+    #[command(flatten)]
+    transfer: DownloadArgs,
+
+    #[command(flatten)]
+    notify: NotifyArgs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum TitleStatus {
+    Ok,
+    NoUpdates,
+    Error,
+}
+
+/// Outcome for one title, shown in the summary table
+#[derive(Serialize)]
+struct TitleSummary {
+    title_id: String,
+    game_title: String,
+    status: TitleStatus,
+    error: Option<String>,
+    latest: Option<String>,
+    size_bytes: u64,
+    packages: Vec<PackageInfo>,
+    /// Packages on disk after `--download`, existing ones included
+    downloaded: Option<usize>,
+    failed_downloads: Vec<FailedDownload>,
+}
+
+#[derive(Serialize)]
+struct FailedDownload {
+    filename: String,
+    error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+}
+
+impl TitleSummary {
+    fn new(title_id: &str, status: TitleStatus) -> Self {
+        Self {
+            title_id: title_id.to_string(),
+            game_title: String::new(),
+            status,
+            error: None,
+            latest: None,
+            size_bytes: 0,
+            packages: vec![],
+            downloaded: None,
+            failed_downloads: vec![],
+        }
+    }
+
+    fn from_fetch(result: &FetchResult) -> Self {
+        let status = if result.results.is_empty() {
+            TitleStatus::NoUpdates
+        } else {
+            TitleStatus::Ok
+        };
+        Self {
+            game_title: result.game_title.clone(),
+            // Results come sorted newest first
+            latest: result.results.first().map(|p| p.version.clone()),
+            size_bytes: result.results.iter().map(|p| p.size_bytes).sum(),
+            packages: result.results.clone(),
+            ..Self::new(&result.cleaned_title_id, status)
+        }
+    }
+
+    fn failed(&self) -> bool {
+        self.status == TitleStatus::Error || !self.failed_downloads.is_empty()
+    }
+
+    fn status_text(&self) -> String {
+        match (self.status, self.downloaded) {
+            (TitleStatus::Ok, Some(n)) => format!("{}/{} downloaded", n, self.packages.len()),
+            (TitleStatus::Ok, None) => "ok".to_string(),
+            (TitleStatus::NoUpdates, _) => "no updates".to_string(),
+            (TitleStatus::Error, _) => "error".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct FetchReport {
+    titles: Vec<TitleSummary>,
+}
+
+#[derive(Serialize)]
+struct ProgressEvent<'a> {
+    title_id: &'a str,
+    #[serde(flatten)]
+    progress: &'a ProgressInfo,
+}
+
+pub async fn run(args: FetchArgs, output: OutputMode, errors: &mut ErrorLog) -> CliResult<Outcome> {
+    let mut titles: Vec<String> = args.titles.iter().map(|t| clean_title_id(t)).collect();
+    if let Some(input) = &args.input {
+        titles.extend(read_title_list(input)?);
+    }
+    titles.retain(|t| !t.is_empty());
+    if titles.is_empty() {
+        return Err("no title IDs given; pass them as arguments or with --input".into());
+    }
+
+    let fetcher = UpdateFetcher::new()?;
+    let notifier = args.notify.notifier()?;
+    let mut download = match &args.download {
+        Some(dir) => {
+            let queue = QueueManager::new(DownloadManager::new()?, args.transfer.options())
+                .with_concurrency(args.jobs);
+            Some((Library::open(dir)?, queue))
+        }
+        None => None,
+    };
+
+    let mut report = FetchReport { titles: vec![] };
+    let mut results = vec![];
+    for title_id in &titles {
+        let summary = match fetcher.fetch_updates(title_id).await {
+            Ok(result) => {
+                output.event("fetched", &result);
+                if output.is_human() {
+                    print_result(&result);
+                }
+                let mut summary = TitleSummary::from_fetch(&result);
+                if let Some((library, queue)) = &mut download {
+                    download_title(
+                        library,
+                        queue,
+                        &args.transfer,
+                        &result,
+                        &mut summary,
+                        &notifier,
+                        output,
+                    )
+                    .await?;
+                }
+                results.push(result);
+                summary
+            }
+            Err(PS3UpdateError::NoUpdatesFound { .. }) => {
+                results.push(empty_result(title_id, None));
+                TitleSummary::new(title_id, TitleStatus::NoUpdates)
+            }
+            Err(e) => {
+                eprintln!("{}: {}", title_id, e);
+                results.push(empty_result(title_id, Some(e.to_string())));
+                errors.add_error(Some(title_id), None, &e);
+                TitleSummary {
+                    error: Some(e.to_string()),
+                    ..TitleSummary::new(title_id, TitleStatus::Error)
+                }
+            }
+        };
+        for failed in &summary.failed_downloads {
+            errors.add(
+                Some(title_id),
+                Some(&failed.filename),
+                failed.error.as_str(),
+                failed.code.as_deref(),
+            );
+        }
+        output.event("title", &summary);
+        report.titles.push(summary);
+    }
+
+    if output.is_human() {
+        println!();
+        println!("{}", summary_table(&report.titles).render());
+    }
+    output.report(&report);
+    if let Some(path) = &args.csv {
+        write_fetch_csv(&results, create_output(path)?)?;
+    }
+
+    let succeeded = report.titles.iter().filter(|s| !s.failed()).count();
+    let nothing_new = report.titles.iter().all(|s| s.packages.is_empty());
+    Ok(errors.outcome(succeeded, nothing_new))
+}
+
+/// A title without packages, for the CSV
+fn empty_result(title_id: &str, error: Option<String>) -> FetchResult {
+    FetchResult {
+        results: vec![],
+        error,
+        game_title: String::new(),
+        cleaned_title_id: title_id.to_string(),
+    }
+}
+
+fn print_result(result: &FetchResult) {
+    println!(
+        "{} — {} ({} update{})",
+        result.cleaned_title_id,
+        result.game_title,
+        result.results.len(),
+        if result.results.len() == 1 { "" } else { "s" }
+    );
+    for pkg in &result.results {
+        println!(
+            "  v{:<6} {:>10}  requires {}  {}",
+            pkg.version, pkg.size_human, pkg.system_ver, pkg.filename
+        );
+    }
+}
+
+/// Download a title's packages into its library folder
+async fn download_title(
+    library: &mut Library,
+    queue: &QueueManager,
+    transfer: &DownloadArgs,
+    result: &FetchResult,
+    summary: &mut TitleSummary,
+    notifier: &Notifier,
+    output: OutputMode,
+) -> CliResult<()> {
+    let title_id = &result.cleaned_title_id;
+    let dir = library.game_dir(title_id, &result.game_title);
+    std::fs::create_dir_all(&dir)?;
+
+    // Oldest first, so an interrupted run leaves a usable install chain (one
+    // package at a time; with --jobs a later one can finish first)
+    let queued = queue.enqueue(
+        result
+            .results
+            .iter()
+            .rev()
+            .map(|pkg| PlannedDownload {
+                title_id: title_id.clone(),
+                game_title: result.game_title.clone(),
+                package: pkg.clone(),
+                dir: dir.clone(),
+            })
+            .collect(),
+    );
+
+    let ids: HashSet<String> = queued.into_iter().map(|item| item.id).collect();
+
+    let mut downloaded = 0;
+    let mut reported = HashSet::new();
+    loop {
+        let items: Vec<QueueItem> = queue
+            .items()
+            .into_iter()
+            .filter(|item| ids.contains(&item.id))
+            .collect();
+        let active: Vec<&ProgressInfo> = items
+            .iter()
+            .filter(|item| item.status == QueueStatus::Downloading)
+            .filter_map(|item| item.progress.as_ref())
+            .collect();
+        for progress in &active {
+            output.event("progress", &ProgressEvent { title_id, progress });
+        }
+        if output.is_human() {
+            match active.as_slice() {
+                [progress] => print!(
+                    "\r  {} {:>5.1}% {:>12}",
+                    progress.filename.as_deref().unwrap_or_default(),
+                    progress.percent,
+                    progress.speed_human
+                ),
+                [] => {}
+                _ => {
+                    let total = QueueProgress::from_items(&items);
+                    print!(
+                        "\r  {} packages {:>5.1}% {:>12}/s",
+                        active.len(),
+                        total.percent,
+                        format_size(total.speed_bytes_per_sec as u64)
+                    );
+                }
+            }
+            std::io::stdout().flush()?;
+        }
+
+        for item in &items {
+            let finished = matches!(item.status, QueueStatus::Done | QueueStatus::Failed);
+            if !finished || !reported.insert(item.id.clone()) {
+                continue;
+            }
+            let pkg = &item.download.package;
+            if let Some(progress) = item.progress.as_ref().filter(|_| output.is_human()) {
+                println!(
+                    "\r  {} {:>5.1}% {:>12}",
+                    pkg.filename, progress.percent, progress.speed_human
+                );
+            }
+            match item.status {
+                QueueStatus::Failed => {
+                    let failure = Failure {
+                        target: pkg.filename.clone(),
+                        error: item.error.clone().unwrap_or_default(),
+                        code: item.error_code.clone(),
+                    };
+                    eprintln!("  {}: {}", failure.target, failure.error);
+                    notifier
+                        .failed(title_id, &result.game_title, &failure)
+                        .await;
+                    summary.failed_downloads.push(FailedDownload {
+                        filename: failure.target,
+                        error: failure.error,
+                        code: failure.code,
+                    });
+                }
+                _ => {
+                    downloaded += 1;
+                    notifier
+                        .send(WebhookEvent::download_complete(
+                            title_id.as_str(),
+                            result.game_title.as_str(),
+                            item.download.path(),
+                            transfer.verifies(),
+                        ))
+                        .await;
+                }
+            }
+        }
+
+        if items.iter().all(|item| reported.contains(&item.id)) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    library.scan()?;
+
+    summary.downloaded = Some(downloaded);
+    Ok(())
+}
+
+fn summary_table(summaries: &[TitleSummary]) -> Table {
+    let mut table = Table::new(["Title ID", "Game", "Updates", "Latest", "Size", "Status"]);
+    for s in summaries {
+        table.push([
+            s.title_id.clone(),
+            s.game_title.clone(),
+            if s.status == TitleStatus::Error {
+                String::new()
+            } else {
+                s.packages.len().to_string()
+            },
+            s.latest.clone().unwrap_or_default(),
+            if s.size_bytes == 0 {
+                String::new()
+            } else {
+                format_size(s.size_bytes)
+            },
+            s.status_text(),
+        ]);
+    }
+    table
+}
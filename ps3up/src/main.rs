@@ -0,0 +1,95 @@
+//! `ps3up` — fetch and download PS3 game updates from the command line
+
+mod download;
+mod export;
+mod fetch;
+mod mirror;
+mod notify;
+mod outcome;
+mod output;
+mod scan;
+mod serve;
+mod sync;
+mod table;
+mod titles;
+mod verify;
+mod watch;
+
+use clap::{Parser, Subcommand};
+use outcome::ErrorLog;
+use output::OutputMode;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+pub type CliResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[command(name = "ps3up", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Print the final report as a single JSON document
+    #[arg(long, global = true, conflicts_with = "ndjson")]
+    json: bool,
+
+    /// Print newline-delimited JSON events as they happen, then the report
+    #[arg(long, global = true)]
+    ndjson: bool,
+
+    /// Write every error as a JSON array to FILE (an empty array when all went well)
+    #[arg(long, global = true, value_name = "FILE")]
+    errors_json: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check (and optionally download) updates for one or more titles
+    Fetch(fetch::FetchArgs),
+    /// Check every package in a folder against its checksums and PKG digest
+    Verify(verify::VerifyArgs),
+    /// Download missing updates for every game in a library folder
+    Sync(sync::SyncArgs),
+    /// Periodically check a watchlist and report (or download) new updates
+    Watch(watch::WatchArgs),
+    /// List games and versions in RPCS3 folders, ISOs and game dumps
+    Scan(scan::ScanArgs),
+    /// Build an offline copy of Sony's update server for a list of titles
+    Mirror(mirror::MirrorArgs),
+    /// Write a download list for aria2c, wget or a Metalink client instead of downloading
+    Export(export::ExportArgs),
+    /// Run a JSON API for listing updates and starting downloads over HTTP
+    Serve(serve::ServeArgs),
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let output = OutputMode::from_flags(cli.json, cli.ndjson);
+    let mut errors = ErrorLog::default();
+    let result = match cli.command {
+        Command::Fetch(args) => fetch::run(args, output, &mut errors).await,
+        Command::Verify(args) => verify::run(args, output, &mut errors).await,
+        Command::Sync(args) => sync::run(args, output, &mut errors).await,
+        Command::Watch(args) => watch::run(args, output, &mut errors).await,
+        Command::Scan(args) => scan::run(args, output, &mut errors).await,
+        Command::Mirror(args) => mirror::run(args, output, &mut errors).await,
+        Command::Export(args) => export::run(args, output, &mut errors).await,
+        Command::Serve(args) => serve::run(args, output, &mut errors).await,
+    };
+    let outcome = match result {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            output.report(&serde_json::json!({ "error": e.to_string() }));
+            errors.add_fatal(e.as_ref());
+            errors.outcome(0, false)
+        }
+    };
+    if let Some(path) = &cli.errors_json {
+        if let Err(e) = errors.write(path) {
+            eprintln!("error: cannot write {}: {}", path.display(), e);
+        }
+    }
+    outcome.into()
+}
@@ -0,0 +1,150 @@
+use crate::download::DownloadArgs;
+use crate::outcome::{ErrorLog, Outcome};
+use crate::output::OutputMode;
+use crate::table::Table;
+use crate::titles::read_title_list;
+use crate::CliResult;
+use clap::Args;
+use ps3_update_core::{
+    clean_title_id, format_size, Mirror, MirrorCoverage, MirrorOptions, MirrorReport,
+    MirrorTitleReport,
+};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct MirrorArgs {
+    /// Titles to mirror (one per line, `#` comments; `-` reads stdin)
+    #[arg(short, long, value_name = "FILE")]
+    titles: Option<PathBuf>,
+
+    /// Title IDs to mirror, in addition to those in `--titles`
+    #[arg(value_name = "TITLE_ID")]
+    title_ids: Vec<String>,
+
+    /// Mirror root, laid out like Sony's update server
+    #[arg(short, long, value_name = "DIR")]
+    out: PathBuf,
+
+    /// Only save the update lists, not the packages
+    #[arg(long)]
+    xml_only: bool,
+
+    // `--resume` also skips titles an interrupted crawl finished
+    #[command(flatten)]
+    transfer: DownloadArgs,
+}
+
+#[derive(Serialize)]
+struct MirrorRun {
+    #[serde(flatten)]
+    report: MirrorReport,
+    coverage: MirrorCoverage,
+}
+
+pub async fn run(
+    args: MirrorArgs,
+    output: OutputMode,
+    errors: &mut ErrorLog,
+) -> CliResult<Outcome> {
+    let mut title_ids: Vec<String> = args.title_ids.iter().map(|t| clean_title_id(t)).collect();
+    if let Some(path) = &args.titles {
+        title_ids.extend(read_title_list(path)?);
+    }
+    let mut seen = HashSet::new();
+    title_ids.retain(|t| !t.is_empty() && seen.insert(t.clone()));
+    if title_ids.is_empty() {
+        return Err("no title IDs given; pass them as arguments or with --titles".into());
+    }
+
+    let mirror = Mirror::new(&args.out)?.with_options(MirrorOptions {
+        packages: !args.xml_only,
+        download: args.transfer.options(),
+        resume: args.transfer.resumes(),
+        ..Default::default()
+    });
+
+    let report = mirror
+        .mirror_titles_with(&title_ids, |title| {
+            output.event("title", title);
+            if output.is_human() {
+                print_title(title);
+            }
+            if let Some(e) = &title.error {
+                let code = title.error_code.as_deref();
+                errors.add(Some(&title.title_id), None, e.as_str(), code);
+            }
+            for failure in &title.failed {
+                errors.add_file(&title.title_id, failure);
+            }
+        })
+        .await;
+    if output.is_human() && !report.already_complete.is_empty() {
+        println!(
+            "{} title(s) already complete from an earlier run",
+            report.already_complete.len()
+        );
+    }
+
+    let coverage = mirror.coverage(&title_ids);
+    if output.is_human() {
+        println!();
+        println!("{}", coverage_table(&coverage).render());
+        println!(
+            "\n{} of {} titles complete, {} of {} packages ({:.1}% of bytes)",
+            coverage.complete_titles(),
+            coverage.titles.len(),
+            coverage.packages_present(),
+            coverage.packages_listed(),
+            coverage.percent()
+        );
+    }
+
+    let succeeded = report.already_complete.len()
+        + report
+            .titles
+            .iter()
+            .filter(|t| t.error.is_none() && t.failed.is_empty())
+            .count();
+    output.report(&MirrorRun { report, coverage });
+    Ok(errors.outcome(succeeded, false))
+}
+
+fn print_title(title: &MirrorTitleReport) {
+    match &title.error {
+        Some(e) => eprintln!("{}: {}", title.title_id, e),
+        None => println!(
+            "{}: {} downloaded, {} already there",
+            title.title_id,
+            title.downloaded.len(),
+            title.skipped.len()
+        ),
+    }
+    for failure in &title.failed {
+        eprintln!("  failed {}: {}", failure.target, failure.error);
+    }
+}
+
+fn coverage_table(coverage: &MirrorCoverage) -> Table {
+    let mut table = Table::new(["Title ID", "List", "Packages", "Size", "Status"]);
+    for title in &coverage.titles {
+        table.push([
+            title.title_id.clone(),
+            if title.has_xml { "yes" } else { "no" }.to_string(),
+            format!("{}/{}", title.packages_present, title.packages_listed),
+            if title.bytes_present == 0 {
+                String::new()
+            } else {
+                format_size(title.bytes_present)
+            },
+            if title.is_complete() {
+                "complete"
+            } else {
+                "incomplete"
+            }
+            .to_string(),
+        ]);
+    }
+    table
+}
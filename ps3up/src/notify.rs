@@ -0,0 +1,116 @@
+use crate::outcome::ErrorKind;
+use crate::CliResult;
+use clap::{Args, ValueEnum};
+use ps3_update_core::{
+    Failure, LibrarySyncTitle, Webhook, WebhookEvent, WebhookEventKind, WebhookFormat,
+    WebhookNotifier,
+};
+
+/// Webhook flags shared by subcommands that download or check packages
+#[derive(Args, Debug, Clone)]
+pub struct NotifyArgs {
+    /// POST a JSON event to URL when a download finishes, verification fails
+    /// or a watched title gets a new version (may be repeated)
+    #[arg(long, value_name = "URL")]
+    webhook: Vec<String>,
+
+    /// Post a one-line message for each event to a Discord webhook
+    #[arg(long, value_name = "URL")]
+    discord: Vec<String>,
+
+    /// Post a one-line message for each event to a Slack (or Mattermost)
+    /// incoming webhook
+    #[arg(long, value_name = "URL")]
+    slack: Vec<String>,
+
+    /// Only send these events to the webhooks (comma-separated)
+    #[arg(long, value_name = "EVENTS", value_enum, value_delimiter = ',')]
+    webhook_events: Vec<EventArg>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum EventArg {
+    DownloadComplete,
+    VerificationFailed,
+    UpdateDiscovered,
+}
+
+impl From<EventArg> for WebhookEventKind {
+    fn from(event: EventArg) -> Self {
+        match event {
+            EventArg::DownloadComplete => WebhookEventKind::DownloadComplete,
+            EventArg::VerificationFailed => WebhookEventKind::VerificationFailed,
+            EventArg::UpdateDiscovered => WebhookEventKind::UpdateDiscovered,
+        }
+    }
+}
+
+impl NotifyArgs {
+    pub fn notifier(&self) -> CliResult<Notifier> {
+        let events: Vec<WebhookEventKind> = self.webhook_events.iter().map(|&e| e.into()).collect();
+        let hook = |url: &String| Webhook::new(url).with_events(events.clone());
+        let hooks: Vec<Webhook> = self
+            .webhook
+            .iter()
+            .map(hook)
+            .chain(
+                self.discord
+                    .iter()
+                    .map(|url| hook(url).with_format(WebhookFormat::Discord)),
+            )
+            .chain(
+                self.slack
+                    .iter()
+                    .map(|url| hook(url).with_format(WebhookFormat::Slack)),
+            )
+            .collect();
+        if hooks.is_empty() {
+            return Ok(Notifier(None));
+        }
+        Ok(Notifier(Some(WebhookNotifier::new(hooks)?)))
+    }
+}
+
+/// Sends events to the configured webhooks, if any; failures are only logged
+pub struct Notifier(Option<WebhookNotifier>);
+
+impl Notifier {
+    pub async fn send(&self, event: WebhookEvent) {
+        let Some(notifier) = &self.0 else {
+            return;
+        };
+        for (url, e) in notifier.notify(&event).await {
+            eprintln!("webhook {}: {}", url, e);
+        }
+    }
+
+    /// Report a package that failed, if it failed its checks
+    pub async fn failed(&self, title_id: &str, game_title: &str, failure: &Failure) {
+        let kind = failure.code.as_deref().map(ErrorKind::of_code);
+        if kind == Some(ErrorKind::Verification) {
+            self.send(WebhookEvent::VerificationFailed {
+                title_id: Some(title_id.to_string()),
+                game_title: game_title.to_string(),
+                target: failure.target.clone(),
+                error: failure.error.clone(),
+            })
+            .await;
+        }
+    }
+
+    /// Report the downloads and failures of a synced library title
+    pub async fn synced(&self, title: &LibrarySyncTitle, game_title: &str, verified: bool) {
+        for path in &title.downloaded {
+            self.send(WebhookEvent::download_complete(
+                &title.title_id,
+                game_title,
+                path,
+                verified,
+            ))
+            .await;
+        }
+        for failure in &title.failed {
+            self.failed(&title.title_id, game_title, failure).await;
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use ps3_update_core::{Failure, PS3UpdateError};
+use serde::Serialize;
+use std::error::Error;
+use std::path::Path;
+use std::process::ExitCode;
+
+/// How a run ended, reported as the exit status
+///
+/// Status 2 is left to clap, which uses it for usage errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Everything worked (0)
+    Success,
+    /// An error not covered below (1)
+    Failure,
+    /// Nothing new: no updates listed, nothing downloaded or discovered (3)
+    NoUpdates,
+    /// Nothing worked because the server or console couldn't be reached (4)
+    Network,
+    /// A file failed its checksum or PKG checks (5)
+    Verification,
+    /// Some titles or packages succeeded and others failed (6)
+    Partial,
+}
+
+impl Outcome {
+    pub fn code(self) -> u8 {
+        match self {
+            Outcome::Success => 0,
+            Outcome::Failure => 1,
+            Outcome::NoUpdates => 3,
+            Outcome::Network => 4,
+            Outcome::Verification => 5,
+            Outcome::Partial => 6,
+        }
+    }
+}
+
+impl From<Outcome> for ExitCode {
+    fn from(outcome: Outcome) -> Self {
+        ExitCode::from(outcome.code())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Network,
+    Verification,
+    NotFound,
+    FileSystem,
+    Other,
+}
+
+impl ErrorKind {
+    /// Classify a library error by its [`PS3UpdateError::code`]
+    pub fn of_code(code: &str) -> Self {
+        match code {
+            "network" | "http_status" | "ftp" => ErrorKind::Network,
+            "verification" | "package_mismatch" | "invalid_pkg" => ErrorKind::Verification,
+            "not_found" => ErrorKind::NotFound,
+            "file_system" => ErrorKind::FileSystem,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    fn of_error(error: &(dyn Error + 'static)) -> Self {
+        if let Some(e) = error.downcast_ref::<PS3UpdateError>() {
+            Self::of_code(e.code())
+        } else if error.is::<std::io::Error>() {
+            ErrorKind::FileSystem
+        } else {
+            ErrorKind::Other
+        }
+    }
+}
+
+/// One error, as written by `--errors-json`
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorRecord {
+    pub kind: ErrorKind,
+    /// The library's code for the error, when it came from the library
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title_id: Option<String>,
+    /// URL or file the error is about
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub message: String,
+}
+
+/// Errors collected during a run, which decide its [`Outcome`]
+#[derive(Debug, Default)]
+pub struct ErrorLog {
+    errors: Vec<ErrorRecord>,
+}
+
+impl ErrorLog {
+    /// Record an error reported by the library, classified by its code
+    ///
+    /// Errors without a code count as [`ErrorKind::Other`].
+    pub fn add(
+        &mut self,
+        title_id: Option<&str>,
+        target: Option<&str>,
+        message: impl Into<String>,
+        code: Option<&str>,
+    ) {
+        let kind = code.map_or(ErrorKind::Other, ErrorKind::of_code);
+        self.add_kind(kind, title_id, target, message);
+        if let Some(record) = self.errors.last_mut() {
+            record.code = code.map(str::to_string);
+        }
+    }
+
+    /// Record a title that couldn't be checked
+    pub fn add_title(&mut self, failure: &Failure) {
+        let code = failure.code.as_deref();
+        self.add(Some(&failure.target), None, failure.error.as_str(), code);
+    }
+
+    /// Record a file of `title_id` that failed
+    pub fn add_file(&mut self, title_id: &str, failure: &Failure) {
+        let code = failure.code.as_deref();
+        let target = Some(failure.target.as_str());
+        self.add(Some(title_id), target, failure.error.as_str(), code);
+    }
+
+    pub fn add_kind(
+        &mut self,
+        kind: ErrorKind,
+        title_id: Option<&str>,
+        target: Option<&str>,
+        message: impl Into<String>,
+    ) {
+        self.errors.push(ErrorRecord {
+            kind,
+            code: None,
+            title_id: title_id.map(str::to_string),
+            target: target.map(str::to_string),
+            message: message.into(),
+        });
+    }
+
+    /// Record a library error, classified by its code
+    pub fn add_error(
+        &mut self,
+        title_id: Option<&str>,
+        target: Option<&str>,
+        error: &PS3UpdateError,
+    ) {
+        self.add(title_id, target, error.to_string(), Some(error.code()));
+    }
+
+    /// Record the error that ended the run
+    pub fn add_fatal(&mut self, error: &(dyn Error + 'static)) {
+        match error.downcast_ref::<PS3UpdateError>() {
+            Some(e) => self.add_error(None, None, e),
+            None => self.add_kind(ErrorKind::of_error(error), None, None, error.to_string()),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.errors.clear();
+    }
+
+    /// Outcome of a run where `succeeded` items (titles, files) went through
+    ///
+    /// A verification failure wins over everything else, since a corrupt
+    /// package is worse than a missing one. `nothing_new` only matters when
+    /// there were no errors.
+    pub fn outcome(&self, succeeded: usize, nothing_new: bool) -> Outcome {
+        let has = |kind| self.errors.iter().any(|e| e.kind == kind);
+        if has(ErrorKind::Verification) {
+            Outcome::Verification
+        } else if !self.errors.is_empty() && succeeded > 0 {
+            Outcome::Partial
+        } else if !self.errors.is_empty() {
+            if self.errors.iter().all(|e| e.kind == ErrorKind::Network) {
+                Outcome::Network
+            } else {
+                Outcome::Failure
+            }
+        } else if nothing_new {
+            Outcome::NoUpdates
+        } else {
+            Outcome::Success
+        }
+    }
+
+    /// Write the errors as a JSON array (empty when all went well)
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let text = serde_json::to_string_pretty(&self.errors).map_err(std::io::Error::other)?;
+        std::fs::write(path, text + "\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome() {
+        let mut errors = ErrorLog::default();
+        assert_eq!(errors.outcome(2, false), Outcome::Success);
+        assert_eq!(errors.outcome(2, true), Outcome::NoUpdates);
+
+        errors.add(
+            Some("BLES00779"),
+            None,
+            "Network error: timed out",
+            Some("network"),
+        );
+        assert_eq!(errors.errors[0].kind, ErrorKind::Network);
+        assert_eq!(errors.outcome(0, true), Outcome::Network);
+        assert_eq!(errors.outcome(1, false), Outcome::Partial);
+
+        errors.add(
+            None,
+            Some("a.pkg"),
+            "Download error: File size is zero",
+            Some("download"),
+        );
+        assert_eq!(errors.outcome(0, false), Outcome::Failure);
+
+        errors.add_file(
+            "BLES00779",
+            &Failure {
+                target: "http://x/b.pkg".into(),
+                error: "Verification failed: SHA-1 mismatch".into(),
+                code: Some("verification".into()),
+            },
+        );
+        assert_eq!(errors.outcome(5, false), Outcome::Verification);
+        assert_eq!(Outcome::Verification.code(), 5);
+
+        let io: Box<dyn Error> = std::io::Error::other("disk full").into();
+        assert_eq!(ErrorKind::of_error(io.as_ref()), ErrorKind::FileSystem);
+
+        let mut errors = ErrorLog::default();
+        let lib: Box<dyn Error> = PS3UpdateError::NotFound("a.pkg".into()).into();
+        errors.add_fatal(lib.as_ref());
+        assert_eq!(errors.errors[0].kind, ErrorKind::NotFound);
+        assert_eq!(errors.errors[0].code.as_deref(), Some("not_found"));
+    }
+}
@@ -0,0 +1,90 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::path::Path;
+
+/// How a subcommand reports results on stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    /// Progress lines and tables for people
+    #[default]
+    Human,
+    /// One JSON document with the final report
+    Json,
+    /// One JSON object per line: events as they happen, then the report
+    Ndjson,
+}
+
+impl OutputMode {
+    pub fn from_flags(json: bool, ndjson: bool) -> Self {
+        if ndjson {
+            OutputMode::Ndjson
+        } else if json {
+            OutputMode::Json
+        } else {
+            OutputMode::Human
+        }
+    }
+
+    pub fn is_human(self) -> bool {
+        self == OutputMode::Human
+    }
+
+    /// Emit a streaming event; only written in NDJSON mode
+    ///
+    /// Object payloads get an `"event"` field; anything else is wrapped as
+    /// `{"event": ..., "data": ...}`.
+    pub fn event(self, event: &str, data: &impl Serialize) {
+        if self == OutputMode::Ndjson {
+            println!("{}", event_line(event, data));
+        }
+    }
+
+    /// Emit the final report; written in both JSON modes
+    pub fn report(self, report: &impl Serialize) {
+        match self {
+            OutputMode::Human => {}
+            OutputMode::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(report).unwrap_or_default()
+            ),
+            OutputMode::Ndjson => println!("{}", event_line("report", report)),
+        }
+    }
+}
+
+/// Open a file for a `--csv`-style flag; `-` is stdout
+pub fn create_output(path: &Path) -> std::io::Result<Box<dyn Write>> {
+    if path == Path::new("-") {
+        Ok(Box::new(std::io::stdout()))
+    } else {
+        Ok(Box::new(std::fs::File::create(path)?))
+    }
+}
+
+fn event_line(event: &str, data: &impl Serialize) -> String {
+    let value = match serde_json::to_value(data).unwrap_or(Value::Null) {
+        Value::Object(mut fields) => {
+            fields.insert("event".to_string(), event.into());
+            Value::Object(fields)
+        }
+        other => serde_json::json!({ "event": event, "data": other }),
+    };
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_line() {
+        let line = event_line("fetched", &serde_json::json!({ "title_id": "BLES00779" }));
+        let value: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["event"], "fetched");
+        assert_eq!(value["title_id"], "BLES00779");
+
+        let line = event_line("count", &3);
+        assert_eq!(line, r#"{"data":3,"event":"count"}"#);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::outcome::{ErrorLog, Outcome};
+use crate::output::OutputMode;
+use crate::CliResult;
+use clap::Args;
+use ps3_update_core::{scan_path, InstallKind, InstalledGame};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct ScanArgs {
+    /// RPCS3 folders, ISO files, or folders of ISOs and game dumps
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct ScanReport {
+    games: Vec<InstalledGame>,
+}
+
+pub async fn run(args: ScanArgs, output: OutputMode, _errors: &mut ErrorLog) -> CliResult<Outcome> {
+    // The same title found in several places is listed once
+    let mut games: BTreeMap<String, InstalledGame> = BTreeMap::new();
+    for path in &args.paths {
+        if !path.exists() {
+            return Err(format!("no such file or folder: {}", path.display()).into());
+        }
+        let path = path.clone();
+        let found = tokio::task::spawn_blocking(move || scan_path(path)).await??;
+        for game in found {
+            output.event("game", &game);
+            games.entry(game.title_id.clone()).or_insert(game);
+        }
+    }
+    let games: Vec<InstalledGame> = games.into_values().collect();
+
+    if output.is_human() {
+        println!(
+            "# {} game{} found",
+            games.len(),
+            if games.len() == 1 { "" } else { "s" }
+        );
+        for game in &games {
+            println!("{}", game_line(game));
+        }
+    }
+    output.report(&ScanReport { games });
+    Ok(Outcome::Success)
+}
+
+/// One line of a title list: the ID, then details as a comment
+fn game_line(game: &InstalledGame) -> String {
+    let kind = match game.kind {
+        InstallKind::Hdd => "hdd",
+        InstallKind::Disc => "disc",
+        InstallKind::Iso => "iso",
+    };
+    let version = game
+        .app_version
+        .as_deref()
+        .map(|v| format!("v{}", v))
+        .unwrap_or_else(|| "v?".to_string());
+    let mut comment = vec![version, kind.to_string()];
+    if !game.title.is_empty() {
+        comment.insert(0, game.title.clone());
+    }
+    format!("{}  # {}", game.title_id, comment.join("  "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::titles::parse_title_list;
+
+    #[test]
+    fn test_game_line_is_a_title_list() {
+        let game = InstalledGame {
+            title_id: "BLES00779".into(),
+            title: "Demon's Souls".into(),
+            app_version: Some("01.00".into()),
+            category: Some("DG".into()),
+            kind: InstallKind::Disc,
+            path: PathBuf::new(),
+        };
+        let line = game_line(&game);
+        assert_eq!(line, "BLES00779  # Demon's Souls  v01.00  disc");
+        assert_eq!(
+            parse_title_list(&format!("# 1 game found\n{}", line)),
+            ["BLES00779"]
+        );
+    }
+}
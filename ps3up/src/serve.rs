@@ -0,0 +1,59 @@
+use crate::outcome::{ErrorLog, Outcome};
+use crate::output::OutputMode;
+use crate::CliResult;
+use clap::Args;
+use ps3_update_core::ApiServer;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Address to listen on
+    #[arg(short, long, value_name = "ADDR", default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+
+    /// Folder downloads are saved into
+    #[arg(short, long, value_name = "DIR", default_value = ".")]
+    dir: PathBuf,
+
+    /// Also serve the gRPC API (proto/ps3update.proto) on this address
+    #[arg(long, value_name = "ADDR")]
+    grpc: Option<SocketAddr>,
+
+    /// Publish the new updates recorded in a `watch` state file as
+    /// /api/feed.atom and /api/feed.rss
+    #[arg(long, value_name = "STATE_FILE")]
+    feed: Option<PathBuf>,
+}
+
+pub async fn run(
+    args: ServeArgs,
+    output: OutputMode,
+    _errors: &mut ErrorLog,
+) -> CliResult<Outcome> {
+    let mut server = ApiServer::new(&args.dir)?;
+    if let Some(path) = &args.feed {
+        server = server.with_feed(path);
+    }
+    output.event(
+        "listening",
+        &serde_json::json!({ "addr": args.listen, "grpc": args.grpc, "dir": args.dir }),
+    );
+    if output.is_human() {
+        eprintln!(
+            "serving the API on http://{} (downloads go to {})",
+            args.listen,
+            args.dir.display()
+        );
+        if let Some(addr) = args.grpc {
+            eprintln!("serving gRPC on {}", addr);
+        }
+    }
+    match args.grpc {
+        Some(addr) => {
+            tokio::try_join!(server.serve(args.listen), server.serve_grpc(addr))?;
+        }
+        None => server.serve(args.listen).await?,
+    }
+    Ok(Outcome::Success)
+}
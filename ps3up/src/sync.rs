@@ -0,0 +1,170 @@
+use crate::download::DownloadArgs;
+use crate::notify::NotifyArgs;
+use crate::outcome::{ErrorLog, Outcome};
+use crate::output::OutputMode;
+use crate::table::Table;
+use crate::titles::read_title_list;
+use crate::CliResult;
+use clap::Args;
+use ps3_update_core::{
+    clean_title_id, DownloadManager, Library, LibrarySyncReport, LibrarySyncTitle, UpdateFetcher,
+};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct SyncArgs {
+    /// Library folder to keep up to date
+    #[arg(short, long, value_name = "DIR")]
+    library: PathBuf,
+
+    /// Titles to sync (one per line, `#` comments; `-` reads stdin).
+    /// Defaults to every game already in the library.
+    #[arg(short, long, value_name = "FILE")]
+    titles: Option<PathBuf>,
+
+    /// Title IDs to sync, in addition to those in `--titles`
+    #[arg(value_name = "TITLE_ID")]
+    title_ids: Vec<String>,
+
+    #[command(flatten)]
+    transfer: DownloadArgs,
+
+    #[command(flatten)]
+    notify: NotifyArgs,
+}
+
+pub async fn run(args: SyncArgs, output: OutputMode, errors: &mut ErrorLog) -> CliResult<Outcome> {
+    let mut library = Library::open(&args.library)?.with_download_options(args.transfer.options());
+
+    let mut title_ids: Vec<String> = args.title_ids.iter().map(|t| clean_title_id(t)).collect();
+    if let Some(path) = &args.titles {
+        title_ids.extend(read_title_list(path)?);
+    }
+    if title_ids.is_empty() {
+        title_ids = library
+            .games()
+            .iter()
+            .filter_map(|g| g.title_id.clone())
+            .collect();
+    }
+    let mut seen = HashSet::new();
+    title_ids.retain(|t| !t.is_empty() && seen.insert(t.clone()));
+    if title_ids.is_empty() {
+        return Err("nothing to sync: the library is empty and no titles were given".into());
+    }
+
+    let fetcher = UpdateFetcher::new()?;
+    let manager = DownloadManager::new()?;
+    let notifier = args.notify.notifier()?;
+    let mut report = LibrarySyncReport::default();
+    for title_id in &title_ids {
+        if output.is_human() {
+            println!("{}: checking…", title_id);
+        }
+        let title = library.sync_title(&fetcher, &manager, title_id).await?;
+        output.event("title", &title);
+        if output.is_human() {
+            print_title(&title);
+        }
+        log_errors(errors, &title);
+        let game = library
+            .game(title_id)
+            .map(|g| g.game_name.clone())
+            .unwrap_or_default();
+        notifier
+            .synced(&title, &game, args.transfer.verifies())
+            .await;
+        report.titles.push(title);
+    }
+
+    if output.is_human() {
+        println!();
+        println!("{}", summary_table(&library, &report).render());
+    }
+    output.report(&report);
+
+    let succeeded = report
+        .titles
+        .iter()
+        .filter(|t| t.error.is_none() && t.failed.is_empty())
+        .count();
+    Ok(errors.outcome(succeeded, report.downloaded_count() == 0))
+}
+
+/// Record a synced title's errors for the exit status and `--errors-json`
+pub fn log_errors(errors: &mut ErrorLog, title: &LibrarySyncTitle) {
+    if let Some(e) = &title.error {
+        let code = title.error_code.as_deref();
+        errors.add(Some(&title.title_id), None, e.as_str(), code);
+    }
+    for failure in &title.failed {
+        errors.add_file(&title.title_id, failure);
+    }
+}
+
+fn print_title(title: &LibrarySyncTitle) {
+    for path in &title.downloaded {
+        println!("  downloaded {}", path.display());
+    }
+    for failure in &title.failed {
+        eprintln!("  failed {}: {}", failure.target, failure.error);
+    }
+    if let Some(e) = &title.error {
+        eprintln!("  {}", e);
+    }
+}
+
+fn status_text(title: &LibrarySyncTitle) -> String {
+    if title.error.is_some() {
+        "error".to_string()
+    } else if !title.failed.is_empty() {
+        format!("{} failed", title.failed.len())
+    } else if title.downloaded.is_empty() {
+        "up to date".to_string()
+    } else {
+        "updated".to_string()
+    }
+}
+
+fn summary_table(library: &Library, report: &LibrarySyncReport) -> Table {
+    let mut table = Table::new(["Title ID", "Game", "Downloaded", "Status"]);
+    for title in &report.titles {
+        let game = library
+            .game(&title.title_id)
+            .map(|g| g.game_name.clone())
+            .unwrap_or_default();
+        table.push([
+            title.title_id.clone(),
+            game,
+            title.downloaded.len().to_string(),
+            status_text(title),
+        ]);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ps3_update_core::Failure;
+
+    #[test]
+    fn test_status_text() {
+        let mut title = LibrarySyncTitle {
+            title_id: "BLES00779".into(),
+            ..Default::default()
+        };
+        assert_eq!(status_text(&title), "up to date");
+        title.downloaded.push(PathBuf::from("a.pkg"));
+        assert_eq!(status_text(&title), "updated");
+        title.failed.push(Failure {
+            target: "http://x/b.pkg".into(),
+            error: "HTTP error".into(),
+            code: None,
+        });
+        assert_eq!(status_text(&title), "1 failed");
+        title.error = Some("offline".into());
+        assert_eq!(status_text(&title), "error");
+    }
+}
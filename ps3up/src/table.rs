@@ -0,0 +1,59 @@
+/// Plain-text table with left-aligned columns
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<S: Into<String>>(headers: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            headers: headers.into_iter().map(Into::into).collect(),
+            rows: vec![],
+        }
+    }
+
+    pub fn push<S: Into<String>>(&mut self, row: impl IntoIterator<Item = S>) {
+        self.rows.push(row.into_iter().map(Into::into).collect());
+    }
+
+    pub fn render(&self) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (i, cell) in row.iter().enumerate() {
+                if let Some(w) = widths.get_mut(i) {
+                    *w = (*w).max(cell.chars().count());
+                }
+            }
+        }
+
+        let line = |cells: &[String]| {
+            let padded: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &w)| format!("{:<w$}", cell, w = w))
+                .collect();
+            padded.join("  ").trim_end().to_string()
+        };
+        let rule: Vec<String> = widths.iter().map(|&w| "-".repeat(w)).collect();
+
+        let mut out = vec![line(&self.headers), line(&rule)];
+        out.extend(self.rows.iter().map(|row| line(row)));
+        out.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut table = Table::new(["Title ID", "Updates"]);
+        table.push(["BLES00779", "3"]);
+        table.push(["NPUB30001", ""]);
+        assert_eq!(
+            table.render(),
+            "Title ID   Updates\n---------  -------\nBLES00779  3\nNPUB30001"
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use crate::CliResult;
+use ps3_update_core::clean_title_id;
+use std::io::Read;
+use std::path::Path;
+
+/// Parse a title list: one serial per line, `#` starts a comment
+///
+/// Serials are cleaned (`BLES-00779` → `BLES00779`) and duplicates dropped,
+/// keeping the first occurrence.
+pub fn parse_title_list(text: &str) -> Vec<String> {
+    let mut titles: Vec<String> = vec![];
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let id = clean_title_id(line);
+        if !id.is_empty() && !titles.contains(&id) {
+            titles.push(id);
+        }
+    }
+    titles
+}
+
+/// Read a title list from a file, or from stdin when `path` is `-`
+pub fn read_title_list(path: &Path) -> CliResult<Vec<String>> {
+    let text = if path == Path::new("-") {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?
+    };
+    Ok(parse_title_list(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_title_list() {
+        let text = "# my games\nBLES-00779\n\n  NPUB30001  # PSN title\nbles00779\n   # indented comment\n";
+        assert_eq!(parse_title_list(text), ["BLES00779", "NPUB30001"]);
+    }
+}
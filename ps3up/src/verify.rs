@@ -0,0 +1,167 @@
+use crate::notify::NotifyArgs;
+use crate::outcome::{ErrorKind, ErrorLog, Outcome};
+use crate::output::{create_output, OutputMode};
+use crate::CliResult;
+use clap::Args;
+use ps3_update_core::{
+    verify_library, FileCheck, FileStatus, LibraryReport, TitleId, WebhookEvent,
+};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// Library or download folder to check
+    dir: PathBuf,
+
+    /// Only list files that are corrupt or missing
+    #[arg(long)]
+    problems_only: bool,
+
+    /// Also write every file's result as a CSV row to FILE (`-` for stdout)
+    #[arg(long, value_name = "FILE")]
+    csv: Option<PathBuf>,
+
+    #[command(flatten)]
+    notify: NotifyArgs,
+}
+
+#[derive(Serialize)]
+struct VerifySummary {
+    ok: usize,
+    corrupt: usize,
+    missing: usize,
+    unknown: usize,
+    healthy: bool,
+}
+
+impl VerifySummary {
+    fn new(report: &LibraryReport) -> Self {
+        Self {
+            ok: report.count(FileStatus::Ok),
+            corrupt: report.count(FileStatus::Corrupt),
+            missing: report.count(FileStatus::Missing),
+            unknown: report.count(FileStatus::Unknown),
+            healthy: report.is_healthy(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct VerifyReport {
+    #[serde(flatten)]
+    report: LibraryReport,
+    summary: VerifySummary,
+}
+
+pub async fn run(
+    args: VerifyArgs,
+    output: OutputMode,
+    errors: &mut ErrorLog,
+) -> CliResult<Outcome> {
+    if !args.dir.is_dir() {
+        return Err(format!("not a folder: {}", args.dir.display()).into());
+    }
+
+    let notifier = args.notify.notifier()?;
+    let dir = args.dir.clone();
+    let report = tokio::task::spawn_blocking(move || {
+        verify_library(&dir, |progress| {
+            output.event("progress", progress);
+            if output.is_human() {
+                eprint!("\rChecking {}/{}", progress.current + 1, progress.total);
+                let _ = std::io::stderr().flush();
+            }
+        })
+    })
+    .await??;
+    if output.is_human() && !report.files.is_empty() {
+        eprintln!();
+    }
+
+    for file in &report.files {
+        output.event("file", file);
+        let problem = matches!(file.status, FileStatus::Corrupt | FileStatus::Missing);
+        if output.is_human() && (problem || !args.problems_only) {
+            println!("{}", file_line(file, &args.dir));
+        }
+        if problem {
+            errors.add_kind(
+                ErrorKind::Verification,
+                None,
+                Some(&file.path.display().to_string()),
+                file_line(file, &args.dir),
+            );
+        }
+        if file.status == FileStatus::Corrupt {
+            notifier
+                .send(WebhookEvent::VerificationFailed {
+                    // Sony names start with the content ID, `EP0001-BLES00779_00-…`
+                    title_id: file
+                        .path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .and_then(|name| name.split('-').nth(1))
+                        .and_then(|content| TitleId::parse(content.get(..9)?).ok())
+                        .map(|t| t.as_str().to_string()),
+                    game_title: String::new(),
+                    target: file.path.display().to_string(),
+                    error: file
+                        .detail
+                        .clone()
+                        .unwrap_or_else(|| "SHA1 mismatch".to_string()),
+                })
+                .await;
+        }
+    }
+
+    let summary = VerifySummary::new(&report);
+    if output.is_human() {
+        println!(
+            "\n{} ok, {} corrupt, {} missing, {} without reference data",
+            summary.ok, summary.corrupt, summary.missing, summary.unknown
+        );
+    }
+    let ok = summary.ok;
+    if let Some(path) = &args.csv {
+        report.write_csv(create_output(path)?)?;
+    }
+    output.report(&VerifyReport { report, summary });
+    Ok(errors.outcome(ok, false))
+}
+
+fn file_line(file: &FileCheck, root: &Path) -> String {
+    let status = match file.status {
+        FileStatus::Ok => "OK",
+        FileStatus::Corrupt => "CORRUPT",
+        FileStatus::Missing => "MISSING",
+        FileStatus::Unknown => "UNKNOWN",
+    };
+    let path = file.path.strip_prefix(root).unwrap_or(&file.path);
+    match &file.detail {
+        Some(detail) => format!("{:<8} {}  ({})", status, path.display(), detail),
+        None => format!("{:<8} {}", status, path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_line() {
+        let root = Path::new("/library");
+        let file = FileCheck {
+            path: root.join("Game (BLES00779)/update.pkg"),
+            status: FileStatus::Corrupt,
+            expected_sha1: None,
+            actual_sha1: None,
+            detail: Some("size mismatch".into()),
+        };
+        assert_eq!(
+            file_line(&file, root),
+            "CORRUPT  Game (BLES00779)/update.pkg  (size mismatch)"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::download::DownloadArgs;
+use crate::notify::NotifyArgs;
+use crate::outcome::{ErrorLog, Outcome};
+use crate::output::OutputMode;
+use crate::sync::log_errors;
+use crate::titles::read_title_list;
+use crate::CliResult;
+use clap::Args;
+use ps3_update_core::{
+    check_watchlist, clean_title_id, format_size, DiscoveredUpdate, DownloadManager, FeedFormat,
+    Library, LibrarySyncTitle, UpdateFetcher, WatchReport, WatchState,
+};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Args)]
+pub struct WatchArgs {
+    /// Watchlist file (one title ID per line, `#` comments), re-read every check
+    #[arg(short, long, value_name = "FILE")]
+    titles: Option<PathBuf>,
+
+    /// Title IDs to watch, in addition to those in `--titles`
+    #[arg(value_name = "TITLE_ID")]
+    title_ids: Vec<String>,
+
+    /// Time between checks, e.g. 90s, 30m, 6h or 1d
+    #[arg(long, value_name = "INTERVAL", default_value = "6h", value_parser = parse_interval)]
+    interval: Duration,
+
+    /// Download new updates into this library folder
+    #[arg(short, long, value_name = "DIR")]
+    download: Option<PathBuf>,
+
+    /// Where seen versions are remembered between runs
+    /// [default: DIR/.ps3up-watch.json with --download, else ./ps3up-watch.json]
+    #[arg(long, value_name = "FILE")]
+    state: Option<PathBuf>,
+
+    /// Run this shell command for each new update, with PS3UP_TITLE_ID,
+    /// PS3UP_GAME, PS3UP_VERSION, PS3UP_SIZE and PS3UP_URL set
+    #[arg(long, value_name = "COMMAND")]
+    on_update: Option<String>,
+
+    /// Write a feed of the latest new updates to FILE after each check
+    /// (RSS for a `.rss` file, Atom otherwise)
+    #[arg(long, value_name = "FILE")]
+    feed: Option<PathBuf>,
+
+    /// Check once and exit instead of running forever
+    #[arg(long)]
+    once: bool,
+
+    #[command(flatten)]
+    transfer: DownloadArgs,
+
+    #[command(flatten)]
+    notify: NotifyArgs,
+}
+
+/// Everything one check found, as reported in JSON modes
+#[derive(Serialize)]
+struct WatchCycle {
+    #[serde(flatten)]
+    report: WatchReport,
+    synced: Vec<LibrarySyncTitle>,
+}
+
+pub async fn run(args: WatchArgs, output: OutputMode, errors: &mut ErrorLog) -> CliResult<Outcome> {
+    let state_path = args.state.clone().unwrap_or_else(|| match &args.download {
+        Some(dir) => dir.join(".ps3up-watch.json"),
+        None => PathBuf::from("ps3up-watch.json"),
+    });
+    let mut state = WatchState::load(&state_path)?;
+    let fetcher = UpdateFetcher::new()?;
+    let manager = DownloadManager::new()?;
+    let notifier = args.notify.notifier()?;
+    let mut library = match &args.download {
+        Some(dir) => Some(Library::open(dir)?.with_download_options(args.transfer.options())),
+        None => None,
+    };
+
+    loop {
+        // Each check starts afresh; `--errors-json` describes the last one
+        errors.clear();
+        let title_ids = watchlist(&args)?;
+        let report = check_watchlist(&fetcher, &mut state, &title_ids).await;
+        state.save(&state_path)?;
+        if let Some(path) = &args.feed {
+            std::fs::write(path, state.feed(feed_format(path)))?;
+        }
+
+        for update in &report.discovered {
+            output.event("discovered", update);
+            if output.is_human() {
+                println!(
+                    "{} {}: new version {} ({})",
+                    update.title_id,
+                    update.game_title,
+                    update.package.version,
+                    format_size(update.package.size_bytes)
+                );
+            }
+            if let Some(command) = &args.on_update {
+                run_hook(command, update);
+            }
+            notifier.send(update.into()).await;
+        }
+        for failure in &report.errors {
+            eprintln!("{}: {}", failure.target, failure.error);
+            errors.add_title(failure);
+        }
+        if output.is_human() && !report.new_titles.is_empty() {
+            println!("now watching {}", report.new_titles.join(", "));
+        }
+
+        let mut synced = vec![];
+        if let Some(library) = &mut library {
+            let mut pending: Vec<&String> = report.new_titles.iter().collect();
+            pending.extend(report.discovered.iter().map(|u| &u.title_id));
+            let mut seen = HashSet::new();
+            pending.retain(|t| seen.insert(*t));
+            for title_id in pending {
+                let title = library.sync_title(&fetcher, &manager, title_id).await?;
+                output.event("synced", &title);
+                if output.is_human() {
+                    for path in &title.downloaded {
+                        println!("  downloaded {}", path.display());
+                    }
+                }
+                for failure in &title.failed {
+                    eprintln!("  failed {}: {}", failure.target, failure.error);
+                }
+                log_errors(errors, &title);
+                let game = library
+                    .game(title_id)
+                    .map(|g| g.game_name.clone())
+                    .unwrap_or_default();
+                notifier
+                    .synced(&title, &game, args.transfer.verifies())
+                    .await;
+                synced.push(title);
+            }
+        }
+
+        let succeeded = title_ids.len() - report.errors.len();
+        let nothing_new = report.discovered.is_empty();
+        output.report(&WatchCycle { report, synced });
+        if args.once {
+            return Ok(errors.outcome(succeeded, nothing_new));
+        }
+        tokio::time::sleep(args.interval).await;
+    }
+}
+
+fn watchlist(args: &WatchArgs) -> CliResult<Vec<String>> {
+    let mut title_ids: Vec<String> = args.title_ids.iter().map(|t| clean_title_id(t)).collect();
+    if let Some(path) = &args.titles {
+        title_ids.extend(read_title_list(path)?);
+    }
+    let mut seen = HashSet::new();
+    title_ids.retain(|t| !t.is_empty() && seen.insert(t.clone()));
+    if title_ids.is_empty() {
+        return Err("the watchlist is empty; pass title IDs or --titles".into());
+    }
+    Ok(title_ids)
+}
+
+/// RSS for `.rss` files, Atom for anything else
+fn feed_format(path: &Path) -> FeedFormat {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("rss") => FeedFormat::Rss,
+        _ => FeedFormat::Atom,
+    }
+}
+
+/// Run the `--on-update` command; failures are logged, not fatal
+fn run_hook(command: &str, update: &DiscoveredUpdate) {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let status = std::process::Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .env("PS3UP_TITLE_ID", &update.title_id)
+        .env("PS3UP_GAME", &update.game_title)
+        .env("PS3UP_VERSION", &update.package.version)
+        .env("PS3UP_SIZE", update.package.size_bytes.to_string())
+        .env("PS3UP_URL", &update.package.url)
+        .status();
+    match status {
+        Ok(status) if !status.success() => eprintln!("--on-update command exited with {}", status),
+        Err(e) => eprintln!("--on-update command failed to start: {}", e),
+        Ok(_) => {}
+    }
+}
+
+/// Parse an interval like `90s`, `30m`, `6h` or `1d`; a bare number is seconds
+pub fn parse_interval(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(digits);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid interval: {}", s))?;
+    let seconds = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("invalid interval unit: {}", unit)),
+    };
+    if number == 0 {
+        return Err("interval must be greater than zero".to_string());
+    }
+    Ok(Duration::from_secs(number * seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_interval("30m"), Ok(Duration::from_secs(1800)));
+        assert_eq!(parse_interval("6h"), Ok(Duration::from_secs(6 * 3600)));
+        assert_eq!(parse_interval("1d"), Ok(Duration::from_secs(86400)));
+        assert!(parse_interval("0").is_err());
+        assert!(parse_interval("1w").is_err());
+        assert!(parse_interval("soon").is_err());
+    }
+
+    #[test]
+    fn test_feed_format() {
+        assert_eq!(feed_format(Path::new("www/ps3.rss")), FeedFormat::Rss);
+        assert_eq!(feed_format(Path::new("ps3.atom")), FeedFormat::Atom);
+        assert_eq!(feed_format(Path::new("feed.xml")), FeedFormat::Atom);
+    }
+}
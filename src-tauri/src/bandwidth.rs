@@ -0,0 +1,77 @@
+use crate::download_manager;
+use crate::settings::{self, Settings};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter};
+
+// Event carrying a `BandwidthState` whenever the limit on all downloads changes
+const BANDWIDTH_EVENT: &str = "bandwidth://changed";
+
+// On until turned off or the app exits; never saved
+static SLOW_MODE: AtomicBool = AtomicBool::new(false);
+
+/// The limit on all downloads together and where it comes from
+///
+/// Payload of the `bandwidth://changed` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthState {
+    /// From the settings; 0 is unlimited
+    pub global_rate_limit: u64,
+    pub slow_mode: bool,
+    pub slow_mode_limit: u64,
+    /// What downloads are held to now, if anything
+    pub effective_limit: Option<u64>,
+}
+
+fn state(settings: &Settings) -> BandwidthState {
+    let slow_mode = SLOW_MODE.load(Ordering::SeqCst);
+    let slow_limit = if slow_mode {
+        settings.slow_mode_limit
+    } else {
+        0
+    };
+    let limits = [settings.global_rate_limit, slow_limit];
+    BandwidthState {
+        global_rate_limit: settings.global_rate_limit,
+        slow_mode,
+        slow_mode_limit: settings.slow_mode_limit,
+        effective_limit: limits.into_iter().filter(|&n| n > 0).min(),
+    }
+}
+
+/// Hold running and future downloads to the limit `settings` and slow mode
+/// call for, telling the frontend
+pub fn apply(app: &AppHandle, settings: &Settings) -> Result<BandwidthState, String> {
+    let state = state(settings);
+    download_manager()?.set_global_rate_limit(state.effective_limit.unwrap_or(0));
+    if let Err(e) = app.emit(BANDWIDTH_EVENT, &state) {
+        eprintln!("Failed to emit bandwidth state: {}", e);
+    }
+    Ok(state)
+}
+
+#[tauri::command]
+pub fn get_bandwidth(app: AppHandle) -> BandwidthState {
+    state(&settings::current(&app))
+}
+
+/// Save a new limit on all downloads together; 0 lifts it
+///
+/// Running downloads slow down or speed up from their next chunk.
+#[tauri::command]
+pub fn set_global_speed_limit(
+    app: AppHandle,
+    bytes_per_sec: u64,
+) -> Result<BandwidthState, String> {
+    let mut settings = settings::current(&app);
+    settings.global_rate_limit = bytes_per_sec;
+    let settings = settings::save_settings(app.clone(), settings)?;
+    Ok(state(&settings))
+}
+
+/// Turn slow mode on or off; it lasts until the app exits
+#[tauri::command]
+pub fn set_slow_mode(app: AppHandle, enabled: bool) -> Result<BandwidthState, String> {
+    SLOW_MODE.store(enabled, Ordering::SeqCst);
+    apply(&app, &settings::current(&app))
+}
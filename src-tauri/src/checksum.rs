@@ -0,0 +1,199 @@
+use crate::{history, new_fetcher};
+use ps3_update_core::{file_sha1_with_progress, verify_pkg, TitleId};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+// Event carrying a `HashProgress` while a file is hashed
+const HASH_EVENT: &str = "hash://progress";
+
+// Least time between progress events, however fast the disk
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Where the hash a file was compared with came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashSource {
+    /// Given by the user
+    Pasted,
+    /// A recorded download of a file with the same name
+    History,
+    /// The update list of the title named in the file name
+    Server,
+}
+
+/// The SHA1 a file should have
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownHash {
+    pub sha1: String,
+    pub source: HashSource,
+    /// Set when the hash came from a package listing
+    pub title_id: Option<String>,
+    pub game_title: Option<String>,
+    pub version: Option<String>,
+}
+
+impl KnownHash {
+    fn pasted(sha1: &str) -> Self {
+        KnownHash {
+            sha1: sha1.to_lowercase(),
+            source: HashSource::Pasted,
+            title_id: None,
+            game_title: None,
+            version: None,
+        }
+    }
+}
+
+/// A file's SHA1, checked against the one it should have when that's known
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashReport {
+    pub path: String,
+    pub size_bytes: u64,
+    pub sha1: String,
+    /// `None` when no hash was given and none is known for the file name
+    pub expected: Option<KnownHash>,
+    pub matches: Option<bool>,
+}
+
+/// Payload of the `hash://progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashProgress {
+    pub path: String,
+    pub hashed: u64,
+    pub total: u64,
+    pub percent: f64,
+}
+
+fn is_sha1(text: &str) -> bool {
+    text.len() == 40 && text.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// The SHA1 listed for a file of this name: from the history first, then
+// from the update list of the title in its name (update PKGs are named
+// after their content ID, which holds the title ID)
+async fn known_hash(path: &Path) -> Option<KnownHash> {
+    let filename = path.file_name()?.to_string_lossy().to_string();
+    if let Some(entry) =
+        history::find_by_filename(&filename).filter(|entry| !entry.package.sha1.is_empty())
+    {
+        return Some(KnownHash {
+            sha1: entry.package.sha1,
+            source: HashSource::History,
+            title_id: Some(entry.title_id),
+            game_title: Some(entry.game_title),
+            version: Some(entry.package.version),
+        });
+    }
+
+    let title_id = TitleId::find_all(&filename).into_iter().next()?;
+    let result = new_fetcher()
+        .ok()?
+        .fetch_updates(title_id.as_str())
+        .await
+        .ok()?;
+    let package = result
+        .results
+        .into_iter()
+        .find(|p| p.filename.eq_ignore_ascii_case(&filename) && !p.sha1.is_empty())?;
+    Some(KnownHash {
+        sha1: package.sha1,
+        source: HashSource::Server,
+        title_id: Some(result.cleaned_title_id),
+        game_title: Some(result.game_title),
+        version: Some(package.version),
+    })
+}
+
+// Hash a file, sending progress events as it goes
+fn hash(app: &AppHandle, path: &Path) -> Result<String, String> {
+    let name = path.to_string_lossy().to_string();
+    let mut last = Instant::now();
+    file_sha1_with_progress(path, |hashed, total| {
+        if hashed < total && last.elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+        last = Instant::now();
+        let progress = HashProgress {
+            path: name.clone(),
+            hashed,
+            total,
+            percent: hashed as f64 / total.max(1) as f64 * 100.0,
+        };
+        if let Err(e) = app.emit(HASH_EVENT, progress) {
+            eprintln!("Failed to emit hash progress: {}", e);
+        }
+    })
+    .map_err(|e| format!("Failed to hash {}: {}", path.display(), e))
+}
+
+#[tauri::command]
+pub async fn pick_file_to_hash(app: AppHandle) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let path = app
+        .dialog()
+        .file()
+        .set_title("Choose a file to check")
+        .blocking_pick_file();
+
+    Ok(path.map(|p| p.to_string()))
+}
+
+/// SHA1 of any file, such as a PKG downloaded elsewhere, compared with
+/// `expected_sha1` or else the hash listed for a file of that name
+///
+/// Sends `hash://progress` events while hashing. A PKG also matches a listed
+/// hash of everything before its digest block, as some listings use that.
+#[tauri::command]
+pub async fn hash_file(
+    app: AppHandle,
+    path: String,
+    expected_sha1: Option<String>,
+) -> Result<HashReport, String> {
+    let path = PathBuf::from(path);
+    let size_bytes = std::fs::metadata(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    let expected = match expected_sha1.as_deref().map(str::trim) {
+        Some(sha1) if !sha1.is_empty() => {
+            if !is_sha1(sha1) {
+                return Err(format!("{} isn't a SHA1 (40 hex digits)", sha1));
+            }
+            Some(KnownHash::pasted(sha1))
+        }
+        _ => known_hash(&path).await,
+    };
+
+    let sha1 = {
+        let app = app.clone();
+        let path = path.clone();
+        tauri::async_runtime::spawn_blocking(move || hash(&app, &path))
+            .await
+            .map_err(|e| e.to_string())??
+    };
+
+    let matches = match &expected {
+        Some(known) if known.sha1.eq_ignore_ascii_case(&sha1) => Some(true),
+        Some(known) => {
+            let path = path.clone();
+            let listed = known.sha1.clone();
+            let pkg_match = tauri::async_runtime::spawn_blocking(move || {
+                verify_pkg(&path).is_ok_and(|check| check.matches_sha1(&listed))
+            })
+            .await
+            .unwrap_or(false);
+            Some(pkg_match)
+        }
+        None => None,
+    };
+
+    Ok(HashReport {
+        path: path.to_string_lossy().to_string(),
+        size_bytes,
+        sha1,
+        expected,
+        matches,
+    })
+}
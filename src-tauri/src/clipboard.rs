@@ -0,0 +1,68 @@
+use crate::{new_fetcher, settings};
+use ps3_update_core::TitleId;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+// Event carrying a `ClipboardTitles` when copied text holds title IDs
+const CLIPBOARD_EVENT: &str = "clipboard://title-ids";
+
+// How often the clipboard is read while watching is on
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A title ID spotted on the clipboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardTitle {
+    pub title_id: String,
+    /// Game name from the title database, when known
+    pub name: Option<String>,
+}
+
+/// Payload of the `clipboard://title-ids` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardTitles {
+    pub titles: Vec<ClipboardTitle>,
+}
+
+/// Watch the clipboard for title IDs while the setting is on
+///
+/// Only text copied after watching starts is looked at, so turning it on
+/// doesn't prompt about whatever was already there.
+pub async fn watch(app: AppHandle) {
+    let mut last: Option<String> = None;
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if !settings::current(&app).watch_clipboard {
+            last = None;
+            continue;
+        }
+
+        let Ok(text) = app.clipboard().read_text() else {
+            continue;
+        };
+        if last.as_deref() == Some(text.as_str()) {
+            continue;
+        }
+        // The first read after watching starts is whatever was already copied
+        if last.replace(text.clone()).is_none() {
+            continue;
+        }
+
+        let ids = TitleId::find_all(&text);
+        if ids.is_empty() {
+            continue;
+        }
+        let fetcher = new_fetcher().ok();
+        let titles = ids
+            .into_iter()
+            .map(|id| ClipboardTitle {
+                name: fetcher.as_ref().and_then(|f| f.title_name(id.as_str())),
+                title_id: id.into(),
+            })
+            .collect();
+        if let Err(e) = app.emit(CLIPBOARD_EVENT, ClipboardTitles { titles }) {
+            eprintln!("Failed to emit clipboard titles: {}", e);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{job_or_path, settings, ProgressInfo};
+use ps3_update_core::ftp::DEFAULT_FTP_PORT;
+use ps3_update_core::{ConsoleFtp, FtpUploader, WebMan};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+// Event carrying a `PushEvent` as an upload to the console moves
+const PUSH_EVENT: &str = "console://progress";
+
+// How often a running upload is checked for progress to send
+const PUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The PS3 to push downloads to, running webMAN MOD or multiMAN
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConsoleSettings {
+    /// IP address or host name; empty until one is set up
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    /// Ask webMAN to install each package once it's on the console
+    pub install: bool,
+}
+
+impl Default for ConsoleSettings {
+    fn default() -> Self {
+        ConsoleSettings {
+            host: String::new(),
+            port: DEFAULT_FTP_PORT,
+            username: "anonymous".to_string(),
+            password: String::new(),
+            install: false,
+        }
+    }
+}
+
+impl ConsoleSettings {
+    fn ftp(&self) -> Result<ConsoleFtp, String> {
+        let host = self.host.trim();
+        if host.is_empty() {
+            return Err("Set up your console in Settings first".to_string());
+        }
+        Ok(ConsoleFtp::new(host)
+            .with_port(self.port)
+            .with_login(&self.username, &self.password))
+    }
+}
+
+/// Payload of the `console://progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushEvent {
+    pub push_id: String,
+    /// `downloaded` counts bytes sent; `source` is the file's `ftp://` URL
+    pub progress: ProgressInfo,
+}
+
+/// Log in to the console with the saved settings to check they work
+#[tauri::command]
+pub async fn check_console(app: AppHandle) -> Result<(), String> {
+    let ftp = settings::current(&app).console.ftp()?;
+    tauri::async_runtime::spawn_blocking(move || ftp.connect().map(|_| ()))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Upload a finished download (job ID or path) to the console's packages
+/// folder, installing it through webMAN if that's turned on; returns an ID
+/// for its `console://progress` events
+#[tauri::command]
+pub async fn push_to_console(app: AppHandle, target: String) -> Result<String, String> {
+    let console = settings::current(&app).console;
+    let path = job_or_path(&target);
+    if !path.is_file() {
+        return Err(format!("{} isn't there to send", path.display()));
+    }
+
+    let mut uploader = FtpUploader::new(console.ftp()?);
+    if console.install {
+        let webman = WebMan::new(console.host.trim()).map_err(|e| e.to_string())?;
+        uploader = uploader.with_install(webman);
+    }
+    let push_id = uploader.start_upload(path).map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn(follow(app, uploader, push_id.clone()));
+    Ok(push_id)
+}
+
+// Send the upload's progress until it finishes
+async fn follow(app: AppHandle, uploader: FtpUploader, push_id: String) {
+    let mut last_sent = None;
+    loop {
+        tokio::time::sleep(PUSH_INTERVAL).await;
+        let Ok(progress) = uploader.get_progress(&push_id) else {
+            return;
+        };
+        let done = progress.done;
+        if done || last_sent != Some(progress.downloaded) {
+            last_sent = Some(progress.downloaded);
+            let event = PushEvent {
+                push_id: push_id.clone(),
+                progress: progress.into(),
+            };
+            if let Err(e) = app.emit(PUSH_EVENT, event) {
+                eprintln!("Failed to emit console progress: {}", e);
+            }
+        }
+        if done {
+            return;
+        }
+    }
+}
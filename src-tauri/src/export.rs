@@ -0,0 +1,63 @@
+use crate::scan::ScannedTitle;
+use crate::{history, FetchResult};
+use ps3_update_core::{write_fetch_csv, OutdatedReport};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+
+/// What to write to a CSV file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+pub enum CsvExport {
+    /// Fetch results as shown, one row per package
+    Fetch(Vec<FetchResult>),
+    /// The saved download history
+    History,
+    /// A scan of installed games, one row per title
+    Outdated(Vec<ScannedTitle>),
+}
+
+impl CsvExport {
+    fn default_name(&self) -> &'static str {
+        match self {
+            CsvExport::Fetch(_) => "updates.csv",
+            CsvExport::History => "download-history.csv",
+            CsvExport::Outdated(_) => "outdated-games.csv",
+        }
+    }
+}
+
+/// Ask where to save, then write `export` there as CSV
+///
+/// Returns the path written, or `None` if the user cancelled.
+#[tauri::command]
+pub async fn export_csv(app: AppHandle, export: CsvExport) -> Result<Option<String>, String> {
+    let Some(path) = app
+        .dialog()
+        .file()
+        .set_file_name(export.default_name())
+        .add_filter("CSV", &["csv"])
+        .blocking_save_file()
+    else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| e.to_string())?;
+    let file =
+        File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+
+    match export {
+        CsvExport::Fetch(results) => {
+            let results: Vec<ps3_update_core::FetchResult> =
+                results.into_iter().map(|r| r.into()).collect();
+            write_fetch_csv(&results, file).map_err(|e| e.to_string())?
+        }
+        CsvExport::History => history::write_csv(file)?,
+        CsvExport::Outdated(titles) => OutdatedReport {
+            titles: titles.into_iter().map(|t| t.into()).collect(),
+        }
+        .write_csv(file)
+        .map_err(|e| e.to_string())?,
+    }
+    Ok(Some(path.to_string_lossy().to_string()))
+}
@@ -0,0 +1,298 @@
+use crate::{begin_download, reveal, settings, PackageInfo};
+use once_cell::sync::Lazy;
+use ps3_update_core::{unix_timestamp, verify_download, Catalog, DownloadRecord, PS3UpdateError};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+
+// In the app data folder, next to the saved queue
+const CATALOG_FILE: &str = "catalog.db";
+
+// Where history was kept before it moved into the catalog
+const LEGACY_HISTORY_FILE: &str = "history.json";
+
+// The download log, opened by `open` at startup
+static CATALOG: Lazy<Mutex<Option<Arc<Catalog>>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// ID of the download in the catalog
+    pub id: i64,
+    pub game_title: String,
+    pub title_id: String,
+    pub package: PackageInfo,
+    /// Where the file was saved, after any rename
+    pub path: String,
+    /// Unix time the download finished
+    pub downloaded_at: u64,
+    /// Outcome of the last verification, if one ran
+    pub verified: Option<bool>,
+    /// The file wasn't on disk when last checked
+    pub missing: bool,
+    /// Unix time of the last re-check
+    pub checked_at: Option<u64>,
+}
+
+impl From<DownloadRecord> for HistoryEntry {
+    fn from(record: DownloadRecord) -> Self {
+        Self {
+            id: record.id,
+            package: record.to_package_info().into(),
+            game_title: record.game_title,
+            title_id: record.title_id,
+            path: record.path.unwrap_or_default(),
+            downloaded_at: record.finished_at,
+            verified: record.verified,
+            missing: record.missing,
+            checked_at: record.checked_at,
+        }
+    }
+}
+
+// An entry of the old `history.json`
+#[derive(Deserialize)]
+struct LegacyEntry {
+    game_title: String,
+    title_id: String,
+    package: PackageInfo,
+    path: String,
+    downloaded_at: u64,
+    verified: Option<bool>,
+    #[serde(default)]
+    missing: bool,
+    #[serde(default)]
+    checked_at: Option<u64>,
+}
+
+/// One recorded download in a CSV export
+#[derive(Serialize)]
+struct HistoryRow<'a> {
+    downloaded_at: u64,
+    title_id: &'a str,
+    game_title: &'a str,
+    version: &'a str,
+    size_bytes: u64,
+    sha1: &'a str,
+    filename: &'a str,
+    path: &'a str,
+    /// `true`, `false`, or empty when never verified
+    verified: Option<bool>,
+    missing: bool,
+}
+
+/// Open the catalog that logs downloads, moving in any old `history.json`
+pub fn open(app: &AppHandle) -> Result<(), String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let catalog = Catalog::open(dir.join(CATALOG_FILE)).map_err(|e| e.to_string())?;
+    import_legacy(&catalog, &dir.join(LEGACY_HISTORY_FILE))?;
+    *CATALOG.lock().unwrap() = Some(Arc::new(catalog));
+    Ok(())
+}
+
+// Log each entry of an old history file in the catalog, then delete the file
+fn import_legacy(catalog: &Catalog, path: &Path) -> Result<(), String> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let entries: Vec<LegacyEntry> = serde_json::from_str(&text)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    for entry in entries {
+        let package = entry.package;
+        catalog
+            .record_download(&DownloadRecord {
+                id: 0,
+                title_id: entry.title_id,
+                game_title: entry.game_title,
+                version: package.version,
+                filename: package.filename,
+                url: package.url,
+                size_bytes: package.size_bytes,
+                sha1: package.sha1,
+                path: Some(entry.path),
+                bytes: package.size_bytes,
+                duration_secs: 0.0,
+                success: entry.verified != Some(false),
+                error: None,
+                verified: entry.verified,
+                missing: entry.missing,
+                checked_at: entry.checked_at,
+                finished_at: entry.downloaded_at,
+            })
+            .map_err(|e| e.to_string())?;
+    }
+    std::fs::remove_file(path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))
+}
+
+/// The catalog downloads are logged in, once `open` has run
+pub fn catalog() -> Option<Arc<Catalog>> {
+    CATALOG.lock().unwrap().clone()
+}
+
+fn require_catalog() -> Result<Arc<Catalog>, String> {
+    catalog().ok_or_else(|| "Download history is unavailable".to_string())
+}
+
+// Downloads that left a file behind, newest first
+//
+// Failed downloads are left out, but a file that downloaded and then failed
+// verification is kept so it shows up as corrupt.
+fn read() -> Result<Vec<HistoryEntry>, String> {
+    Ok(require_catalog()?
+        .downloads()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|record| record.success || record.verified == Some(false))
+        .map(HistoryEntry::from)
+        .collect())
+}
+
+fn find(id: i64) -> Result<HistoryEntry, String> {
+    require_catalog()?
+        .download(id)
+        .map_err(|e| e.to_string())?
+        .map(HistoryEntry::from)
+        .ok_or_else(|| format!("No history entry {}", id))
+}
+
+/// The latest recorded download saved as, or listed as, `filename`
+pub fn find_by_filename(filename: &str) -> Option<HistoryEntry> {
+    read().ok()?.into_iter().find(|entry| {
+        let saved_as = Path::new(&entry.path).file_name();
+        entry.package.filename.eq_ignore_ascii_case(filename)
+            || saved_as.is_some_and(|name| name.to_string_lossy().eq_ignore_ascii_case(filename))
+    })
+}
+
+/// Write the history as CSV, one row per download, oldest first
+pub fn write_csv(out: impl Write) -> Result<(), String> {
+    let mut writer = csv::Writer::from_writer(out);
+    for entry in read()?.into_iter().rev() {
+        writer
+            .serialize(HistoryRow {
+                downloaded_at: entry.downloaded_at,
+                title_id: &entry.title_id,
+                game_title: &entry.game_title,
+                version: &entry.package.version,
+                size_bytes: entry.package.size_bytes,
+                sha1: &entry.package.sha1,
+                filename: &entry.package.filename,
+                path: &entry.path,
+                verified: entry.verified,
+                missing: entry.missing,
+            })
+            .map_err(|e| e.to_string())?;
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
+
+/// Every recorded download, newest first
+#[tauri::command]
+pub fn list_history() -> Result<Vec<HistoryEntry>, String> {
+    read()
+}
+
+/// Recorded downloads whose game, title ID, version or file name contain `query`
+#[tauri::command]
+pub fn search_history(query: String) -> Result<Vec<HistoryEntry>, String> {
+    let query = query.trim().to_lowercase();
+    Ok(list_history()?
+        .into_iter()
+        .filter(|entry| {
+            [
+                &entry.game_title,
+                &entry.title_id,
+                &entry.package.version,
+                &entry.package.filename,
+            ]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&query))
+        })
+        .collect())
+}
+
+// Hash a recorded file again: `None` if it's gone, else whether it's intact
+async fn check(entry: &HistoryEntry) -> Result<Option<bool>, String> {
+    if !Path::new(&entry.path).exists() {
+        return Ok(None);
+    }
+    let path = entry.path.clone();
+    let sha1 = entry.package.sha1.clone();
+    let outcome = tauri::async_runtime::spawn_blocking(move || verify_download(&path, &sha1))
+        .await
+        .map_err(|e| e.to_string())?;
+    // An unreadable file says nothing about the download itself
+    match outcome {
+        Ok(_) => Ok(Some(true)),
+        Err(PS3UpdateError::Verification(_)) => Ok(Some(false)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Check a recorded file again and store the outcome
+///
+/// A file that has since been deleted or moved is flagged as `missing`.
+#[tauri::command]
+pub async fn reverify_history_entry(id: i64) -> Result<HistoryEntry, String> {
+    let entry = find(id)?;
+    let outcome = check(&entry).await?;
+    require_catalog()?
+        .record_check(id, outcome, unix_timestamp())
+        .map_err(|e| e.to_string())?;
+    find(id)
+}
+
+/// Check every recorded file again, newest first, and return the entries
+/// whose files are now missing or corrupt
+///
+/// Files that can't be read are skipped and keep their last outcome.
+#[tauri::command]
+pub async fn reverify_history() -> Result<Vec<HistoryEntry>, String> {
+    let catalog = require_catalog()?;
+    let checked_at = unix_timestamp();
+    let mut flagged = vec![];
+    for entry in list_history()? {
+        match check(&entry).await {
+            Ok(outcome) => {
+                catalog
+                    .record_check(entry.id, outcome, checked_at)
+                    .map_err(|e| e.to_string())?;
+                let entry = find(entry.id)?;
+                if entry.missing || entry.verified == Some(false) {
+                    flagged.push(entry);
+                }
+            }
+            Err(e) => eprintln!("Failed to check {}: {}", entry.path, e),
+        }
+    }
+    Ok(flagged)
+}
+
+/// Download a recorded package again with the current settings; returns the job ID
+#[tauri::command]
+pub async fn redownload_history_entry(app: AppHandle, id: i64) -> Result<String, String> {
+    let entry = find(id)?;
+    let settings = settings::current(&app);
+    let package = entry.package;
+    begin_download(
+        &settings,
+        &package.url,
+        &package.filename,
+        &entry.game_title,
+        &entry.title_id,
+        Some(package.clone()),
+        false,
+    )
+    .await
+}
+
+/// Show a recorded file in the OS file manager
+#[tauri::command]
+pub fn reveal_history_entry(id: i64) -> Result<(), String> {
+    let entry = find(id)?;
+    reveal(Path::new(&entry.path))
+}
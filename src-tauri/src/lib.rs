@@ -1,13 +1,50 @@
+mod bandwidth;
+mod checksum;
+mod clipboard;
+mod console;
+mod export;
+mod history;
+mod metered;
+mod notify;
+mod queue;
+mod rpcs3;
+mod scan;
+mod schedule;
+mod session;
+mod settings;
+mod shutdown;
+mod status;
+mod titles;
+mod tray;
+
 use once_cell::sync::Lazy;
-use ps3_update_core::{DownloadManager, DownloadMode, UpdateFetcher};
+use ps3_update_core::{format_size, DownloadManager, DownloadPhase, TitleDatabase, UpdateFetcher};
 use serde::{Deserialize, Serialize};
+use settings::Settings;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Emitter;
 
 // Global state for download manager
 static DOWNLOAD_MANAGER: Lazy<Mutex<Option<Arc<DownloadManager>>>> = Lazy::new(|| Mutex::new(None));
 
+// Event carrying a `ProgressEvent` whenever a job moves
+const PROGRESS_EVENT: &str = "download://progress";
+
+// Event carrying a `DownloadSummary` once, when a job finishes
+const FINISHED_EVENT: &str = "download://finished";
+
+// How often jobs are checked for progress to send
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+// Event carrying a `BatchProgress` as each title of a batch lookup finishes
+const BATCH_EVENT: &str = "fetch://progress";
+
+// Titles looked up at once by `fetch_updates_batch`
+const BATCH_CONCURRENCY: usize = 4;
+
 // Track file paths for cleanup on cancel
 static DOWNLOAD_PATHS: Lazy<Mutex<HashMap<String, PathBuf>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 
@@ -36,16 +73,140 @@ pub struct ProgressInfo {
     pub filename: Option<String>,
     pub total: u64,
     pub downloaded: u64,
+    /// Bytes still to come; 0 while the size is unknown
+    #[serde(default)]
+    pub remaining: u64,
     pub percent: f64,
     pub speed_bytes_per_sec: f64,
     pub speed_human: String,
     pub done: bool,
     pub error: Option<String>,
+    pub source: Option<String>,
+    pub verifying: bool,
+    pub verified: Option<bool>,
+    pub already_downloaded: bool,
+    /// Bytes per second the job is held to, if limited
+    pub rate_limit: Option<u64>,
+    pub paused: bool,
+    /// Seconds since the job started, frozen once it's done
+    #[serde(default)]
+    pub elapsed_secs: f64,
+    /// Retries, fallbacks and the like, in the order they happened
+    #[serde(default)]
+    pub notes: Vec<String>,
+    #[serde(default)]
+    pub phase: DownloadPhase,
+    /// Tries at fetching the file so far, counting retries and mirrors
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+/// Payload of the `download://progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub job_id: String,
+    pub progress: ProgressInfo,
+}
+
+/// Everything about a finished job, so nothing has to be asked for after
+///
+/// Payload of the `download://finished` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadSummary {
+    pub job_id: String,
+    pub filename: Option<String>,
+    /// Where the file ended up, after any rename
+    pub path: Option<String>,
+    pub bytes: u64,
+    pub elapsed_secs: f64,
+    /// Over the whole job, verification included; 0 for a file already on disk
+    pub average_speed_bytes_per_sec: f64,
+    pub average_speed_human: String,
+    pub verified: Option<bool>,
+    pub error: Option<String>,
+    pub already_downloaded: bool,
+    /// URL the file came from, which may be a mirror
+    pub source: Option<String>,
+    pub notes: Vec<String>,
+}
+
+impl DownloadSummary {
+    fn new(job_id: &str, progress: &ProgressInfo) -> Self {
+        let path = DOWNLOAD_PATHS
+            .lock()
+            .unwrap()
+            .get(job_id)
+            .map(|p| p.to_string_lossy().to_string());
+        let average = if progress.already_downloaded || progress.elapsed_secs <= 0.0 {
+            0.0
+        } else {
+            progress.downloaded as f64 / progress.elapsed_secs
+        };
+        DownloadSummary {
+            job_id: job_id.to_string(),
+            filename: progress.filename.clone(),
+            path,
+            bytes: progress.downloaded,
+            elapsed_secs: progress.elapsed_secs,
+            average_speed_bytes_per_sec: average,
+            average_speed_human: if average > 0.0 {
+                format!("{}/s", format_size(average as u64))
+            } else {
+                "0 B/s".to_string()
+            },
+            verified: progress.verified,
+            error: progress.error.clone(),
+            already_downloaded: progress.already_downloaded,
+            source: progress.source.clone(),
+            notes: progress.notes.clone(),
+        }
+    }
+}
+
+/// Payload of the `fetch://progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchProgress {
+    pub done: usize,
+    pub total: usize,
+    pub title_id: String,
+    pub error: Option<String>,
+}
+
+/// Outcome of checking a downloaded PKG
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyResult {
+    pub path: String,
+    pub valid: bool,
+    pub content_id: Option<String>,
+    /// SHA1 of the whole file
+    pub sha1: Option<String>,
+    pub error: Option<String>,
+}
+
+/// A file an earlier session left at a download's destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialDownload {
+    pub path: String,
+    pub existing_bytes: u64,
+    pub existing_human: String,
+    /// Size of the whole package
+    pub total_bytes: u64,
+    pub percent: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Settings {
-    pub download_path: String,
+pub struct TitleMatch {
+    pub title_id: String,
+    pub name: String,
+    pub region: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TitleSuggestion {
+    pub title_id: String,
+    pub name: String,
+    pub reason: String,
 }
 
 // Convert ps3_update_core types to our types
@@ -63,6 +224,20 @@ impl From<ps3_update_core::PackageInfo> for PackageInfo {
     }
 }
 
+impl From<PackageInfo> for ps3_update_core::PackageInfo {
+    fn from(pkg: PackageInfo) -> Self {
+        ps3_update_core::PackageInfo {
+            version: pkg.version,
+            system_ver: pkg.system_ver,
+            size_bytes: pkg.size_bytes,
+            size_human: pkg.size_human,
+            url: pkg.url,
+            sha1: pkg.sha1,
+            filename: pkg.filename,
+        }
+    }
+}
+
 impl From<ps3_update_core::FetchResult> for FetchResult {
     fn from(result: ps3_update_core::FetchResult) -> Self {
         FetchResult {
@@ -74,73 +249,189 @@ impl From<ps3_update_core::FetchResult> for FetchResult {
     }
 }
 
+impl From<FetchResult> for ps3_update_core::FetchResult {
+    fn from(result: FetchResult) -> Self {
+        ps3_update_core::FetchResult {
+            results: result.results.into_iter().map(|p| p.into()).collect(),
+            error: result.error,
+            game_title: result.game_title,
+            cleaned_title_id: result.cleaned_title_id,
+        }
+    }
+}
+
 impl From<ps3_update_core::ProgressInfo> for ProgressInfo {
     fn from(progress: ps3_update_core::ProgressInfo) -> Self {
         ProgressInfo {
             filename: progress.filename,
             total: progress.total,
             downloaded: progress.downloaded,
+            remaining: progress.remaining,
             percent: progress.percent,
             speed_bytes_per_sec: progress.speed_bytes_per_sec,
             speed_human: progress.speed_human,
             done: progress.done,
             error: progress.error,
+            source: progress.source,
+            verifying: progress.phase == DownloadPhase::Verifying,
+            verified: progress.verified,
+            already_downloaded: progress.already_downloaded,
+            rate_limit: progress.rate_limit,
+            paused: progress.paused,
+            elapsed_secs: progress.elapsed_secs,
+            notes: progress.notes,
+            phase: progress.phase,
+            attempts: progress.attempts,
         }
     }
 }
 
-#[tauri::command]
-async fn check_server_status() -> Result<bool, String> {
-    let fetcher = UpdateFetcher::new().map_err(|e| e.to_string())?;
-    Ok(fetcher.check_server_status().await)
+impl From<ps3_update_core::TitleMatch> for TitleMatch {
+    fn from(m: ps3_update_core::TitleMatch) -> Self {
+        TitleMatch {
+            title_id: m.title_id,
+            name: m.name,
+            region: m.region.code().to_string(),
+            score: m.score,
+        }
+    }
+}
+
+impl From<ps3_update_core::TitleSuggestion> for TitleSuggestion {
+    fn from(s: ps3_update_core::TitleSuggestion) -> Self {
+        use ps3_update_core::SuggestionReason;
+        let reason = match s.reason {
+            SuggestionReason::WrongRegion => "wrong_region",
+            SuggestionReason::WrongPrefix => "wrong_prefix",
+            SuggestionReason::TransposedDigits => "transposed_digits",
+            SuggestionReason::MistypedDigit => "mistyped_digit",
+        };
+        TitleSuggestion {
+            title_id: s.title_id,
+            name: s.name,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+// Fetcher backed by the embedded title database for offline naming and search
+fn new_fetcher() -> Result<UpdateFetcher, String> {
+    Ok(UpdateFetcher::new()
+        .map_err(|e| e.to_string())?
+        .with_title_database(TitleDatabase::embedded()))
 }
 
 #[tauri::command]
 async fn fetch_updates(title_id: String) -> Result<FetchResult, String> {
-    let fetcher = UpdateFetcher::new().map_err(|e| e.to_string())?;
+    let fetcher = new_fetcher()?;
     let result = fetcher.fetch_updates(&title_id).await.map_err(|e| e.to_string())?;
     Ok(result.into())
 }
 
-#[tauri::command]
-async fn start_download(
-    url: String,
-    filename: String,
-    download_path: String,
-    game_title: String,
-    title_id: String,
-    multi_part: bool,
-) -> Result<String, String> {
-    // Initialize download manager if needed and get an Arc clone
-    let manager = {
-        let mut manager_lock = DOWNLOAD_MANAGER.lock().unwrap();
-        if manager_lock.is_none() {
-            *manager_lock = Some(Arc::new(DownloadManager::new().map_err(|e| e.to_string())?));
-        }
-        manager_lock.as_ref().unwrap().clone()
+// Tell the frontend one more title of a batch lookup is done
+fn emit_batch_progress(
+    app: &tauri::AppHandle,
+    done: usize,
+    total: usize,
+    title_id: &str,
+    result: &ps3_update_core::Result<ps3_update_core::FetchResult>,
+) {
+    let event = BatchProgress {
+        done,
+        total,
+        title_id: title_id.to_string(),
+        error: result.as_ref().err().map(|e| e.to_string()),
     };
+    if let Err(e) = app.emit(BATCH_EVENT, event) {
+        eprintln!("Failed to emit batch progress: {}", e);
+    }
+}
 
-    // Create subfolder: "GameTitle (TITLEID)"
-    let folder_name = format!("{} ({})", game_title, title_id);
-    let safe_folder_name = folder_name
-        .chars()
-        .map(|c| match c {
-            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
-            _ => c,
+#[tauri::command]
+async fn fetch_updates_batch(
+    app: tauri::AppHandle,
+    title_ids: Vec<String>,
+) -> Result<Vec<FetchResult>, String> {
+    let fetcher = new_fetcher()?;
+    let results = fetcher
+        .fetch_updates_batch(&title_ids, BATCH_CONCURRENCY, |done, total, title_id, result| {
+            emit_batch_progress(&app, done, total, title_id, result);
         })
-        .collect::<String>();
+        .await;
 
-    let subfolder = PathBuf::from(download_path).join(safe_folder_name);
-    let path = subfolder.join(&filename);
+    // Titles that failed stay in the list with their error
+    Ok(results
+        .into_iter()
+        .map(|(title_id, result)| match result {
+            Ok(result) => result.into(),
+            Err(e) => FetchResult {
+                results: vec![],
+                error: Some(e.to_string()),
+                game_title: fetcher.title_name(&title_id).unwrap_or_default(),
+                cleaned_title_id: title_id,
+            },
+        })
+        .collect())
+}
 
-    let mode = if multi_part {
-        DownloadMode::MultiPart { num_parts: 4 }
-    } else {
-        DownloadMode::Direct
-    };
+#[tauri::command]
+fn search_titles(name: String) -> Result<Vec<TitleMatch>, String> {
+    let fetcher = new_fetcher()?;
+    Ok(fetcher.search(&name).into_iter().map(|m| m.into()).collect())
+}
+
+#[tauri::command]
+fn suggest_title_ids(title_id: String) -> Result<Vec<TitleSuggestion>, String> {
+    let fetcher = new_fetcher()?;
+    Ok(fetcher.suggest(&title_id).into_iter().map(|s| s.into()).collect())
+}
+
+// The shared download manager, created on first use
+fn download_manager() -> Result<Arc<DownloadManager>, String> {
+    let mut manager_lock = DOWNLOAD_MANAGER.lock().unwrap();
+    if manager_lock.is_none() {
+        let mut manager = DownloadManager::new().map_err(|e| e.to_string())?;
+        if let Some(catalog) = history::catalog() {
+            manager = manager.with_catalog(catalog);
+        }
+        *manager_lock = Some(Arc::new(manager));
+    }
+    Ok(manager_lock.as_ref().unwrap().clone())
+}
+
+// Where a package is downloaded to, in its game's folder under the download folder
+fn download_path(settings: &Settings, game_title: &str, title_id: &str, filename: &str) -> PathBuf {
+    settings.folder_template().package_path(
+        Path::new(&settings.download_path),
+        game_title,
+        title_id,
+        filename,
+    )
+}
+
+// Start a download, continuing a partial file when `resume` is set
+async fn begin_download(
+    settings: &Settings,
+    url: &str,
+    filename: &str,
+    game_title: &str,
+    title_id: &str,
+    package: Option<PackageInfo>,
+    resume: bool,
+) -> Result<String, String> {
+    let manager = download_manager()?;
+    let path = download_path(settings, game_title, title_id, filename);
+
+    let mut options = settings.download_options().expect_title(title_id);
+    if resume {
+        options = options.with_resume();
+    }
+    if let Some(package) = package {
+        options = options.with_manifest(title_id, game_title, package.into());
+    }
 
     let job_id = manager
-        .start_download(&url, path.clone(), mode)
+        .start_download_with(url, path.clone(), options)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -153,20 +444,65 @@ async fn start_download(
     Ok(job_id)
 }
 
-#[tauri::command]
-async fn cancel_download(job_id: String) -> Result<(), String> {
+// Follow a rename by the naming scheme so the job's file can still be found
+fn record_final_name(job_id: &str, filename: Option<&str>) {
+    let mut paths = DOWNLOAD_PATHS.lock().unwrap();
+    if let (Some(path), Some(filename)) = (paths.get_mut(job_id), filename) {
+        path.set_file_name(filename);
+    }
+}
+
+// Forward the core's progress updates to the frontend, for every job
+async fn emit_progress(app: tauri::AppHandle) {
+    let manager = match download_manager() {
+        Ok(manager) => manager,
+        Err(e) => {
+            eprintln!("Progress events unavailable: {}", e);
+            return;
+        }
+    };
+    let mut updates = manager.subscribe(PROGRESS_INTERVAL);
+    loop {
+        let changed: Vec<(String, ProgressInfo)> = updates
+            .next()
+            .await
+            .into_iter()
+            .map(|(job_id, progress)| (job_id, progress.into()))
+            .collect();
+        let totals = session::record(&changed);
+        for (job_id, progress) in changed {
+            let event = ProgressEvent { job_id, progress };
+            if event.progress.done {
+                record_final_name(&event.job_id, event.progress.filename.as_deref());
+                notify::download_finished(&app, &event.progress);
+                let summary = DownloadSummary::new(&event.job_id, &event.progress);
+                if let Err(e) = app.emit(FINISHED_EVENT, summary) {
+                    eprintln!("Failed to emit download summary: {}", e);
+                }
+            }
+            if let Err(e) = app.emit(PROGRESS_EVENT, event) {
+                eprintln!("Failed to emit progress: {}", e);
+            }
+        }
+        session::show(&app, &totals);
+    }
+}
+
+// Stop tracking a job and delete its partial file
+async fn cancel_job(job_id: &str) -> Result<(), String> {
     // Remove the job from the manager
     {
         let manager_lock = DOWNLOAD_MANAGER.lock().unwrap();
         if let Some(manager) = manager_lock.as_ref() {
-            manager.remove_job(&job_id);
+            manager.remove_job(job_id);
         }
     }
+    session::forget(job_id);
 
     // Delete the partial file
     let path = {
         let mut paths = DOWNLOAD_PATHS.lock().unwrap();
-        paths.remove(&job_id)
+        paths.remove(job_id)
     };
 
     if let Some(file_path) = path {
@@ -180,15 +516,153 @@ async fn cancel_download(job_id: String) -> Result<(), String> {
     Ok(())
 }
 
+/// A partial file at the destination a download would use, so the user can
+/// choose between resuming and starting over
+///
+/// Only a file smaller than the package counts, and not one a download
+/// running now is writing.
 #[tauri::command]
-fn get_download_progress(job_id: String) -> Result<ProgressInfo, String> {
-    let manager_lock = DOWNLOAD_MANAGER.lock().unwrap();
-    if let Some(manager) = manager_lock.as_ref() {
-        let progress = manager.get_progress(&job_id).map_err(|e| e.to_string())?;
-        Ok(progress.into())
-    } else {
-        Err("Download manager not initialized".to_string())
+fn find_partial_download(
+    app: tauri::AppHandle,
+    filename: String,
+    game_title: String,
+    title_id: String,
+    size_bytes: u64,
+) -> Result<Option<PartialDownload>, String> {
+    let settings = settings::current(&app);
+    let path = download_path(&settings, &game_title, &title_id, &filename);
+    let existing_bytes = match std::fs::metadata(&path) {
+        Ok(metadata) if metadata.is_file() => metadata.len(),
+        _ => return Ok(None),
+    };
+    if existing_bytes == 0 || existing_bytes >= size_bytes {
+        return Ok(None);
+    }
+    let manager = download_manager()?;
+    let in_use = DOWNLOAD_PATHS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|(job_id, job_path)| {
+            *job_path == path && manager.get_progress(job_id).is_ok_and(|p| !p.done)
+        });
+    if in_use {
+        return Ok(None);
     }
+
+    Ok(Some(PartialDownload {
+        path: path.to_string_lossy().to_string(),
+        existing_bytes,
+        existing_human: format_size(existing_bytes),
+        total_bytes: size_bytes,
+        percent: existing_bytes as f64 / size_bytes as f64 * 100.0,
+    }))
+}
+
+/// Start a download; with `resume` set, a partial file at the destination
+/// is continued rather than overwritten
+#[tauri::command]
+async fn start_download(
+    app: tauri::AppHandle,
+    url: String,
+    filename: String,
+    game_title: String,
+    title_id: String,
+    package: Option<PackageInfo>,
+    resume: Option<bool>,
+) -> Result<String, String> {
+    let settings = settings::current(&app);
+    let resume = resume.unwrap_or(false);
+    begin_download(
+        &settings,
+        &url,
+        &filename,
+        &game_title,
+        &title_id,
+        package,
+        resume,
+    )
+    .await
+}
+
+#[tauri::command]
+async fn cancel_download(app: tauri::AppHandle, job_id: String) -> Result<(), String> {
+    cancel_job(&job_id).await?;
+    session::show(&app, &session::current());
+    Ok(())
+}
+
+// The file a job this session downloaded, or else `target` as a path
+fn job_or_path(target: &str) -> PathBuf {
+    DOWNLOAD_PATHS
+        .lock()
+        .unwrap()
+        .get(target)
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from(target))
+}
+
+#[tauri::command]
+async fn verify_download(
+    target: String,
+    expected_sha1: Option<String>,
+) -> Result<VerifyResult, String> {
+    let path = job_or_path(&target);
+    let sha1 = expected_sha1.unwrap_or_default();
+
+    let file = path.clone();
+    let outcome = tauri::async_runtime::spawn_blocking(move || {
+        ps3_update_core::verify_download(&file, &sha1)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let path = path.to_string_lossy().to_string();
+    Ok(match outcome {
+        Ok(verification) => VerifyResult {
+            path,
+            valid: true,
+            content_id: Some(verification.content_id),
+            sha1: Some(verification.file_sha1),
+            error: None,
+        },
+        Err(e) => VerifyResult {
+            path,
+            valid: false,
+            content_id: None,
+            sha1: None,
+            error: Some(e.to_string()),
+        },
+    })
+}
+
+#[tauri::command]
+fn set_download_speed_limit(job_id: String, bytes_per_sec: u64) -> Result<(), String> {
+    download_manager()?
+        .set_rate_limit(&job_id, bytes_per_sec)
+        .map_err(|e| e.to_string())
+}
+
+// Show a file in the OS file manager, or open its folder if the file is gone
+fn reveal(path: &Path) -> Result<(), String> {
+    if path.exists() {
+        return tauri_plugin_opener::reveal_item_in_dir(path).map_err(|e| e.to_string());
+    }
+    match path.parent().filter(|dir| dir.is_dir()) {
+        Some(dir) => tauri_plugin_opener::open_path(dir, None::<&str>).map_err(|e| e.to_string()),
+        None => Err(format!("{} no longer exists", path.display())),
+    }
+}
+
+#[tauri::command]
+fn reveal_download(job_id: String) -> Result<(), String> {
+    let path = DOWNLOAD_PATHS
+        .lock()
+        .unwrap()
+        .get(&job_id)
+        .cloned()
+        .ok_or_else(|| format!("Unknown download {}", job_id))?;
+    reveal(&path)
 }
 
 #[tauri::command]
@@ -204,10 +678,7 @@ fn remove_download_job(job_id: String) -> Result<(), String> {
 
 #[tauri::command]
 fn get_default_download_path() -> String {
-    dirs_next::download_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .to_string_lossy()
-        .to_string()
+    settings::default_download_path()
 }
 
 #[tauri::command]
@@ -226,17 +697,81 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_store::Builder::new().build())
+        .setup(|app| {
+            tray::create(app.handle())?;
+            if let Err(e) = history::open(app.handle()) {
+                eprintln!("Download history unavailable: {}", e);
+            }
+            tauri::async_runtime::spawn(emit_progress(app.handle().clone()));
+            tauri::async_runtime::spawn(clipboard::watch(app.handle().clone()));
+            tauri::async_runtime::spawn(schedule::run(app.handle().clone()));
+            tauri::async_runtime::spawn(status::watch(app.handle().clone()));
+            tauri::async_runtime::spawn(metered::watch(app.handle().clone()));
+            if let Err(e) = bandwidth::apply(app.handle(), &settings::current(app.handle())) {
+                eprintln!("Failed to set the bandwidth limit: {}", e);
+            }
+            queue::restore(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
-            check_server_status,
+            status::check_server_status,
             fetch_updates,
+            fetch_updates_batch,
+            search_titles,
+            suggest_title_ids,
+            find_partial_download,
             start_download,
             cancel_download,
-            get_download_progress,
             remove_download_job,
+            verify_download,
+            reveal_download,
+            set_download_speed_limit,
             get_default_download_path,
             pick_download_directory,
+            settings::load_settings,
+            settings::save_settings,
+            bandwidth::get_bandwidth,
+            bandwidth::set_global_speed_limit,
+            bandwidth::set_slow_mode,
+            notify::request_notification_permission,
+            history::list_history,
+            history::search_history,
+            history::reverify_history_entry,
+            history::reverify_history,
+            history::redownload_history_entry,
+            history::reveal_history_entry,
+            queue::enqueue_downloads,
+            queue::enqueue_title,
+            queue::plan_title,
+            queue::get_queue,
+            queue::get_queue_group,
+            queue::move_queue_item,
+            queue::remove_queue_item,
+            scan::scan_folder,
+            export::export_csv,
+            console::check_console,
+            console::push_to_console,
+            rpcs3::locate_rpcs3,
+            rpcs3::pick_rpcs3_directory,
+            rpcs3::check_rpcs3_updates,
+            schedule::check_watchlist_now,
+            schedule::list_new_updates,
+            schedule::clear_new_updates,
+            metered::get_network_state,
+            titles::pick_title_list,
+            titles::import_title_list,
+            checksum::pick_file_to_hash,
+            checksum::hash_file,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Closing the last window or quitting from the tray
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                shutdown::stop_downloads(app);
+            }
+        });
 }
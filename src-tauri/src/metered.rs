@@ -0,0 +1,108 @@
+use crate::{queue, settings};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+// Event carrying a `NetworkState` whenever it changes
+const NETWORK_EVENT: &str = "network://metered";
+
+// How often the connection is looked at
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+static STATE: Lazy<Mutex<NetworkState>> = Lazy::new(|| Mutex::new(NetworkState::default()));
+
+/// Whether the connection is metered and the queue held because of it
+///
+/// Payload of the `network://metered` event.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkState {
+    /// `None` where the OS doesn't say
+    pub metered: Option<bool>,
+    /// The queue is paused until the connection is unmetered again
+    pub held: bool,
+}
+
+/// Whether the OS counts the current connection as metered
+///
+/// Windows reports a cost for every connection, with phone hotspots usually
+/// marked as metered.
+#[cfg(windows)]
+fn is_metered() -> Option<bool> {
+    use windows::Networking::Connectivity::{NetworkCostType, NetworkInformation};
+
+    let cost = NetworkInformation::GetInternetConnectionProfile()
+        .ok()?
+        .GetConnectionCost()
+        .ok()?;
+    let kind = cost.NetworkCostType().ok()?;
+    Some(
+        kind == NetworkCostType::Fixed
+            || kind == NetworkCostType::Variable
+            || cost.Roaming().unwrap_or(false)
+            || cost.OverDataLimit().unwrap_or(false),
+    )
+}
+
+/// Whether NetworkManager counts the current connection as metered
+///
+/// Its guess covers phone hotspots it recognises. Systems without
+/// NetworkManager don't say.
+#[cfg(target_os = "linux")]
+fn is_metered() -> Option<bool> {
+    let output = std::process::Command::new("busctl")
+        .args([
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // "u 1": yes, guessed yes (3), no (2), guessed no (4) or unknown (0)
+    let text = String::from_utf8_lossy(&output.stdout);
+    match text.split_whitespace().nth(1)?.parse::<u32>().ok()? {
+        1 | 3 => Some(true),
+        2 | 4 => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn is_metered() -> Option<bool> {
+    None
+}
+
+/// Hold the queue while the connection is metered and the setting is on,
+/// letting it go once the connection is unmetered or the setting is off
+pub async fn watch(app: AppHandle) {
+    loop {
+        let metered = tauri::async_runtime::spawn_blocking(is_metered)
+            .await
+            .unwrap_or(None);
+        let held = metered == Some(true) && settings::current(&app).pause_on_metered;
+        let state = NetworkState { metered, held };
+
+        let changed = {
+            let mut current = STATE.lock().unwrap();
+            std::mem::replace(&mut *current, state.clone()) != state
+        };
+        if changed {
+            queue::set_held(held);
+            if let Err(e) = app.emit(NETWORK_EVENT, &state) {
+                eprintln!("Failed to emit network state: {}", e);
+            }
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[tauri::command]
+pub fn get_network_state() -> NetworkState {
+    STATE.lock().unwrap().clone()
+}
@@ -0,0 +1,84 @@
+use crate::schedule::NewUpdate;
+use crate::{settings, ProgressInfo};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::{NotificationExt, PermissionState};
+
+// The window the app runs in; no need to notify while the user is looking at it
+const MAIN_WINDOW: &str = "main";
+
+// Updates named in a notification before the rest are counted
+const NOTIFY_LINES: usize = 3;
+
+fn window_focused(app: &AppHandle) -> bool {
+    app.get_webview_window(MAIN_WINDOW)
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false)
+}
+
+/// Tell the user a download finished or failed, if they asked to be told
+/// and the app isn't in front of them
+pub fn download_finished(app: &AppHandle, progress: &ProgressInfo) {
+    // A copy that was already there finishes at once, with nothing to tell
+    if progress.already_downloaded || !settings::current(app).notify || window_focused(app) {
+        return;
+    }
+
+    let filename = progress.filename.as_deref().unwrap_or("Download");
+    let (title, body) = match &progress.error {
+        Some(error) => ("Download failed", format!("{}: {}", filename, error)),
+        None => ("Download complete", filename.to_string()),
+    };
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("Failed to show notification: {}", e);
+    }
+}
+
+/// Tell the user a background check found new updates, if they asked to
+/// be told and the app isn't in front of them
+pub fn updates_found(app: &AppHandle, updates: &[NewUpdate]) {
+    if updates.is_empty() || !settings::current(app).notify || window_focused(app) {
+        return;
+    }
+
+    let title = match updates.len() {
+        1 => "New update available".to_string(),
+        n => format!("{} new updates available", n),
+    };
+    let mut lines: Vec<String> = updates
+        .iter()
+        .take(NOTIFY_LINES)
+        .map(|u| {
+            let game = if u.game_title.is_empty() {
+                &u.title_id
+            } else {
+                &u.game_title
+            };
+            format!("{} {}", game, u.package.version)
+        })
+        .collect();
+    if updates.len() > NOTIFY_LINES {
+        lines.push(format!("and {} more", updates.len() - NOTIFY_LINES));
+    }
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(title)
+        .body(lines.join("\n"))
+        .show()
+    {
+        eprintln!("Failed to show notification: {}", e);
+    }
+}
+
+/// Ask the OS to let the app show notifications; true once it may
+#[tauri::command]
+pub fn request_notification_permission(app: AppHandle) -> Result<bool, String> {
+    let notification = app.notification();
+    let mut state = notification.permission_state().map_err(|e| e.to_string())?;
+    if !matches!(state, PermissionState::Granted | PermissionState::Denied) {
+        state = notification
+            .request_permission()
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(matches!(state, PermissionState::Granted))
+}
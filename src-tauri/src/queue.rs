@@ -0,0 +1,410 @@
+use crate::{
+    bandwidth, cancel_job, download_manager, download_path, settings, FetchResult, PackageInfo,
+    ProgressInfo, DOWNLOAD_PATHS,
+};
+use once_cell::sync::Lazy;
+use ps3_update_core::{compare_versions, DownloadPlan, PlannedDownload, QueueManager};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+pub use ps3_update_core::QueueStatus;
+
+// In the app data folder, so a restart picks up where the queue left off
+const QUEUE_FILE: &str = "queue.json";
+
+// Set up by `restore` when the app starts
+static QUEUE: Lazy<Mutex<Option<QueueManager>>> = Lazy::new(|| Mutex::new(None));
+
+/// One package to queue, as sent by the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueRequest {
+    pub game_title: String,
+    pub title_id: String,
+    pub package: PackageInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueItem {
+    pub id: String,
+    pub game_title: String,
+    pub title_id: String,
+    pub package: PackageInfo,
+    pub status: QueueStatus,
+    /// Download manager job, once the item has started
+    pub job_id: Option<String>,
+    pub progress: Option<ProgressInfo>,
+    pub error: Option<String>,
+    /// Set for the packages of a title queued together by `enqueue_title`
+    #[serde(default)]
+    pub group_id: Option<String>,
+}
+
+impl From<ps3_update_core::QueueItem> for QueueItem {
+    fn from(item: ps3_update_core::QueueItem) -> Self {
+        QueueItem {
+            id: item.id,
+            game_title: item.download.game_title,
+            title_id: item.download.title_id,
+            package: item.download.package.into(),
+            status: item.status,
+            job_id: item.job_id,
+            progress: item.progress.map(Into::into),
+            error: item.error,
+            group_id: item.group_id,
+        }
+    }
+}
+
+/// A group of queued items added up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueGroup {
+    pub group_id: String,
+    pub title_id: String,
+    pub game_title: String,
+    /// Items still in the queue; removed ones no longer count
+    pub items: usize,
+    pub done: usize,
+    pub failed: usize,
+    pub total_bytes: u64,
+    /// Finished items count in full, the one downloading by its progress
+    pub downloaded_bytes: u64,
+    pub percent: f64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SavedQueue {
+    items: Vec<ps3_update_core::QueueItem>,
+}
+
+// The queue as saved before it ran on the core's `QueueManager`
+#[derive(Debug, Deserialize)]
+struct LegacyQueue {
+    items: Vec<QueueItem>,
+    partial: Option<LegacyPartial>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyPartial {
+    id: String,
+    contiguous: bool,
+}
+
+impl LegacyQueue {
+    fn upgrade(self, settings: &settings::Settings) -> SavedQueue {
+        let partial = self.partial;
+        let items = self
+            .items
+            .into_iter()
+            .map(|item| {
+                let contiguous = partial
+                    .as_ref()
+                    .is_some_and(|p| p.id == item.id && p.contiguous);
+                let request = QueueRequest {
+                    game_title: item.game_title,
+                    title_id: item.title_id,
+                    package: item.package,
+                };
+                ps3_update_core::QueueItem {
+                    id: item.id,
+                    download: planned(settings, request),
+                    group_id: item.group_id,
+                    status: item.status,
+                    job_id: None,
+                    progress: None,
+                    error: item.error,
+                    error_code: None,
+                    attempts: 0,
+                    contiguous,
+                    resume: false,
+                }
+            })
+            .collect();
+        SavedQueue { items }
+    }
+}
+
+fn queue_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(QUEUE_FILE))
+}
+
+fn read(app: &AppHandle) -> Result<SavedQueue, String> {
+    let path = queue_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text)
+            .or_else(|e| {
+                serde_json::from_str::<LegacyQueue>(&text)
+                    .map(|legacy| legacy.upgrade(&settings::current(app)))
+                    .map_err(|_| e)
+            })
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(SavedQueue::default()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+fn write(app: &AppHandle, saved: &SavedQueue) -> Result<(), String> {
+    let path = queue_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let text = serde_json::to_string_pretty(saved).map_err(|e| e.to_string())?;
+    std::fs::write(&path, text).map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+}
+
+fn queue() -> Result<QueueManager, String> {
+    QUEUE
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "The download queue isn't ready".to_string())
+}
+
+// Write out the queue; a failure only costs the restore, so it isn't fatal
+fn save(app: &AppHandle) {
+    let Ok(queue) = queue() else {
+        return;
+    };
+    let saved = SavedQueue {
+        items: queue.items(),
+    };
+    if let Err(e) = write(app, &saved) {
+        eprintln!("Failed to save download queue: {}", e);
+    }
+}
+
+// Track an item's job like any other download, and save the queue as it moves
+//
+// The queue keeps a finished job for a few seconds, so `emit_progress` sees it
+// end and records, notifies and summarizes it like any other download.
+fn changed(app: &AppHandle, item: &ps3_update_core::QueueItem) {
+    if let (QueueStatus::Downloading, Some(job_id)) = (item.status, &item.job_id) {
+        DOWNLOAD_PATHS
+            .lock()
+            .unwrap()
+            .insert(job_id.clone(), item.download.path());
+    }
+    save(app);
+}
+
+/// Set up the queue and carry on with the one saved by the last run
+///
+/// Items that were downloading go back to waiting. The partial file of one
+/// that was written in order is resumed, and deleted otherwise.
+pub fn restore(app: &AppHandle) {
+    let manager = match download_manager() {
+        Ok(manager) => manager,
+        Err(e) => {
+            eprintln!("Download queue unavailable: {}", e);
+            return;
+        }
+    };
+    let handle = app.clone();
+    let queue = QueueManager::new(
+        (*manager).clone(),
+        settings::current(app).download_options(),
+    )
+    .with_listener(move |item| changed(&handle, item));
+    *QUEUE.lock().unwrap() = Some(queue.clone());
+
+    match read(app) {
+        // Workers start on the async runtime
+        Ok(saved) => {
+            tauri::async_runtime::spawn(async move { queue.restore(saved.items) });
+        }
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+/// Download the queue's next items with `settings`' options
+pub fn apply(settings: &settings::Settings) {
+    if let Ok(queue) = queue() {
+        queue.set_options(settings.download_options());
+    }
+}
+
+/// Download manager jobs of the items being downloaded
+pub fn active_jobs() -> Vec<String> {
+    queue().map(|queue| queue.active_jobs()).unwrap_or_default()
+}
+
+/// Hold the queue, pausing its download and starting nothing new, or let it
+/// carry on
+pub fn set_held(held: bool) {
+    if let Ok(queue) = queue() {
+        queue.set_held(held);
+    }
+}
+
+/// Save the queue before the app exits
+///
+/// Called once its jobs are paused; their partial files are continued on
+/// the next start.
+pub fn suspend(app: &AppHandle) {
+    save(app);
+}
+
+// Where a request's package goes under the current settings
+fn planned(settings: &settings::Settings, request: QueueRequest) -> PlannedDownload {
+    let path = download_path(
+        settings,
+        &request.game_title,
+        &request.title_id,
+        &request.package.filename,
+    );
+    PlannedDownload {
+        title_id: request.title_id,
+        game_title: request.game_title,
+        package: request.package.into(),
+        dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+    }
+}
+
+// The queue, set to download with the current settings, and where they put
+// each requested package
+fn prepare(
+    app: &AppHandle,
+    items: Vec<QueueRequest>,
+) -> Result<(QueueManager, Vec<PlannedDownload>), String> {
+    let queue = queue()?;
+    let settings = settings::current(app);
+    queue.set_options(settings.download_options());
+    let downloads = items
+        .into_iter()
+        .map(|request| planned(&settings, request))
+        .collect();
+    Ok((queue, downloads))
+}
+
+/// Add packages to the end of the queue and start working through it
+#[tauri::command]
+pub async fn enqueue_downloads(
+    app: AppHandle,
+    items: Vec<QueueRequest>,
+) -> Result<Vec<QueueItem>, String> {
+    let (queue, downloads) = prepare(&app, items)?;
+    let added = queue.enqueue(downloads);
+    save(&app);
+    Ok(added.into_iter().map(Into::into).collect())
+}
+
+// Packages of a title newer than `installed_version`, or all of them
+fn title_requests(
+    result: FetchResult,
+    installed_version: Option<String>,
+) -> Result<Vec<QueueRequest>, String> {
+    let installed = installed_version
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+    let items: Vec<QueueRequest> = result
+        .results
+        .into_iter()
+        .filter(|package| installed.is_none_or(|v| compare_versions(&package.version, v).is_gt()))
+        .map(|package| QueueRequest {
+            game_title: result.game_title.clone(),
+            title_id: result.cleaned_title_id.clone(),
+            package,
+        })
+        .collect();
+    if items.is_empty() {
+        return Err(match installed {
+            Some(v) => format!("No updates newer than {} to queue", v),
+            None => "No updates to queue".to_string(),
+        });
+    }
+    Ok(items)
+}
+
+/// What `enqueue_title` would download, for the user to confirm first
+///
+/// Files already in the download folder at full size are left out of the
+/// totals; the estimate uses the bandwidth limit, if one is set.
+#[tauri::command]
+pub fn plan_title(
+    app: AppHandle,
+    result: FetchResult,
+    installed_version: Option<String>,
+) -> Result<DownloadPlan, String> {
+    let settings = settings::current(&app);
+    let downloads = title_requests(result, installed_version)?
+        .into_iter()
+        .map(|request| planned(&settings, request))
+        .collect();
+    let plan = DownloadPlan::new(downloads);
+    Ok(match bandwidth::get_bandwidth(app).effective_limit {
+        Some(limit) => plan.with_speed(limit),
+        None => plan,
+    })
+}
+
+/// Queue every package of a title, or only those newer than
+/// `installed_version`, as one group
+///
+/// Returns the group ID for `get_queue_group`.
+#[tauri::command]
+pub async fn enqueue_title(
+    app: AppHandle,
+    result: FetchResult,
+    installed_version: Option<String>,
+) -> Result<String, String> {
+    let (queue, downloads) = prepare(&app, title_requests(result, installed_version)?)?;
+    let group_id = queue.enqueue_group(downloads);
+    save(&app);
+    Ok(group_id)
+}
+
+/// Combined progress of the items queued together as `group_id`
+#[tauri::command]
+pub fn get_queue_group(group_id: String) -> Result<QueueGroup, String> {
+    let queue = queue()?;
+    let missing = || format!("No queue group {}", group_id);
+    let progress = queue.group_progress(&group_id).ok_or_else(missing)?;
+    let first = queue
+        .items()
+        .into_iter()
+        .find(|item| item.group_id.as_deref() == Some(group_id.as_str()))
+        .ok_or_else(missing)?;
+    Ok(QueueGroup {
+        group_id: group_id.clone(),
+        title_id: first.download.title_id,
+        game_title: first.download.game_title,
+        items: progress.items,
+        done: progress.done,
+        failed: progress.failed,
+        total_bytes: progress.total_bytes,
+        downloaded_bytes: progress.downloaded_bytes,
+        percent: progress.percent,
+    })
+}
+
+/// Every item in queue order, with live progress for the one downloading
+#[tauri::command]
+pub fn get_queue() -> Vec<QueueItem> {
+    queue()
+        .map(|queue| queue.items().into_iter().map(Into::into).collect())
+        .unwrap_or_default()
+}
+
+/// Move an item to a new position; only the order of waiting items matters
+#[tauri::command]
+pub fn move_queue_item(app: AppHandle, id: String, index: usize) -> Result<Vec<QueueItem>, String> {
+    queue()?.move_item(&id, index).map_err(|e| e.to_string())?;
+    save(&app);
+    Ok(get_queue())
+}
+
+/// Drop an item from the queue, cancelling it if it's downloading
+#[tauri::command]
+pub async fn remove_queue_item(app: AppHandle, id: String) -> Result<Vec<QueueItem>, String> {
+    let removed = queue()?.remove(&id).map_err(|e| e.to_string())?;
+    if removed.status == QueueStatus::Downloading {
+        if let Some(job_id) = removed.job_id {
+            cancel_job(&job_id).await?;
+        }
+    }
+    save(&app);
+    Ok(get_queue())
+}
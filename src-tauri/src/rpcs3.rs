@@ -0,0 +1,83 @@
+use crate::scan::{check_games, ScannedTitle};
+use crate::settings::{self, Settings};
+use ps3_update_core::scan_rpcs3;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Installed RPCS3 games and the updates each is missing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rpcs3Report {
+    /// RPCS3 folder that was scanned
+    pub path: String,
+    pub titles: Vec<ScannedTitle>,
+}
+
+/// Whether `path` holds RPCS3's data (its emulated `dev_hdd0` drive)
+pub fn is_rpcs3_dir(path: &Path) -> bool {
+    path.join("dev_hdd0").is_dir()
+}
+
+// Where RPCS3 keeps its data when installed the usual way, most likely first
+fn standard_paths() -> Vec<PathBuf> {
+    let mut paths = vec![];
+    // ~/.config/rpcs3 on Linux, ~/Library/Application Support/rpcs3 on macOS
+    if let Some(config) = dirs_next::config_dir() {
+        paths.push(config.join("rpcs3"));
+    }
+    if let Some(home) = dirs_next::home_dir() {
+        paths.push(home.join(".var/app/net.rpcs3.RPCS3/config/rpcs3"));
+        // The Windows build is portable and keeps its data next to rpcs3.exe
+        paths.push(home.join("RPCS3"));
+    }
+    if cfg!(windows) {
+        paths.push(PathBuf::from(r"C:\RPCS3"));
+        if let Some(program_files) = std::env::var_os("ProgramFiles") {
+            paths.push(PathBuf::from(program_files).join("RPCS3"));
+        }
+    }
+    paths
+}
+
+/// The RPCS3 folder to use: the one chosen in Settings, or else the first
+/// standard location that has RPCS3's data in it
+pub fn locate(settings: &Settings) -> Option<PathBuf> {
+    if !settings.rpcs3_path.trim().is_empty() {
+        return Some(PathBuf::from(settings.rpcs3_path.trim()));
+    }
+    standard_paths().into_iter().find(|path| is_rpcs3_dir(path))
+}
+
+#[tauri::command]
+pub fn locate_rpcs3(app: AppHandle) -> Option<String> {
+    locate(&settings::current(&app)).map(|path| path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn pick_rpcs3_directory(app: AppHandle) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let path = app
+        .dialog()
+        .file()
+        .set_title("Choose your RPCS3 folder")
+        .blocking_pick_folder();
+
+    Ok(path.map(|p| p.to_string()))
+}
+
+/// Scan the RPCS3 install and look up updates for every game in it
+///
+/// Sends `fetch://progress` events while the titles are looked up.
+#[tauri::command]
+pub async fn check_rpcs3_updates(app: AppHandle) -> Result<Rpcs3Report, String> {
+    let root = locate(&settings::current(&app))
+        .ok_or("RPCS3 wasn't found; choose its folder in Settings")?;
+    let path = root.to_string_lossy().to_string();
+    let games = tauri::async_runtime::spawn_blocking(move || scan_rpcs3(&root))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    let titles = check_games(&app, games).await?;
+    Ok(Rpcs3Report { path, titles })
+}
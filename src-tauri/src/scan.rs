@@ -0,0 +1,137 @@
+use crate::{emit_batch_progress, new_fetcher, PackageInfo, BATCH_CONCURRENCY};
+use ps3_update_core::{scan_path, InstallKind, InstalledGame, OutdatedTitle, PS3UpdateError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// A game found on disk, with the updates it's missing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScannedTitle {
+    pub title_id: String,
+    pub title: String,
+    /// "hdd", "disc" or "iso"
+    pub kind: String,
+    /// Folder or image the game was found in
+    pub path: String,
+    pub installed_version: Option<String>,
+    pub latest_version: Option<String>,
+    /// Updates newer than the installed version, oldest first (install order)
+    pub packages: Vec<PackageInfo>,
+    pub total_bytes: u64,
+    /// Set when the update list couldn't be fetched
+    pub error: Option<String>,
+}
+
+impl ScannedTitle {
+    fn new(game: InstalledGame, outdated: OutdatedTitle) -> Self {
+        let kind = match game.kind {
+            InstallKind::Hdd => "hdd",
+            InstallKind::Disc => "disc",
+            InstallKind::Iso => "iso",
+        };
+        ScannedTitle {
+            title_id: outdated.title_id,
+            title: outdated.title,
+            kind: kind.to_string(),
+            path: game.path.to_string_lossy().to_string(),
+            installed_version: outdated.installed_version,
+            latest_version: outdated.latest_version,
+            packages: outdated.packages.into_iter().map(|p| p.into()).collect(),
+            total_bytes: outdated.total_bytes,
+            error: outdated.error,
+        }
+    }
+}
+
+impl From<ScannedTitle> for OutdatedTitle {
+    fn from(title: ScannedTitle) -> Self {
+        OutdatedTitle {
+            title_id: title.title_id,
+            title: title.title,
+            installed_version: title.installed_version,
+            latest_version: title.latest_version,
+            packages: title.packages.into_iter().map(|p| p.into()).collect(),
+            total_bytes: title.total_bytes,
+            error: title.error,
+        }
+    }
+}
+
+/// Look up updates for scanned games, sending a `fetch://progress` event as
+/// each title finishes
+///
+/// Titles whose lookup failed stay in the list with their error; titles the
+/// server has no updates for are up to date.
+pub async fn check_games(
+    app: &AppHandle,
+    games: Vec<InstalledGame>,
+) -> Result<Vec<ScannedTitle>, String> {
+    let fetcher = new_fetcher()?;
+    let title_ids: Vec<&str> = games.iter().map(|g| g.title_id.as_str()).collect();
+    let results = fetcher
+        .fetch_updates_batch(
+            &title_ids,
+            BATCH_CONCURRENCY,
+            |done, total, title_id, result| {
+                emit_batch_progress(app, done, total, title_id, result);
+            },
+        )
+        .await;
+
+    // Results come back once per distinct title ID
+    let results: HashMap<String, _> = results.into_iter().collect();
+    Ok(games
+        .into_iter()
+        .map(|game| {
+            let result = results.get(&fetcher.resolve_title_id(&game.title_id));
+            let outdated = match result {
+                Some(Ok(result)) => OutdatedTitle::from_fetch(&game, result),
+                // No update list means no patches: nothing is missing
+                Some(Err(PS3UpdateError::NoUpdatesFound { title_id, .. })) => {
+                    OutdatedTitle::from_fetch(
+                        &game,
+                        &ps3_update_core::FetchResult {
+                            results: vec![],
+                            error: None,
+                            game_title: fetcher.title_name(title_id).unwrap_or_default(),
+                            cleaned_title_id: title_id.clone(),
+                        },
+                    )
+                }
+                failed => OutdatedTitle {
+                    title_id: game.title_id.clone(),
+                    title: if game.title.is_empty() {
+                        fetcher.title_name(&game.title_id).unwrap_or_default()
+                    } else {
+                        game.title.clone()
+                    },
+                    installed_version: game.app_version.clone(),
+                    error: Some(match failed {
+                        Some(Err(e)) => e.to_string(),
+                        _ => format!("{} was not looked up", game.title_id),
+                    }),
+                    ..Default::default()
+                },
+            };
+            ScannedTitle::new(game, outdated)
+        })
+        .collect())
+}
+
+/// Find the games in a dropped folder or ISO and the updates each is missing
+///
+/// `path` may be an RPCS3 directory, a single `.iso`, or a folder of ISOs and
+/// folder-format dumps.
+#[tauri::command]
+pub async fn scan_folder(app: AppHandle, path: String) -> Result<Vec<ScannedTitle>, String> {
+    let root = PathBuf::from(&path);
+    if !root.exists() {
+        return Err(format!("{} does not exist", path));
+    }
+    let games = tauri::async_runtime::spawn_blocking(move || scan_path(&root))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    check_games(&app, games).await
+}
@@ -0,0 +1,267 @@
+use crate::settings::{self, Settings};
+use crate::{new_fetcher, notify, rpcs3, PackageInfo};
+use chrono::{Local, Timelike};
+use once_cell::sync::Lazy;
+use ps3_update_core::{
+    check_watchlist, scan_rpcs3, unix_timestamp, DiscoveredUpdate, TitleId, WatchState,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// In the app data folder; the versions seen of each title, kept between runs
+const STATE_FILE: &str = "watch.json";
+
+// Event carrying a `CheckReport` after every check
+const UPDATES_EVENT: &str = "schedule://updates";
+
+// Window whose taskbar or dock icon carries the badge
+const MAIN_WINDOW: &str = "main";
+
+// How often the scheduler wakes to see whether a check is due
+const TICK: Duration = Duration::from_secs(60);
+
+pub const MAX_INTERVAL_HOURS: u32 = 24 * 7;
+
+// Updates found since the user last cleared them, newest first
+static UNSEEN: Lazy<Mutex<Vec<NewUpdate>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// Unix time the last check started, read from the state file on first use
+static LAST_CHECK: Lazy<Mutex<Option<u64>>> = Lazy::new(|| Mutex::new(None));
+
+// One check at a time, whether it was scheduled or asked for
+static CHECKING: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+/// When the background checker runs and which games it looks at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduleSettings {
+    pub enabled: bool,
+    pub interval_hours: u32,
+    /// Title IDs to check for new updates
+    pub watchlist: Vec<String>,
+    /// Also check every game installed in RPCS3
+    pub include_rpcs3: bool,
+    /// Skip checks from `quiet_start` until `quiet_end` (local hours, 0-23)
+    pub quiet_hours: bool,
+    pub quiet_start: u8,
+    pub quiet_end: u8,
+}
+
+impl Default for ScheduleSettings {
+    fn default() -> Self {
+        ScheduleSettings {
+            enabled: false,
+            interval_hours: 6,
+            watchlist: vec![],
+            include_rpcs3: false,
+            quiet_hours: false,
+            quiet_start: 22,
+            quiet_end: 7,
+        }
+    }
+}
+
+impl ScheduleSettings {
+    /// Whether `hour` falls in the quiet hours, which may run past midnight
+    pub fn is_quiet(&self, hour: u8) -> bool {
+        if !self.quiet_hours || self.quiet_start == self.quiet_end {
+            return false;
+        }
+        if self.quiet_start < self.quiet_end {
+            (self.quiet_start..self.quiet_end).contains(&hour)
+        } else {
+            hour >= self.quiet_start || hour < self.quiet_end
+        }
+    }
+
+    pub(crate) fn validated(mut self) -> Result<Self, String> {
+        if !(1..=MAX_INTERVAL_HOURS).contains(&self.interval_hours) {
+            return Err(format!(
+                "Check interval must be between 1 and {} hours",
+                MAX_INTERVAL_HOURS
+            ));
+        }
+        if self.quiet_start > 23 || self.quiet_end > 23 {
+            return Err("Quiet hours must be between 0 and 23".to_string());
+        }
+        let mut watchlist: Vec<String> = vec![];
+        for raw in self.watchlist.iter().filter(|t| !t.trim().is_empty()) {
+            let title_id: String = TitleId::parse(raw).map_err(|e| e.to_string())?.into();
+            if !watchlist.contains(&title_id) {
+                watchlist.push(title_id);
+            }
+        }
+        self.watchlist = watchlist;
+        Ok(self)
+    }
+}
+
+/// An update a check found for the first time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewUpdate {
+    pub title_id: String,
+    pub game_title: String,
+    pub package: PackageInfo,
+    /// Unix time of the check that found it
+    pub discovered_at: u64,
+}
+
+impl From<DiscoveredUpdate> for NewUpdate {
+    fn from(update: DiscoveredUpdate) -> Self {
+        NewUpdate {
+            title_id: update.title_id,
+            game_title: update.game_title,
+            package: update.package.into(),
+            discovered_at: update.discovered_at,
+        }
+    }
+}
+
+/// Outcome of one check
+///
+/// Payload of the `schedule://updates` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckReport {
+    /// Updates this check found
+    pub discovered: Vec<NewUpdate>,
+    /// Everything found since the list was last cleared, newest first
+    pub unseen: Vec<NewUpdate>,
+    /// Titles checked for the first time; their current versions aren't reported
+    pub new_titles: Vec<String>,
+    /// `(title_id, error)` for titles that couldn't be checked
+    pub errors: Vec<(String, String)>,
+    pub checked_at: u64,
+}
+
+fn state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(STATE_FILE))
+}
+
+// When the last check started; before the first one this run, the newest
+// check recorded in the state file
+fn last_check(app: &AppHandle) -> u64 {
+    let mut last = LAST_CHECK.lock().unwrap();
+    *last.get_or_insert_with(|| {
+        state_path(app)
+            .ok()
+            .and_then(|path| WatchState::load(path).ok())
+            .and_then(|state| state.titles.values().map(|t| t.last_checked).max())
+            .unwrap_or(0)
+    })
+}
+
+// The watchlist followed by the RPCS3 library, without repeats
+async fn watched_titles(settings: &Settings) -> Vec<String> {
+    let mut title_ids = settings.schedule.watchlist.clone();
+    if settings.schedule.include_rpcs3 {
+        if let Some(root) = rpcs3::locate(settings) {
+            let games = tauri::async_runtime::spawn_blocking(move || scan_rpcs3(&root))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|games| games.map_err(|e| e.to_string()));
+            match games {
+                Ok(games) => title_ids.extend(games.into_iter().map(|g| g.title_id)),
+                Err(e) => eprintln!("Failed to scan RPCS3 for the scheduled check: {}", e),
+            }
+        }
+    }
+    let mut seen = std::collections::HashSet::new();
+    title_ids.retain(|id| seen.insert(id.clone()));
+    title_ids
+}
+
+fn show_badge(app: &AppHandle, count: usize) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.set_badge_count((count > 0).then_some(count as i64));
+    }
+}
+
+async fn check(app: &AppHandle) -> Result<CheckReport, String> {
+    let _checking = CHECKING.lock().await;
+    let checked_at = unix_timestamp();
+    // Counted even if it fails, so a check that can't reach the server
+    // waits for the next interval rather than retrying every minute
+    *LAST_CHECK.lock().unwrap() = Some(checked_at);
+
+    let settings = settings::current(app);
+    let title_ids = watched_titles(&settings).await;
+    let path = state_path(app)?;
+    let mut state = WatchState::load(&path).map_err(|e| e.to_string())?;
+    let fetcher = new_fetcher()?;
+    let report = check_watchlist(&fetcher, &mut state, &title_ids).await;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    state.save(&path).map_err(|e| e.to_string())?;
+
+    let discovered: Vec<NewUpdate> = report.discovered.into_iter().map(|u| u.into()).collect();
+    let unseen = {
+        let mut unseen = UNSEEN.lock().unwrap();
+        unseen.splice(0..0, discovered.iter().cloned());
+        unseen.clone()
+    };
+    show_badge(app, unseen.len());
+    notify::updates_found(app, &discovered);
+
+    let report = CheckReport {
+        discovered,
+        unseen,
+        new_titles: report.new_titles,
+        errors: report
+            .errors
+            .into_iter()
+            .map(|failure| (failure.target, failure.error))
+            .collect(),
+        checked_at,
+    };
+    if let Err(e) = app.emit(UPDATES_EVENT, &report) {
+        eprintln!("Failed to emit check report: {}", e);
+    }
+    Ok(report)
+}
+
+/// Check the watched games whenever a check is due
+///
+/// Nothing is checked while scheduling is off or in quiet hours; a check
+/// that falls due in quiet hours runs once they end.
+pub async fn run(app: AppHandle) {
+    loop {
+        tokio::time::sleep(TICK).await;
+        let schedule = settings::current(&app).schedule;
+        if !schedule.enabled || schedule.is_quiet(Local::now().hour() as u8) {
+            continue;
+        }
+        let interval = u64::from(schedule.interval_hours) * 60 * 60;
+        if unix_timestamp().saturating_sub(last_check(&app)) < interval {
+            continue;
+        }
+        if let Err(e) = check(&app).await {
+            eprintln!("Scheduled check failed: {}", e);
+        }
+    }
+}
+
+/// Check the watched games now, whatever the schedule says
+///
+/// Sends the same `schedule://updates` event as a scheduled check.
+#[tauri::command]
+pub async fn check_watchlist_now(app: AppHandle) -> Result<CheckReport, String> {
+    check(&app).await
+}
+
+/// Updates found since the list was last cleared, newest first
+#[tauri::command]
+pub fn list_new_updates() -> Vec<NewUpdate> {
+    UNSEEN.lock().unwrap().clone()
+}
+
+/// Mark the found updates as seen and take the badge down
+#[tauri::command]
+pub fn clear_new_updates(app: AppHandle) {
+    UNSEEN.lock().unwrap().clear();
+    show_badge(&app, 0);
+}
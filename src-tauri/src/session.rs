@@ -0,0 +1,121 @@
+use crate::{tray, ProgressInfo};
+use once_cell::sync::Lazy;
+use ps3_update_core::format_size;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Emitter, Manager};
+
+// Event carrying a `SessionProgress` whenever any job moves
+const SESSION_EVENT: &str = "download://session";
+
+// Window whose taskbar entry shows the overall progress
+const MAIN_WINDOW: &str = "main";
+
+// Latest progress of every job in the session, including finished ones the
+// frontend has since removed from the download manager
+static SESSION: Lazy<Mutex<HashMap<String, ProgressInfo>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// All downloads since the last time none were running, added together
+///
+/// Payload of the `download://session` event.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionProgress {
+    pub total_bytes: u64,
+    pub downloaded_bytes: u64,
+    pub percent: f64,
+    /// Combined speed of the downloads still running
+    pub speed_bytes_per_sec: f64,
+    pub speed_human: String,
+    /// Finished downloads, failed ones included
+    pub files_done: usize,
+    pub files_failed: usize,
+    pub files_remaining: usize,
+    /// Every remaining download is paused
+    pub paused: bool,
+}
+
+impl SessionProgress {
+    fn from_jobs<'a>(jobs: impl Iterator<Item = &'a ProgressInfo>) -> Self {
+        let mut session = SessionProgress::default();
+        let mut all_paused = true;
+        for job in jobs {
+            // A copy that was already there took no downloading
+            if !job.already_downloaded {
+                session.total_bytes += job.total;
+                session.downloaded_bytes += job.downloaded;
+            }
+            if job.done {
+                session.files_done += 1;
+                session.files_failed += usize::from(job.error.is_some());
+            } else {
+                session.files_remaining += 1;
+                session.speed_bytes_per_sec += job.speed_bytes_per_sec;
+                all_paused &= job.paused;
+            }
+        }
+        if session.total_bytes > 0 {
+            session.percent = session.downloaded_bytes as f64 / session.total_bytes as f64 * 100.0;
+        }
+        session.speed_human = format!("{}/s", format_size(session.speed_bytes_per_sec as u64));
+        session.paused = session.files_remaining > 0 && all_paused;
+        session
+    }
+}
+
+/// Fold jobs that moved into the session and return its new totals
+///
+/// A job starting after every earlier one finished begins a new session.
+pub fn record(changed: &[(String, ProgressInfo)]) -> SessionProgress {
+    let mut jobs = SESSION.lock().unwrap();
+    let finished = jobs.values().all(|job| job.done);
+    let starting = changed
+        .iter()
+        .any(|(id, progress)| !progress.done && !jobs.contains_key(id));
+    if finished && starting {
+        jobs.clear();
+    }
+    for (id, progress) in changed {
+        jobs.insert(id.clone(), progress.clone());
+    }
+    SessionProgress::from_jobs(jobs.values())
+}
+
+/// Drop a cancelled job from the session
+pub fn forget(job_id: &str) {
+    SESSION.lock().unwrap().remove(job_id);
+}
+
+/// The session's totals as they stand
+pub fn current() -> SessionProgress {
+    SessionProgress::from_jobs(SESSION.lock().unwrap().values())
+}
+
+/// Send the session's totals to the frontend, tray and taskbar
+pub fn show(app: &AppHandle, session: &SessionProgress) {
+    if let Err(e) = app.emit(SESSION_EVENT, session) {
+        eprintln!("Failed to emit session progress: {}", e);
+    }
+    tray::refresh(app, session);
+
+    let state = if session.files_remaining == 0 {
+        ProgressBarState {
+            status: Some(ProgressBarStatus::None),
+            progress: None,
+        }
+    } else {
+        ProgressBarState {
+            status: Some(if session.paused {
+                ProgressBarStatus::Paused
+            } else {
+                ProgressBarStatus::Normal
+            }),
+            progress: Some(session.percent as u64),
+        }
+    };
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.set_progress_bar(state);
+    }
+}
@@ -0,0 +1,286 @@
+use crate::console::ConsoleSettings;
+use crate::rpcs3::is_rpcs3_dir;
+use crate::schedule::ScheduleSettings;
+use once_cell::sync::Lazy;
+use ps3_update_core::{DownloadMode, DownloadOptions, FolderTemplate, NamingScheme};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+// In the app config folder; the store plugin has `settings.json` in the data folder
+const SETTINGS_FILE: &str = "preferences.json";
+
+// Store file the frontend kept its settings in before they moved here
+const LEGACY_STORE_FILE: &str = "settings.json";
+
+pub const MAX_PARTS: usize = 16;
+
+/// Layout of the saved settings; bump it and add a step to `MIGRATIONS`
+/// whenever a field is renamed, moved or changes meaning
+pub const SETTINGS_VERSION: u32 = 1;
+
+// `MIGRATIONS[n]` brings a version `n` file up to version `n + 1`
+const MIGRATIONS: [fn(&mut Value); SETTINGS_VERSION as usize] = [
+    // Files from before settings were versioned already have version 1's layout
+    |_| {},
+];
+
+// Settings as last loaded or saved, shared by every command
+static CURRENT: Lazy<Mutex<Option<Settings>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Always `SETTINGS_VERSION` once loaded
+    pub version: u32,
+    pub download_path: String,
+    pub multi_part: bool,
+    pub num_parts: usize,
+    pub naming: NamingScheme,
+    /// Name of each game's folder under the download folder
+    pub folder_template: String,
+    /// Put game folders in a folder per region (EU, US, JP…), unless the
+    /// template already uses `{region}`
+    pub by_region: bool,
+    /// Keep accented and non-Latin letters in game folder names
+    pub unicode_names: bool,
+    /// Bytes per second each new download starts with; 0 is unlimited
+    pub rate_limit: u64,
+    /// Bytes per second for all downloads together; 0 is unlimited
+    pub global_rate_limit: u64,
+    /// What all downloads together are held to while slow mode is on
+    pub slow_mode_limit: u64,
+    pub verify: bool,
+    /// Show an OS notification when a download finishes or fails in the background
+    pub notify: bool,
+    /// Pause the queue while the OS reports a metered connection
+    pub pause_on_metered: bool,
+    /// Offer to fetch updates when a title ID is copied to the clipboard
+    pub watch_clipboard: bool,
+    /// RPCS3 folder to check for outdated games; empty looks in the usual places
+    pub rpcs3_path: String,
+    pub console: ConsoleSettings,
+    /// Background checks for new updates
+    pub schedule: ScheduleSettings,
+    pub theme: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            version: SETTINGS_VERSION,
+            download_path: default_download_path(),
+            multi_part: true,
+            num_parts: 4,
+            naming: NamingScheme::Original,
+            folder_template: FolderTemplate::DEFAULT.to_string(),
+            by_region: false,
+            unicode_names: false,
+            rate_limit: 0,
+            global_rate_limit: 0,
+            slow_mode_limit: 256 * 1024,
+            verify: false,
+            notify: true,
+            pause_on_metered: false,
+            watch_clipboard: false,
+            rpcs3_path: String::new(),
+            console: ConsoleSettings::default(),
+            schedule: ScheduleSettings::default(),
+            theme: "classic".to_string(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn download_mode(&self) -> DownloadMode {
+        if self.multi_part {
+            DownloadMode::from_parts(self.num_parts)
+        } else {
+            DownloadMode::Direct
+        }
+    }
+
+    pub fn folder_template(&self) -> FolderTemplate {
+        let mut template = FolderTemplate::parse(&self.folder_template).unwrap_or_default();
+        if self.by_region && !template.as_str().contains("{region}") {
+            template = FolderTemplate::parse(&format!("{{region}}/{}", template.as_str()))
+                .unwrap_or(template);
+        }
+        template.with_unicode(self.unicode_names)
+    }
+
+    pub fn download_options(&self) -> DownloadOptions {
+        // Keep an intact copy already in the folder rather than overwrite it
+        let options = DownloadOptions::new(self.download_mode())
+            .with_naming(self.naming)
+            .with_rate_limit(self.rate_limit)
+            .with_skip_existing();
+        if self.verify {
+            options.with_verification()
+        } else {
+            options
+        }
+    }
+
+    fn validated(mut self) -> Result<Self, String> {
+        self.version = SETTINGS_VERSION;
+        if !(1..=MAX_PARTS).contains(&self.num_parts) {
+            return Err(format!("Part count must be between 1 and {}", MAX_PARTS));
+        }
+        if self.download_path.trim().is_empty() {
+            self.download_path = default_download_path();
+        }
+        self.folder_template = FolderTemplate::parse(&self.folder_template)
+            .map_err(|e| e.to_string())?
+            .into();
+        if self.slow_mode_limit == 0 {
+            return Err("Slow mode needs a speed limit above 0".to_string());
+        }
+        if self.console.port == 0 {
+            return Err("Console FTP port can't be 0".to_string());
+        }
+        let rpcs3_path = self.rpcs3_path.trim();
+        if !rpcs3_path.is_empty() && !is_rpcs3_dir(Path::new(rpcs3_path)) {
+            return Err(format!(
+                "{} isn't an RPCS3 folder (it has no dev_hdd0)",
+                rpcs3_path
+            ));
+        }
+        self.schedule = self.schedule.validated()?;
+        Ok(self)
+    }
+}
+
+pub fn default_download_path() -> String {
+    dirs_next::download_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .to_string_lossy()
+        .to_string()
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(SETTINGS_FILE))
+}
+
+// Pick up what the frontend saved through the store plugin before
+fn migrate_legacy(app: &AppHandle) -> Settings {
+    let mut settings = Settings::default();
+    let Some(store) = app
+        .path()
+        .app_data_dir()
+        .ok()
+        .and_then(|dir| std::fs::read_to_string(dir.join(LEGACY_STORE_FILE)).ok())
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+    else {
+        return settings;
+    };
+    if let Some(path) = store["downloadPath"].as_str().filter(|p| !p.is_empty()) {
+        settings.download_path = path.to_string();
+    }
+    if let Some(multi_part) = store["multiPart"].as_bool() {
+        settings.multi_part = multi_part;
+    }
+    if let Some(theme) = store["theme"].as_str() {
+        settings.theme = theme.to_string();
+    }
+    settings
+}
+
+// Copy the settings file aside before it's replaced, keeping the first copy
+// made under each name
+fn back_up(path: &Path, suffix: &str) {
+    let backup = path.with_extension(format!("{}.json", suffix));
+    if backup.exists() {
+        return;
+    }
+    if let Err(e) = std::fs::copy(path, &backup) {
+        eprintln!("Failed to back up {}: {}", path.display(), e);
+    }
+}
+
+// Bring saved settings up to `SETTINGS_VERSION`, backing up the file first
+//
+// A file from a newer release keeps the fields this one knows; the backup
+// holds the rest for when that release runs again.
+fn migrate(path: &Path, mut value: Value) -> Result<Value, String> {
+    if !value.is_object() {
+        return Err("not a settings object".to_string());
+    }
+    let version = value["version"].as_u64().unwrap_or(0);
+    if version == u64::from(SETTINGS_VERSION) {
+        return Ok(value);
+    }
+    back_up(path, &format!("v{}", version));
+    if let Some(steps) = MIGRATIONS.get(version as usize..) {
+        for step in steps {
+            step(&mut value);
+        }
+    } else {
+        eprintln!(
+            "{} is from a newer version of the app (settings version {}); a copy was kept",
+            path.display(),
+            version
+        );
+    }
+    value["version"] = SETTINGS_VERSION.into();
+    Ok(value)
+}
+
+fn read(app: &AppHandle) -> Result<Settings, String> {
+    let path = settings_path(app)?;
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(migrate_legacy(app)),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    // Anything unreadable is kept aside before a save can replace it
+    let invalid = |e: String| {
+        back_up(&path, "invalid");
+        format!("Failed to read {}: {}", path.display(), e)
+    };
+    let value: Value = serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+    let migrated = value["version"] != Value::from(SETTINGS_VERSION);
+    let value = migrate(&path, value).map_err(&invalid)?;
+    let settings: Settings = serde_json::from_value(value).map_err(|e| invalid(e.to_string()))?;
+    if migrated {
+        write(app, &settings)?;
+    }
+    Ok(settings)
+}
+
+fn write(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    let path = settings_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let text = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(&path, text).map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+}
+
+/// The saved settings, read from disk on first use
+pub fn current(app: &AppHandle) -> Settings {
+    let mut current = CURRENT.lock().unwrap();
+    current
+        .get_or_insert_with(|| read(app).unwrap_or_default())
+        .clone()
+}
+
+#[tauri::command]
+pub fn load_settings(app: AppHandle) -> Result<Settings, String> {
+    let settings = read(&app)?;
+    *CURRENT.lock().unwrap() = Some(settings.clone());
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn save_settings(app: AppHandle, settings: Settings) -> Result<Settings, String> {
+    let settings = settings.validated()?;
+    write(&app, &settings)?;
+    *CURRENT.lock().unwrap() = Some(settings.clone());
+    crate::bandwidth::apply(&app, &settings)?;
+    crate::queue::apply(&settings);
+    Ok(settings)
+}
@@ -0,0 +1,46 @@
+use crate::{download_manager, queue, DOWNLOAD_PATHS};
+use std::time::Duration;
+use tauri::AppHandle;
+
+// Time for chunks already on their way to be written once jobs are paused
+const SETTLE: Duration = Duration::from_millis(300);
+
+/// Stop every running download before the app exits
+///
+/// The queue's downloads are paused and saved, so the next start resumes
+/// them. Downloads started on their own are cancelled and their partial
+/// files deleted rather than left half-written.
+pub fn stop_downloads(app: &AppHandle) {
+    let Ok(manager) = download_manager() else {
+        return;
+    };
+    let running: Vec<String> = manager
+        .jobs()
+        .into_iter()
+        .filter(|(_, progress)| !progress.done)
+        .map(|(job_id, _)| job_id)
+        .collect();
+    if running.is_empty() {
+        return;
+    }
+    // A paused job stops after the chunk it's writing
+    for job_id in &running {
+        let _ = manager.pause(job_id);
+    }
+    std::thread::sleep(SETTLE);
+
+    queue::suspend(app);
+    let queued = queue::active_jobs();
+    let mut paths = DOWNLOAD_PATHS.lock().unwrap();
+    for job_id in running.iter().filter(|id| !queued.contains(*id)) {
+        manager.remove_job(job_id);
+        let Some(path) = paths.remove(job_id) else {
+            continue;
+        };
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => eprintln!("Failed to delete partial file {}: {}", path.display(), e),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use once_cell::sync::Lazy;
+use ps3_update_core::{unix_timestamp, UpdateFetcher};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+// Event carrying a `ServerStatus` whenever the server goes up or down
+const STATUS_EVENT: &str = "server://status";
+
+// Time between checks while the server answers
+const ONLINE_INTERVAL: Duration = Duration::from_secs(60);
+
+// Time between checks while it doesn't, so coming back shows up quickly
+const OFFLINE_INTERVAL: Duration = Duration::from_secs(15);
+
+// Result of the latest check, shared with the command
+static LATEST: Lazy<Mutex<Option<ServerStatus>>> = Lazy::new(|| Mutex::new(None));
+
+/// Whether the update server answered the latest check
+///
+/// Payload of the `server://status` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerStatus {
+    pub online: bool,
+    /// Round trip of the check, when the server answered
+    pub latency_ms: Option<u64>,
+    /// Unix time of the check
+    pub checked_at: u64,
+}
+
+async fn probe(fetcher: &UpdateFetcher) -> ServerStatus {
+    let started = Instant::now();
+    let online = fetcher.check_server_status().await;
+    ServerStatus {
+        online,
+        latency_ms: online.then(|| started.elapsed().as_millis() as u64),
+        checked_at: unix_timestamp(),
+    }
+}
+
+// Keep the new status, telling the frontend if the server came up or went down
+fn record(app: &AppHandle, status: ServerStatus) {
+    let changed = LATEST
+        .lock()
+        .unwrap()
+        .replace(status.clone())
+        .is_none_or(|last| last.online != status.online);
+    if changed {
+        if let Err(e) = app.emit(STATUS_EVENT, &status) {
+            eprintln!("Failed to emit server status: {}", e);
+        }
+    }
+}
+
+/// Check the update server for as long as the app runs
+pub async fn watch(app: AppHandle) {
+    let fetcher = match UpdateFetcher::new() {
+        Ok(fetcher) => fetcher,
+        Err(e) => {
+            eprintln!("Server status checks are off: {}", e);
+            return;
+        }
+    };
+    loop {
+        let status = probe(&fetcher).await;
+        let interval = if status.online {
+            ONLINE_INTERVAL
+        } else {
+            OFFLINE_INTERVAL
+        };
+        record(&app, status);
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// The latest status, checking now if there hasn't been a check yet
+///
+/// Later changes arrive as `server://status` events.
+#[tauri::command]
+pub async fn check_server_status(app: AppHandle) -> Result<ServerStatus, String> {
+    if let Some(status) = LATEST.lock().unwrap().clone() {
+        return Ok(status);
+    }
+    let fetcher = UpdateFetcher::new().map_err(|e| e.to_string())?;
+    let status = probe(&fetcher).await;
+    record(&app, status.clone());
+    Ok(status)
+}
@@ -0,0 +1,83 @@
+use ps3_update_core::TitleId;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::AppHandle;
+
+/// A line of a title list that held no valid title ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedLine {
+    /// 1-based
+    pub line: usize,
+    pub text: String,
+}
+
+/// Title IDs read from a list file, ready for a batch fetch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TitleList {
+    /// Normalised (`BLES00779`), in file order, without repeats
+    pub title_ids: Vec<String>,
+    pub skipped: Vec<SkippedLine>,
+    /// Title IDs listed more than once
+    pub duplicates: usize,
+}
+
+/// Read title IDs from a text or CSV list
+///
+/// Each line holds one title ID, either on its own (`BLES-00779`, any case)
+/// or as one column of a CSV row; the first valid ID on a line is taken.
+/// `#` starts a comment. Lines without an ID, such as a CSV header, are
+/// reported rather than failing the import.
+pub fn parse_title_list(text: &str) -> TitleList {
+    let mut list = TitleList::default();
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let title_id = TitleId::parse(line)
+            .ok()
+            .or_else(|| TitleId::find_all(line).into_iter().next());
+        let Some(title_id) = title_id else {
+            list.skipped.push(SkippedLine {
+                line: index + 1,
+                text: raw.trim().to_string(),
+            });
+            continue;
+        };
+        let title_id: String = title_id.into();
+        if list.title_ids.contains(&title_id) {
+            list.duplicates += 1;
+        } else {
+            list.title_ids.push(title_id);
+        }
+    }
+    list
+}
+
+#[tauri::command]
+pub async fn pick_title_list(app: AppHandle) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let path = app
+        .dialog()
+        .file()
+        .set_title("Import a title list")
+        .add_filter("Title lists", &["txt", "csv"])
+        .blocking_pick_file();
+
+    Ok(path.map(|p| p.to_string()))
+}
+
+/// Read a `.txt` or `.csv` list of title IDs for the batch fetch
+#[tauri::command]
+pub async fn import_title_list(path: String) -> Result<TitleList, String> {
+    let text = tokio::fs::read(Path::new(&path))
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    // Spreadsheet exports aren't always UTF-8
+    let list = parse_title_list(&String::from_utf8_lossy(&text));
+    if list.title_ids.is_empty() {
+        return Err(format!("No title IDs found in {}", path));
+    }
+    Ok(list)
+}
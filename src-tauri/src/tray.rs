@@ -0,0 +1,106 @@
+use crate::download_manager;
+use crate::session::SessionProgress;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+
+const TRAY_ID: &str = "main";
+
+// The window the tray brings back
+const MAIN_WINDOW: &str = "main";
+
+// Disabled first menu entry showing the download summary, kept so it can be
+// updated; the tooltip mirrors it where the platform has one
+struct TrayStatus(MenuItem<Wry>);
+
+/// Add the tray icon with its summary line and actions
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let idle = summary(&SessionProgress::default());
+    let status = MenuItem::with_id(app, "status", &idle, false, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, "show", "Show window", true, None::<&str>)?,
+            &MenuItem::with_id(app, "pause_all", "Pause all", true, None::<&str>)?,
+            &MenuItem::with_id(app, "resume_all", "Resume all", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?,
+        ],
+    )?;
+
+    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(&idle)
+        .menu(&menu)
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    app.manage(TrayStatus(status));
+    Ok(())
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        "show" => {
+            if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+                let _ = window.unminimize();
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }
+        // The progress loop picks up the change and updates the summary
+        "pause_all" => set_all_paused(true),
+        "resume_all" => set_all_paused(false),
+        "quit" => app.exit(0),
+        _ => {}
+    }
+}
+
+fn set_all_paused(paused: bool) {
+    let Ok(manager) = download_manager() else {
+        return;
+    };
+    for (job_id, progress) in manager.jobs() {
+        if progress.done {
+            continue;
+        }
+        let result = if paused {
+            manager.pause(&job_id)
+        } else {
+            manager.resume(&job_id)
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to change job {}: {}", job_id, e);
+        }
+    }
+}
+
+// "2 downloads, 47%, 5.2 MB/s" for the current session
+fn summary(session: &SessionProgress) -> String {
+    let count = match session.files_remaining {
+        0 => return "No downloads".to_string(),
+        1 => "1 download".to_string(),
+        n => format!("{} downloads", n),
+    };
+    if session.paused {
+        return format!("{} paused, {:.0}%", count, session.percent);
+    }
+    format!(
+        "{}, {:.0}%, {}",
+        count, session.percent, session.speed_human
+    )
+}
+
+/// Show the session's progress in the tray
+pub fn refresh(app: &AppHandle, session: &SessionProgress) {
+    let text = summary(session);
+    if let Some(status) = app.try_state::<TrayStatus>() {
+        let _ = status.0.set_text(&text);
+    }
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(&text));
+    }
+}
@@ -1,8 +1,43 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Store } from "@tauri-apps/plugin-store";
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWebview } from "@tauri-apps/api/webview";
 import "./App.css";
-import { FetchResult, PackageInfo, DownloadJob, ProgressInfo } from "./types";
+import {
+  BatchProgress,
+  ClipboardTitle,
+  CheckReport,
+  ClipboardTitles,
+  ConsoleSettings,
+  CsvExport,
+  FetchResult,
+  HashProgress,
+  HashReport,
+  PackageInfo,
+  DownloadJob,
+  DownloadPlan,
+  DownloadPhase,
+  ProgressEvent,
+  NetworkState,
+  NewUpdate,
+  ProgressInfo,
+  PushEvent,
+  QueueItem,
+  QueueGroup,
+  PartialDownload,
+  BandwidthState,
+  QueueRequest,
+  Rpcs3Report,
+  ScannedTitle,
+  ScheduleSettings,
+  ServerStatus,
+  SessionProgress,
+  DownloadSummary,
+  Settings,
+  TitleList,
+  TitleSuggestion,
+  VerifyResult,
+} from "./types";
 import { Ps3WaveBackground } from "./components/Ps3WaveBackground";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
@@ -22,8 +57,6 @@ import { ScrollArea } from "@/components/ui/scroll-area";
 import { X } from "lucide-react";
 import ps3FactsData from "./data/ps3-facts.json";
 
-// Store will be initialized in the component
-
 const PS3_THEMES = {
   classic: { name: "Classic Blue", color: "#00C8FF" },
   cyan: { name: "Cyan Wave", color: "#00FFFF" },
@@ -37,20 +70,109 @@ const PS3_THEMES = {
 
 type ThemeKey = keyof typeof PS3_THEMES;
 
+const MAX_PARTS = 16;
+
+const NAMING_SCHEMES: { key: Settings["naming"]; name: string }[] = [
+  { key: "Original", name: "Original" },
+  { key: "Descriptive", name: "Descriptive" },
+  { key: "ContentId", name: "Content ID" },
+];
+
+const DEFAULT_SETTINGS: Settings = {
+  version: 1,
+  download_path: "",
+  multi_part: true,
+  num_parts: 4,
+  naming: "Original",
+  folder_template: "{title} ({title_id})",
+  by_region: false,
+  unicode_names: false,
+  rate_limit: 0,
+  global_rate_limit: 0,
+  slow_mode_limit: 256 * 1024,
+  verify: false,
+  notify: true,
+  pause_on_metered: false,
+  watch_clipboard: false,
+  rpcs3_path: "",
+  console: {
+    host: "",
+    port: 21,
+    username: "anonymous",
+    password: "",
+    install: false,
+  },
+  schedule: {
+    enabled: false,
+    interval_hours: 6,
+    watchlist: [],
+    include_rpcs3: false,
+    quiet_hours: false,
+    quiet_start: 22,
+    quiet_end: 7,
+  },
+  theme: "classic",
+};
+
+// Top of the per-download speed slider, in KB/s; the left end is unlimited
+const MAX_JOB_LIMIT_KB = 10240;
+
 const PS3_FACTS = ps3FactsData.facts;
 
+// "42s", "3m 05s" or "1h 02m"
+function formatDuration(secs: number): string {
+  const total = Math.round(secs);
+  const h = Math.floor(total / 3600);
+  const m = Math.floor((total % 3600) / 60);
+  const s = total % 60;
+  if (h > 0) return `${h}h ${String(m).padStart(2, "0")}m`;
+  if (m > 0) return `${m}m ${String(s).padStart(2, "0")}s`;
+  return `${s}s`;
+}
+
 function App() {
   const [titleId, setTitleId] = useState("");
   const [searchResult, setSearchResult] = useState<FetchResult | null>(null);
+  const [installedVersion, setInstalledVersion] = useState("");
+  const [queueGroup, setQueueGroup] = useState<QueueGroup | null>(null);
+  const [partialPrompt, setPartialPrompt] = useState<{ pkg: PackageInfo; partial: PartialDownload } | null>(
+    null
+  );
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
-  const [serverOnline, setServerOnline] = useState<boolean | null>(null);
-  const [downloadPath, setDownloadPath] = useState("");
+  const [serverStatus, setServerStatus] = useState<ServerStatus | null>(null);
+  const [settings, setSettings] = useState<Settings>(DEFAULT_SETTINGS);
   const [downloads, setDownloads] = useState<DownloadJob[]>([]);
-  const [multiPart, setMultiPart] = useState(true);
+  // All downloads running together, queued ones included
+  const [session, setSession] = useState<SessionProgress | null>(null);
+  const [bandwidth, setBandwidth] = useState<BandwidthState | null>(null);
+  // Jobs whose progress events this view cares about
+  const startedJobs = useRef(new Set<string>());
   const [showSettings, setShowSettings] = useState(false);
-  const [themeColor, setThemeColor] = useState<ThemeKey>("classic");
+  const themeColor: ThemeKey = settings.theme in PS3_THEMES ? (settings.theme as ThemeKey) : "classic";
   const [ps3Fact, setPs3Fact] = useState("");
+  // Title IDs just copied to the clipboard, waiting for the user to search them
+  const [copiedTitles, setCopiedTitles] = useState<ClipboardTitle[]>([]);
+  // Known title IDs close to one the server had nothing for
+  const [suggestions, setSuggestions] = useState<TitleSuggestion[]>([]);
+  // Games found in the last folder dropped on the window
+  const [scannedTitles, setScannedTitles] = useState<ScannedTitle[] | null>(null);
+  const [scanning, setScanning] = useState(false);
+  const [queuedCount, setQueuedCount] = useState<number | null>(null);
+  const [exportedPath, setExportedPath] = useState<string | null>(null);
+  const [consoleStatus, setConsoleStatus] = useState<string | null>(null);
+  const [newUpdates, setNewUpdates] = useState<NewUpdate[]>([]);
+  const [checkStatus, setCheckStatus] = useState<string | null>(null);
+  const [network, setNetwork] = useState<NetworkState | null>(null);
+  const [importedResults, setImportedResults] = useState<FetchResult[] | null>(null);
+  const [importNote, setImportNote] = useState<string | null>(null);
+  const [batchProgress, setBatchProgress] = useState<BatchProgress | null>(null);
+  const [showHashTool, setShowHashTool] = useState(false);
+  const [hashPath, setHashPath] = useState<string | null>(null);
+  const [hashExpected, setHashExpected] = useState("");
+  const [hashProgress, setHashProgress] = useState<HashProgress | null>(null);
+  const [hashReport, setHashReport] = useState<HashReport | null>(null);
+  const [hashing, setHashing] = useState(false);
 
   useEffect(() => {
     checkServerStatus();
@@ -60,21 +182,172 @@ function App() {
     setPs3Fact(randomFact);
   }, []);
 
+  // The backend sends progress for every job; keep the ones started here
   useEffect(() => {
-    const interval = setInterval(() => {
-      updateDownloadProgress();
-    }, 500);
+    const unlisten = listen<ProgressEvent>("download://progress", (event) => {
+      const { job_id, progress } = event.payload;
+      if (!startedJobs.current.has(job_id)) return;
 
-    return () => clearInterval(interval);
-  }, [downloads]);
+      setDownloads((prev) =>
+        prev.map((d) => (d.jobId === job_id ? { ...d, progress } : d))
+      );
+      if (progress.done) {
+        startedJobs.current.delete(job_id);
+        if (!progress.error) {
+          invoke("remove_download_job", { jobId: job_id }).catch((err) =>
+            console.error("Failed to remove finished job:", err)
+          );
+        }
+      }
+    });
 
-  // Periodically check server status every 30 seconds
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  // One summary per finished job, shown on its card
   useEffect(() => {
-    const interval = setInterval(() => {
-      checkServerStatus();
-    }, 30000);
+    const unlisten = listen<DownloadSummary>("download://finished", (event) => {
+      const summary = event.payload;
+      setDownloads((prev) =>
+        prev.map((d) => (d.jobId === summary.job_id ? { ...d, summary } : d))
+      );
+    });
 
-    return () => clearInterval(interval);
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  // Follow a queued title until every one of its items has finished
+  useEffect(() => {
+    if (!queueGroup || queueGroup.done + queueGroup.failed >= queueGroup.items) return;
+    const timer = setTimeout(() => {
+      invoke<QueueGroup>("get_queue_group", { groupId: queueGroup.group_id })
+        .then(setQueueGroup)
+        .catch(() => setQueueGroup(null));
+    }, 1000);
+    return () => clearTimeout(timer);
+  }, [queueGroup]);
+
+  useEffect(() => {
+    const unlisten = listen<PushEvent>("console://progress", (event) => {
+      const { push_id, progress } = event.payload;
+      setDownloads((prev) =>
+        prev.map((d) => (d.push?.pushId === push_id ? { ...d, push: { pushId: push_id, progress } } : d))
+      );
+    });
+
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  useEffect(() => {
+    invoke<BandwidthState>("get_bandwidth")
+      .then(setBandwidth)
+      .catch((err) => console.error("Failed to get bandwidth limit:", err));
+    const unlisten = listen<BandwidthState>("bandwidth://changed", (event) => {
+      setBandwidth(event.payload);
+    });
+
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<SessionProgress>("download://session", (event) => {
+      setSession(event.payload);
+    });
+
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  // The queue is held while on a metered connection, if the user asked for that
+  useEffect(() => {
+    invoke<NetworkState>("get_network_state").then(setNetwork).catch(console.error);
+    const unlisten = listen<NetworkState>("network://metered", (event) => {
+      setNetwork(event.payload);
+    });
+
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  // Background checks report what they found since the list was last cleared
+  useEffect(() => {
+    invoke<NewUpdate[]>("list_new_updates").then(setNewUpdates).catch(console.error);
+    const unlisten = listen<CheckReport>("schedule://updates", (event) => {
+      setNewUpdates(event.payload.unseen);
+    });
+
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  // The backend only sends these while clipboard watching is on
+  useEffect(() => {
+    const unlisten = listen<ClipboardTitles>("clipboard://title-ids", (event) => {
+      setCopiedTitles(event.payload.titles);
+    });
+
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  // Dropping a games folder, RPCS3 directory or ISO on the window scans it;
+  // dropping a .txt or .csv title list imports it
+  useEffect(() => {
+    const unlisten = getCurrentWebview().onDragDropEvent((event) => {
+      if (event.payload.type !== "drop") return;
+      const [path] = event.payload.paths;
+      if (!path) return;
+      // A list of title IDs is imported; anything else is scanned for games
+      if (/\.(txt|csv)$/i.test(path)) importTitleList(path);
+      else scanFolder(path);
+    });
+
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<BatchProgress>("fetch://progress", (event) => {
+      setBatchProgress(event.payload);
+    });
+
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<HashProgress>("hash://progress", (event) => {
+      setHashProgress(event.payload);
+    });
+
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  // The backend keeps checking the server and says when it goes up or down
+  useEffect(() => {
+    const unlisten = listen<ServerStatus>("server://status", (event) => {
+      setServerStatus(event.payload);
+    });
+
+    return () => {
+      unlisten.then((stop) => stop());
+    };
   }, []);
 
   useEffect(() => {
@@ -94,109 +367,138 @@ function App() {
     root.style.setProperty('--theme-color-rgb', rgb);
   }, [themeColor]);
 
+  // One overall bar is only worth showing while several files are on the go
+  const showSession =
+    !!session && session.files_remaining > 0 && session.files_done + session.files_remaining > 1;
+
   const checkServerStatus = async () => {
     try {
-      const online = await invoke<boolean>("check_server_status");
-      setServerOnline(online);
+      setServerStatus(await invoke<ServerStatus>("check_server_status"));
     } catch (err) {
       console.error("Failed to check server status:", err);
-      setServerOnline(false);
+      setServerStatus({ online: false, latency_ms: null, checked_at: Date.now() / 1000 });
     }
   };
 
   const loadSettings = async () => {
     try {
-      console.log("[Settings] Loading settings from store...");
-      const store = await Store.load("settings.json");
-
-      // Load theme color from store
-      const savedTheme = await store.get<string>("theme");
-      console.log("[Settings] Loaded theme:", savedTheme);
-      if (savedTheme && savedTheme in PS3_THEMES) {
-        setThemeColor(savedTheme as ThemeKey);
-        console.log("[Settings] Applied theme:", savedTheme);
-      } else {
-        console.log("[Settings] No valid theme found, using default");
-      }
-
-      // Load multipart setting
-      const savedMultiPart = await store.get<boolean>("multiPart");
-      console.log("[Settings] Loaded multiPart:", savedMultiPart);
-      if (savedMultiPart !== null && savedMultiPart !== undefined) {
-        setMultiPart(savedMultiPart);
-        console.log("[Settings] Applied multiPart:", savedMultiPart);
-      } else {
-        console.log("[Settings] No multiPart setting found, using default");
-      }
-
-      // Load download path from store
-      const savedPath = await store.get<string>("downloadPath");
-      console.log("[Settings] Loaded downloadPath:", savedPath);
-      if (savedPath) {
-        setDownloadPath(savedPath);
-        console.log("[Settings] Applied downloadPath:", savedPath);
-      } else {
-        // Fallback to default download directory
-        const path = await invoke<string>("get_default_download_path");
-        setDownloadPath(path);
-        console.log("[Settings] Using default downloadPath:", path);
-      }
+      const saved = await invoke<Settings>("load_settings");
+      setSettings(saved);
       console.log("[Settings] Settings loaded successfully");
     } catch (err) {
       console.error("Failed to load settings:", err);
-      // Fallback to defaults on error
-      try {
-        const path = await invoke<string>("get_default_download_path");
-        setDownloadPath(path);
-      } catch (pathErr) {
-        console.error("Failed to get default path:", pathErr);
-      }
     }
   };
 
-  const saveThemeColor = async (theme: ThemeKey) => {
-    console.log("[Settings] Saving theme:", theme);
-    setThemeColor(theme);
+  // Save a change to one or more settings; the backend validates and echoes them back
+  const setProgress = (jobId: string, patch: Partial<ProgressInfo>) => {
+    setDownloads((prev) =>
+      prev.map((d) =>
+        d.jobId === jobId && d.progress ? { ...d, progress: { ...d.progress, ...patch } } : d
+      )
+    );
+  };
+
+  const verifyDownload = async (download: DownloadJob) => {
+    setProgress(download.jobId, { verifying: true });
+    try {
+      const result = await invoke<VerifyResult>("verify_download", {
+        target: download.jobId,
+        expectedSha1: download.package.sha1,
+      });
+      setProgress(download.jobId, {
+        verifying: false,
+        verified: result.valid,
+        error: result.error ?? undefined,
+      });
+    } catch (err) {
+      setProgress(download.jobId, { verifying: false });
+      setError(`Failed to verify download: ${err}`);
+    }
+  };
+
+  const setSpeedLimit = async (jobId: string, bytesPerSec: number) => {
+    setProgress(jobId, { rate_limit: bytesPerSec || undefined });
+    try {
+      await invoke("set_download_speed_limit", { jobId, bytesPerSec });
+    } catch (err) {
+      setError(`Failed to change speed limit: ${err}`);
+    }
+  };
+
+  const toggleSlowMode = async () => {
+    try {
+      setBandwidth(await invoke<BandwidthState>("set_slow_mode", { enabled: !bandwidth?.slow_mode }));
+    } catch (err) {
+      setError(`Failed to change slow mode: ${err}`);
+    }
+  };
+
+  const pushToConsole = async (jobId: string) => {
+    try {
+      const pushId = await invoke<string>("push_to_console", { target: jobId });
+      setDownloads((prev) =>
+        prev.map((d) => (d.jobId === jobId ? { ...d, push: { pushId, progress: null } } : d))
+      );
+    } catch (err) {
+      setError(`Failed to send to console: ${err}`);
+    }
+  };
+
+  const updateConsole = (patch: Partial<ConsoleSettings>) =>
+    updateSettings({ console: { ...settings.console, ...patch } });
+
+  const checkConsole = async () => {
     try {
-      const store = await Store.load("settings.json");
-      await store.set("theme", theme);
-      await store.save();
-      console.log("[Settings] Theme saved successfully:", theme);
+      await invoke("check_console");
+      setError(null);
+      setConsoleStatus("Connected");
     } catch (err) {
-      console.error("Failed to save theme:", err);
+      setConsoleStatus(`Couldn't connect: ${err}`);
     }
   };
 
-  const saveDownloadPath = async (path: string) => {
-    console.log("[Settings] Saving downloadPath:", path);
-    setDownloadPath(path);
+  const revealDownload = async (jobId: string) => {
     try {
-      const store = await Store.load("settings.json");
-      await store.set("downloadPath", path);
-      await store.save();
-      console.log("[Settings] downloadPath saved successfully:", path);
+      await invoke("reveal_download", { jobId });
     } catch (err) {
-      console.error("Failed to save download path:", err);
+      setError(`Failed to open folder: ${err}`);
     }
   };
 
-  const saveMultiPart = async (enabled: boolean) => {
-    console.log("[Settings] Saving multiPart:", enabled);
-    setMultiPart(enabled);
+  const updateSettings = async (patch: Partial<Settings>) => {
+    const next = { ...settings, ...patch };
+    setSettings(next);
     try {
-      const store = await Store.load("settings.json");
-      await store.set("multiPart", enabled);
-      await store.save();
-      console.log("[Settings] multiPart saved successfully:", enabled);
+      const saved = await invoke<Settings>("save_settings", { settings: next });
+      setSettings(saved);
     } catch (err) {
-      console.error("Failed to save multiPart setting:", err);
+      console.error("Failed to save settings:", err);
+      setError(String(err));
+    }
+  };
+
+  const toggleNotifications = async (enabled: boolean) => {
+    if (enabled) {
+      try {
+        const allowed = await invoke<boolean>("request_notification_permission");
+        if (!allowed) {
+          setError("Notifications are turned off for this app in your system settings");
+        }
+      } catch (err) {
+        console.error("Failed to request notification permission:", err);
+      }
     }
+    updateSettings({ notify: enabled });
   };
 
   const searchUpdates = async (e: React.FormEvent) => {
     e.preventDefault();
+    await runSearch(titleId);
+  };
 
-    if (!titleId.trim()) {
+  const runSearch = async (id: string) => {
+    if (!id.trim()) {
       setSearchResult(null);
       setError(null);
       return;
@@ -204,45 +506,266 @@ function App() {
 
     setLoading(true);
     setError(null);
+    setSuggestions([]);
     setSearchResult(null);
+    setQueueGroup(null);
+    setPartialPrompt(null);
 
     try {
-      const result = await invoke<FetchResult>("fetch_updates", { titleId });
+      const result = await invoke<FetchResult>("fetch_updates", { titleId: id });
       setSearchResult(result);
       if (result.error) {
         setError(result.error);
       }
     } catch (err) {
       setError(String(err));
+      // A mistyped ID: offer the known ones it was probably meant to be
+      if (String(err).startsWith("No updates found")) {
+        const near = await invoke<TitleSuggestion[]>("suggest_title_ids", { titleId: id }).catch(() => []);
+        setSuggestions(near);
+      }
     } finally {
       setLoading(false);
     }
   };
 
+  const searchTitle = (id: string) => {
+    setTitleId(id);
+    runSearch(id);
+  };
+
+  const searchCopiedTitle = (id: string) => {
+    setCopiedTitles([]);
+    searchTitle(id);
+  };
+
+  // Look up every title in a list file, as collectors keep them
+  const importTitleList = async (path: string) => {
+    setError(null);
+    setImportNote(null);
+    setSearchResult(null);
+    try {
+      const list = await invoke<TitleList>("import_title_list", { path });
+      const notes = [];
+      if (list.skipped.length > 0) {
+        notes.push(
+          `skipped ${list.skipped.length} line${list.skipped.length === 1 ? "" : "s"} without a title ID (line ${list.skipped.map((s) => s.line).join(", ")})`
+        );
+      }
+      if (list.duplicates > 0) {
+        notes.push(`${list.duplicates} repeat${list.duplicates === 1 ? "" : "s"} ignored`);
+      }
+      setImportNote(notes.length > 0 ? `Imported ${list.title_ids.length} titles; ${notes.join("; ")}` : null);
+      setImportedResults([]);
+      setLoading(true);
+      const results = await invoke<FetchResult[]>("fetch_updates_batch", { titleIds: list.title_ids });
+      setImportedResults(results);
+    } catch (err) {
+      setError(String(err));
+      setImportedResults(null);
+    } finally {
+      setLoading(false);
+      setBatchProgress(null);
+    }
+  };
+
+  const pickTitleList = async () => {
+    const path = await invoke<string | null>("pick_title_list").catch(() => null);
+    if (path) importTitleList(path);
+  };
+
+  const pickFileToHash = async () => {
+    const path = await invoke<string | null>("pick_file_to_hash").catch(() => null);
+    if (path) {
+      setHashPath(path);
+      setHashReport(null);
+    }
+  };
+
+  // SHA1 of a file from anywhere, compared with a pasted hash or the one listed for it
+  const hashFile = async () => {
+    if (!hashPath) return;
+    setHashing(true);
+    setHashReport(null);
+    setHashProgress(null);
+    try {
+      setHashReport(
+        await invoke<HashReport>("hash_file", {
+          path: hashPath,
+          expectedSha1: hashExpected.trim() || null,
+        })
+      );
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setHashing(false);
+    }
+  };
+
+  const dismissNewUpdates = async () => {
+    setNewUpdates([]);
+    await invoke("clear_new_updates").catch(console.error);
+  };
+
+  const updateSchedule = (patch: Partial<ScheduleSettings>) =>
+    updateSettings({ schedule: { ...settings.schedule, ...patch } });
+
+  const checkWatchlistNow = async () => {
+    setCheckStatus("Checking...");
+    try {
+      const report = await invoke<CheckReport>("check_watchlist_now");
+      const found = report.discovered.length;
+      const failed = report.errors.length;
+      setCheckStatus(
+        `${found === 0 ? "Nothing new" : `${found} new update${found === 1 ? "" : "s"}`}` +
+          (failed > 0 ? `, ${failed} couldn't be checked` : "")
+      );
+    } catch (err) {
+      setCheckStatus(`Check failed: ${err}`);
+    }
+  };
+
+  const scanFolder = async (path: string) => {
+    setScanning(true);
+    setError(null);
+    setQueuedCount(null);
+    try {
+      const titles = await invoke<ScannedTitle[]>("scan_folder", { path });
+      setScannedTitles(titles);
+      if (titles.length === 0) {
+        setError(`No PS3 games found in ${path}`);
+      }
+    } catch (err) {
+      setError(`Failed to scan ${path}: ${err}`);
+    } finally {
+      setScanning(false);
+    }
+  };
+
+  const checkRpcs3 = async () => {
+    setScanning(true);
+    setError(null);
+    setQueuedCount(null);
+    try {
+      const report = await invoke<Rpcs3Report>("check_rpcs3_updates");
+      setScannedTitles(report.titles);
+      if (report.titles.length === 0) {
+        setError(`No games installed in ${report.path}`);
+      }
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setScanning(false);
+    }
+  };
+
+  // Queue every missing update of every scanned game, in install order
+  const updateEverything = async () => {
+    if (!scannedTitles) return;
+    const items: QueueRequest[] = scannedTitles.flatMap((t) =>
+      t.packages.map((pkg) => ({ game_title: t.title, title_id: t.title_id, package: pkg }))
+    );
+    try {
+      const added = await invoke<QueueItem[]>("enqueue_downloads", { items });
+      setQueuedCount(added.length);
+    } catch (err) {
+      setError(`Failed to queue updates: ${err}`);
+    }
+  };
+
+  const exportCsv = async (csv: CsvExport) => {
+    try {
+      const path = await invoke<string | null>("export_csv", { export: csv });
+      if (path) setExportedPath(path);
+    } catch (err) {
+      setError(`Failed to export CSV: ${err}`);
+    }
+  };
+
+  const pickRpcs3Directory = async () => {
+    try {
+      const path = await invoke<string | null>("pick_rpcs3_directory");
+      if (path) {
+        await updateSettings({ rpcs3_path: path });
+      }
+    } catch (err) {
+      console.error("Failed to pick directory:", err);
+    }
+  };
+
   const pickDownloadDirectory = async () => {
     try {
       const path = await invoke<string | null>("pick_download_directory");
       if (path) {
-        await saveDownloadPath(path);
+        await updateSettings({ download_path: path });
       }
     } catch (err) {
       console.error("Failed to pick directory:", err);
     }
   };
 
+  // Queue every update of the title shown, or those after the installed version,
+  // once the user confirms what that downloads
+  const queueTitle = async () => {
+    if (!searchResult) return;
+    const args = {
+      result: searchResult,
+      installedVersion: installedVersion.trim() || null,
+    };
+    try {
+      const plan = await invoke<DownloadPlan>("plan_title", args);
+      if (plan.file_count === 0) {
+        setError("Every update is already in the download folder");
+        return;
+      }
+      const files = plan.file_count === 1 ? "file" : "files";
+      const eta =
+        plan.estimated_secs !== null ? ` (about ${formatDuration(plan.estimated_secs)})` : "";
+      const prompt = `You're about to download ${plan.file_count} ${files}, ${plan.total_human}${eta} — continue?`;
+      if (!window.confirm(prompt)) return;
+      const groupId = await invoke<string>("enqueue_title", args);
+      setQueueGroup(await invoke<QueueGroup>("get_queue_group", { groupId }));
+    } catch (err) {
+      setError(`Failed to queue updates: ${err}`);
+    }
+  };
+
+  // Ask before touching a partial file an earlier session left behind
   const startDownload = async (pkg: PackageInfo) => {
     if (!searchResult) return;
 
+    try {
+      const partial = await invoke<PartialDownload | null>("find_partial_download", {
+        filename: pkg.filename,
+        gameTitle: searchResult.game_title,
+        titleId: searchResult.cleaned_title_id,
+        sizeBytes: pkg.size_bytes,
+      });
+      if (partial) {
+        setPartialPrompt({ pkg, partial });
+        return;
+      }
+    } catch (err) {
+      console.error("Failed to look for a partial file:", err);
+    }
+    await beginDownload(pkg, false);
+  };
+
+  const beginDownload = async (pkg: PackageInfo, resume: boolean) => {
+    if (!searchResult) return;
+    setPartialPrompt(null);
+
     try {
       const jobId = await invoke<string>("start_download", {
         url: pkg.url,
         filename: pkg.filename,
-        downloadPath: downloadPath,
         gameTitle: searchResult.game_title,
         titleId: searchResult.cleaned_title_id,
-        multiPart: multiPart,
+        package: pkg,
+        resume,
       });
 
+      startedJobs.current.add(jobId);
       setDownloads((prev) => [
         ...prev,
         {
@@ -259,40 +782,13 @@ function App() {
   const cancelDownload = async (jobId: string) => {
     try {
       await invoke("cancel_download", { jobId });
+      startedJobs.current.delete(jobId);
       setDownloads((prev) => prev.filter((d) => d.jobId !== jobId));
     } catch (err) {
       setError(`Failed to cancel download: ${err}`);
     }
   };
 
-  const updateDownloadProgress = async () => {
-    const updatedDownloads = [...downloads];
-    let hasChanges = false;
-
-    for (let i = 0; i < updatedDownloads.length; i++) {
-      const download = updatedDownloads[i];
-      if (!download.progress?.done) {
-        try {
-          const progress = await invoke<ProgressInfo>("get_download_progress", {
-            jobId: download.jobId,
-          });
-          updatedDownloads[i] = { ...download, progress };
-          hasChanges = true;
-
-          if (progress.done && !progress.error) {
-            await invoke("remove_download_job", { jobId: download.jobId });
-          }
-        } catch (err) {
-          console.error("Failed to get progress:", err);
-        }
-      }
-    }
-
-    if (hasChanges) {
-      setDownloads(updatedDownloads);
-    }
-  };
-
   return (
     <div
       className="app dark"
@@ -313,8 +809,11 @@ function App() {
             </div>
           </div>
           <div className="header-right">
-            <Badge variant={serverOnline ? "success" : serverOnline === null ? "secondary" : "destructive"}>
-              {serverOnline === null ? "Checking..." : serverOnline ? "Online" : "Offline"}
+            <Badge
+              variant={serverStatus?.online ? "success" : serverStatus === null ? "secondary" : "destructive"}
+              title={serverStatus?.latency_ms != null ? `${serverStatus.latency_ms} ms` : undefined}
+            >
+              {serverStatus === null ? "Checking..." : serverStatus.online ? "Online" : "Offline"}
             </Badge>
             <Button
               variant="ghost"
@@ -391,31 +890,324 @@ function App() {
               <div className="flex w-full items-center space-x-2">
                 <Input
                   type="text"
-                  value={downloadPath}
-                  onChange={(e) => setDownloadPath(e.target.value)}
-                  onBlur={(e) => saveDownloadPath(e.target.value)}
+                  value={settings.download_path}
+                  onChange={(e) => setSettings({ ...settings, download_path: e.target.value })}
+                  onBlur={(e) => updateSettings({ download_path: e.target.value })}
                   placeholder="Download directory path"
                 />
                 <Button type="button" onClick={pickDownloadDirectory} variant="secondary">
                   Browse
                 </Button>
               </div>
+              <Input
+                type="text"
+                value={settings.folder_template}
+                onChange={(e) => setSettings({ ...settings, folder_template: e.target.value })}
+                onBlur={(e) => updateSettings({ folder_template: e.target.value })}
+                placeholder="{title} ({title_id})"
+              />
               <TypographyMuted>
-                Files will be saved in a subfolder named: "Game Title (TITLE_ID)"
+                Each game gets its own subfolder, named from {"{title}"}, {"{title_id}"} and{" "}
+                {"{region}"}; use / for nested folders
               </TypographyMuted>
+              <div className="flex items-center space-x-3">
+                <Checkbox
+                  id="by-region"
+                  checked={settings.by_region}
+                  onCheckedChange={(checked) => updateSettings({ by_region: checked as boolean })}
+                />
+                <Label htmlFor="by-region" className="cursor-pointer font-normal">
+                  Sort games into region folders (EU, US, JP…)
+                </Label>
+              </div>
+              <div className="flex items-center space-x-3">
+                <Checkbox
+                  id="unicode-names"
+                  checked={settings.unicode_names}
+                  onCheckedChange={(checked) => updateSettings({ unicode_names: checked as boolean })}
+                />
+                <Label htmlFor="unicode-names" className="cursor-pointer font-normal">
+                  Keep accented and Japanese characters in folder names
+                </Label>
+              </div>
+            </div>
+
+            <div className="space-y-3">
+              <Label>RPCS3 Folder</Label>
+              <div className="flex w-full items-center space-x-2">
+                <Input
+                  type="text"
+                  value={settings.rpcs3_path}
+                  onChange={(e) => setSettings({ ...settings, rpcs3_path: e.target.value })}
+                  onBlur={(e) => updateSettings({ rpcs3_path: e.target.value })}
+                  placeholder="Found automatically"
+                />
+                <Button type="button" onClick={pickRpcs3Directory} variant="secondary">
+                  Browse
+                </Button>
+              </div>
+              <TypographyMuted>The folder holding RPCS3's dev_hdd0</TypographyMuted>
+            </div>
+
+            <div className="space-y-3">
+              <Label>Background Checks</Label>
+              <div className="flex items-center space-x-3">
+                <Checkbox
+                  id="schedule-enabled"
+                  checked={settings.schedule.enabled}
+                  onCheckedChange={(checked) => updateSchedule({ enabled: checked as boolean })}
+                />
+                <Label htmlFor="schedule-enabled" className="cursor-pointer font-normal">
+                  Check for new updates every
+                </Label>
+                <Input
+                  type="number"
+                  min={1}
+                  max={168}
+                  className="w-20"
+                  value={settings.schedule.interval_hours}
+                  onChange={(e) =>
+                    updateSchedule({ interval_hours: Math.min(168, Math.max(1, Number(e.target.value) || 6)) })
+                  }
+                />
+                <span>hours</span>
+              </div>
+              <Input
+                type="text"
+                key={settings.schedule.watchlist.join(",")}
+                defaultValue={settings.schedule.watchlist.join(", ")}
+                onBlur={(e) => updateSchedule({ watchlist: e.target.value.split(/[\s,]+/).filter(Boolean) })}
+                placeholder="Title IDs to watch, e.g. BLUS30443, NPEB00874"
+              />
+              <div className="flex items-center space-x-3">
+                <Checkbox
+                  id="schedule-rpcs3"
+                  checked={settings.schedule.include_rpcs3}
+                  onCheckedChange={(checked) => updateSchedule({ include_rpcs3: checked as boolean })}
+                />
+                <Label htmlFor="schedule-rpcs3" className="cursor-pointer font-normal">
+                  Also watch my RPCS3 games
+                </Label>
+              </div>
+              <div className="flex items-center space-x-3">
+                <Checkbox
+                  id="schedule-quiet"
+                  checked={settings.schedule.quiet_hours}
+                  onCheckedChange={(checked) => updateSchedule({ quiet_hours: checked as boolean })}
+                />
+                <Label htmlFor="schedule-quiet" className="cursor-pointer font-normal">
+                  Quiet from
+                </Label>
+                <Input
+                  type="number"
+                  min={0}
+                  max={23}
+                  className="w-16"
+                  value={settings.schedule.quiet_start}
+                  onChange={(e) => updateSchedule({ quiet_start: Math.min(23, Math.max(0, Number(e.target.value) || 0)) })}
+                />
+                <span>to</span>
+                <Input
+                  type="number"
+                  min={0}
+                  max={23}
+                  className="w-16"
+                  value={settings.schedule.quiet_end}
+                  onChange={(e) => updateSchedule({ quiet_end: Math.min(23, Math.max(0, Number(e.target.value) || 0)) })}
+                />
+              </div>
+              <div className="flex items-center space-x-3">
+                <Button type="button" variant="secondary" onClick={checkWatchlistNow}>
+                  Check now
+                </Button>
+                {checkStatus && <TypographyMuted>{checkStatus}</TypographyMuted>}
+              </div>
             </div>
 
             <div className="space-y-3">
               <div className="flex items-center space-x-3">
                 <Checkbox
                   id="multipart"
-                  checked={multiPart}
-                  onCheckedChange={(checked) => saveMultiPart(checked as boolean)}
+                  checked={settings.multi_part}
+                  onCheckedChange={(checked) => updateSettings({ multi_part: checked as boolean })}
                 />
                 <Label htmlFor="multipart" className="cursor-pointer font-normal">
-                  Multi-part download (4 parts, faster)
+                  Multi-part download (faster)
+                </Label>
+              </div>
+              {settings.multi_part && (
+                <div className="flex items-center space-x-2">
+                  <Label htmlFor="num-parts" className="font-normal">Parts</Label>
+                  <Input
+                    id="num-parts"
+                    type="number"
+                    min={1}
+                    max={MAX_PARTS}
+                    className="w-24"
+                    value={settings.num_parts}
+                    onChange={(e) =>
+                      updateSettings({ num_parts: Math.min(MAX_PARTS, Math.max(1, Number(e.target.value) || 1)) })
+                    }
+                  />
+                </div>
+              )}
+            </div>
+
+            <div className="space-y-3">
+              <Label>File Names</Label>
+              <div className="grid grid-cols-3 gap-2">
+                {NAMING_SCHEMES.map(({ key, name }) => (
+                  <Button
+                    key={key}
+                    variant={settings.naming === key ? "default" : "outline"}
+                    onClick={() => updateSettings({ naming: key })}
+                  >
+                    {name}
+                  </Button>
+                ))}
+              </div>
+            </div>
+
+            <div className="space-y-3">
+              <Label htmlFor="rate-limit">Bandwidth Limit (KB/s)</Label>
+              <Input
+                id="rate-limit"
+                type="number"
+                min={0}
+                className="w-32"
+                value={settings.rate_limit / 1024}
+                onChange={(e) =>
+                  updateSettings({ rate_limit: Math.max(0, Math.round(Number(e.target.value))) * 1024 })
+                }
+              />
+              <TypographyMuted>Where each new download starts; 0 is unlimited</TypographyMuted>
+            </div>
+
+            <div className="space-y-3">
+              <Label htmlFor="global-rate-limit">Total Bandwidth Limit (KB/s)</Label>
+              <Input
+                id="global-rate-limit"
+                type="number"
+                min={0}
+                className="w-32"
+                value={settings.global_rate_limit / 1024}
+                onChange={(e) =>
+                  updateSettings({ global_rate_limit: Math.max(0, Math.round(Number(e.target.value))) * 1024 })
+                }
+              />
+              <TypographyMuted>Shared by all downloads, running ones included; 0 is unlimited</TypographyMuted>
+            </div>
+
+            <div className="space-y-3">
+              <Label htmlFor="slow-mode-limit">Slow Mode Limit (KB/s)</Label>
+              <Input
+                id="slow-mode-limit"
+                type="number"
+                min={1}
+                className="w-32"
+                value={settings.slow_mode_limit / 1024}
+                onChange={(e) =>
+                  updateSettings({ slow_mode_limit: Math.max(1, Math.round(Number(e.target.value))) * 1024 })
+                }
+              />
+              <TypographyMuted>All downloads together while slow mode is on, until the app closes</TypographyMuted>
+            </div>
+
+            <div className="space-y-3">
+              <div className="flex items-center space-x-3">
+                <Checkbox
+                  id="verify"
+                  checked={settings.verify}
+                  onCheckedChange={(checked) => updateSettings({ verify: checked as boolean })}
+                />
+                <Label htmlFor="verify" className="cursor-pointer font-normal">
+                  Verify SHA-1 when a download completes
+                </Label>
+              </div>
+              <div className="flex items-center space-x-3">
+                <Checkbox
+                  id="notify"
+                  checked={settings.notify}
+                  onCheckedChange={(checked) => toggleNotifications(checked as boolean)}
+                />
+                <Label htmlFor="notify" className="cursor-pointer font-normal">
+                  Notify me when a download finishes or fails in the background
+                </Label>
+              </div>
+              <div className="flex items-center space-x-3">
+                <Checkbox
+                  id="pause-on-metered"
+                  checked={settings.pause_on_metered}
+                  onCheckedChange={(checked) => updateSettings({ pause_on_metered: checked as boolean })}
+                />
+                <Label htmlFor="pause-on-metered" className="cursor-pointer font-normal">
+                  Pause the queue on metered connections and hotspots
+                </Label>
+              </div>
+              <div className="flex items-center space-x-3">
+                <Checkbox
+                  id="watch-clipboard"
+                  checked={settings.watch_clipboard}
+                  onCheckedChange={(checked) => {
+                    if (!checked) setCopiedTitles([]);
+                    updateSettings({ watch_clipboard: checked as boolean });
+                  }}
+                />
+                <Label htmlFor="watch-clipboard" className="cursor-pointer font-normal">
+                  Offer to fetch updates when I copy a title ID
+                </Label>
+              </div>
+            </div>
+
+            <div className="space-y-3">
+              <Label>PS3 Console</Label>
+              <div className="grid grid-cols-2 gap-2">
+                <Input
+                  type="text"
+                  value={settings.console.host}
+                  onChange={(e) => setSettings({ ...settings, console: { ...settings.console, host: e.target.value } })}
+                  onBlur={(e) => updateConsole({ host: e.target.value })}
+                  placeholder="IP address (e.g. 192.168.1.50)"
+                />
+                <Input
+                  type="number"
+                  min={1}
+                  max={65535}
+                  value={settings.console.port}
+                  onChange={(e) => updateConsole({ port: Math.min(65535, Math.max(1, Number(e.target.value) || 21)) })}
+                  placeholder="FTP port"
+                />
+                <Input
+                  type="text"
+                  value={settings.console.username}
+                  onChange={(e) => setSettings({ ...settings, console: { ...settings.console, username: e.target.value } })}
+                  onBlur={(e) => updateConsole({ username: e.target.value })}
+                  placeholder="FTP user"
+                />
+                <Input
+                  type="password"
+                  value={settings.console.password}
+                  onChange={(e) => setSettings({ ...settings, console: { ...settings.console, password: e.target.value } })}
+                  onBlur={(e) => updateConsole({ password: e.target.value })}
+                  placeholder="FTP password"
+                />
+              </div>
+              <div className="flex items-center space-x-3">
+                <Checkbox
+                  id="console-install"
+                  checked={settings.console.install}
+                  onCheckedChange={(checked) => updateConsole({ install: checked as boolean })}
+                />
+                <Label htmlFor="console-install" className="cursor-pointer font-normal">
+                  Install with webMAN after sending
                 </Label>
               </div>
+              <div className="flex items-center space-x-3">
+                <Button type="button" variant="secondary" onClick={checkConsole} disabled={!settings.console.host}>
+                  Test connection
+                </Button>
+                {consoleStatus && <TypographyMuted>{consoleStatus}</TypographyMuted>}
+              </div>
             </div>
 
             <div className="space-y-3">
@@ -426,7 +1218,7 @@ function App() {
                     key={key}
                     variant={themeColor === key ? "default" : "outline"}
                     className="justify-start gap-2 h-auto py-2.5 px-3"
-                    onClick={() => saveThemeColor(key)}
+                    onClick={() => updateSettings({ theme: key })}
                     data-theme-active={themeColor === key}
                   >
                     <span
@@ -444,26 +1236,299 @@ function App() {
 
       <main className="container">
         {error && <div className="error">{error}</div>}
+        {network?.held && (
+          <div className="export-status">Queue paused while on a metered connection</div>
+        )}
+        {exportedPath && (
+          <div className="export-status" onClick={() => setExportedPath(null)}>
+            Saved {exportedPath}
+          </div>
+        )}
+
+        {newUpdates.length > 0 && (
+          <div className="clipboard-prompt">
+            {newUpdates.map((update) => (
+              <div key={`${update.title_id}-${update.package.version}`} className="clipboard-prompt-row">
+                <span>
+                  New: {update.game_title || update.title_id} {update.package.version} ({update.package.size_human})
+                </span>
+                <Button size="sm" onClick={() => searchTitle(update.title_id)} disabled={loading}>
+                  Search
+                </Button>
+              </div>
+            ))}
+            <Button variant="ghost" size="sm" onClick={dismissNewUpdates}>
+              Dismiss
+            </Button>
+          </div>
+        )}
+
+        {suggestions.length > 0 && (
+          <div className="clipboard-prompt">
+            {suggestions.map(({ title_id, name }) => (
+              <div key={title_id} className="clipboard-prompt-row">
+                <span>
+                  Did you mean {title_id} ({name})?
+                </span>
+                <Button size="sm" onClick={() => searchTitle(title_id)} disabled={loading}>
+                  Search
+                </Button>
+              </div>
+            ))}
+            <Button variant="ghost" size="sm" onClick={() => setSuggestions([])}>
+              Dismiss
+            </Button>
+          </div>
+        )}
 
-        {!searchResult && !error && (
+        {copiedTitles.length > 0 && (
+          <div className="clipboard-prompt">
+            {copiedTitles.map(({ title_id, name }) => (
+              <div key={title_id} className="clipboard-prompt-row">
+                <span>
+                  Fetch updates for {title_id}
+                  {name ? ` (${name})` : ""}?
+                </span>
+                <Button size="sm" onClick={() => searchCopiedTitle(title_id)} disabled={loading}>
+                  Search
+                </Button>
+              </div>
+            ))}
+            <Button variant="ghost" size="sm" onClick={() => setCopiedTitles([])}>
+              Dismiss
+            </Button>
+          </div>
+        )}
+
+        {!searchResult && !error && !scanning && !scannedTitles?.length && !importedResults && !showHashTool && (
           <div className="empty-state">
             <p className="did-you-know">
               Did you know...
               <br />
               {ps3Fact}
             </p>
-            <p>To get started make a search</p>
+            <p>To get started make a search, or drop your games folder here</p>
+            <Button variant="outline" onClick={checkRpcs3}>
+              Check my RPCS3 games
+            </Button>
+            <Button variant="outline" onClick={pickTitleList}>
+              Import a title list
+            </Button>
+            <Button variant="outline" onClick={() => setShowHashTool(true)}>
+              Check a file's SHA1
+            </Button>
           </div>
         )}
 
+        {scanning && <p className="scan-status">Scanning for games...</p>}
+
+        {showHashTool && (
+          <Card className="results-section">
+            <CardHeader>
+              <CardTitle>Check a file</CardTitle>
+            </CardHeader>
+            <CardContent className="space-y-3">
+              <div className="flex w-full items-center space-x-2">
+                <Input type="text" value={hashPath ?? ""} readOnly placeholder="No file chosen" />
+                <Button type="button" variant="secondary" onClick={pickFileToHash} disabled={hashing}>
+                  Browse
+                </Button>
+              </div>
+              <Input
+                type="text"
+                value={hashExpected}
+                onChange={(e) => setHashExpected(e.target.value)}
+                placeholder="Expected SHA1 (optional; looked up from the file name when left empty)"
+              />
+              <div className="scan-actions">
+                <Button onClick={hashFile} disabled={!hashPath || hashing}>
+                  {hashing ? "Hashing..." : "Check"}
+                </Button>
+                <Button variant="ghost" onClick={() => setShowHashTool(false)} disabled={hashing}>
+                  Close
+                </Button>
+              </div>
+              {hashing && hashProgress && <Progress value={hashProgress.percent} />}
+              {hashReport && (
+                <div>
+                  <div>SHA1: {hashReport.sha1}</div>
+                  <TypographyMuted>
+                    {hashReport.expected === null
+                      ? "No known hash for this file name; paste one to compare"
+                      : `${hashReport.matches ? "Matches" : "Does not match"} ${
+                          hashReport.expected.source === "pasted"
+                            ? "the pasted hash"
+                            : `${hashReport.expected.game_title ?? hashReport.expected.title_id} ${hashReport.expected.version ?? ""} (${
+                                hashReport.expected.source === "history" ? "from your downloads" : "from the update server"
+                              })`
+                        }`}
+                  </TypographyMuted>
+                </div>
+              )}
+            </CardContent>
+          </Card>
+        )}
+
+        {importedResults && !searchResult && (
+          <Card className="results-section">
+            <CardHeader>
+              <CardTitle>
+                {loading
+                  ? `Checking ${batchProgress?.done ?? 0} of ${batchProgress?.total ?? "..."} titles`
+                  : `Imported titles (${importedResults.filter((r) => r.results.length > 0).length} with updates)`}
+              </CardTitle>
+            </CardHeader>
+            <CardContent>
+              {importNote && <TypographyMuted>{importNote}</TypographyMuted>}
+              {!loading && importedResults.length > 0 && (
+                <div className="scan-actions">
+                  <Button variant="outline" onClick={() => exportCsv({ kind: "fetch", data: importedResults })}>
+                    Export CSV
+                  </Button>
+                  <Button variant="ghost" onClick={() => setImportedResults(null)}>
+                    Close
+                  </Button>
+                </div>
+              )}
+              <ScrollArea className="max-h-[400px] w-full pr-4">
+                <div className="scanned-list">
+                  {importedResults.map((r) => (
+                    <div key={r.cleaned_title_id} className="scanned-title">
+                      <div>
+                        <div>
+                          {r.game_title || r.cleaned_title_id} ({r.cleaned_title_id})
+                        </div>
+                        <TypographyMuted>
+                          {r.error
+                            ? r.error
+                            : `${r.results.length} update${r.results.length === 1 ? "" : "s"}, latest ${r.results[r.results.length - 1]?.version ?? "none"}`}
+                        </TypographyMuted>
+                      </div>
+                      {r.results.length > 0 && (
+                        <Button size="sm" onClick={() => setSearchResult(r)}>
+                          View updates
+                        </Button>
+                      )}
+                    </div>
+                  ))}
+                </div>
+              </ScrollArea>
+            </CardContent>
+          </Card>
+        )}
+
+        {scannedTitles && scannedTitles.length > 0 && !searchResult && (
+          <Card className="results-section">
+            <CardHeader>
+              <CardTitle>
+                Your games ({scannedTitles.filter((t) => t.packages.length > 0).length} with updates)
+              </CardTitle>
+            </CardHeader>
+            <CardContent>
+              {scannedTitles.some((t) => t.packages.length > 0) && (
+                <div className="scan-actions">
+                  <Button onClick={updateEverything} disabled={queuedCount !== null}>
+                    Update everything
+                  </Button>
+                  <Button
+                    variant="outline"
+                    onClick={() => exportCsv({ kind: "outdated", data: scannedTitles })}
+                  >
+                    Export CSV
+                  </Button>
+                  {queuedCount !== null && (
+                    <TypographyMuted>
+                      {queuedCount} update{queuedCount === 1 ? "" : "s"} queued; they download one at a time
+                    </TypographyMuted>
+                  )}
+                </div>
+              )}
+              <ScrollArea className="max-h-[400px] w-full pr-4">
+                <div className="scanned-list">
+                  {scannedTitles.map((t) => (
+                    <div key={t.title_id} className="scanned-title">
+                      <div>
+                        <div>
+                          {t.title || t.title_id} ({t.title_id})
+                        </div>
+                        <TypographyMuted>
+                          {t.error
+                            ? t.error
+                            : t.packages.length > 0
+                              ? `${t.installed_version ?? "Unknown"} → ${t.latest_version} (${t.packages.length} update${t.packages.length === 1 ? "" : "s"})`
+                              : `Up to date (${t.installed_version ?? "no updates"})`}
+                        </TypographyMuted>
+                      </div>
+                      {t.packages.length > 0 && (
+                        <Button size="sm" onClick={() => searchTitle(t.title_id)} disabled={loading}>
+                          View updates
+                        </Button>
+                      )}
+                    </div>
+                  ))}
+                </div>
+              </ScrollArea>
+            </CardContent>
+          </Card>
+        )}
+
         {searchResult && !searchResult.error && (
           <Card className="results-section">
             <CardHeader>
               <CardTitle>
                 {searchResult.game_title} ({searchResult.cleaned_title_id})
               </CardTitle>
+              {searchResult.results.length > 0 && (
+                <Button
+                  variant="outline"
+                  size="sm"
+                  onClick={() => exportCsv({ kind: "fetch", data: [searchResult] })}
+                >
+                  Export CSV
+                </Button>
+              )}
             </CardHeader>
             <CardContent>
+              {partialPrompt && (
+                <div className="partial-prompt">
+                  <p>
+                    {partialPrompt.pkg.filename} is already {partialPrompt.partial.percent.toFixed(0)}% downloaded (
+                    {partialPrompt.partial.existing_human} of {partialPrompt.pkg.size_human}).
+                  </p>
+                  <Button onClick={() => beginDownload(partialPrompt.pkg, true)}>Resume</Button>
+                  <Button variant="outline" onClick={() => beginDownload(partialPrompt.pkg, false)}>
+                    Start over
+                  </Button>
+                  <Button variant="ghost" onClick={() => setPartialPrompt(null)}>
+                    Cancel
+                  </Button>
+                </div>
+              )}
+              {searchResult.results.length > 0 && (
+                <div className="queue-title">
+                  <Input
+                    value={installedVersion}
+                    onChange={(e) => setInstalledVersion(e.target.value)}
+                    placeholder="Installed version (optional)"
+                  />
+                  <Button
+                    variant="outline"
+                    onClick={queueTitle}
+                    disabled={!!queueGroup && queueGroup.done + queueGroup.failed < queueGroup.items}
+                  >
+                    {installedVersion.trim() ? "Queue newer updates" : "Queue all updates"}
+                  </Button>
+                  {queueGroup && (
+                    <div className="queue-title-progress">
+                      <span>
+                        {queueGroup.done} of {queueGroup.items} downloaded
+                        {queueGroup.failed > 0 && ` (${queueGroup.failed} failed)`}
+                      </span>
+                      <Progress value={queueGroup.percent} />
+                    </div>
+                  )}
+                </div>
+              )}
               {searchResult.results.length === 0 ? (
                 <p className="no-results">No updates found for this title.</p>
               ) : (
@@ -509,8 +1574,33 @@ function App() {
         )}
       </main>
 
-      {downloads.length > 0 && (
+      {(downloads.length > 0 || showSession) && (
         <div className="floating-downloads">
+          <Button
+            variant={bandwidth?.slow_mode ? "default" : "outline"}
+            size="sm"
+            className="floating-slow-mode-btn"
+            onClick={toggleSlowMode}
+            title={`Hold all downloads to ${Math.round((bandwidth?.slow_mode_limit ?? settings.slow_mode_limit) / 1024)} KB/s until the app closes`}
+          >
+            {bandwidth?.slow_mode ? "Slow mode on" : "Slow mode"}
+          </Button>
+          {showSession && session && (
+            <Card className="floating-download-item">
+              <CardContent className="p-3">
+                <div className="floating-download-info">
+                  <span className="floating-download-name">
+                    {session.files_done} of {session.files_done + session.files_remaining} files
+                    {session.files_failed > 0 && ` (${session.files_failed} failed)`}
+                  </span>
+                  <span className="floating-download-stats">
+                    {session.percent.toFixed(0)}% • {session.paused ? "Paused" : session.speed_human}
+                  </span>
+                </div>
+                <Progress value={session.percent} className="floating-progress-bar" />
+              </CardContent>
+            </Card>
+          )}
           {downloads.map((download) => (
             <Card key={download.jobId} className="floating-download-item">
               <CardContent className="p-3">
@@ -518,13 +1608,27 @@ function App() {
                   <span className="floating-download-name">{download.package.filename}</span>
                   {download.progress && (
                     <span className="floating-download-stats">
-                      {download.progress.done ? (
+                      {download.progress.verifying ||
+                      download.progress.phase === DownloadPhase.Verifying ? (
+                        <>Verifying…</>
+                      ) : download.progress.phase === DownloadPhase.Connecting ? (
+                        <>
+                          Connecting…
+                          {download.progress.attempts > 1 && <> (attempt {download.progress.attempts})</>}
+                        </>
+                      ) : download.progress.done ? (
                         <span className={download.progress.error ? "error" : "success"}>
-                          {download.progress.error || "Complete"}
+                          {download.progress.error ||
+                            (download.progress.already_downloaded
+                              ? "Already downloaded"
+                              : download.progress.verified
+                                ? "Complete • Verified"
+                                : "Complete")}
                         </span>
                       ) : (
                         <>
-                          {download.progress.percent.toFixed(0)}% • {download.progress.speed_human}
+                          {download.progress.percent.toFixed(0)}% •{" "}
+                          {download.progress.paused ? "Paused" : download.progress.speed_human}
                         </>
                       )}
                     </span>
@@ -534,6 +1638,38 @@ function App() {
                   value={download.progress?.percent || 0}
                   className="floating-progress-bar"
                 />
+                {download.summary && (
+                  <div className="floating-download-summary" title={download.summary.path}>
+                    {!download.summary.already_downloaded && (
+                      <span>
+                        {download.package.size_human} in {formatDuration(download.summary.elapsed_secs)} •{" "}
+                        {download.summary.average_speed_human} average
+                      </span>
+                    )}
+                    {download.summary.notes.map((note) => (
+                      <span key={note} className="floating-download-note">
+                        {note}
+                      </span>
+                    ))}
+                  </div>
+                )}
+                {download.progress && !download.progress.done && (
+                  <label className="floating-speed-limit" title="Speed limit for this download">
+                    <input
+                      type="range"
+                      min={0}
+                      max={MAX_JOB_LIMIT_KB}
+                      step={256}
+                      value={(download.progress.rate_limit ?? 0) / 1024}
+                      onChange={(e) => setSpeedLimit(download.jobId, Number(e.target.value) * 1024)}
+                    />
+                    <span>
+                      {download.progress.rate_limit
+                        ? `${Math.round(download.progress.rate_limit / 1024)} KB/s`
+                        : "Unlimited"}
+                    </span>
+                  </label>
+                )}
                 {!download.progress?.done && (
                   <Button
                     variant="ghost"
@@ -545,6 +1681,47 @@ function App() {
                     ✕
                   </Button>
                 )}
+                {download.progress?.done && !download.progress?.error && !download.progress?.verified && (
+                  <Button
+                    variant="ghost"
+                    size="sm"
+                    className="floating-verify-btn"
+                    disabled={download.progress.verifying}
+                    onClick={() => verifyDownload(download)}
+                    title="Check the file against its digest and SHA-1"
+                  >
+                    Verify
+                  </Button>
+                )}
+                {download.progress?.done && !download.progress?.error && settings.console.host && (
+                  <Button
+                    variant="ghost"
+                    size="sm"
+                    className="floating-push-btn"
+                    disabled={!!download.push && !download.push.progress?.done}
+                    onClick={() => pushToConsole(download.jobId)}
+                    title={settings.console.install ? "Send to the PS3 and install" : "Send to the PS3's packages folder"}
+                  >
+                    {!download.push
+                      ? "Send to PS3"
+                      : !download.push.progress?.done
+                        ? `Sending ${download.push.progress?.percent.toFixed(0) ?? 0}%`
+                        : download.push.progress.error
+                          ? "Retry send"
+                          : "Sent"}
+                  </Button>
+                )}
+                {download.progress?.done && !download.progress?.error && (
+                  <Button
+                    variant="ghost"
+                    size="sm"
+                    className="floating-reveal-btn"
+                    onClick={() => revealDownload(download.jobId)}
+                    title="Show in folder"
+                  >
+                    Show
+                  </Button>
+                )}
                 {download.progress?.done && !download.progress?.error && (
                   <Button
                     variant="ghost"
@@ -0,0 +1,97 @@
+/*
+ Generated by typeshare 1.13.4
+*/
+
+/** Represents a single PS3 update package */
+export interface PackageInfo {
+	version: string;
+	system_ver: string;
+	size_bytes: number;
+	size_human: string;
+	url: string;
+	sha1: string;
+	filename: string;
+}
+
+/** Result of fetching updates for a title */
+export interface FetchResult {
+	results: PackageInfo[];
+	error?: string;
+	game_title: string;
+	cleaned_title_id: string;
+}
+
+/** Download progress information */
+export interface ProgressInfo {
+	filename?: string;
+	total: number;
+	downloaded: number;
+	/** Bytes still to come; 0 while the size is unknown */
+	remaining: number;
+	percent: number;
+	speed_bytes_per_sec: number;
+	speed_human: string;
+	done: boolean;
+	error?: string;
+	/** [`PS3UpdateError::code`] of `error` */
+	error_code?: string;
+	/** URL that served (or is serving) the file, which may be an archive mirror */
+	source?: string;
+	/** Outcome of verification, once it ran: false means the file is corrupt */
+	verified?: boolean;
+	/** The destination already held an intact copy, so nothing was downloaded */
+	already_downloaded: boolean;
+	/** Bytes per second the job is held to, if limited */
+	rate_limit?: number;
+	/** Held by `DownloadManager::pause` until resumed */
+	paused: boolean;
+	/** Seconds since the job started, frozen once it's done */
+	elapsed_secs: number;
+	/** Retries, fallbacks and the like, in the order they happened */
+	notes: string[];
+	/** What the job is doing now */
+	phase: DownloadPhase;
+	/** Tries at fetching the file so far, counting retries and mirrors */
+	attempts: number;
+}
+
+/** Outcome for one file in a library check */
+export enum FileStatus {
+	/** Matches its recorded checksum */
+	Ok = "Ok",
+	/** Checksum, size or PKG digest doesn't match */
+	Corrupt = "Corrupt",
+	/** Listed in a manifest but not on disk */
+	Missing = "Missing",
+	/** Intact PKG with no reference data */
+	Unknown = "Unknown",
+}
+
+/** Stage a download job is in */
+export enum DownloadPhase {
+	/** Waiting for the server to answer, including between retries */
+	Connecting = "connecting",
+	/** Receiving (or, for uploads, sending) the file */
+	Downloading = "downloading",
+	/** Checking the finished file against its digest and listed SHA1 */
+	Verifying = "verifying",
+	/** Finished, failed or cancelled; see `error` */
+	Done = "done",
+}
+
+/** What happened to a job; the SSE event name */
+export enum JobEventKind {
+	Started = "started",
+	Progress = "progress",
+	Finished = "finished",
+	Failed = "failed",
+	Removed = "removed",
+}
+
+/** Names of [`WebhookEvent`] variants, for filtering */
+export enum WebhookEventKind {
+	DownloadComplete = "download_complete",
+	VerificationFailed = "verification_failed",
+	UpdateDiscovered = "update_discovered",
+}
+
@@ -1,33 +1,375 @@
-export interface PackageInfo {
-  version: string;
-  system_ver: string;
-  size_bytes: number;
-  size_human: string;
-  url: string;
-  sha1: string;
-  filename: string;
+// PackageInfo, FetchResult, ProgressInfo and the status enums are generated
+// from ps3-update-core by `npm run types`; don't edit bindings.ts by hand.
+import type { FetchResult, PackageInfo, ProgressInfo } from "./bindings";
+
+export * from "./bindings";
+
+export interface DownloadJob {
+  jobId: string;
+  package: PackageInfo;
+  progress: ProgressInfo | null;
+  /** Upload of the finished file to the console, once one is started */
+  push?: PushState;
+  /** Sent once the job finishes */
+  summary?: DownloadSummary;
 }
 
-export interface FetchResult {
-  results: PackageInfo[];
-  error: string | null;
-  game_title: string;
-  cleaned_title_id: string;
+export interface PushState {
+  pushId: string;
+  progress: ProgressInfo | null;
 }
 
-export interface ProgressInfo {
-  filename: string | null;
-  total: number;
-  downloaded: number;
+// Payload of the backend's `download://progress` event
+export interface ProgressEvent {
+  job_id: string;
+  progress: ProgressInfo;
+}
+
+// Mirrors `DownloadSummary` in src-tauri/src/lib.rs, the payload of the
+// backend's `download://finished` event
+export interface DownloadSummary {
+  job_id: string;
+  filename?: string;
+  /** Where the file ended up, after any rename */
+  path?: string;
+  bytes: number;
+  elapsed_secs: number;
+  /** Over the whole job, verification included; 0 for a file already on disk */
+  average_speed_bytes_per_sec: number;
+  average_speed_human: string;
+  verified?: boolean;
+  error?: string;
+  already_downloaded: boolean;
+  /** URL the file came from, which may be a mirror */
+  source?: string;
+  notes: string[];
+}
+
+// Mirrors `SessionProgress` in src-tauri/src/session.rs, the payload of the
+// backend's `download://session` event
+export interface SessionProgress {
+  total_bytes: number;
+  downloaded_bytes: number;
   percent: number;
+  /** Combined speed of the downloads still running */
   speed_bytes_per_sec: number;
   speed_human: string;
-  done: boolean;
+  /** Finished downloads, failed ones included */
+  files_done: number;
+  files_failed: number;
+  files_remaining: number;
+  /** Every remaining download is paused */
+  paused: boolean;
+}
+
+// Payload of the backend's `fetch://progress` event during `fetch_updates_batch`
+export interface BatchProgress {
+  done: number;
+  total: number;
+  title_id: string;
   error: string | null;
 }
 
-export interface DownloadJob {
-  jobId: string;
+// Mirrors `VerifyResult` in src-tauri/src/lib.rs
+export interface VerifyResult {
+  path: string;
+  valid: boolean;
+  content_id: string | null;
+  /** SHA1 of the whole file */
+  sha1: string | null;
+  error: string | null;
+}
+
+// Mirrors `ScannedTitle` in src-tauri/src/scan.rs
+export interface ScannedTitle {
+  title_id: string;
+  title: string;
+  kind: "hdd" | "disc" | "iso";
+  /** Folder or image the game was found in */
+  path: string;
+  installed_version: string | null;
+  latest_version: string | null;
+  /** Updates newer than the installed version, oldest first (install order) */
+  packages: PackageInfo[];
+  total_bytes: number;
+  /** Set when the update list couldn't be fetched */
+  error: string | null;
+}
+
+// Mirrors `Rpcs3Report` in src-tauri/src/rpcs3.rs
+export interface Rpcs3Report {
+  /** RPCS3 folder that was scanned */
+  path: string;
+  titles: ScannedTitle[];
+}
+
+// Mirrors `CsvExport` in src-tauri/src/export.rs
+export type CsvExport =
+  | { kind: "fetch"; data: FetchResult[] }
+  | { kind: "history" }
+  | { kind: "outdated"; data: ScannedTitle[] };
+
+// Mirrors `PartialDownload` in src-tauri/src/lib.rs
+export interface PartialDownload {
+  path: string;
+  existing_bytes: number;
+  existing_human: string;
+  /** Size of the whole package */
+  total_bytes: number;
+  percent: number;
+}
+
+// Mirrors `HistoryEntry` in src-tauri/src/history.rs
+export interface HistoryEntry {
+  /** ID of the download in the catalog */
+  id: number;
+  game_title: string;
+  title_id: string;
   package: PackageInfo;
+  /** Where the file was saved, after any rename */
+  path: string;
+  /** Unix time the download finished */
+  downloaded_at: number;
+  /** Outcome of the last verification, if one ran */
+  verified: boolean | null;
+  /** The file wasn't on disk when last checked */
+  missing: boolean;
+  /** Unix time of the last re-check */
+  checked_at: number | null;
+}
+
+// Mirrors `TitleList` in src-tauri/src/titles.rs
+export interface TitleList {
+  /** Normalised (`BLES00779`), in file order, without repeats */
+  title_ids: string[];
+  /** Lines with no valid title ID, such as a CSV header */
+  skipped: { line: number; text: string }[];
+  /** Title IDs listed more than once */
+  duplicates: number;
+}
+
+// Mirrors `HashReport` in src-tauri/src/checksum.rs
+export interface HashReport {
+  path: string;
+  size_bytes: number;
+  sha1: string;
+  /** `null` when no hash was given and none is known for the file name */
+  expected: KnownHash | null;
+  matches: boolean | null;
+}
+
+export interface KnownHash {
+  sha1: string;
+  /** Pasted by the user, from a recorded download, or from the title's update list */
+  source: "pasted" | "history" | "server";
+  title_id: string | null;
+  game_title: string | null;
+  version: string | null;
+}
+
+// Payload of the backend's `hash://progress` event
+export interface HashProgress {
+  path: string;
+  hashed: number;
+  total: number;
+  percent: number;
+}
+
+// Mirrors `ServerStatus` in src-tauri/src/status.rs
+export interface ServerStatus {
+  online: boolean;
+  /** Round trip of the check, when the server answered */
+  latency_ms: number | null;
+  /** Unix time of the check */
+  checked_at: number;
+}
+
+// Mirrors `NetworkState` in src-tauri/src/metered.rs
+export interface NetworkState {
+  /** `null` where the OS doesn't say */
+  metered: boolean | null;
+  /** The queue is paused until the connection is unmetered again */
+  held: boolean;
+}
+
+// Mirrors `Settings` in src-tauri/src/settings.rs
+export interface Settings {
+  /** Layout version of the saved settings; the backend keeps it current */
+  version: number;
+  download_path: string;
+  multi_part: boolean;
+  num_parts: number;
+  naming: "Original" | "Descriptive" | "ContentId";
+  /** Name of each game's folder under the download folder */
+  folder_template: string;
+  /** Put game folders in a folder per region (EU, US, JP…), unless the template already uses `{region}` */
+  by_region: boolean;
+  /** Keep accented and non-Latin letters in game folder names */
+  unicode_names: boolean;
+  /** Bytes per second each new download starts with; 0 is unlimited */
+  rate_limit: number;
+  /** Bytes per second for all downloads together; 0 is unlimited */
+  global_rate_limit: number;
+  /** What all downloads together are held to while slow mode is on */
+  slow_mode_limit: number;
+  verify: boolean;
+  /** Show an OS notification when a download finishes or fails in the background */
+  notify: boolean;
+  /** Pause the queue while the OS reports a metered connection */
+  pause_on_metered: boolean;
+  /** Offer to fetch updates when a title ID is copied to the clipboard */
+  watch_clipboard: boolean;
+  /** RPCS3 folder to check for outdated games; empty looks in the usual places */
+  rpcs3_path: string;
+  console: ConsoleSettings;
+  /** Background checks for new updates */
+  schedule: ScheduleSettings;
+  theme: string;
+}
+
+// Mirrors `BandwidthState` in src-tauri/src/bandwidth.rs, the payload of the
+// backend's `bandwidth://changed` event
+export interface BandwidthState {
+  /** From the settings; 0 is unlimited */
+  global_rate_limit: number;
+  slow_mode: boolean;
+  slow_mode_limit: number;
+  /** What downloads are held to now, if anything */
+  effective_limit: number | null;
+}
+
+// Mirrors `ScheduleSettings` in src-tauri/src/schedule.rs
+export interface ScheduleSettings {
+  enabled: boolean;
+  interval_hours: number;
+  /** Title IDs to check for new updates */
+  watchlist: string[];
+  /** Also check every game installed in RPCS3 */
+  include_rpcs3: boolean;
+  /** Skip checks from `quiet_start` until `quiet_end` (local hours, 0-23) */
+  quiet_hours: boolean;
+  quiet_start: number;
+  quiet_end: number;
+}
+
+// An update a background check found for the first time
+export interface NewUpdate {
+  title_id: string;
+  game_title: string;
+  package: PackageInfo;
+  /** Unix time of the check that found it */
+  discovered_at: number;
+}
+
+// Payload of the backend's `schedule://updates` event
+export interface CheckReport {
+  /** Updates this check found */
+  discovered: NewUpdate[];
+  /** Everything found since the list was last cleared, newest first */
+  unseen: NewUpdate[];
+  /** Titles checked for the first time; their current versions aren't reported */
+  new_titles: string[];
+  /** `[title_id, error]` for titles that couldn't be checked */
+  errors: [string, string][];
+  checked_at: number;
+}
+
+// Mirrors `ClipboardTitles` in src-tauri/src/clipboard.rs
+export interface ClipboardTitle {
+  title_id: string;
+  /** Game name from the title database, when known */
+  name: string | null;
+}
+
+export interface ClipboardTitles {
+  titles: ClipboardTitle[];
+}
+
+// Mirrors `TitleSuggestion` in src-tauri/src/lib.rs
+export interface TitleSuggestion {
+  title_id: string;
+  name: string;
+  reason: "wrong_region" | "wrong_prefix" | "transposed_digits" | "mistyped_digit";
+}
+
+// Mirrors `ConsoleSettings` in src-tauri/src/console.rs
+export interface ConsoleSettings {
+  /** IP address or host name; empty until one is set up */
+  host: string;
+  port: number;
+  username: string;
+  password: string;
+  /** Ask webMAN to install each package once it's on the console */
+  install: boolean;
+}
+
+// Payload of the backend's `console://progress` event
+export interface PushEvent {
+  push_id: string;
+  /** `downloaded` counts bytes sent; `source` is the file's `ftp://` URL */
+  progress: ProgressInfo;
+}
+
+// Mirrors `QueueRequest` in src-tauri/src/queue.rs
+export interface QueueRequest {
+  game_title: string;
+  title_id: string;
+  package: PackageInfo;
+}
+
+// Mirrors `QueueItem` in src-tauri/src/queue.rs
+export type QueueStatus = "queued" | "downloading" | "done" | "failed";
+
+export interface QueueItem {
+  id: string;
+  game_title: string;
+  title_id: string;
+  package: PackageInfo;
+  status: QueueStatus;
+  /** Download manager job, once the item has started */
+  job_id: string | null;
   progress: ProgressInfo | null;
+  error: string | null;
+  /** Set for the packages of a title queued together by `enqueue_title` */
+  group_id: string | null;
+}
+
+// Mirrors `PlanItem` in ps3-update-core/src/plan.rs
+export interface PlanItem {
+  title_id: string;
+  game_title: string;
+  package: PackageInfo;
+  /** Folder the package goes into */
+  dir: string;
+  /** Already there at full size, so it won't be downloaded */
+  present: boolean;
+}
+
+// Mirrors `DownloadPlan` in ps3-update-core/src/plan.rs, returned by
+// `plan_title` before a title is queued
+export interface DownloadPlan {
+  items: PlanItem[];
+  /** Files to download, leaving out those already present */
+  file_count: number;
+  total_bytes: number;
+  total_human: string;
+  present_count: number;
+  /** Only when a bandwidth limit is set */
+  estimated_secs: number | null;
+  errors: [string, string][];
+}
+
+// Mirrors `QueueGroup` in src-tauri/src/queue.rs
+export interface QueueGroup {
+  group_id: string;
+  title_id: string;
+  game_title: string;
+  /** Items still in the queue; removed ones no longer count */
+  items: number;
+  done: number;
+  failed: number;
+  total_bytes: number;
+  /** Finished items count in full, the one downloading by its progress */
+  downloaded_bytes: number;
+  percent: number;
 }